@@ -1,14 +1,26 @@
 use anyhow::{Result, Context, bail};
 use std::path::{Path, PathBuf};
 use tracing::{info, warn, debug};
-use crate::cli::{BuildArgs, GhostwinConfig};
+use crate::cli::{BuildArgs, GhostwinConfig, InstallImageConfig, IsoAuthoring};
+use crate::iso::{self, IsoOptions, IsoReader, IsoWriter};
+use crate::iso::cache::IsoCache;
 use crate::wim::WimManager;
+use crate::wim::metadata;
+use crate::wim::registry::{self, RegistryEdit};
 use crate::config::ConfigManager;
 use crate::tools::ToolDetector;
-use crate::drivers::DriverManager;
+use crate::tools::integrity::{self, HASH_MANIFEST_FILE, HashManifest};
+use crate::drivers::{self, DetectedDriver, DriverManager};
+use crate::drivers::inventory::{DriverSelection, HardwareInventory};
 use crate::utils;
+use crate::utils::build_state::{BUILD_STATE_FILE, BuildState, Fingerprint};
 use crate::utils::recovery::RecoveryManager;
 
+mod plan;
+mod report;
+
+use report::{BuildReport, DroppedEdition, FileDigest, InstallImageReport, InventoryReport};
+
 const STEP_EXTRACT: &str = "Step 1: Extracting source ISO";
 const STEP_MOUNT: &str = "Step 2: Mounting WIM image";
 const STEP_HELPERS: &str = "Step 3: Copying helper files";
@@ -16,15 +28,32 @@ const STEP_EXTRA: &str = "Step 4: Copying extra files";
 const STEP_PACKAGES: &str = "Step 5: Adding WinPE packages";
 const STEP_DRIVERS: &str = "Step 6: Detecting and injecting drivers";
 const STEP_DPI_FIX: &str = "Step 7: Applying DPI fix";
-const STEP_UNMOUNT: &str = "Step 8: Unmounting and committing WIM";
-const STEP_CREATE_ISO: &str = "Step 9: Creating final ISO";
-const STEP_VERIFY: &str = "Step 10: Verifying ISO integrity";
+const STEP_REGISTRY: &str = "Step 8: Applying offline registry tweaks";
+const STEP_UNMOUNT: &str = "Step 9: Unmounting and committing WIM";
+const STEP_INSTALL_IMAGE: &str = "Step 10: Injecting drivers into install.wim";
+const STEP_CREATE_ISO: &str = "Step 11: Creating final ISO";
+const STEP_VERIFY: &str = "Step 12: Verifying ISO integrity";
+
+const ISO_VOLUME_ID: &str = "GHOSTWIN";
+
+/// Media files rewritten in place during servicing; never hardlinked from the ISO cache
+const MUTABLE_MEDIA_FILES: &[&str] = &["sources/boot.wim"];
+/// Also rewritten when `drivers.install_image` is enabled; install.esd is replaced, not modified
+const MUTABLE_INSTALL_MEDIA_FILES: &[&str] = &["sources/boot.wim", "sources/install.wim"];
+
+const CHECKPOINT_EXTRACT: &str = "extract";
+const CHECKPOINT_SERVICE: &str = "service";
+const CHECKPOINT_INSTALL_IMAGE: &str = "install_image";
+const CHECKPOINT_CREATE_ISO: &str = "create_iso";
+const CHECKPOINT_VERIFY: &str = "verify";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct BuildProgressState {
     pub current_step: &'static str,
     pub completed_steps: usize,
     pub total_steps: usize,
+    /// Per-item detail within the current step (e.g. the file being extracted)
+    pub detail: Option<String>,
 }
 
 type BuildProgressCallback = dyn Fn(&BuildProgressState) + Send + Sync;
@@ -38,10 +67,15 @@ pub async fn execute(args: BuildArgs) -> Result<()> {
     } else {
         ConfigManager::load_default()?
     };
+
+    if let Some(format) = args.plan {
+        return plan::execute(&args, &config, format);
+    }
     
     // Validate inputs and host state before touching artifacts.
     validate_inputs(&args, &config)?;
     validate_build_prerequisites(&config)?;
+    validate_package_backend(&args, &config)?;
     validate_helper_sources_for_config(&config)?;
     RecoveryManager::pre_build_check().await?;
 
@@ -50,9 +84,13 @@ pub async fn execute(args: BuildArgs) -> Result<()> {
 
     if let Err(ref error) = build_result {
         warn!("Build failed, starting cleanup: {}", error);
-        if let Err(cleanup_error) = RecoveryManager::cleanup_failed_build(&output_dir).await {
+        let keep_output = args.keep_on_failure || args.resume;
+        if let Err(cleanup_error) = RecoveryManager::cleanup_failed_build(&output_dir, keep_output).await {
             warn!("Cleanup after failed build also failed: {}", cleanup_error);
         }
+        if keep_output {
+            info!("Re-run with --resume to continue from the last completed step");
+        }
     }
 
     build_result
@@ -68,61 +106,168 @@ pub(crate) async fn execute_build_with_progress(
     progress_callback: Option<&BuildProgressCallback>,
 ) -> Result<()> {
     // Initialize WIM manager
-    let mut wim_manager = WimManager::new(config)?;
+    let wim_manager = WimManager::new(config)?;
+    let mut report = BuildReport::new(config);
+    let result = run_build_steps(args, config, wim_manager, progress_callback, &mut report).await;
+
+    report.finalize(args, &result).await;
+    if let Err(error) = report.write(&report::report_path(&args.output_iso)) {
+        warn!("{:#}", error);
+    }
+
+    result
+}
+
+async fn run_build_steps(
+    args: &BuildArgs,
+    config: &GhostwinConfig,
+    mut wim_manager: WimManager,
+    progress_callback: Option<&BuildProgressCallback>,
+    report: &mut BuildReport,
+) -> Result<()> {
     let total_steps = total_build_steps(args, config);
+    let output_dir = Path::new(&args.output_dir);
+    let source_iso = source_iso_digest(args, config).await?;
+    let fingerprints = BuildFingerprints::compute(args, config, &source_iso.sha256)?;
+    let source_sha256 = source_iso.sha256.clone();
+    report.source_iso = Some(source_iso);
+    let mut state = BuildState::prepare(output_dir, args.resume)?;
+
+    // Each checkpoint's fingerprint chains the previous one, so a changed input
+    // invalidates that step and everything after it.
+    let resume_extract = state.is_complete(CHECKPOINT_EXTRACT, &fingerprints.extract);
+    let resume_service = resume_extract && state.is_complete(CHECKPOINT_SERVICE, &fingerprints.service);
+    let service_install_image = config.drivers.install_image.enabled();
+    let resume_install_image = resume_service
+        && (!service_install_image || state.is_complete(CHECKPOINT_INSTALL_IMAGE, &fingerprints.install_image));
+    let last_servicing_checkpoint = if service_install_image { CHECKPOINT_INSTALL_IMAGE } else { CHECKPOINT_SERVICE };
+    let resume_create_iso = resume_install_image
+        && state.is_complete(CHECKPOINT_CREATE_ISO, &fingerprints.create_iso)
+        && Path::new(&args.output_iso).exists();
+    let resume_verify = resume_create_iso && state.is_complete(CHECKPOINT_VERIFY, &fingerprints.verify);
     
     // Execute build steps
     let mut completed_steps = 0;
 
     let build_result: Result<()> = async {
-        log_build_step(build_progress(STEP_EXTRACT, completed_steps, total_steps), progress_callback);
-        extract_iso(&args.source_iso, &args.output_dir).await?;
-        validate_extracted_media_layout(Path::new(&args.output_dir))?;
+        if resume_extract {
+            skip_build_step(report, STEP_EXTRACT, completed_steps, total_steps, progress_callback);
+            validate_extracted_media_layout(output_dir)
+                .context("Extracted media from the previous run is incomplete; rebuild without --resume")?;
+        } else {
+            state.truncate_after("")?;
+            log_build_step(report, build_progress(STEP_EXTRACT, completed_steps, total_steps), progress_callback);
+            extract_iso(
+                &args.source_iso,
+                &source_sha256,
+                &args.output_dir,
+                config.cache.enabled.then(|| IsoCache::from_config(&config.cache)),
+                mutable_media_files(config),
+                build_progress(STEP_EXTRACT, completed_steps, total_steps),
+                progress_callback,
+            )
+            .await?;
+            validate_extracted_media_layout(output_dir)?;
+            state.record(CHECKPOINT_EXTRACT, &fingerprints.extract)?;
+        }
         completed_steps += 1;
 
-        log_build_step(build_progress(STEP_MOUNT, completed_steps, total_steps), progress_callback);
-        let wim_path = Path::new(&args.output_dir).join("sources/boot.wim");
-        wim_manager.mount(&wim_path, &config.iso.wim_index).await?;
-        completed_steps += 1;
+        if resume_service {
+            for step in servicing_steps(args, config) {
+                skip_build_step(report, step, completed_steps, total_steps, progress_callback);
+                completed_steps += 1;
+            }
+        } else {
+            state.truncate_after(CHECKPOINT_EXTRACT)?;
+            let wim_path = output_dir.join("sources/boot.wim");
+            if resume_extract {
+                // A previous run may already have committed changes into boot.wim
+                restore_boot_wim(&args.source_iso, &wim_path).await?;
+            }
 
-        log_build_step(build_progress(STEP_HELPERS, completed_steps, total_steps), progress_callback);
-        copy_helper_files(&wim_manager, &config).await?;
-        completed_steps += 1;
+            log_build_step(report, build_progress(STEP_MOUNT, completed_steps, total_steps), progress_callback);
+            let wim_index = resolve_wim_index(&wim_path, &config.iso.wim_index)?;
+            wim_manager.mount(&wim_path, wim_index).await?;
+            completed_steps += 1;
 
-        if let Some(extra_files) = &args.extra_files {
-            log_build_step(build_progress(STEP_EXTRA, completed_steps, total_steps), progress_callback);
-            copy_extra_files(&wim_manager, extra_files).await?;
+            log_build_step(report, build_progress(STEP_HELPERS, completed_steps, total_steps), progress_callback);
+            copy_helper_files(&wim_manager, config, report).await?;
             completed_steps += 1;
-        }
 
-        if !args.skip_packages {
-            log_build_step(build_progress(STEP_PACKAGES, completed_steps, total_steps), progress_callback);
-            add_winpe_packages(&wim_manager, &config).await?;
+            if let Some(extra_files) = &args.extra_files {
+                log_build_step(report, build_progress(STEP_EXTRA, completed_steps, total_steps), progress_callback);
+                copy_extra_files(&wim_manager, extra_files).await?;
+                report.record_copied_dir(Path::new(extra_files), "");
+                completed_steps += 1;
+            }
+
+            if let Some(signing_key) = &config.security.tool_manifest_signing_key {
+                write_tool_manifest(&wim_manager, Path::new(signing_key), config.security.tool_manifest_public_key.as_deref())?;
+            }
+
+            if !args.skip_packages {
+                log_build_step(report, build_progress(STEP_PACKAGES, completed_steps, total_steps), progress_callback);
+                add_winpe_packages(&wim_manager, config, report).await?;
+                completed_steps += 1;
+            }
+
+            log_build_step(report, build_progress(STEP_DRIVERS, completed_steps, total_steps), progress_callback);
+            inject_drivers(&wim_manager, args, config, report).await?;
             completed_steps += 1;
-        }
 
-        log_build_step(build_progress(STEP_DRIVERS, completed_steps, total_steps), progress_callback);
-        inject_drivers(&wim_manager, &args).await?;
-        completed_steps += 1;
+            if !args.skip_dpi_fix && config.winpe.disable_dpi_scaling {
+                log_build_step(report, build_progress(STEP_DPI_FIX, completed_steps, total_steps), progress_callback);
+                apply_dpi_fix(&wim_manager).await?;
+                completed_steps += 1;
+            }
+
+            if has_registry_tweaks(config) {
+                log_build_step(report, build_progress(STEP_REGISTRY, completed_steps, total_steps), progress_callback);
+                apply_registry_tweaks(&wim_manager, config).await?;
+                completed_steps += 1;
+            }
 
-        if !args.skip_dpi_fix && config.winpe.disable_dpi_scaling {
-            log_build_step(build_progress(STEP_DPI_FIX, completed_steps, total_steps), progress_callback);
-            apply_dpi_fix(&wim_manager).await?;
+            log_build_step(report, build_progress(STEP_UNMOUNT, completed_steps, total_steps), progress_callback);
+            wim_manager.unmount_and_commit().await?;
+            state.record(CHECKPOINT_SERVICE, &fingerprints.service)?;
             completed_steps += 1;
         }
 
-        log_build_step(build_progress(STEP_UNMOUNT, completed_steps, total_steps), progress_callback);
-        wim_manager.unmount_and_commit().await?;
-        completed_steps += 1;
+        if service_install_image {
+            if resume_install_image {
+                skip_build_step(report, STEP_INSTALL_IMAGE, completed_steps, total_steps, progress_callback);
+            } else {
+                state.truncate_after(CHECKPOINT_SERVICE)?;
+                log_build_step(report, build_progress(STEP_INSTALL_IMAGE, completed_steps, total_steps), progress_callback);
+                if resume_extract {
+                    // A previous run may already have exported or committed the install image
+                    restore_install_image(&args.source_iso, output_dir).await?;
+                }
+                inject_install_image_drivers(&mut wim_manager, args, config, report).await?;
+                state.record(CHECKPOINT_INSTALL_IMAGE, &fingerprints.install_image)?;
+            }
+            completed_steps += 1;
+        }
 
-        log_build_step(build_progress(STEP_CREATE_ISO, completed_steps, total_steps), progress_callback);
-        validate_iso_creation_layout(Path::new(&args.output_dir))?;
-        create_iso(&args.output_dir, &args.output_iso).await?;
+        if resume_create_iso {
+            skip_build_step(report, STEP_CREATE_ISO, completed_steps, total_steps, progress_callback);
+        } else {
+            state.truncate_after(last_servicing_checkpoint)?;
+            log_build_step(report, build_progress(STEP_CREATE_ISO, completed_steps, total_steps), progress_callback);
+            validate_iso_creation_layout(output_dir)?;
+            create_iso(&args.output_dir, &args.output_iso, config).await?;
+            state.record(CHECKPOINT_CREATE_ISO, &fingerprints.create_iso)?;
+        }
         completed_steps += 1;
 
         if args.verify {
-            log_build_step(build_progress(STEP_VERIFY, completed_steps, total_steps), progress_callback);
-            verify_iso(&args.output_iso).await?;
+            if resume_verify {
+                skip_build_step(report, STEP_VERIFY, completed_steps, total_steps, progress_callback);
+            } else {
+                log_build_step(report, build_progress(STEP_VERIFY, completed_steps, total_steps), progress_callback);
+                verify_iso(&args.output_iso).await?;
+                state.record(CHECKPOINT_VERIFY, &fingerprints.verify)?;
+            }
         }
 
         Ok(())
@@ -144,7 +289,177 @@ pub(crate) async fn execute_build_with_progress(
     Ok(())
 }
 
-fn log_build_step(progress: BuildProgressState, progress_callback: Option<&BuildProgressCallback>) {
+/// Hash the source ISO once per build for the fingerprints, the extraction cache and the report
+///
+/// With the cache enabled, an ISO whose path, size and mtime match a cache entry reuses its hash.
+async fn source_iso_digest(args: &BuildArgs, config: &GhostwinConfig) -> Result<FileDigest> {
+    let path = PathBuf::from(&args.source_iso);
+    let cache = config.cache.enabled.then(|| IsoCache::from_config(&config.cache));
+    tokio::task::spawn_blocking(move || {
+        let sha256 = match cache {
+            Some(cache) => cache.source_sha256(&path)?,
+            None => {
+                info!("Hashing source ISO {}", path.display());
+                utils::sha256_file(&path)?
+            }
+        };
+        FileDigest::with_sha256(path, sha256)
+    })
+    .await
+    .context("Source ISO hashing task panicked")?
+}
+
+/// Input hashes for each checkpoint; later steps fold in the earlier fingerprints
+struct BuildFingerprints {
+    extract: String,
+    service: String,
+    install_image: String,
+    create_iso: String,
+    verify: String,
+}
+
+impl BuildFingerprints {
+    fn compute(args: &BuildArgs, config: &GhostwinConfig, source_sha256: &str) -> Result<Self> {
+        let extract = Fingerprint::new(CHECKPOINT_EXTRACT)
+            .text("source_iso", &args.source_iso)
+            .text("source_sha256", source_sha256)
+            .finish();
+
+        let mut service = Fingerprint::new(CHECKPOINT_SERVICE);
+        service
+            .text("extract", &extract)
+            .text("config", &serde_json::to_string(config)?)
+            .text("skip_packages", &args.skip_packages.to_string())
+            .text("skip_dpi_fix", &args.skip_dpi_fix.to_string());
+        let mut input_paths: Vec<PathBuf> = helper_source_path(config)
+            .into_iter()
+            .chain(windows_overlay_source_path(config))
+            .chain(args.extra_files.iter().map(PathBuf::from))
+            .chain(args.hardware_inventory.iter().map(PathBuf::from))
+            .chain(config.winpe.registry_files.iter().map(PathBuf::from))
+            .chain(config.tools.folders.iter().map(PathBuf::from))
+            .collect();
+        input_paths.extend(config.drivers.paths.iter().map(PathBuf::from));
+        for path in &input_paths {
+            service.path("input", path)?;
+        }
+        if let Some(signing_key) = &config.security.tool_manifest_signing_key {
+            // The public half identifies the signer without the private key feeding the build state
+            let key = integrity::load_signing_key(Path::new(signing_key))?;
+            service.text("tool_manifest_signer", &integrity::public_key_hex(&key.verifying_key()));
+        }
+        let service = service.finish();
+
+        // Config and driver inputs are already part of `service`
+        let install_image = Fingerprint::new(CHECKPOINT_INSTALL_IMAGE).text("service", &service).finish();
+
+        let create_iso = Fingerprint::new(CHECKPOINT_CREATE_ISO)
+            .text("install_image", &install_image)
+            .text("output_iso", &args.output_iso)
+            .finish();
+        let verify = Fingerprint::new(CHECKPOINT_VERIFY).text("create_iso", &create_iso).finish();
+
+        Ok(Self {
+            extract,
+            service,
+            install_image,
+            create_iso,
+            verify,
+        })
+    }
+}
+
+/// Steps between mounting and committing boot.wim; they only checkpoint as a unit
+fn servicing_steps(args: &BuildArgs, config: &GhostwinConfig) -> Vec<&'static str> {
+    let mut steps = vec![STEP_MOUNT, STEP_HELPERS];
+    if args.extra_files.is_some() {
+        steps.push(STEP_EXTRA);
+    }
+    if !args.skip_packages {
+        steps.push(STEP_PACKAGES);
+    }
+    steps.push(STEP_DRIVERS);
+    if !args.skip_dpi_fix && config.winpe.disable_dpi_scaling {
+        steps.push(STEP_DPI_FIX);
+    }
+    if has_registry_tweaks(config) {
+        steps.push(STEP_REGISTRY);
+    }
+    steps.push(STEP_UNMOUNT);
+    steps
+}
+
+fn skip_build_step(
+    report: &mut BuildReport,
+    step: &'static str,
+    completed_steps: usize,
+    total_steps: usize,
+    progress_callback: Option<&BuildProgressCallback>,
+) {
+    report.skip_step(step);
+    info!("⏭️ {} (completed in a previous run)", step);
+    if let Some(callback) = progress_callback {
+        callback(&BuildProgressState {
+            detail: Some("skipped, already completed".to_string()),
+            ..build_progress(step, completed_steps, total_steps)
+        });
+    }
+}
+
+fn mutable_media_files(config: &GhostwinConfig) -> &'static [&'static str] {
+    if config.drivers.install_image.enabled() {
+        MUTABLE_INSTALL_MEDIA_FILES
+    } else {
+        MUTABLE_MEDIA_FILES
+    }
+}
+
+/// Re-extract the untouched boot.wim from the source ISO before servicing it again
+async fn restore_boot_wim(source_iso: &str, wim_path: &Path) -> Result<()> {
+    info!("Restoring pristine boot.wim from {}", source_iso);
+    let source_iso = PathBuf::from(source_iso);
+    let wim_path = wim_path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let reader = IsoReader::open(&source_iso)?;
+        let entry = reader
+            .find("sources/boot.wim")
+            .with_context(|| format!("{} has no sources/boot.wim", source_iso.display()))?;
+        reader.extract_file(entry, &wim_path)
+    })
+    .await
+    .context("boot.wim restore task panicked")?
+}
+
+/// Put the source ISO's install.wim or install.esd back, dropping any install.wim exported from it
+async fn restore_install_image(source_iso: &str, output_dir: &Path) -> Result<()> {
+    info!("Restoring pristine install image from {}", source_iso);
+    let source_iso = PathBuf::from(source_iso);
+    let sources = output_dir.join("sources");
+
+    tokio::task::spawn_blocking(move || {
+        let reader = IsoReader::open(&source_iso)?;
+        for name in ["install.wim", "install.esd"] {
+            let path = sources.join(name);
+            if path.exists() {
+                std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            if let Some(entry) = reader.find(&format!("sources/{}", name)) {
+                reader.extract_file(entry, &path)?;
+            }
+        }
+        Ok(())
+    })
+    .await
+    .context("install image restore task panicked")?
+}
+
+fn log_build_step(
+    report: &mut BuildReport,
+    progress: BuildProgressState,
+    progress_callback: Option<&BuildProgressCallback>,
+) {
+    report.begin_step(progress.current_step);
     info!(
         "{} ({}/{})",
         progress.current_step,
@@ -162,6 +477,7 @@ fn build_progress(current_step: &'static str, completed_steps: usize, total_step
         current_step,
         completed_steps,
         total_steps,
+        detail: None,
     }
 }
 
@@ -176,6 +492,12 @@ fn total_build_steps(args: &BuildArgs, config: &GhostwinConfig) -> usize {
     if !args.skip_dpi_fix && config.winpe.disable_dpi_scaling {
         steps += 1;
     }
+    if has_registry_tweaks(config) {
+        steps += 1;
+    }
+    if config.drivers.install_image.enabled() {
+        steps += 1;
+    }
     if args.verify {
         steps += 1;
     }
@@ -194,10 +516,10 @@ fn validate_inputs(args: &BuildArgs, _config: &GhostwinConfig) -> Result<()> {
     }
     
     // Validate parent directory exists
-    if let Some(parent) = Path::new(&args.output_iso).parent() {
-        if !parent.exists() {
-            bail!("Output directory does not exist: {}", parent.display());
-        }
+    if let Some(parent) = Path::new(&args.output_iso).parent()
+        && !parent.exists()
+    {
+        bail!("Output directory does not exist: {}", parent.display());
     }
 
     if !Path::new(&args.output_dir).exists() {
@@ -205,6 +527,10 @@ fn validate_inputs(args: &BuildArgs, _config: &GhostwinConfig) -> Result<()> {
             .with_context(|| format!("Failed to create output directory {}", args.output_dir))?;
     }
 
+    if let Some(inventory) = &args.hardware_inventory {
+        HardwareInventory::load(Path::new(inventory))?;
+    }
+
     if let Some(extra_files) = &args.extra_files {
         let extra_path = Path::new(extra_files);
         if !extra_path.exists() {
@@ -219,34 +545,53 @@ fn validate_inputs(args: &BuildArgs, _config: &GhostwinConfig) -> Result<()> {
 }
 
 fn validate_build_prerequisites(config: &GhostwinConfig) -> Result<()> {
-    #[cfg(target_os = "windows")]
-    {
-        validate_windows_build_prerequisites(config)?;
+    if config.drivers.install_image.enabled() && crate::wim::backend::backend_for_config(config).name() != "dism" {
+        bail!("drivers.install_image needs the dism servicing backend: only DISM can add drivers to the install image's driver store");
     }
 
-    #[cfg(not(target_os = "windows"))]
+    validate_iso_authoring(config)?;
+
+    #[cfg(target_os = "windows")]
     {
-        let _ = config;
+        validate_windows_build_prerequisites(config)?;
     }
 
     Ok(())
 }
 
-#[cfg(target_os = "windows")]
-fn validate_windows_build_prerequisites(config: &GhostwinConfig) -> Result<()> {
-    if !utils::command_exists("7z") {
-        bail!("7-Zip is required for ISO extraction on Windows hosts");
+/// Only DISM adds WinPE packages; fail before extraction instead of at the packages step
+fn validate_package_backend(args: &BuildArgs, config: &GhostwinConfig) -> Result<()> {
+    let backend = crate::wim::backend::backend_for_config(config);
+    if !args.skip_packages && backend.name() != "dism" && !winpe_packages(config)?.is_empty() {
+        bail!(
+            "The {} servicing backend cannot add WinPE packages; build on Windows with the dism backend or pass --skip-packages",
+            backend.name()
+        );
     }
+    Ok(())
+}
 
-    if !utils::command_exists("dism") {
-        bail!("DISM is required for WIM mounting and package injection");
+/// Catch a missing oscdimg before extraction rather than at the ISO step
+pub(crate) fn validate_iso_authoring(config: &GhostwinConfig) -> Result<()> {
+    if config.iso.authoring != IsoAuthoring::Oscdimg {
+        return Ok(());
+    }
+    if !cfg!(target_os = "windows") {
+        bail!("oscdimg ISO authoring requires a Windows host; set iso.authoring = \"native\"");
     }
-
     let oscdimg_path = resolve_oscdimg_path(config)
         .ok_or_else(|| anyhow::anyhow!("oscdimg.exe not found in Windows ADK deployment tools"))?;
     if !oscdimg_path.exists() {
         bail!("Resolved oscdimg path does not exist: {}", oscdimg_path.display());
     }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn validate_windows_build_prerequisites(config: &GhostwinConfig) -> Result<()> {
+    if !utils::command_exists("dism") {
+        bail!("DISM is required for WIM mounting and package injection");
+    }
 
     let winpe_root = resolve_winpe_root(config)
         .ok_or_else(|| anyhow::anyhow!("Windows PE add-on not found in configured or default ADK paths"))?;
@@ -334,59 +679,83 @@ fn resolve_winpe_root(config: &GhostwinConfig) -> Option<PathBuf> {
     })
 }
 
-async fn extract_iso(source_iso: &str, output_dir: &str) -> Result<()> {
+async fn extract_iso(
+    source_iso: &str,
+    source_sha256: &str,
+    output_dir: &str,
+    cache: Option<IsoCache>,
+    mutable_files: &'static [&'static str],
+    progress: BuildProgressState,
+    progress_callback: Option<&BuildProgressCallback>,
+) -> Result<()> {
     debug!("Extracting ISO {} to {}", source_iso, output_dir);
-    
-    #[cfg(target_os = "windows")]
-    {
-        // Use 7-Zip on Windows
-        let status = tokio::process::Command::new("7z")
-            .args(&["x", source_iso, &format!("-o{}", output_dir), "-y"])
-            .status()
-            .await
-            .context("Failed to run 7z command")?;
-        
-        if !status.success() {
-            bail!("7-Zip extraction failed");
-        }
-    }
-    
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Use 7z on Linux/macOS
-        let status = tokio::process::Command::new("mkdir")
-            .args(&["-p", output_dir])
-            .status()
-            .await?;
-        
-        if !status.success() {
-            bail!("Failed to create output directory");
-        }
-        
-        let status = tokio::process::Command::new("7z")
-            .args(&["x", source_iso, &format!("-o{}", output_dir), "-y"])
-            .status()
-            .await
-            .context("Failed to run 7z command")?;
-        
-        if !status.success() {
-            bail!("7-Zip extraction failed");
+
+    let source_iso = PathBuf::from(source_iso);
+    let source_sha256 = source_sha256.to_string();
+    let output_dir = PathBuf::from(output_dir);
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+    let extraction = tokio::task::spawn_blocking(move || -> Result<usize> {
+        let on_file = |entry: &iso::reader::IsoEntry, index: usize, total: usize| {
+            debug!("Extracting {} ({}/{})", entry.path, index, total);
+            let _ = sender.send(format!("{} ({}/{})", entry.path, index, total));
+        };
+
+        let Some(cache) = cache else {
+            let reader = IsoReader::open(&source_iso)?;
+            info!("Reading source ISO through its {:?} file system", reader.file_system());
+            return Ok(reader.extract_to(&output_dir, on_file)?.files);
+        };
+
+        let entry = cache.get_or_extract(&source_iso, &source_sha256, on_file)?;
+        let summary = cache.populate(&entry, &output_dir, mutable_files)?;
+        info!(
+            "Populated {} from cache ({} hardlinked, {} copied)",
+            output_dir.display(),
+            summary.linked,
+            summary.copied
+        );
+        Ok(summary.linked + summary.copied)
+    });
+
+    // The sender is dropped when extraction finishes, which ends this loop
+    while let Some(detail) = receiver.recv().await {
+        if let Some(callback) = progress_callback {
+            callback(&BuildProgressState {
+                detail: Some(detail),
+                ..progress.clone()
+            });
         }
     }
-    
+
+    let files = extraction.await.context("ISO extraction task panicked")??;
+    info!("✅ Extracted {} files from source ISO", files);
     Ok(())
 }
 
-async fn copy_helper_files(wim_manager: &WimManager, config: &GhostwinConfig) -> Result<()> {
+/// Resolve `iso.wim_index` (number or image name) against the extracted boot.wim
+fn resolve_wim_index(wim_path: &Path, selector: &str) -> Result<u32> {
+    let wim_info = metadata::read_wim_info(wim_path)?;
+    let index = metadata::resolve_image_index(&wim_info, selector)?;
+
+    if let Some(image) = wim_info.images.iter().find(|image| image.index == index) {
+        info!("Using WIM image {} ({})", index, image.name);
+    }
+    Ok(index)
+}
+
+async fn copy_helper_files(wim_manager: &WimManager, config: &GhostwinConfig, report: &mut BuildReport) -> Result<()> {
     let helper_source = helper_source_path(config);
     let windows_source = windows_overlay_source_path(config);
     
     if let Some(helper_source) = helper_source.as_deref().filter(|path| path.exists()) {
         wim_manager.copy_to_mount(helper_source, "Helper").await?;
+        report.record_copied_dir(helper_source, "Helper");
     }
     
     if let Some(windows_source) = windows_source.as_deref().filter(|path| path.exists()) {
         wim_manager.copy_to_mount(windows_source, "Windows").await?;
+        report.record_copied_dir(windows_source, "Windows");
     }
     
     // Detect and copy tool folders
@@ -403,11 +772,37 @@ async fn copy_helper_files(wim_manager: &WimManager, config: &GhostwinConfig) ->
         let destination = tool_destination_for_dir(&tool_dir);
         debug!("Copying tool directory: {}", tool_dir.display());
         wim_manager.copy_to_mount(&tool_dir, destination).await?;
+        report.record_copied_dir(&tool_dir, destination);
     }
     
     Ok(())
 }
 
+/// Sign the hashes of everything now under `Helper/` so runs can refuse anything else
+///
+/// Only the signature goes into the image; the private key stays on the build host.
+fn write_tool_manifest(wim_manager: &WimManager, signing_key: &Path, public_key: Option<&str>) -> Result<()> {
+    let key = integrity::load_signing_key(signing_key)?;
+    let key_hex = integrity::public_key_hex(&key.verifying_key());
+    if public_key.is_some_and(|public_key| !public_key.trim().eq_ignore_ascii_case(&key_hex)) {
+        bail!(
+            "security.tool_manifest_public_key does not match {}; its public key is {}",
+            signing_key.display(),
+            key_hex
+        );
+    }
+
+    let helper = wim_manager.mount_path().join("Helper");
+    if !helper.is_dir() {
+        warn!("⚠️ No Helper folder in the image; skipping the tool manifest");
+        return Ok(());
+    }
+    let manifest = HashManifest::generate(&helper, &key)?;
+    manifest.write(&helper.join(HASH_MANIFEST_FILE))?;
+    info!("🔏 Signed tool manifest covering {} file(s); public key {}", manifest.files.len(), key_hex);
+    Ok(())
+}
+
 fn tool_destination_for_dir(tool_dir: &Path) -> &'static str {
     let folder_name = tool_dir
         .file_name()
@@ -471,22 +866,65 @@ async fn copy_extra_files(wim_manager: &WimManager, extra_files: &str) -> Result
     Ok(())
 }
 
-async fn add_winpe_packages(wim_manager: &WimManager, config: &GhostwinConfig) -> Result<()> {
-    for package in &config.winpe.packages {
+async fn add_winpe_packages(wim_manager: &WimManager, config: &GhostwinConfig, report: &mut BuildReport) -> Result<()> {
+    for package in &winpe_packages(config)? {
         info!("Adding WinPE package: {}", package);
         wim_manager.add_package(package).await?;
+        report.record_package(package, crate::wim::winpe_package_path(config, package));
     }
     Ok(())
 }
 
-async fn inject_drivers(wim_manager: &WimManager, _args: &BuildArgs) -> Result<()> {
-    info!("🔍 Scanning for drivers");
+/// Configured WinPE packages followed by those the copied tools declare in their sidecars
+fn winpe_packages(config: &GhostwinConfig) -> Result<Vec<String>> {
+    let detector = ToolDetector::new(&config.tools);
+    let mut folders: Vec<PathBuf> = helper_source_path(config).into_iter().filter(|path| path.exists()).collect();
+    folders.extend(detector.scan_tools()?);
 
-    let mut driver_manager = DriverManager::new();
-    driver_manager.scan_driver_directories()?;
+    let mut packages = config.winpe.packages.clone();
+    for package in crate::tools::required_packages(&detector, &folders)? {
+        if !package.starts_with("WinPE-") {
+            warn!("Ignoring invalid WinPE package {} requested by a tool manifest", package);
+            continue;
+        }
+        if !packages.iter().any(|existing| existing.eq_ignore_ascii_case(&package)) {
+            debug!("Tool manifest requires WinPE package {}", package);
+            packages.push(package);
+        }
+    }
+    Ok(packages)
+}
 
+/// Detect drivers and, with `--hardware-inventory`, narrow them to the best match per device
+fn select_build_drivers(
+    args: &BuildArgs,
+    config: &GhostwinConfig,
+) -> Result<(DriverManager, Vec<DetectedDriver>, Option<DriverSelection>)> {
+    let mut driver_manager = DriverManager::from_config(&config.drivers)?;
+    driver_manager.scan_driver_directories()?;
     let drivers = driver_manager.detect_drivers()?;
 
+    let Some(inventory_path) = &args.hardware_inventory else {
+        return Ok((driver_manager, drivers, None));
+    };
+    let inventory = HardwareInventory::load(Path::new(inventory_path))?;
+    let selection = driver_manager.select_for_inventory(&drivers, &inventory);
+    Ok((driver_manager, selection.drivers.clone(), Some(selection)))
+}
+
+async fn inject_drivers(
+    wim_manager: &WimManager,
+    args: &BuildArgs,
+    config: &GhostwinConfig,
+    report: &mut BuildReport,
+) -> Result<()> {
+    info!("🔍 Scanning for drivers");
+
+    let (driver_manager, drivers, selection) = select_build_drivers(args, config)?;
+    if let (Some(path), Some(selection)) = (&args.hardware_inventory, selection) {
+        report.hardware_inventory = Some(InventoryReport::new(path, selection));
+    }
+
     if drivers.is_empty() {
         info!("No drivers found to inject");
         return Ok(());
@@ -495,43 +933,229 @@ async fn inject_drivers(wim_manager: &WimManager, _args: &BuildArgs) -> Result<(
     info!("Found {} drivers, beginning injection", drivers.len());
     driver_manager.warn_about_driver_risks(&drivers);
 
+    let verifications = driver_manager.verify_drivers(&drivers);
+    let policy = driver_manager.apply_class_policies(drivers);
+    report.drivers.extend(policy.skipped);
+    let (inject, stage) = if config.drivers.require_signed {
+        let (inject, refused) = driver_manager.refuse_unsigned(policy.inject, &verifications);
+        report.drivers.extend(refused);
+        let (stage, refused) = driver_manager.refuse_unsigned(policy.stage, &verifications);
+        report.drivers.extend(refused);
+        (inject, stage)
+    } else {
+        (policy.inject, policy.stage)
+    };
+    report.driver_verification = verifications;
+
     // Inject drivers into WIM
-    driver_manager.inject_drivers_to_wim(wim_manager, &drivers).await?;
+    report.drivers.extend(driver_manager.inject_drivers_to_wim(wim_manager, &inject).await?);
 
-    // Also copy drivers to WIM for manual installation
-    driver_manager.copy_drivers_to_wim(wim_manager, &drivers).await?;
+    // Injected and staged drivers also go to the repository `ghostwin load-drivers` reads inside WinPE;
+    // its index is built from this list, so refused packages are never drvloaded either
+    let mut staged = inject;
+    staged.extend(stage.iter().cloned());
+    driver_manager.stage_drivers_to_wim(wim_manager, &staged).await?;
+    report.drivers.extend(stage.iter().map(drivers::staged_injection));
 
     info!("✅ Driver injection completed");
     Ok(())
 }
 
+/// Inject the `drivers.install_image.classes` drivers into each selected install image
+async fn inject_install_image_drivers(
+    wim_manager: &mut WimManager,
+    args: &BuildArgs,
+    config: &GhostwinConfig,
+    report: &mut BuildReport,
+) -> Result<()> {
+    let install_image = &config.drivers.install_image;
+    let (driver_manager, drivers, _) = select_build_drivers(args, config)?;
+    let mut drivers = drivers::install_image_drivers(&drivers, &install_image.classes);
+    if config.drivers.require_signed {
+        let verifications = driver_manager.verify_drivers(&drivers);
+        drivers = driver_manager.refuse_unsigned(drivers, &verifications).0;
+    }
+    if drivers.is_empty() {
+        info!("No drivers match drivers.install_image.classes ({}); install image left untouched", install_image.classes.join(", "));
+        return Ok(());
+    }
+
+    let install_wim = prepare_install_wim(wim_manager, &Path::new(&args.output_dir).join("sources"), install_image).await?;
+    report.dropped_editions = install_wim.dropped;
+    for (index, name) in install_wim.images {
+        info!("💉 Injecting {} driver(s) into install image {} ({})", drivers.len(), index, name);
+        wim_manager.mount(&install_wim.path, index).await?;
+        let injections = driver_manager.inject_drivers_to_wim(wim_manager, &drivers).await?;
+        wim_manager.unmount_and_commit().await?;
+        report.install_images.push(InstallImageReport { index, name, drivers: injections });
+    }
+
+    Ok(())
+}
+
+/// install.wim to service, with the configured images resolved against it
+struct InstallWim {
+    path: PathBuf,
+    images: Vec<(u32, String)>,
+    /// install.esd images not exported (`drop_unselected_editions`)
+    dropped: Vec<DroppedEdition>,
+}
+
+/// Resolve the configured images; an install.esd is exported to an install.wim first
+///
+/// Every edition is exported, keeping its index, unless `drop_unselected_editions` is set:
+/// then only the selected images are, renumbered from 1 in the configured order.
+async fn prepare_install_wim(
+    wim_manager: &WimManager,
+    sources: &Path,
+    install_image: &InstallImageConfig,
+) -> Result<InstallWim> {
+    let wim_path = sources.join("install.wim");
+    let esd_path = sources.join("install.esd");
+    let source = if wim_path.is_file() {
+        wim_path.clone()
+    } else if esd_path.is_file() {
+        esd_path.clone()
+    } else if sources.join("install.swm").is_file() {
+        bail!("Split install.swm images are not supported by drivers.install_image");
+    } else {
+        bail!("The source media has no sources/install.wim or sources/install.esd");
+    };
+
+    let wim_info = metadata::read_wim_info(&source)?;
+    let mut images: Vec<(u32, String)> = Vec::new();
+    for selector in &install_image.indexes {
+        let index = metadata::resolve_image_index(&wim_info, selector)
+            .with_context(|| format!("Invalid drivers.install_image.indexes entry for {}", source.display()))?;
+        if !images.iter().any(|(existing, _)| *existing == index) {
+            let name = wim_info
+                .images
+                .iter()
+                .find(|image| image.index == index)
+                .map(|image| image.name.clone())
+                .unwrap_or_default();
+            images.push((index, name));
+        }
+    }
+
+    if source == wim_path {
+        return Ok(InstallWim { path: wim_path, images, dropped: Vec::new() });
+    }
+
+    let mut prepared = InstallWim { path: wim_path, images: Vec::new(), dropped: Vec::new() };
+    if install_image.drop_unselected_editions {
+        for image in wim_info.images.iter().filter(|image| !images.iter().any(|(index, _)| *index == image.index)) {
+            warn!(
+                "⚠️ Dropping edition {} ({}) from the media: drivers.install_image.drop_unselected_editions is set",
+                image.index, image.name
+            );
+            prepared.dropped.push(DroppedEdition { index: image.index, name: image.name.clone() });
+        }
+        info!("install.esd cannot be mounted; exporting {} selected image(s) to install.wim", images.len());
+        for (position, (index, name)) in images.into_iter().enumerate() {
+            wim_manager.export_image(&esd_path, index, &prepared.path).await?;
+            prepared.images.push((position as u32 + 1, name));
+        }
+    } else {
+        info!("install.esd cannot be mounted; exporting all {} image(s) to install.wim", wim_info.images.len());
+        for (position, image) in wim_info.images.iter().enumerate() {
+            wim_manager.export_image(&esd_path, image.index, &prepared.path).await?;
+            if let Some((_, name)) = images.iter().find(|(index, _)| *index == image.index) {
+                prepared.images.push((position as u32 + 1, name.clone()));
+            }
+        }
+    }
+    // Setup prefers install.esd when both exist
+    std::fs::remove_file(&esd_path).with_context(|| format!("Failed to remove {}", esd_path.display()))?;
+    Ok(prepared)
+}
+
 async fn apply_dpi_fix(wim_manager: &WimManager) -> Result<()> {
     info!("Applying DPI scaling fix");
     wim_manager.apply_registry_fix("dpi_scaling").await?;
     Ok(())
 }
 
-async fn create_iso(media_path: &str, output_iso: &str) -> Result<()> {
+fn has_registry_tweaks(config: &GhostwinConfig) -> bool {
+    !config.winpe.registry.is_empty() || !config.winpe.registry_files.is_empty()
+}
+
+/// `[[winpe.registry]]` tweaks followed by every configured `.reg` file, in apply order
+fn registry_tweak_edits(config: &GhostwinConfig) -> Result<Vec<RegistryEdit>> {
+    let mut edits = config
+        .winpe
+        .registry
+        .iter()
+        .map(registry::tweak_edit)
+        .collect::<Result<Vec<_>>>()?;
+
+    for registry_file in &config.winpe.registry_files {
+        debug!("Parsing registry file: {}", registry_file);
+        edits.extend(registry::parse_reg_file(Path::new(registry_file))?);
+    }
+
+    Ok(edits)
+}
+
+async fn apply_registry_tweaks(wim_manager: &WimManager, config: &GhostwinConfig) -> Result<()> {
+    let edits = registry_tweak_edits(config)?;
+    let applied = wim_manager.apply_registry_edits(edits).await?;
+    info!("✅ Applied {} offline registry edit(s)", applied);
+    Ok(())
+}
+
+async fn create_iso(media_path: &str, output_iso: &str, config: &GhostwinConfig) -> Result<()> {
     debug!("Creating ISO from {} to {}", media_path, output_iso);
-    
+
+    match config.iso.authoring {
+        IsoAuthoring::Native => create_iso_native(media_path, output_iso).await,
+        IsoAuthoring::Oscdimg => create_iso_oscdimg(media_path, output_iso, config).await,
+    }
+}
+
+async fn create_iso_native(media_path: &str, output_iso: &str) -> Result<()> {
+    let media_path = PathBuf::from(media_path);
+    let output_iso = PathBuf::from(output_iso);
+
+    let summary = tokio::task::spawn_blocking(move || {
+        let options = IsoOptions {
+            exclude: vec![BUILD_STATE_FILE.to_string()],
+            ..IsoOptions::windows_media(ISO_VOLUME_ID)
+        };
+        IsoWriter::new(options).write(&media_path, &output_iso)
+    })
+    .await
+    .context("ISO writer task panicked")??;
+
+    info!(
+        "✅ ISO created natively ({} files, {} MB)",
+        summary.files,
+        summary.total_sectors * iso::SECTOR_SIZE as u64 / (1024 * 1024)
+    );
+    Ok(())
+}
+
+async fn create_iso_oscdimg(media_path: &str, output_iso: &str, config: &GhostwinConfig) -> Result<()> {
     #[cfg(target_os = "windows")]
     {
-        // Use oscdimg from Windows ADK
-        let adk_path = std::env::var("ProgramFiles(x86)")
-            .unwrap_or_else(|_| "C:\\Program Files (x86)".to_string());
-        let oscdimg_path = format!("{}\\Windows Kits\\10\\Assessment and Deployment Kit\\Deployment Tools\\amd64\\Oscdimg\\oscdimg.exe", adk_path);
-        
+        let oscdimg_path = resolve_oscdimg_path(config)
+            .ok_or_else(|| anyhow::anyhow!("oscdimg.exe not found in Windows ADK deployment tools"))?;
+
+        // oscdimg has no exclude list, so park the build state outside the media tree
+        let state_path = Path::new(media_path).join(BUILD_STATE_FILE);
+        let parked_state_path = Path::new(media_path).with_extension("ghostwin-build-state.json");
+        let parked = state_path.exists() && std::fs::rename(&state_path, &parked_state_path).is_ok();
+
         let status = tokio::process::Command::new(&oscdimg_path)
-            .args(&[
-                "-m", "-o", "-u2", "-udfver102",
-                "-bootdata:2#p0,e,b\"boot\\etfsboot.com\"#pEF,e,b\"efi\\microsoft\\boot\\efisys.bin\"",
-                media_path,
-                output_iso
-            ])
+            .args(oscdimg_args(media_path, output_iso))
             .status()
-            .await
-            .context("Failed to run oscdimg command")?;
-        
+            .await;
+
+        if parked && let Err(error) = std::fs::rename(&parked_state_path, &state_path) {
+            warn!("Failed to restore build state file {}: {}", state_path.display(), error);
+        }
+        let status = status.context("Failed to run oscdimg command")?;
+
         if !status.success() {
             bail!("oscdimg ISO creation failed");
         }
@@ -541,60 +1165,46 @@ async fn create_iso(media_path: &str, output_iso: &str) -> Result<()> {
 
     #[cfg(not(target_os = "windows"))]
     {
-        bail!("ISO creation not implemented for this platform");
+        let _ = (media_path, output_iso, config);
+        bail!("oscdimg ISO authoring requires a Windows host; set iso.authoring = \"native\"");
     }
 }
 
+/// Dual BIOS/UEFI oscdimg arguments for Windows media
+fn oscdimg_args(media_path: &str, output_iso: &str) -> Vec<String> {
+    vec![
+        "-m".to_string(),
+        "-o".to_string(),
+        "-u2".to_string(),
+        "-udfver102".to_string(),
+        "-bootdata:2#p0,e,b\"boot\\etfsboot.com\"#pEF,e,b\"efi\\microsoft\\boot\\efisys.bin\"".to_string(),
+        media_path.to_string(),
+        output_iso.to_string(),
+    ]
+}
+
 async fn verify_iso(iso_path: &str) -> Result<()> {
     debug!("Verifying ISO: {}", iso_path);
 
     verify_iso_sync(Path::new(iso_path))?;
 
-    // Check 5: List key files using 7z (if available)
-    #[cfg(target_os = "windows")]
-    {
-        let output = tokio::process::Command::new("7z")
-            .args(&["l", "-ba", iso_path])
-            .output()
-            .await;
-
-        if let Ok(output) = output {
-            if output.status.success() {
-                let listing = String::from_utf8_lossy(&output.stdout);
-
-                // Check for critical Windows boot files
-                let required_files = [
-                    "bootmgr",
-                    "boot\\bcd",
-                    "sources\\boot.wim",
-                ];
-
-                let mut missing_files = Vec::new();
-                for required_file in &required_files {
-                    if !listing.contains(required_file) {
-                        missing_files.push(*required_file);
-                    }
-                }
-
-                if missing_files.is_empty() {
-                    info!("✅ All critical boot files present");
-                } else {
-                    warn!("⚠️ Missing boot files: {:?}", missing_files);
-                    warn!("ISO may not be bootable");
-                }
-
-                // Count WIM files
-                let wim_count = listing.matches(".wim").count();
-                info!("Found {} WIM files in ISO", wim_count);
-
-            } else {
-                warn!("Could not verify file contents (7z failed)");
-            }
-        } else {
-            warn!("Could not verify file contents (7z not available)");
-        }
+    // Check 5: List key files from the ISO's own directory tree
+    let reader = IsoReader::open(Path::new(iso_path))?;
+    let missing_files = missing_boot_files(&reader);
+    if missing_files.is_empty() {
+        info!("✅ All critical boot files present");
+    } else {
+        warn!("⚠️ Missing boot files: {:?}", missing_files);
+        warn!("ISO may not be bootable");
     }
 
+    let wim_count = reader
+        .entries()
+        .iter()
+        .filter(|entry| !entry.is_dir && entry.path.to_ascii_lowercase().ends_with(".wim"))
+        .count();
+    info!("Found {} WIM files in ISO", wim_count);
+
     info!("✅ ISO verification completed successfully");
     Ok(())
 }
@@ -639,38 +1249,73 @@ fn verify_iso_sync(iso_file: &Path) -> Result<()> {
     info!("✅ ISO signature valid (ISO 9660)");
 
     // Check 4: El Torito boot record (bootable ISO)
-    file.seek(SeekFrom::Start(32768))
-        .context("Failed to seek to boot record")?;
-
-    let mut boot_record = [0u8; 32];
-    file.read_exact(&mut boot_record)
-        .context("Failed to read boot record")?;
-
-    // First byte should be 0 (boot record volume descriptor)
-    if boot_record[0] == 0 && &boot_record[1..6] == b"CD001" {
-        // Check for El Torito boot indicator
-        if &boot_record[7..30] == b"EL TORITO SPECIFICATION" {
-            info!("✅ Bootable ISO detected (El Torito)");
-        } else {
-            info!("✅ ISO 9660 volume descriptor found");
-        }
+    match el_torito_boot_catalog(&mut file)? {
+        Some(catalog_sector) => info!("✅ Bootable ISO detected (El Torito, catalog at sector {})", catalog_sector),
+        None => info!("✅ ISO 9660 volume descriptor found"),
     }
 
     Ok(())
 }
 
+fn missing_boot_files(reader: &IsoReader) -> Vec<&'static str> {
+    ["bootmgr", "boot/bcd", "sources/boot.wim"]
+        .into_iter()
+        .filter(|required| reader.find(required).is_none_or(|entry| entry.is_dir))
+        .collect()
+}
+
+/// Walk the volume descriptor set (sector 16 onward) looking for the El Torito boot record
+fn el_torito_boot_catalog(file: &mut std::fs::File) -> Result<Option<u32>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    for sector in 16..64u64 {
+        file.seek(SeekFrom::Start(sector * 2048))
+            .context("Failed to seek to volume descriptor")?;
+
+        let mut descriptor = [0u8; 75];
+        file.read_exact(&mut descriptor)
+            .context("Failed to read volume descriptor")?;
+
+        if &descriptor[1..6] != b"CD001" || descriptor[0] == 255 {
+            break;
+        }
+
+        if descriptor[0] == 0 && &descriptor[7..30] == b"EL TORITO SPECIFICATION" {
+            return Ok(Some(u32::from_le_bytes([descriptor[71], descriptor[72], descriptor[73], descriptor[74]])));
+        }
+    }
+
+    Ok(None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
+        BuildReport,
+        HASH_MANIFEST_FILE,
+        HashManifest,
+        integrity,
+        report,
         build_progress,
+        create_iso,
+        el_torito_boot_catalog,
+        validate_build_prerequisites,
+        validate_package_backend,
+        extract_iso,
+        missing_boot_files,
+        resolve_wim_index,
+        run_build_steps,
         helper_source_path,
+        MUTABLE_MEDIA_FILES,
         STEP_CREATE_ISO,
         STEP_DPI_FIX,
         STEP_DRIVERS,
         STEP_EXTRACT,
         STEP_HELPERS,
+        STEP_INSTALL_IMAGE,
         STEP_MOUNT,
         STEP_PACKAGES,
+        STEP_REGISTRY,
         STEP_UNMOUNT,
         STEP_VERIFY,
         resolve_adk_root,
@@ -685,7 +1330,9 @@ mod tests {
         validate_iso_creation_layout,
         verify_iso_sync,
     };
-    use crate::cli::{BuildArgs, GhostwinConfig};
+    use crate::cli::{BuildArgs, GhostwinConfig, IsoAuthoring};
+    use crate::iso::{IsoOptions, IsoReader, IsoWriter};
+    use crate::utils::build_state::BUILD_STATE_FILE;
     use tempfile::tempdir;
     use std::io::{Seek, SeekFrom, Write};
 
@@ -699,6 +1346,10 @@ mod tests {
             skip_dpi_fix: false,
             config: None,
             verify: false,
+            resume: false,
+            keep_on_failure: false,
+            plan: None,
+            hardware_inventory: None,
         }
     }
 
@@ -715,6 +1366,29 @@ mod tests {
         assert!(temp.path().join("build").exists());
     }
 
+    #[test]
+    fn package_injection_needs_the_dism_backend() {
+        let temp = tempdir().unwrap();
+        let mut args = build_args(&temp.path().join("source.iso"), temp.path());
+        let mut config = GhostwinConfig::default();
+        config.iso.servicing_backend = crate::cli::ServicingBackend::Wimlib;
+
+        let error = validate_package_backend(&args, &config).unwrap_err();
+        assert!(error.to_string().contains("--skip-packages"));
+
+        args.skip_packages = true;
+        validate_package_backend(&args, &config).unwrap();
+
+        args.skip_packages = false;
+        config.winpe.packages.clear();
+        config.iso.helper_source = None;
+        validate_package_backend(&args, &config).unwrap();
+
+        config.winpe.packages = vec!["WinPE-WMI".to_string()];
+        config.iso.servicing_backend = crate::cli::ServicingBackend::Dism;
+        validate_package_backend(&args, &config).unwrap();
+    }
+
     #[test]
     fn validate_inputs_rejects_missing_extra_files_dir() {
         let temp = tempdir().unwrap();
@@ -728,6 +1402,21 @@ mod tests {
         assert!(error.to_string().contains("Extra files directory does not exist"));
     }
 
+    #[test]
+    fn validate_inputs_rejects_unreadable_hardware_inventory() {
+        let temp = tempdir().unwrap();
+        let source_iso = temp.path().join("windows.iso");
+        std::fs::write(&source_iso, vec![0_u8; 101 * 1024 * 1024]).unwrap();
+        let inventory = temp.path().join("inventory.json");
+        std::fs::write(&inventory, "{\"devices\": []}").unwrap();
+
+        let mut args = build_args(&source_iso, temp.path());
+        args.hardware_inventory = Some(inventory.display().to_string());
+
+        let error = validate_inputs(&args, &GhostwinConfig::default()).unwrap_err();
+        assert!(format!("{:#}", error).contains("no devices with hardware IDs"));
+    }
+
     #[test]
     fn verify_iso_sync_rejects_invalid_signature() {
         let temp = tempdir().unwrap();
@@ -760,6 +1449,281 @@ mod tests {
         verify_iso_sync(&iso_path).unwrap();
     }
 
+    #[tokio::test]
+    async fn create_iso_native_round_trips_through_verify() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        crate::iso::test_support::write_test_media(&media, &[]);
+        // Sparse payload keeps the ISO above verify_iso_sync's size floor
+        std::fs::File::create(media.join("sources/boot.wim"))
+            .unwrap()
+            .set_len(101 * 1024 * 1024)
+            .unwrap();
+
+        let iso_path = temp.path().join("ghostwin.iso");
+        let config = GhostwinConfig::default();
+        assert_eq!(config.iso.authoring, IsoAuthoring::Native);
+
+        validate_iso_creation_layout(&media).unwrap();
+        create_iso(&media.display().to_string(), &iso_path.display().to_string(), &config)
+            .await
+            .unwrap();
+
+        verify_iso_sync(&iso_path).unwrap();
+        let mut file = std::fs::File::open(&iso_path).unwrap();
+        assert!(el_torito_boot_catalog(&mut file).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn extract_iso_reports_each_file_through_progress_callback() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        crate::iso::test_support::write_test_media(&media, &[]);
+
+        let iso_path = temp.path().join("source.iso");
+        IsoWriter::new(IsoOptions::windows_media("TEST")).write(&media, &iso_path).unwrap();
+
+        let reader = IsoReader::open(&iso_path).unwrap();
+        assert!(missing_boot_files(&reader).is_empty());
+
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = seen.clone();
+        let callback = move |progress: &super::BuildProgressState| {
+            recorder.lock().unwrap().push(progress.clone());
+        };
+        let output = temp.path().join("build");
+        extract_iso(
+            &iso_path.display().to_string(),
+            &crate::utils::sha256_file(&iso_path).unwrap(),
+            &output.display().to_string(),
+            None,
+            MUTABLE_MEDIA_FILES,
+            build_progress(STEP_EXTRACT, 0, 8),
+            Some(&callback),
+        )
+        .await
+        .unwrap();
+
+        validate_extracted_media_layout(&output).unwrap();
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 5);
+        assert!(seen.iter().all(|progress| progress.current_step == STEP_EXTRACT));
+        assert!(seen.iter().any(|progress| progress.detail.as_deref() == Some("sources/boot.wim (5/5)")));
+    }
+
+    #[tokio::test]
+    async fn full_pipeline_runs_against_recording_backend() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        crate::iso::test_support::write_test_media(
+            &media,
+            &[
+                ("Microsoft Windows PE (amd64)", "WindowsPE", "9", 22621),
+                ("Microsoft Windows Setup (amd64)", "WindowsPE", "9", 22621),
+            ],
+        );
+        let source_iso = temp.path().join("source.iso");
+        IsoWriter::new(IsoOptions::windows_media("SOURCE")).write(&media, &source_iso).unwrap();
+
+        let helper = temp.path().join("Helper");
+        std::fs::create_dir_all(&helper).unwrap();
+        std::fs::write(helper.join("readme.txt"), "helper").unwrap();
+
+        let mount = temp.path().join("mount");
+        crate::wim::write_test_offline_hives(&mount);
+
+        let mut config = GhostwinConfig::default();
+        config.iso.mount_path = Some(mount.display().to_string());
+        config.iso.wim_index = "Microsoft Windows Setup (amd64)".to_string();
+        config.iso.helper_source = Some(helper.display().to_string());
+        config.iso.windows_overlay_source = None;
+        config.tools.auto_detect = false;
+        config.tools.folders = Vec::new();
+        config.cache.path = Some(temp.path().join("cache").display().to_string());
+        config.winpe.packages = vec!["WinPE-WMI".to_string(), "WinPE-PowerShell".to_string()];
+        config.winpe.registry = vec![crate::cli::RegistryTweak {
+            hive: crate::cli::RegistryHive::System,
+            key: r"CurrentControlSet\Control\Session Manager".to_string(),
+            name: "GhostwinBuild".to_string(),
+            value_type: crate::cli::RegistryValueType::Sz,
+            data: crate::cli::RegistryData::Text("pipeline".to_string()),
+        }];
+        let reg_file = temp.path().join("tweaks.reg");
+        std::fs::write(
+            &reg_file,
+            "Windows Registry Editor Version 5.00\n\n[HKEY_LOCAL_MACHINE\\SOFTWARE\\Ghostwin]\n\"Imported\"=dword:00000002\n",
+        )
+        .unwrap();
+        config.winpe.registry_files = vec![reg_file.display().to_string()];
+        let signing_key = temp.path().join("signing.pem");
+        let public_key = integrity::tests::write_signing_key(&signing_key, 7);
+        config.security.tool_manifest_signing_key = Some(signing_key.display().to_string());
+        config.security.tool_manifest_public_key = Some(public_key.clone());
+
+        let args = build_args(&source_iso, temp.path());
+        let backend = crate::wim::backend::RecordingBackend::default();
+        let wim_manager = crate::wim::WimManager::with_backend(&config, Box::new(backend.clone())).unwrap();
+
+        let mut report = BuildReport::new(&config);
+        let result = run_build_steps(&args, &config, wim_manager, None, &mut report).await;
+        assert!(result.is_ok());
+
+        assert_eq!(
+            backend.calls(),
+            vec![
+                "mount boot.wim 2",
+                "add_package WinPE-WMI.cab",
+                "add_package WinPE-PowerShell.cab",
+                "unmount commit",
+            ]
+        );
+        let system = crate::wim::hive::Hive::open(&mount.join("Windows/System32/config/SYSTEM")).unwrap();
+        assert_eq!(
+            system
+                .get_value(r"ControlSet001\Control\GraphicsDrivers\Configuration", "DisableScalingOptimizations")
+                .unwrap(),
+            Some(crate::wim::hive::RegValue::Dword(1))
+        );
+        assert_eq!(
+            system
+                .get_value(r"ControlSet001\Control\Session Manager", "GhostwinBuild")
+                .unwrap(),
+            Some(crate::wim::hive::RegValue::Sz("pipeline".to_string()))
+        );
+        let software = crate::wim::hive::Hive::open(&mount.join("Windows/System32/config/SOFTWARE")).unwrap();
+        assert_eq!(
+            software.get_value("Ghostwin", "Imported").unwrap(),
+            Some(crate::wim::hive::RegValue::Dword(2))
+        );
+        assert!(mount.join("Helper/readme.txt").exists());
+        let verifying_key = integrity::parse_public_key(&public_key).unwrap();
+        let tool_manifest = HashManifest::load(&mount.join("Helper").join(HASH_MANIFEST_FILE), &verifying_key).unwrap();
+        assert!(tool_manifest.files.contains_key("readme.txt"));
+        // Only the signature reaches the image, never the private key
+        assert!(walkdir::WalkDir::new(&mount).into_iter().filter_map(|entry| entry.ok()).all(|entry| {
+            !entry.file_type().is_file() || !std::fs::read(entry.path()).unwrap().windows(11).any(|bytes| bytes == b"PRIVATE KEY")
+        }));
+        let cached = crate::iso::cache::IsoCache::from_config(&config.cache).entries().unwrap();
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].source_path, source_iso);
+        let output = IsoReader::open(std::path::Path::new(&args.output_iso)).unwrap();
+        assert!(missing_boot_files(&output).is_empty());
+
+        report.finalize(&args, &result).await;
+        let report_file = report::report_path(&args.output_iso);
+        report.write(&report_file).unwrap();
+        assert_eq!(report_file, temp.path().join("build-report.json"));
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&report_file).unwrap()).unwrap();
+        assert_eq!(json["success"], true);
+        assert_eq!(json["ghostwin_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["config"]["winpe"]["packages"][1], "WinPE-PowerShell");
+        assert_eq!(json["config"]["security"]["tool_manifest_public_key"], public_key.as_str());
+        assert_eq!(json["packages"][0]["name"], "WinPE-WMI");
+        assert_eq!(json["copied_dirs"][0]["destination"], "Helper");
+        assert_eq!(json["steps"].as_array().unwrap().len(), total_build_steps(&args, &config));
+        assert!(json["steps"].as_array().unwrap().iter().all(|step| step["outcome"] == "completed"));
+        assert_eq!(
+            json["source_iso"]["sha256"],
+            crate::utils::sha256_file(&source_iso).unwrap()
+        );
+        assert_eq!(
+            json["output_iso"]["size"],
+            std::fs::metadata(&args.output_iso).unwrap().len()
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_skips_completed_steps_and_reservices_on_config_change() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        crate::iso::test_support::write_test_media(
+            &media,
+            &[("Microsoft Windows PE (amd64)", "WindowsPE", "9", 22621)],
+        );
+        let pristine_wim = std::fs::read(media.join("sources/boot.wim")).unwrap();
+        let source_iso = temp.path().join("source.iso");
+        IsoWriter::new(IsoOptions::windows_media("SOURCE")).write(&media, &source_iso).unwrap();
+
+        let mount = temp.path().join("mount");
+        std::fs::create_dir_all(&mount).unwrap();
+        let mut config = GhostwinConfig::default();
+        config.iso.mount_path = Some(mount.display().to_string());
+        config.iso.wim_index = "1".to_string();
+        config.iso.helper_source = None;
+        config.iso.windows_overlay_source = None;
+        config.tools.auto_detect = false;
+        config.tools.folders = Vec::new();
+        config.cache.path = Some(temp.path().join("cache").display().to_string());
+        config.winpe.packages = Vec::new();
+        config.winpe.disable_dpi_scaling = false;
+
+        let run = |args: BuildArgs, config: GhostwinConfig| async move {
+            let backend = crate::wim::backend::RecordingBackend::default();
+            let wim_manager = crate::wim::WimManager::with_backend(&config, Box::new(backend.clone())).unwrap();
+            let mut report = BuildReport::new(&config);
+            run_build_steps(&args, &config, wim_manager, None, &mut report).await.unwrap();
+            report.end_steps(true);
+            (backend.calls(), report.steps)
+        };
+
+        let mut args = build_args(&source_iso, temp.path());
+        assert_eq!(run(args.clone(), config.clone()).await.0, vec!["mount boot.wim 1", "unmount commit"]);
+        let output = IsoReader::open(std::path::Path::new(&args.output_iso)).unwrap();
+        assert!(output.find(BUILD_STATE_FILE).is_none());
+
+        args.resume = true;
+        let (calls, steps) = run(args.clone(), config.clone()).await;
+        assert!(calls.is_empty());
+        assert!(steps.iter().all(|step| step.outcome == report::StepOutcome::Skipped));
+
+        // Simulate the committed image, then change the servicing inputs
+        let built_wim = temp.path().join("build/sources/boot.wim");
+        std::fs::write(&built_wim, "serviced").unwrap();
+        config.winpe.packages = vec!["WinPE-WMI".to_string()];
+        assert_eq!(
+            run(args.clone(), config.clone()).await.0,
+            vec!["mount boot.wim 1", "add_package WinPE-WMI.cab", "unmount commit"]
+        );
+        assert_eq!(std::fs::read(&built_wim).unwrap(), pristine_wim);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn create_iso_oscdimg_requires_windows() {
+        let temp = tempdir().unwrap();
+        let mut config = GhostwinConfig::default();
+        config.iso.authoring = IsoAuthoring::Oscdimg;
+
+        let error = create_iso(
+            &temp.path().display().to_string(),
+            &temp.path().join("out.iso").display().to_string(),
+            &config,
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("requires a Windows host"));
+
+        // Caught before the build starts
+        let error = validate_build_prerequisites(&config).unwrap_err();
+        assert!(error.to_string().contains("requires a Windows host"));
+    }
+
+    #[test]
+    fn resolve_wim_index_accepts_image_names_from_config() {
+        let temp = tempdir().unwrap();
+        let wim_path = temp.path().join("boot.wim");
+        crate::wim::metadata::write_test_wim(
+            &wim_path,
+            &[
+                ("Microsoft Windows PE (amd64)", "WindowsPE", "9", 22621),
+                ("Microsoft Windows Setup (amd64)", "WindowsPE", "9", 22621),
+            ],
+        );
+
+        assert_eq!(resolve_wim_index(&wim_path, "Microsoft Windows Setup (amd64)").unwrap(), 2);
+        assert_eq!(resolve_wim_index(&wim_path, "1").unwrap(), 1);
+    }
+
     #[test]
     fn validate_extracted_media_layout_accepts_required_files() {
         let temp = tempdir().unwrap();
@@ -808,6 +1772,167 @@ mod tests {
         assert!(message.contains("boot/bcd"));
     }
 
+    #[tokio::test]
+    async fn install_image_drivers_are_injected_into_exported_esd_images() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        crate::iso::test_support::write_test_media(
+            &media,
+            &[("Microsoft Windows Setup (amd64)", "WindowsPE", "9", 22621)],
+        );
+        crate::wim::metadata::write_test_wim(
+            &media.join("sources/install.esd"),
+            &[
+                ("Windows 11 Home", "Core", "9", 22621),
+                ("Windows 11 Pro", "Professional", "9", 22621),
+            ],
+        );
+        let source_iso = temp.path().join("source.iso");
+        IsoWriter::new(IsoOptions::windows_media("SOURCE")).write(&media, &source_iso).unwrap();
+
+        let drivers = temp.path().join("drivers");
+        std::fs::create_dir_all(&drivers).unwrap();
+        std::fs::write(drivers.join("iastorvd.inf"), "[Version]\nClass=SCSIAdapter\n").unwrap();
+        std::fs::write(drivers.join("e1d.inf"), "[Version]\nClass=Net\n").unwrap();
+
+        let mount = temp.path().join("mount");
+        crate::wim::write_test_offline_hives(&mount);
+
+        let mut config = GhostwinConfig::default();
+        config.iso.mount_path = Some(mount.display().to_string());
+        config.iso.wim_index = "1".to_string();
+        config.iso.helper_source = None;
+        config.iso.windows_overlay_source = None;
+        config.tools.auto_detect = false;
+        config.tools.folders = Vec::new();
+        config.cache.enabled = false;
+        config.winpe.packages = Vec::new();
+        config.drivers.paths = vec![drivers.display().to_string()];
+        config.drivers.scan_drives = false;
+        config.drivers.install_image.indexes = vec!["Windows 11 Pro".to_string()];
+
+        let args = build_args(&source_iso, temp.path());
+        let backend = crate::wim::backend::RecordingBackend::default();
+        let wim_manager = crate::wim::WimManager::with_backend(&config, Box::new(backend.clone())).unwrap();
+
+        let mut report = BuildReport::new(&config);
+        run_build_steps(&args, &config, wim_manager, None, &mut report).await.unwrap();
+
+        // Every edition is exported, so Pro keeps its index and Home stays on the media
+        let calls = backend.calls();
+        let boot_commit = calls.iter().position(|call| call == "unmount commit").unwrap();
+        assert!(calls[..boot_commit].iter().any(|call| call == "add_driver e1d.inf"));
+        assert_eq!(
+            calls[boot_commit + 1..],
+            [
+                "export install.esd 1 install.wim",
+                "export install.esd 2 install.wim",
+                "mount install.wim 2",
+                "add_driver iastorvd.inf",
+                "unmount commit"
+            ]
+        );
+        let sources = std::path::Path::new(&args.output_dir).join("sources");
+        assert!(!sources.join("install.esd").exists());
+
+        assert_eq!(report.install_images.len(), 1);
+        assert_eq!(report.install_images[0].index, 2);
+        assert_eq!(report.install_images[0].name, "Windows 11 Pro");
+        assert_eq!(report.install_images[0].drivers.len(), 1);
+        assert!(report.dropped_editions.is_empty());
+        assert!(report.steps.iter().any(|step| step.name == STEP_INSTALL_IMAGE));
+
+        // Opting in exports only the selection and records what was left behind
+        config.drivers.install_image.drop_unselected_editions = true;
+        let backend = crate::wim::backend::RecordingBackend::default();
+        let wim_manager = crate::wim::WimManager::with_backend(&config, Box::new(backend.clone())).unwrap();
+        let mut report = BuildReport::new(&config);
+        run_build_steps(&args, &config, wim_manager, None, &mut report).await.unwrap();
+
+        let calls = backend.calls();
+        let boot_commit = calls.iter().position(|call| call == "unmount commit").unwrap();
+        assert_eq!(
+            calls[boot_commit + 1..],
+            ["export install.esd 2 install.wim", "mount install.wim 1", "add_driver iastorvd.inf", "unmount commit"]
+        );
+        assert_eq!(report.install_images[0].index, 1);
+        assert_eq!(
+            report.dropped_editions,
+            [report::DroppedEdition { index: 1, name: "Windows 11 Home".to_string() }]
+        );
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["dropped_editions"][0]["name"], "Windows 11 Home");
+    }
+
+    #[tokio::test]
+    async fn require_signed_refuses_unsigned_staged_drivers() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        crate::iso::test_support::write_test_media(
+            &media,
+            &[("Microsoft Windows Setup (amd64)", "WindowsPE", "9", 22621)],
+        );
+        let source_iso = temp.path().join("source.iso");
+        IsoWriter::new(IsoOptions::windows_media("SOURCE")).write(&media, &source_iso).unwrap();
+
+        let drivers = temp.path().join("drivers");
+        for name in ["signed", "unsigned"] {
+            std::fs::create_dir_all(drivers.join(name)).unwrap();
+        }
+        let models = "[Manufacturer]\nVendor=Models,NTamd64\n[Models.NTamd64]\nNIC=Install,PCI\\VEN_8086&DEV_15F3\n";
+        let signed_inf = format!("[Version]\nClass=Net\nCatalogFile=signed.cat\n{}", models);
+        std::fs::write(drivers.join("signed/signed.inf"), &signed_inf).unwrap();
+        std::fs::write(
+            drivers.join("signed/signed.cat"),
+            crate::drivers::catalog::tests::build_catalog(&[("signed.inf", signed_inf.as_bytes())], true),
+        )
+        .unwrap();
+        std::fs::write(drivers.join("unsigned/unsigned.inf"), format!("[Version]\nClass=Net\n{}", models)).unwrap();
+
+        let mount = temp.path().join("mount");
+        crate::wim::write_test_offline_hives(&mount);
+
+        let mut config = GhostwinConfig::default();
+        config.iso.mount_path = Some(mount.display().to_string());
+        config.iso.wim_index = "1".to_string();
+        config.iso.helper_source = None;
+        config.iso.windows_overlay_source = None;
+        config.tools.auto_detect = false;
+        config.tools.folders = Vec::new();
+        config.cache.enabled = false;
+        config.winpe.packages = Vec::new();
+        config.drivers.paths = vec![drivers.display().to_string()];
+        config.drivers.scan_drives = false;
+        config.drivers.default_policy = crate::cli::ClassPolicy::Stage;
+        config.drivers.require_signed = true;
+
+        let args = build_args(&source_iso, temp.path());
+        let backend = crate::wim::backend::RecordingBackend::default();
+        let wim_manager = crate::wim::WimManager::with_backend(&config, Box::new(backend.clone())).unwrap();
+
+        let mut report = BuildReport::new(&config);
+        run_build_steps(&args, &config, wim_manager, None, &mut report).await.unwrap();
+
+        assert!(backend.calls().iter().all(|call| !call.starts_with("add_driver")));
+        let results: Vec<String> = report
+            .drivers
+            .iter()
+            .map(|injection| format!("{}: {:?}", injection.name, injection.result))
+            .collect();
+        assert!(results.contains(&"signed.inf: Staged".to_string()), "{:?}", results);
+        assert!(results.contains(&"unsigned.inf: Skipped(\"unsigned: missing catalog\")".to_string()), "{:?}", results);
+
+        // `ghostwin load-drivers` only sees what passed the catalog check
+        let repository = mount.join(crate::drivers::repository::REPOSITORY_DIR);
+        let index = crate::drivers::repository::DriverIndex::load(&repository.join(crate::drivers::repository::INDEX_FILE)).unwrap();
+        assert_eq!(index.packages.len(), 1);
+        assert!(index.packages[0].inf.ends_with("/signed.inf"));
+        assert!(walkdir::WalkDir::new(&repository)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .all(|entry| entry.file_name() != "unsigned.inf"));
+    }
+
     #[test]
     fn build_step_labels_stay_stable() {
         assert_eq!(STEP_EXTRACT, "Step 1: Extracting source ISO");
@@ -816,9 +1941,11 @@ mod tests {
         assert_eq!(STEP_PACKAGES, "Step 5: Adding WinPE packages");
         assert_eq!(STEP_DRIVERS, "Step 6: Detecting and injecting drivers");
         assert_eq!(STEP_DPI_FIX, "Step 7: Applying DPI fix");
-        assert_eq!(STEP_UNMOUNT, "Step 8: Unmounting and committing WIM");
-        assert_eq!(STEP_CREATE_ISO, "Step 9: Creating final ISO");
-        assert_eq!(STEP_VERIFY, "Step 10: Verifying ISO integrity");
+        assert_eq!(STEP_REGISTRY, "Step 8: Applying offline registry tweaks");
+        assert_eq!(STEP_UNMOUNT, "Step 9: Unmounting and committing WIM");
+        assert_eq!(STEP_INSTALL_IMAGE, "Step 10: Injecting drivers into install.wim");
+        assert_eq!(STEP_CREATE_ISO, "Step 11: Creating final ISO");
+        assert_eq!(STEP_VERIFY, "Step 12: Verifying ISO integrity");
     }
 
     #[test]
@@ -850,13 +1977,19 @@ mod tests {
     fn total_build_steps_reflects_optional_flags() {
         let temp = tempdir().unwrap();
         let mut args = build_args(&temp.path().join("input.iso"), temp.path());
-        let config = GhostwinConfig::default();
+        let mut config = GhostwinConfig::default();
 
         assert_eq!(total_build_steps(&args, &config), 8);
 
         args.extra_files = Some(temp.path().join("extra").display().to_string());
         args.verify = true;
         assert_eq!(total_build_steps(&args, &config), 10);
+
+        config.winpe.registry_files = vec!["tweaks.reg".to_string()];
+        assert_eq!(total_build_steps(&args, &config), 11);
+
+        config.drivers.install_image.indexes = vec!["1".to_string()];
+        assert_eq!(total_build_steps(&args, &config), 12);
     }
 
     #[test]
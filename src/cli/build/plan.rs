@@ -0,0 +1,546 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use crate::cli::{BuildArgs, ClassPolicy, GhostwinConfig, IsoAuthoring, PlanFormat};
+use crate::iso::cache::IsoCache;
+use crate::drivers::{DriverRiskSummary, InjectionResult};
+use crate::drivers::catalog::{CatalogStatus, PackageVerification};
+use crate::tools::ToolDetector;
+use crate::wim::{self, backend, registry::RegistryEdit};
+use super::{
+    ISO_VOLUME_ID, STEP_CREATE_ISO, STEP_EXTRACT, STEP_INSTALL_IMAGE, STEP_VERIFY, helper_source_path, oscdimg_args,
+    registry_tweak_edits, resolve_oscdimg_path, select_build_drivers, servicing_steps,
+    tool_destination_for_dir, windows_overlay_source_path, winpe_packages,
+};
+use super::report::InventoryReport;
+
+/// Everything `ghostwin build` would do with the current arguments and config
+#[derive(Debug, Serialize)]
+pub(crate) struct BuildPlan {
+    pub source_iso: String,
+    pub output_dir: String,
+    pub output_iso: String,
+    pub config: String,
+    pub resume: bool,
+    /// Extraction cache root when `cache.enabled`
+    pub extraction_cache: Option<PathBuf>,
+    pub steps: Vec<&'static str>,
+    pub wim: WimPlan,
+    pub helper_source: Option<PlannedPath>,
+    pub windows_overlay_source: Option<PlannedPath>,
+    pub tool_dirs: Vec<PlannedToolDir>,
+    pub extra_files: Option<String>,
+    pub packages: Vec<PlannedPackage>,
+    pub drivers: Vec<PlannedDriver>,
+    pub driver_summary: DriverRiskSummary,
+    pub driver_verification: Vec<PackageVerification>,
+    /// Device matches when `--hardware-inventory` narrows the driver set
+    pub hardware_inventory: Option<InventoryReport>,
+    /// Set when `drivers.install_image` selects install images
+    pub install_image: Option<InstallImagePlan>,
+    pub registry_edits: Vec<String>,
+    pub iso: IsoPlan,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct WimPlan {
+    pub path: PathBuf,
+    pub index: String,
+    pub backend: &'static str,
+    /// `None` means a temporary directory is created at build time
+    pub mount_path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct InstallImagePlan {
+    pub indexes: Vec<String>,
+    pub classes: Vec<String>,
+    pub drivers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PlannedPath {
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PlannedToolDir {
+    pub path: PathBuf,
+    pub destination: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PlannedPackage {
+    pub name: String,
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PlannedDriver {
+    pub name: String,
+    pub path: PathBuf,
+    pub driver_type: &'static str,
+    pub inf_file: Option<PathBuf>,
+    pub priority: bool,
+    pub policy: ClassPolicy,
+    /// CAB package the driver was expanded from; `path` then points into a temp directory
+    pub source_cab: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct IsoPlan {
+    pub authoring: IsoAuthoring,
+    pub volume_id: &'static str,
+    /// Full oscdimg command line when `iso.authoring = "oscdimg"`
+    pub command: Option<Vec<String>>,
+}
+
+pub(super) fn execute(args: &BuildArgs, config: &GhostwinConfig, format: PlanFormat) -> Result<()> {
+    let plan = build_plan(args, config)?;
+    match format {
+        PlanFormat::Text => print!("{}", format_plan_text(&plan)),
+        PlanFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&plan).context("Failed to serialize build plan")?
+        ),
+    }
+    Ok(())
+}
+
+/// Resolve the build without mounting, extracting, or writing anything
+pub(crate) fn build_plan(args: &BuildArgs, config: &GhostwinConfig) -> Result<BuildPlan> {
+    let mut warnings = Vec::new();
+
+    if !Path::new(&args.source_iso).exists() {
+        warnings.push(format!("Source ISO not found: {}", args.source_iso));
+    }
+    if let Some(extra_files) = &args.extra_files
+        && !Path::new(extra_files).is_dir()
+    {
+        warnings.push(format!("Extra files directory does not exist: {}", extra_files));
+    }
+
+    let mut steps = vec![STEP_EXTRACT];
+    steps.extend(servicing_steps(args, config));
+    if config.drivers.install_image.enabled() {
+        steps.push(STEP_INSTALL_IMAGE);
+    }
+    steps.push(STEP_CREATE_ISO);
+    if args.verify {
+        steps.push(STEP_VERIFY);
+    }
+
+    let helper_source = helper_source_path(config);
+    let tool_dirs = ToolDetector::new(&config.tools)
+        .scan_tools()?
+        .into_iter()
+        .filter(|tool_dir| {
+            !helper_source
+                .as_deref()
+                .is_some_and(|path| path.exists() && tool_dir.starts_with(path))
+        })
+        .map(|path| PlannedToolDir {
+            destination: tool_destination_for_dir(&path),
+            path,
+        })
+        .collect();
+
+    let packages: Vec<PlannedPackage> = if args.skip_packages {
+        Vec::new()
+    } else {
+        winpe_packages(config)?
+            .into_iter()
+            .map(|name| {
+                let path = wim::winpe_package_path(config, &name);
+                PlannedPackage {
+                    name,
+                    exists: path.exists(),
+                    path,
+                }
+            })
+            .collect()
+    };
+    if !packages.is_empty() && backend::backend_for_config(config).name() != "dism" {
+        warnings.push("WinPE packages need the dism servicing backend; pass --skip-packages to build without them".to_string());
+    }
+    for package in packages.iter().filter(|package| !package.exists) {
+        warnings.push(format!("WinPE package not found: {}", package.path.display()));
+    }
+
+    let (driver_manager, detected_drivers, selection) = select_build_drivers(args, config)?;
+    let hardware_inventory = selection.map(|selection| {
+        for device in &selection.unmatched {
+            warnings.push(format!("No driver matches inventory device {}", device.id));
+        }
+        for cab in &selection.unmatched_cabs {
+            warnings.push(format!("CAB package {} skipped: it could not be expanded to match hardware IDs", cab.display()));
+        }
+        InventoryReport::new(args.hardware_inventory.as_deref().unwrap_or_default(), selection)
+    });
+    let driver_summary = driver_manager.summarize_driver_risks(&detected_drivers);
+    let driver_verification = driver_manager.verify_drivers(&detected_drivers);
+    if config.drivers.require_signed {
+        let (_, refused) = driver_manager.refuse_unsigned(detected_drivers.clone(), &driver_verification);
+        for injection in refused {
+            if let InjectionResult::Skipped(reason) = injection.result {
+                warnings.push(format!("Driver {} will not be injected or staged ({})", injection.name, reason));
+            }
+        }
+    }
+    let install_image = config.drivers.install_image.enabled().then(|| InstallImagePlan {
+        indexes: config.drivers.install_image.indexes.clone(),
+        classes: config.drivers.install_image.classes.clone(),
+        drivers: crate::drivers::install_image_drivers(&detected_drivers, &config.drivers.install_image.classes)
+            .into_iter()
+            .map(|driver| driver.name)
+            .collect(),
+    });
+    if config.drivers.install_image.enabled() && backend::backend_for_config(config).name() != "dism" {
+        warnings.push("drivers.install_image needs the dism servicing backend".to_string());
+    }
+    let drivers = detected_drivers
+        .into_iter()
+        .map(|driver| PlannedDriver {
+            priority: driver_manager.is_priority_driver(&driver.name),
+            policy: driver_manager.policy_for(&driver),
+            driver_type: driver.driver_type.as_str(),
+            name: driver.name,
+            path: driver.path,
+            inf_file: driver.inf_file,
+            source_cab: driver.source_cab,
+        })
+        .collect();
+
+    let mut edits = Vec::new();
+    if !args.skip_dpi_fix && config.winpe.disable_dpi_scaling {
+        edits.extend(wim::dpi_scaling_edits());
+    }
+    edits.extend(registry_tweak_edits(config)?);
+    let registry_edits = edits.iter().map(describe_edit).collect();
+
+    let command = match config.iso.authoring {
+        IsoAuthoring::Native => None,
+        IsoAuthoring::Oscdimg => {
+            let program = resolve_oscdimg_path(config).unwrap_or_else(|| {
+                warnings.push("oscdimg.exe not found in Windows ADK deployment tools".to_string());
+                PathBuf::from("oscdimg.exe")
+            });
+            let mut command = vec![program.display().to_string()];
+            command.extend(oscdimg_args(&args.output_dir, &args.output_iso));
+            Some(command)
+        }
+    };
+
+    Ok(BuildPlan {
+        source_iso: args.source_iso.clone(),
+        output_dir: args.output_dir.clone(),
+        output_iso: args.output_iso.clone(),
+        config: config_source(args),
+        resume: args.resume,
+        extraction_cache: config
+            .cache
+            .enabled
+            .then(|| IsoCache::from_config(&config.cache).root().to_path_buf()),
+        steps,
+        wim: WimPlan {
+            path: Path::new(&args.output_dir).join("sources").join("boot.wim"),
+            index: config.iso.wim_index.clone(),
+            backend: backend::backend_for_config(config).name(),
+            mount_path: config.iso.mount_path.clone(),
+        },
+        helper_source: helper_source.map(planned_path),
+        windows_overlay_source: windows_overlay_source_path(config).map(planned_path),
+        tool_dirs,
+        extra_files: args.extra_files.clone(),
+        packages,
+        drivers,
+        driver_summary,
+        driver_verification,
+        hardware_inventory,
+        install_image,
+        registry_edits,
+        iso: IsoPlan {
+            authoring: config.iso.authoring,
+            volume_id: ISO_VOLUME_ID,
+            command,
+        },
+        warnings,
+    })
+}
+
+/// Mirrors `ConfigManager::load_default` lookup order
+fn config_source(args: &BuildArgs) -> String {
+    if let Some(config) = &args.config {
+        return config.clone();
+    }
+
+    ["ghostwin.toml", "ghostwin.json"]
+        .into_iter()
+        .find(|candidate| Path::new(candidate).exists())
+        .unwrap_or("built-in defaults")
+        .to_string()
+}
+
+fn planned_path(path: PathBuf) -> PlannedPath {
+    PlannedPath {
+        exists: path.is_dir(),
+        path,
+    }
+}
+
+fn describe_edit(edit: &RegistryEdit) -> String {
+    match edit {
+        RegistryEdit::SetValue { value, .. } => format!("{} = {}", edit, value),
+        _ => edit.to_string(),
+    }
+}
+
+pub(crate) fn format_plan_text(plan: &BuildPlan) -> String {
+    let mut output = String::from("📋 GhostWin build plan (nothing will be modified)\n\n");
+    let _ = writeln!(output, "Source ISO:  {}", plan.source_iso);
+    let _ = writeln!(output, "Output dir:  {}", plan.output_dir);
+    let _ = writeln!(output, "Output ISO:  {}", plan.output_iso);
+    let _ = writeln!(output, "Config:      {}", plan.config);
+    if plan.resume {
+        let _ = writeln!(output, "Resume:      completed checkpoints will be skipped");
+    }
+    if let Some(cache) = &plan.extraction_cache {
+        let _ = writeln!(output, "ISO cache:   {}", cache.display());
+    }
+
+    let _ = writeln!(output, "\nSteps ({}):", plan.steps.len());
+    for step in &plan.steps {
+        let _ = writeln!(output, "  {}", step);
+    }
+
+    let _ = writeln!(
+        output,
+        "\nWIM: {} index \"{}\" via {}, mounted at {}",
+        plan.wim.path.display(),
+        plan.wim.index,
+        plan.wim.backend,
+        plan.wim.mount_path.as_deref().unwrap_or("<temporary directory>")
+    );
+    for (label, source) in [
+        ("Helper source", &plan.helper_source),
+        ("Windows overlay", &plan.windows_overlay_source),
+    ] {
+        if let Some(source) = source {
+            let _ = writeln!(
+                output,
+                "{}: {}{}",
+                label,
+                source.path.display(),
+                if source.exists { "" } else { " (missing, skipped)" }
+            );
+        }
+    }
+    if let Some(extra_files) = &plan.extra_files {
+        let _ = writeln!(output, "Extra files: {}", extra_files);
+    }
+
+    let _ = writeln!(output, "\nTool directories ({}):", plan.tool_dirs.len());
+    for tool_dir in &plan.tool_dirs {
+        let _ = writeln!(output, "  {} -> {}", tool_dir.path.display(), tool_dir.destination);
+    }
+
+    let _ = writeln!(output, "\nWinPE packages ({}):", plan.packages.len());
+    for package in &plan.packages {
+        let _ = writeln!(
+            output,
+            "  {}  {}{}",
+            package.name,
+            package.path.display(),
+            if package.exists { "" } else { " (missing)" }
+        );
+    }
+
+    let summary = &plan.driver_summary;
+    let _ = writeln!(
+        output,
+        "\nDrivers: {} total, {} priority storage, {} CAB packages expanded, {} unreadable CAB packages, {} SYS without INF, {} duplicates skipped",
+        summary.total,
+        summary.priority,
+        summary.cabs_expanded,
+        summary.cab_packages,
+        summary.missing_inf_for_sys,
+        summary.duplicates_skipped
+    );
+    for driver in &plan.drivers {
+        let _ = writeln!(
+            output,
+            "  {}{} [{}, {}]  {}",
+            if driver.priority { "🔥 " } else { "" },
+            driver.name,
+            driver.driver_type,
+            driver.policy,
+            match &driver.source_cab {
+                Some(cab) => format!("from {}", cab.display()),
+                None => driver.path.display().to_string(),
+            }
+        );
+    }
+    let consistent = plan
+        .driver_verification
+        .iter()
+        .filter(|verification| verification.status == CatalogStatus::CatalogConsistent)
+        .count();
+    let _ = writeln!(
+        output,
+        "\nDriver catalogs: {} of {} packages catalog-consistent",
+        consistent,
+        plan.driver_verification.len()
+    );
+    for verification in plan
+        .driver_verification
+        .iter()
+        .filter(|verification| verification.status != CatalogStatus::CatalogConsistent)
+    {
+        let _ = writeln!(
+            output,
+            "  {} [{}]  {}",
+            verification.inf_file.display(),
+            match verification.status {
+                CatalogStatus::MissingCatalog => "missing catalog",
+                _ => "hash mismatch",
+            },
+            verification.problems.join("; ")
+        );
+    }
+
+    if let Some(inventory) = &plan.hardware_inventory {
+        let _ = writeln!(
+            output,
+            "\nHardware inventory {}: {} matched, {} without a driver",
+            inventory.path.display(),
+            inventory.matched.len(),
+            inventory.unmatched.len()
+        );
+        for device_match in &inventory.matched {
+            let _ = writeln!(
+                output,
+                "  {} -> {} ({})",
+                device_match.device.id,
+                device_match.inf_file.display(),
+                device_match.matched_id
+            );
+        }
+        for device in &inventory.unmatched {
+            let _ = writeln!(output, "  {} -> (none)", device.id);
+        }
+    }
+
+    if let Some(install_image) = &plan.install_image {
+        let _ = writeln!(
+            output,
+            "\nInstall image: indexes {} receive {} driver(s) of class {}",
+            install_image.indexes.join(", "),
+            install_image.drivers.len(),
+            install_image.classes.join(", ")
+        );
+        for driver in &install_image.drivers {
+            let _ = writeln!(output, "  {}", driver);
+        }
+    }
+
+    let _ = writeln!(output, "\nRegistry edits ({}):", plan.registry_edits.len());
+    for edit in &plan.registry_edits {
+        let _ = writeln!(output, "  {}", edit);
+    }
+
+    match &plan.iso.command {
+        Some(command) => {
+            let _ = writeln!(output, "\nISO authoring: oscdimg\n  {}", command.join(" "));
+        }
+        None => {
+            let _ = writeln!(output, "\nISO authoring: native (volume {})", plan.iso.volume_id);
+        }
+    }
+
+    if !plan.warnings.is_empty() {
+        let _ = writeln!(output, "\nWarnings:");
+        for warning in &plan.warnings {
+            let _ = writeln!(output, "  ⚠️ {}", warning);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_plan, format_plan_text};
+    use crate::cli::{BuildArgs, GhostwinConfig, IsoAuthoring};
+    use tempfile::tempdir;
+
+    #[test]
+    fn plans_build_without_touching_disk() {
+        let temp = tempdir().unwrap();
+        let adk = temp.path().join("adk");
+        let reg_file = temp.path().join("tweaks.reg");
+        std::fs::write(
+            &reg_file,
+            "Windows Registry Editor Version 5.00\n\n[HKEY_LOCAL_MACHINE\\SOFTWARE\\Ghostwin]\n\"Imported\"=dword:00000002\n",
+        )
+        .unwrap();
+
+        let mut config = GhostwinConfig::default();
+        config.iso.adk_path = Some(adk.display().to_string());
+        config.iso.authoring = IsoAuthoring::Oscdimg;
+        config.iso.helper_source = None;
+        config.iso.windows_overlay_source = None;
+        config.tools.auto_detect = false;
+        config.tools.folders = Vec::new();
+        config.winpe.packages = vec!["WinPE-WMI".to_string()];
+        config.winpe.disable_dpi_scaling = true;
+        config.winpe.registry_files = vec![reg_file.display().to_string()];
+
+        let output_dir = temp.path().join("build");
+        let args = BuildArgs {
+            source_iso: temp.path().join("missing.iso").display().to_string(),
+            output_dir: output_dir.display().to_string(),
+            output_iso: temp.path().join("ghostwin.iso").display().to_string(),
+            extra_files: None,
+            skip_packages: false,
+            skip_dpi_fix: false,
+            config: None,
+            verify: true,
+            resume: false,
+            keep_on_failure: false,
+            plan: None,
+            hardware_inventory: None,
+        };
+
+        let plan = build_plan(&args, &config).unwrap();
+        assert!(!output_dir.exists());
+        assert_eq!(plan.steps.first(), Some(&super::STEP_EXTRACT));
+        assert_eq!(plan.steps.last(), Some(&super::STEP_VERIFY));
+        assert!(!plan.steps.contains(&super::STEP_INSTALL_IMAGE));
+        assert!(plan.install_image.is_none());
+        assert!(plan.packages[0].path.starts_with(&adk));
+        assert_eq!(plan.packages[0].path.file_name().unwrap(), "WinPE-WMI.cab");
+        assert!(!plan.packages[0].exists);
+        assert_eq!(
+            plan.registry_edits.last().unwrap(),
+            r"set SOFTWARE\Ghostwin [Imported] = REG_DWORD 0x00000002"
+        );
+        assert!(plan.registry_edits.len() > 1);
+
+        let command = plan.iso.command.as_ref().unwrap();
+        assert!(command[0].ends_with("oscdimg.exe"));
+        assert!(command.iter().any(|arg| arg.starts_with("-bootdata:2#")));
+        assert_eq!(command.last().unwrap(), &args.output_iso);
+        assert!(plan.warnings.iter().any(|warning| warning.contains("Source ISO not found")));
+
+        let text = format_plan_text(&plan);
+        assert!(text.contains("WinPE-WMI"));
+        assert!(text.contains("ISO authoring: oscdimg"));
+        let json: serde_json::Value = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["iso"]["authoring"], "oscdimg");
+        assert_eq!(json["packages"][0]["name"], "WinPE-WMI");
+    }
+}
@@ -0,0 +1,293 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+use crate::cli::{BuildArgs, GhostwinConfig};
+use crate::drivers::DriverInjection;
+use crate::drivers::catalog::PackageVerification;
+use crate::drivers::inventory::{DeviceMatch, DriverSelection, InventoryDevice};
+use crate::utils;
+
+/// Written next to the output ISO after every build attempt
+pub(crate) const BUILD_REPORT_FILE: &str = "build-report.json";
+
+/// Machine-readable record of one `ghostwin build` run
+#[derive(Debug, Serialize)]
+pub(crate) struct BuildReport {
+    pub ghostwin_version: &'static str,
+    /// Unix seconds
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub config: GhostwinConfig,
+    pub source_iso: Option<FileDigest>,
+    pub steps: Vec<StepReport>,
+    pub packages: Vec<PackageReport>,
+    pub copied_dirs: Vec<CopiedDir>,
+    pub drivers: Vec<DriverInjection>,
+    /// Catalog check for every detected INF package
+    pub driver_verification: Vec<PackageVerification>,
+    /// Present when the build ran with `--hardware-inventory`
+    pub hardware_inventory: Option<InventoryReport>,
+    /// Images of `sources/install.wim` serviced for `drivers.install_image`
+    pub install_images: Vec<InstallImageReport>,
+    /// install.esd images left out of the exported install.wim (`drop_unselected_editions`)
+    pub dropped_editions: Vec<DroppedEdition>,
+    pub output_iso: Option<FileDigest>,
+    #[serde(skip)]
+    current_step: Option<(&'static str, Instant)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct FileDigest {
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl FileDigest {
+    /// Digest for a file whose SHA-256 is already known
+    pub fn with_sha256(path: PathBuf, sha256: String) -> Result<Self> {
+        let size = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        Ok(Self { path, size, sha256 })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct StepReport {
+    pub name: &'static str,
+    pub outcome: StepOutcome,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StepOutcome {
+    Completed,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct PackageReport {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct InventoryReport {
+    pub path: PathBuf,
+    pub matched: Vec<DeviceMatch>,
+    /// Devices no detected driver claims
+    pub unmatched: Vec<InventoryDevice>,
+}
+
+impl InventoryReport {
+    pub fn new(path: &str, selection: DriverSelection) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            matched: selection.matches,
+            unmatched: selection.unmatched,
+        }
+    }
+}
+
+/// Driver injection into one image of the install image
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct InstallImageReport {
+    /// Index in the serviced `install.wim` (renumbered when exported from install.esd)
+    pub index: u32,
+    pub name: String,
+    pub drivers: Vec<DriverInjection>,
+}
+
+/// An install.esd image that is not on the output media
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct DroppedEdition {
+    /// Index in the source install.esd
+    pub index: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct CopiedDir {
+    pub source: PathBuf,
+    /// Destination relative to the mounted image root
+    pub destination: String,
+}
+
+impl BuildReport {
+    pub fn new(config: &GhostwinConfig) -> Self {
+        Self {
+            ghostwin_version: env!("CARGO_PKG_VERSION"),
+            started_at: unix_now(),
+            finished_at: 0,
+            success: false,
+            error: None,
+            config: redacted(config),
+            source_iso: None,
+            steps: Vec::new(),
+            packages: Vec::new(),
+            copied_dirs: Vec::new(),
+            drivers: Vec::new(),
+            driver_verification: Vec::new(),
+            hardware_inventory: None,
+            install_images: Vec::new(),
+            dropped_editions: Vec::new(),
+            output_iso: None,
+            current_step: None,
+        }
+    }
+
+    /// Start timing a step; the previous one (if any) is recorded as completed
+    pub fn begin_step(&mut self, name: &'static str) {
+        self.close_step(StepOutcome::Completed);
+        self.current_step = Some((name, Instant::now()));
+    }
+
+    pub fn skip_step(&mut self, name: &'static str) {
+        self.close_step(StepOutcome::Completed);
+        self.steps.push(StepReport {
+            name,
+            outcome: StepOutcome::Skipped,
+            duration_ms: 0,
+        });
+    }
+
+    /// Close the running step with the build's overall outcome
+    pub fn end_steps(&mut self, success: bool) {
+        self.close_step(if success { StepOutcome::Completed } else { StepOutcome::Failed });
+    }
+
+    fn close_step(&mut self, outcome: StepOutcome) {
+        if let Some((name, started)) = self.current_step.take() {
+            self.steps.push(StepReport {
+                name,
+                outcome,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+    }
+
+    pub fn record_package(&mut self, name: &str, path: PathBuf) {
+        self.packages.push(PackageReport {
+            name: name.to_string(),
+            path,
+        });
+    }
+
+    pub fn record_copied_dir(&mut self, source: &Path, destination: &str) {
+        self.copied_dirs.push(CopiedDir {
+            source: source.to_path_buf(),
+            destination: destination.to_string(),
+        });
+    }
+
+    /// Stamp the outcome and hash the source and output ISOs
+    pub async fn finalize(&mut self, args: &BuildArgs, result: &Result<()>) {
+        self.end_steps(result.is_ok());
+        self.success = result.is_ok();
+        self.error = result.as_ref().err().map(|error| format!("{:#}", error));
+
+        info!("Hashing ISOs for the build report");
+        // Normally set by the build from its single hash of the source ISO
+        if self.source_iso.is_none() {
+            self.source_iso = digest_or_warn(PathBuf::from(&args.source_iso)).await;
+        }
+        if self.success {
+            self.output_iso = digest_or_warn(PathBuf::from(&args.output_iso)).await;
+        }
+        self.finished_at = unix_now();
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize build report")?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write build report {}", path.display()))?;
+        info!("📝 Build report written to {}", path.display());
+        Ok(())
+    }
+}
+
+/// `build-report.json` in the output ISO's directory
+pub(crate) fn report_path(output_iso: &str) -> PathBuf {
+    Path::new(output_iso)
+        .parent()
+        .map(|parent| parent.join(BUILD_REPORT_FILE))
+        .unwrap_or_else(|| PathBuf::from(BUILD_REPORT_FILE))
+}
+
+async fn digest_or_warn(path: PathBuf) -> Option<FileDigest> {
+    if !path.is_file() {
+        return None;
+    }
+
+    let label = path.display().to_string();
+    let digest = tokio::task::spawn_blocking(move || -> Result<FileDigest> {
+        Ok(FileDigest {
+            size: std::fs::metadata(&path)?.len(),
+            sha256: utils::sha256_file(&path)?,
+            path,
+        })
+    })
+    .await;
+
+    match digest {
+        Ok(Ok(digest)) => Some(digest),
+        Ok(Err(error)) => {
+            warn!("Could not hash {} for the build report: {:#}", label, error);
+            None
+        }
+        Err(error) => {
+            warn!("Hashing task for {} panicked: {}", label, error);
+            None
+        }
+    }
+}
+
+/// Config snapshot with `[security]` secrets replaced, since the report travels with the ISO
+fn redacted(config: &GhostwinConfig) -> GhostwinConfig {
+    let mut config = config.clone();
+    let security = &mut config.security;
+    for secret in [&mut security.password_hash, &mut security.access_secret, &mut security.vnc_password] {
+        if secret.is_some() {
+            *secret = Some("<redacted>".to_string());
+        }
+    }
+    config
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BuildReport;
+    use crate::cli::GhostwinConfig;
+
+    #[test]
+    fn security_secrets_are_redacted() {
+        let mut config = GhostwinConfig::default();
+        config.security.password_hash = Some("hash-value".to_string());
+        config.security.access_secret = Some("access-value".to_string());
+        config.security.vnc_password = Some("vnc-value".to_string());
+
+        let json = serde_json::to_value(BuildReport::new(&config)).unwrap();
+        let security = &json["config"]["security"];
+        for field in ["password_hash", "access_secret", "vnc_password"] {
+            assert_eq!(security[field], "<redacted>", "{}", field);
+        }
+        assert!(!json.to_string().contains("-value"));
+
+        let unset = serde_json::to_value(BuildReport::new(&GhostwinConfig::default())).unwrap();
+        assert!(unset["config"]["security"]["vnc_password"].is_null());
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+use crate::cli::{CacheArgs, CacheCommand};
+use crate::config::ConfigManager;
+use crate::iso::cache::{CacheEntry, IsoCache, short_hash};
+use crate::utils;
+
+pub async fn execute(args: CacheArgs) -> Result<()> {
+    let config = ConfigManager::load_config(args.config).await?;
+    let cache = IsoCache::from_config(&config.cache);
+
+    match args.command {
+        CacheCommand::List => {
+            let entries = cache.entries()?;
+            print!("{}", format_cache_list(&cache, &entries, unix_now()));
+        }
+        CacheCommand::Prune { max_size_gb } => {
+            let max_bytes = max_size_gb
+                .map(|size| size.saturating_mul(1024 * 1024 * 1024))
+                .unwrap_or(cache.max_bytes());
+            info!("🧹 Pruning ISO cache at {} to {}", cache.root().display(), utils::format_file_size(max_bytes));
+            let removed = cache.prune(max_bytes, None)?;
+            let freed: u64 = removed.iter().map(|entry| entry.size_bytes).sum();
+            println!(
+                "🧹 Removed {} cached extraction(s), freed {}",
+                removed.len(),
+                utils::format_file_size(freed)
+            );
+        }
+        CacheCommand::Clear => {
+            let removed = cache.clear()?;
+            println!("🧹 Removed {} cached extraction(s) from {}", removed, cache.root().display());
+        }
+    }
+
+    Ok(())
+}
+
+fn format_cache_list(cache: &IsoCache, entries: &[CacheEntry], now: u64) -> String {
+    let total: u64 = entries.iter().map(|entry| entry.size_bytes).sum();
+    let mut output = format!(
+        "🗄️ ISO cache at {}: {} entr{}, {} of {} cap\n",
+        cache.root().display(),
+        entries.len(),
+        if entries.len() == 1 { "y" } else { "ies" },
+        utils::format_file_size(total),
+        utils::format_file_size(cache.max_bytes()),
+    );
+
+    for entry in entries {
+        output.push_str(&format!(
+            "\n  {}  {:>9}  {} files  used {}\n      {}\n",
+            short_hash(&entry.sha256),
+            utils::format_file_size(entry.size_bytes),
+            entry.files,
+            format_age(now.saturating_sub(entry.last_used)),
+            entry.source_path.display(),
+        ));
+    }
+
+    output
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_cache_list;
+    use crate::iso::cache::{CacheEntry, IsoCache};
+    use std::path::PathBuf;
+
+    #[test]
+    fn lists_entries_with_size_and_age() {
+        let cache = IsoCache::new("/var/cache/ghostwin", 20 * 1024 * 1024 * 1024);
+        let entry = CacheEntry {
+            sha256: "0123456789abcdef0123".to_string(),
+            source_path: PathBuf::from("/isos/Win11_24H2.iso"),
+            source_size: 6 * 1024 * 1024 * 1024,
+            source_modified: 0,
+            files: 1042,
+            size_bytes: 6 * 1024 * 1024 * 1024,
+            created_at: 1_000,
+            last_used: 1_000,
+            dir: PathBuf::from("/var/cache/ghostwin/0123456789abcdef0123"),
+        };
+
+        let output = format_cache_list(&cache, &[entry], 1_000 + 7_200);
+        assert!(output.contains("1 entry, 6.0 GB of 20.0 GB cap"));
+        assert!(output.contains("0123456789ab"));
+        assert!(output.contains("1042 files  used 2h ago"));
+        assert!(output.contains("/isos/Win11_24H2.iso"));
+    }
+}
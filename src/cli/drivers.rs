@@ -0,0 +1,507 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::info;
+use crate::cli::{
+    ClassPolicy, DriverCheckConfig, DriverSourceArgs, DriversArgs, DriversCommand, DriversConfig, DriversImportArgs,
+    OutputFormat,
+};
+use crate::config::ConfigManager;
+use crate::drivers::catalog::{self, CatalogStatus, PackageVerification};
+use crate::drivers::dedupe::SkippedDuplicate;
+use crate::drivers::inf::{Architecture, InfFile};
+use crate::drivers::packs::{DriverPack, PackCatalog, PackImport, import_pack};
+use crate::drivers::{DetectedDriver, DriverManager, DriverRiskSummary};
+
+/// One detected driver as listed by `drivers scan`
+#[derive(Debug, Serialize)]
+struct ScannedDriver {
+    name: String,
+    driver_type: &'static str,
+    path: PathBuf,
+    inf_file: Option<PathBuf>,
+    source_cab: Option<PathBuf>,
+    class: Option<String>,
+    provider: Option<String>,
+    driver_ver: Option<String>,
+    priority: bool,
+    policy: ClassPolicy,
+}
+
+#[derive(Debug, Serialize)]
+struct ScanReport {
+    drivers: Vec<ScannedDriver>,
+    duplicates_skipped: Vec<SkippedDuplicate>,
+}
+
+#[derive(Debug, Serialize)]
+struct InspectReport {
+    inf: InfFile,
+    /// Architecture the catalog was verified for
+    architecture: Architecture,
+    verification: PackageVerification,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckReport {
+    summary: DriverRiskSummary,
+    /// INF packages without a consistent catalog
+    unsigned: usize,
+    violations: Vec<String>,
+}
+
+pub async fn execute(args: DriversArgs) -> Result<()> {
+    let config = ConfigManager::load_config(args.config).await?;
+
+    match args.command {
+        DriversCommand::Scan { source, format } => {
+            let (manager, drivers) = detect(&config.drivers, &source)?;
+            let report = scan_report(&manager, drivers);
+            match format {
+                OutputFormat::Table => print!("{}", format_scan_table(&report)),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            }
+        }
+        DriversCommand::Inspect { inf, arch, format } => {
+            let architecture = match arch {
+                Some(arch) => arch.parse::<Architecture>()?,
+                None => DriverManager::from_config(&config.drivers)?.target_architecture(),
+            };
+            let inf = InfFile::open(Path::new(&inf))?;
+            let verification = catalog::verify_package(&inf, architecture);
+            let report = InspectReport { inf, architecture, verification };
+            match format {
+                OutputFormat::Table => print!("{}", format_inspect_table(&report)),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            }
+        }
+        DriversCommand::Check { source, format } => {
+            let (manager, drivers) = detect(&config.drivers, &source)?;
+            let summary = manager.summarize_driver_risks(&drivers);
+            let unsigned = manager
+                .verify_drivers(&drivers)
+                .iter()
+                .filter(|verification| verification.status != CatalogStatus::CatalogConsistent)
+                .count();
+            let violations = threshold_violations(&summary, unsigned, &config.drivers.check);
+            let report = CheckReport { summary, unsigned, violations };
+            match format {
+                OutputFormat::Table => print!("{}", format_check_table(&report)),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            }
+            if !report.violations.is_empty() {
+                bail!("Driver check failed: {} threshold(s) exceeded", report.violations.len());
+            }
+        }
+        DriversCommand::Import(import) => {
+            let output = import
+                .output
+                .clone()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| default_output(&config.drivers.paths));
+            import_packs(&import, &output)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn import_packs(args: &DriversImportArgs, output: &Path) -> Result<()> {
+    let catalog = PackCatalog::load(Path::new(&args.catalog))?;
+    info!("📚 Loaded {} {} driver pack(s) from {}", catalog.packs.len(), catalog.vendor, catalog.path.display());
+
+    let selected = catalog.select(&args.model, args.os.as_deref());
+    if selected.is_empty() {
+        bail!(
+            "No {} driver pack in {} matches model {}{}",
+            catalog.vendor,
+            catalog.path.display(),
+            args.model.join(", "),
+            args.os.as_ref().map(|os| format!(" and OS {}", os)).unwrap_or_default()
+        );
+    }
+
+    let downloads = Path::new(&args.downloads);
+    let mut results = Vec::new();
+    for pack in selected {
+        let result = import_pack(pack, downloads, output, args.dry_run)?;
+        results.push((pack, result));
+    }
+    print!("{}", format_import_report(&results, output, args.dry_run));
+
+    let problems = results.iter().filter(|(_, result)| result.is_problem()).count();
+    if problems > 0 && !args.dry_run {
+        bail!("{} driver pack(s) could not be imported", problems);
+    }
+    Ok(())
+}
+
+/// Detect drivers the way a build does, optionally in `--path` directories instead of `drivers.paths`
+fn detect(config: &DriversConfig, source: &DriverSourceArgs) -> Result<(DriverManager, Vec<DetectedDriver>)> {
+    let mut config = config.clone();
+    if !source.paths.is_empty() {
+        if let Some(missing) = source.paths.iter().find(|path| !Path::new(path).is_dir()) {
+            bail!("Driver directory {} does not exist", missing);
+        }
+        config.paths = source.paths.clone();
+        config.scan_drives = false;
+    }
+
+    let mut manager = DriverManager::from_config(&config)?;
+    manager.scan_driver_directories()?;
+    let drivers = manager.detect_drivers()?;
+    Ok((manager, drivers))
+}
+
+fn scan_report(manager: &DriverManager, drivers: Vec<DetectedDriver>) -> ScanReport {
+    let duplicates_skipped = manager.summarize_driver_risks(&drivers).duplicates;
+    let drivers = drivers
+        .into_iter()
+        .map(|driver| {
+            let metadata = driver.metadata.as_ref();
+            ScannedDriver {
+                driver_type: driver.driver_type.as_str(),
+                class: metadata.and_then(|inf| inf.class.clone()),
+                provider: metadata.and_then(|inf| inf.provider.clone()),
+                driver_ver: metadata.and_then(|inf| inf.driver_ver).map(|ver| ver.to_string()),
+                priority: manager.is_priority_driver(&driver.name),
+                policy: manager.policy_for(&driver),
+                name: driver.name,
+                path: driver.path,
+                inf_file: driver.inf_file,
+                source_cab: driver.source_cab,
+            }
+        })
+        .collect();
+
+    ScanReport { drivers, duplicates_skipped }
+}
+
+fn threshold_violations(summary: &DriverRiskSummary, unsigned: usize, limits: &DriverCheckConfig) -> Vec<String> {
+    let maxima = [
+        ("max_sys_without_inf", ".sys file(s) without an INF", summary.missing_inf_for_sys, limits.max_sys_without_inf),
+        ("max_unreadable_cabs", "CAB package(s) could not be expanded", summary.cab_packages, limits.max_unreadable_cabs),
+        ("max_duplicates", "duplicate package(s) skipped", summary.duplicates_skipped, limits.max_duplicates),
+        ("max_unsigned", "package(s) without a matching catalog", unsigned, limits.max_unsigned),
+    ];
+
+    let mut violations = Vec::new();
+    for (key, label, count, limit) in maxima {
+        if let Some(limit) = limit
+            && count > limit
+        {
+            violations.push(format!("{} {} (drivers.check.{} = {})", count, label, key, limit));
+        }
+    }
+    if let Some(minimum) = limits.min_priority
+        && summary.priority < minimum
+    {
+        violations.push(format!(
+            "{} priority storage driver(s) found (drivers.check.min_priority = {})",
+            summary.priority, minimum
+        ));
+    }
+    violations
+}
+
+fn format_scan_table(report: &ScanReport) -> String {
+    let mut output = format!(
+        "{:<4} {:<3} {:<7} {:<16} {:<22} {}\n",
+        "TYPE", "PRI", "POLICY", "CLASS", "DRIVERVER", "PATH"
+    );
+    for driver in &report.drivers {
+        let source = driver
+            .source_cab
+            .as_ref()
+            .map(|cab| format!(" (from {})", cab.display()))
+            .unwrap_or_default();
+        output.push_str(&format!(
+            "{:<4} {:<3} {:<7} {:<16} {:<22} {}{}\n",
+            driver.driver_type,
+            if driver.priority { "*" } else { "" },
+            driver.policy.to_string(),
+            driver.class.as_deref().unwrap_or("-"),
+            driver.driver_ver.as_deref().unwrap_or("-"),
+            driver.path.display(),
+            source,
+        ));
+    }
+
+    for duplicate in &report.duplicates_skipped {
+        output.push_str(&format!("dup  {}: {}\n", duplicate.path.display(), duplicate.reason));
+    }
+    output.push_str(&format!(
+        "\n{} driver(s), {} priority, {} duplicate(s) skipped\n",
+        report.drivers.len(),
+        report.drivers.iter().filter(|driver| driver.priority).count(),
+        report.duplicates_skipped.len()
+    ));
+    output
+}
+
+fn format_inspect_table(report: &InspectReport) -> String {
+    let inf = &report.inf;
+    let field = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let mut output = format!("📄 {}\n", inf.path.display());
+    output.push_str(&format!("  Class:        {} {}\n", field(&inf.class), inf.class_guid.as_deref().unwrap_or("")));
+    output.push_str(&format!("  Provider:     {}\n", field(&inf.provider)));
+    output.push_str(&format!("  DriverVer:    {}\n", field(&inf.driver_ver.map(|ver| ver.to_string()))));
+    output.push_str(&format!("  CatalogFile:  {}\n", field(&inf.catalog_file)));
+    for (architecture, catalog) in &inf.arch_catalog_files {
+        output.push_str(&format!("  CatalogFile.NT{}: {}\n", architecture, catalog));
+    }
+    let architectures: Vec<String> = inf
+        .architectures()
+        .iter()
+        .map(|architecture| architecture.map_or("x86 (undecorated)".to_string(), |architecture| architecture.to_string()))
+        .collect();
+    output.push_str(&format!("  Architectures: {}\n", architectures.join(", ")));
+
+    let verification = &report.verification;
+    output.push_str(&format!(
+        "  Catalog ({}): {:?}, {} file(s) checked\n",
+        report.architecture,
+        verification.status,
+        verification.files_checked
+    ));
+    for problem in &verification.problems {
+        output.push_str(&format!("    ⚠️ {}\n", problem));
+    }
+
+    output.push_str(&format!("\n  Manufacturers ({}):\n", inf.manufacturers.len()));
+    for manufacturer in &inf.manufacturers {
+        output.push_str(&format!(
+            "    {} -> {} [{}]\n",
+            manufacturer.name,
+            manufacturer.models_section,
+            manufacturer.decorations.join(", ")
+        ));
+    }
+
+    output.push_str(&format!("\n  Models ({}):\n", inf.models.len()));
+    for model in &inf.models {
+        output.push_str(&format!(
+            "    [{}] {} ({})\n",
+            model.architecture.map_or("x86".to_string(), |architecture| architecture.to_string()),
+            model.description,
+            model.install_section
+        ));
+        for hardware_id in &model.hardware_ids {
+            output.push_str(&format!("        {}\n", hardware_id));
+        }
+    }
+
+    if !inf.copy_files.is_empty() {
+        output.push_str(&format!("\n  CopyFiles ({}): {}\n", inf.copy_files.len(), inf.copy_files.join(", ")));
+    }
+    output
+}
+
+fn format_check_table(report: &CheckReport) -> String {
+    let summary = &report.summary;
+    let mut output = format!(
+        "🔍 {} driver(s): {} priority, {} CAB package(s) expanded, {} unreadable CAB(s), {} .sys without INF, {} duplicate(s) skipped, {} unsigned package(s)\n",
+        summary.total,
+        summary.priority,
+        summary.cabs_expanded,
+        summary.cab_packages,
+        summary.missing_inf_for_sys,
+        summary.duplicates_skipped,
+        report.unsigned,
+    );
+    if report.violations.is_empty() {
+        output.push_str("✅ All [drivers.check] thresholds passed\n");
+    }
+    for violation in &report.violations {
+        output.push_str(&format!("❌ {}\n", violation));
+    }
+    output
+}
+
+/// First configured driver folder that exists, otherwise the first configured one
+fn default_output(paths: &[String]) -> PathBuf {
+    paths
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_dir())
+        .or_else(|| paths.first().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("drivers"))
+}
+
+fn format_import_report(results: &[(&DriverPack, PackImport)], output: &Path, dry_run: bool) -> String {
+    let mut report = format!(
+        "📦 {} driver pack(s) selected for {}{}\n",
+        results.len(),
+        output.display(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+
+    for (pack, result) in results {
+        let version = pack.version.as_deref().map(|version| format!(" {}", version)).unwrap_or_default();
+        report.push_str(&format!("\n  {}{}  [{}]\n", pack.name, version, pack.file_name));
+        let line = match result {
+            PackImport::Imported { destination } => format!("✅ imported to {}", destination.display()),
+            PackImport::AlreadyPresent { destination } => format!("✅ already present at {}", destination.display()),
+            PackImport::Planned { destination } => format!("🔎 verified, would copy to {}", destination.display()),
+            PackImport::NotDownloaded { expected, url } => match url {
+                Some(url) => format!("⬇️ not downloaded: fetch {} into {}", url, expected.display()),
+                None => format!("⬇️ not downloaded: expected {}", expected.display()),
+            },
+            PackImport::HashMismatch { archive, algorithm, expected, actual } => format!(
+                "❌ {} mismatch for {}: catalog {}, file {}",
+                algorithm,
+                archive.display(),
+                expected,
+                actual
+            ),
+            PackImport::Unverifiable { archive } => format!(
+                "⚠️ catalog lists no SHA-256 or SHA-1 hash for {}; refusing to import it unverified",
+                archive.display()
+            ),
+        };
+        report.push_str(&format!("      {}\n", line));
+
+        let extract_hint = matches!(result, PackImport::Imported { .. } | PackImport::AlreadyPresent { .. } | PackImport::Planned { .. })
+            && !pack.file_name.to_ascii_lowercase().ends_with(".cab");
+        if extract_hint {
+            report.push_str("      ℹ️ self-extracting archive: extract it in place so its INF files can be detected\n");
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        InspectReport, default_output, detect, format_check_table, format_import_report, format_inspect_table,
+        format_scan_table, scan_report, threshold_violations,
+    };
+    use crate::cli::{DriverCheckConfig, DriverSourceArgs, DriversConfig};
+    use crate::drivers::catalog;
+    use crate::drivers::inf::{Architecture, InfFile};
+    use crate::drivers::packs::{DriverPack, PackImport, PackVendor};
+    use crate::drivers::DriverRiskSummary;
+    use std::path::{Path, PathBuf};
+
+    const NIC_INF: &str = "[Version]\nClass=Net\nProvider=Contoso\nDriverVer=01/02/2024,1.2.3.4\nCatalogFile=nic.cat\n\
+        [Manufacturer]\nContoso=Models,NTamd64\n[Models.NTamd64]\nContoso NIC=Install,PCI\\VEN_1234&DEV_0001\n";
+
+    #[test]
+    fn scan_lists_detected_drivers_from_path_override() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("nic.inf"), NIC_INF).unwrap();
+        std::fs::write(temp.path().join("iastorvd.sys"), "binary").unwrap();
+        let source = DriverSourceArgs { paths: vec![temp.path().to_string_lossy().to_string()] };
+
+        let (manager, drivers) = detect(&DriversConfig::default(), &source).unwrap();
+        let report = scan_report(&manager, drivers);
+        let nic = report.drivers.iter().find(|driver| driver.name == "nic.inf").unwrap();
+        assert_eq!(nic.driver_type, "inf");
+        assert_eq!(nic.class.as_deref(), Some("Net"));
+        assert_eq!(nic.driver_ver.as_deref(), Some("01/02/2024,1.2.3.4"));
+        assert!(report.drivers.iter().any(|driver| driver.name == "iastorvd.sys" && driver.priority));
+
+        let table = format_scan_table(&report);
+        assert!(table.starts_with("TYPE"));
+        assert!(table.contains("Net"));
+        assert!(table.contains("2 driver(s), 1 priority, 0 duplicate(s) skipped"));
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["drivers"].as_array().unwrap().len(), 2);
+
+        let missing = DriverSourceArgs { paths: vec![temp.path().join("missing").to_string_lossy().to_string()] };
+        assert!(detect(&DriversConfig::default(), &missing).is_err());
+    }
+
+    #[test]
+    fn inspect_shows_models_and_catalog_status() {
+        let inf = InfFile::parse(Path::new("nic.inf"), NIC_INF);
+        let verification = catalog::verify_package(&inf, Architecture::Arm64);
+        let table = format_inspect_table(&InspectReport {
+            inf,
+            architecture: Architecture::Arm64,
+            verification,
+        });
+
+        assert!(table.contains("Provider:     Contoso"));
+        assert!(table.contains("Contoso -> Models [NTamd64]"));
+        assert!(table.contains("[amd64] Contoso NIC (Install)"));
+        assert!(table.contains("PCI\\VEN_1234&DEV_0001"));
+        assert!(table.contains("Catalog (arm64): MissingCatalog"));
+        assert_eq!("ARM64".parse::<Architecture>().unwrap(), Architecture::Arm64);
+        assert!("sparc".parse::<Architecture>().is_err());
+    }
+
+    #[test]
+    fn check_reports_exceeded_thresholds_only() {
+        let summary = DriverRiskSummary {
+            total: 4,
+            priority: 1,
+            missing_inf_for_sys: 2,
+            cab_packages: 1,
+            ..DriverRiskSummary::default()
+        };
+        assert!(threshold_violations(&summary, 3, &DriverCheckConfig::default()).is_empty());
+
+        let limits = DriverCheckConfig {
+            max_sys_without_inf: Some(0),
+            max_unreadable_cabs: Some(1),
+            max_unsigned: Some(2),
+            min_priority: Some(2),
+            ..DriverCheckConfig::default()
+        };
+        let violations = threshold_violations(&summary, 3, &limits);
+        assert_eq!(
+            violations,
+            vec![
+                "2 .sys file(s) without an INF (drivers.check.max_sys_without_inf = 0)",
+                "3 package(s) without a matching catalog (drivers.check.max_unsigned = 2)",
+                "1 priority storage driver(s) found (drivers.check.min_priority = 2)",
+            ]
+        );
+
+        let table = format_check_table(&super::CheckReport { summary, unsigned: 3, violations });
+        assert_eq!(table.matches("❌").count(), 3);
+    }
+
+    fn pack(file_name: &str) -> DriverPack {
+        DriverPack {
+            vendor: PackVendor::Hp,
+            name: "HP EliteBook 840 G8 Driver Pack".to_string(),
+            version: Some("1.00 A 1".to_string()),
+            models: vec!["HP EliteBook 840 G8".to_string()],
+            operating_systems: vec!["Windows 11 64-bit, 22H2".to_string()],
+            url: Some(format!("https://ftp.hp.com/pub/softpaq/{}", file_name)),
+            file_name: file_name.to_string(),
+            size: None,
+            hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn report_lists_download_urls_and_extract_hints() {
+        let missing = pack("sp140000.exe");
+        let imported = pack("sp140001.exe");
+        let results = vec![
+            (&missing, PackImport::NotDownloaded { expected: PathBuf::from("dl/sp140000.exe"), url: missing.url.clone() }),
+            (&imported, PackImport::Imported { destination: PathBuf::from("drivers/HP/x/win11/sp140001.exe") }),
+        ];
+
+        let report = format_import_report(&results, Path::new("drivers"), false);
+        assert!(report.contains("2 driver pack(s) selected for drivers\n"));
+        assert!(report.contains("fetch https://ftp.hp.com/pub/softpaq/sp140000.exe into dl/sp140000.exe"));
+        assert_eq!(report.matches("self-extracting archive").count(), 1);
+    }
+
+    #[test]
+    fn default_output_prefers_existing_driver_folder() {
+        let temp = tempfile::tempdir().unwrap();
+        let existing = temp.path().join("drivers");
+        std::fs::create_dir(&existing).unwrap();
+        let missing = temp.path().join("PEAutoRun/Drivers");
+        let paths = vec![missing.to_string_lossy().to_string(), existing.to_string_lossy().to_string()];
+
+        assert_eq!(default_output(&paths), existing);
+        assert_eq!(default_output(&paths[..1]), missing);
+    }
+}
@@ -4,7 +4,7 @@ use tracing::{info, error};
 use tracing::warn;
 use std::thread;
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 #[cfg(target_os = "windows")]
 use std::process::Command;
 use crate::config::ConfigManager;
@@ -13,7 +13,8 @@ use crate::cli::BuildArgs;
 use crate::tools::{ToolDetector, ToolCategory};
 use crate::vnc::VncManager;
 use crate::executor::ScriptExecutor;
-use slint::{ModelRc, VecModel};
+use crate::executor::process::OutputLine;
+use slint::{Model, ModelRc, SharedString, VecModel};
 
 slint::include_modules!();
 
@@ -60,7 +61,14 @@ pub async fn execute() -> Result<()> {
     // Convert detected tools to Slint format
     let slint_tools: Vec<ToolItem> = detected_tools.iter().map(|tool| {
         ToolItem {
-            name: tool.name.clone().into(),
+            name: tool.display_name().into(),
+            description: tool.metadata.description.clone().unwrap_or_default().into(),
+            icon: tool
+                .metadata
+                .icon
+                .as_deref()
+                .and_then(|icon| slint::Image::load_from_path(icon).ok())
+                .unwrap_or_default(),
             category: match tool.category {
                 ToolCategory::Tool => "Tool".into(),
                 ToolCategory::PEAutoRun => "PEAutoRun".into(),
@@ -105,6 +113,22 @@ pub async fn execute() -> Result<()> {
         completed: false,
         error: "".into(),
     });
+    ui.set_tool_output(ModelRc::new(VecModel::<SharedString>::default()));
+
+    // Stream tool output into the output panels
+    let ui_weak_output = ui.as_weak();
+    script_executor.subscribe(Arc::new(move |output: &OutputLine| {
+        let line: SharedString = format!("[{}] {}", output.tool, output.line).into();
+        let ui_weak = ui_weak_output.clone();
+        let _ = slint::invoke_from_event_loop(move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                append_tool_output(&ui, line);
+            }
+        });
+    }));
+
+    let executor_cancel = script_executor.clone();
+    ui.on_cancel_tools(move || executor_cancel.cancel());
     
     // Build running guard - prevents concurrent builds
     let build_running = Arc::new(AtomicBool::new(false));
@@ -157,6 +181,10 @@ pub async fn execute() -> Result<()> {
                 skip_dpi_fix: false,
                 config: None,
                 verify: request.verify,
+                resume: false,
+                keep_on_failure: false,
+                plan: None,
+                hardware_inventory: None,
             };
 
             thread::spawn(move || {
@@ -254,6 +282,51 @@ pub async fn execute() -> Result<()> {
         }
     });
 
+    // Staged driver loading guard - drvload calls must not overlap
+    let drivers_loading = Arc::new(AtomicBool::new(false));
+    let ui_weak = ui.as_weak();
+    ui.on_load_drivers(move || {
+        if drivers_loading.swap(true, Ordering::SeqCst) {
+            info!("Driver loading already in progress, ignoring request");
+            return;
+        }
+        if let Some(ui) = ui_weak.upgrade() {
+            ui.set_drivers_loading(true);
+        }
+
+        let ui_weak = ui_weak.clone();
+        let drivers_loading = drivers_loading.clone();
+        thread::spawn(move || {
+            let index = crate::cli::load_drivers::default_index_path();
+            let result = crate::cli::load_drivers::load_drivers(&index, None, false);
+            let _ = slint::invoke_from_event_loop(move || {
+                drivers_loading.store(false, Ordering::SeqCst);
+                let Some(ui) = ui_weak.upgrade() else {
+                    return;
+                };
+                ui.set_drivers_loading(false);
+                match result {
+                    Ok(report) => {
+                        ui.set_driver_loads(ModelRc::new(VecModel::from(driver_load_items(&report))));
+                        let (message, kind) = match (report.loads.len(), report.failed()) {
+                            (0, _) => ("No present device matches a staged driver".to_string(), "info"),
+                            (matched, 0) => (format!("Loaded drivers for {} device(s)", matched), "success"),
+                            (_, failed) => (format!("{} device(s) failed to load their driver", failed), "error"),
+                        };
+                        ui.invoke_show_notification(message.into(), kind.into());
+                    }
+                    Err(e) => {
+                        error!("Failed to load staged drivers: {:#}", e);
+                        ui.invoke_show_notification(
+                            format!("Driver loading failed: {}", truncate_error(&e.to_string(), 50)).into(),
+                            "error".into(),
+                        );
+                    }
+                }
+            });
+        });
+    });
+
     // Shared helper for tool/script execution
     fn execute_tool_async(
         path: &str,
@@ -261,6 +334,7 @@ pub async fn execute() -> Result<()> {
         tools: &[crate::tools::DetectedTool],
         executor: Arc<ScriptExecutor>,
         ui_weak: slint::Weak<GhostWinApp>,
+        tools_active: Arc<AtomicUsize>,
     ) {
         let path_str = path.to_string();
 
@@ -268,13 +342,21 @@ pub async fn execute() -> Result<()> {
             let tool = tool.clone();
             let executor = executor.clone();
             let ui_weak = ui_weak.clone();
+            tools_active.fetch_add(1, Ordering::SeqCst);
+            if let Some(ui) = ui_weak.upgrade() {
+                ui.set_tools_running(true);
+            }
 
             thread::spawn(move || {
-                let result = executor.execute_tool(&tool);
-                let tool_name = tool.name.clone();
+                let result = tokio::runtime::Runtime::new()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|runtime| runtime.block_on(executor.execute_tool(&tool)));
+                let tool_name = tool.display_name().to_string();
 
                 let _ = slint::invoke_from_event_loop(move || {
+                    let still_running = tools_active.fetch_sub(1, Ordering::SeqCst) > 1;
                     if let Some(ui) = ui_weak.upgrade() {
+                        ui.set_tools_running(still_running);
                         match result {
                             Ok(exec_result) => {
                                 info!("{}", exec_result.summary());
@@ -316,12 +398,16 @@ pub async fn execute() -> Result<()> {
         }
     }
 
+    // Tools launched from the GUI that have not finished yet
+    let tools_active = Arc::new(AtomicUsize::new(0));
+
     let executor_clone = script_executor.clone();
     let tools_clone = detected_tools.clone();
     let ui_weak_tool = ui.as_weak();
+    let tools_active_tool = tools_active.clone();
     ui.on_launch_tool(move |path| {
         info!("Launching tool: {}", path);
-        execute_tool_async(&path, "Tool", &tools_clone, executor_clone.clone(), ui_weak_tool.clone());
+        execute_tool_async(&path, "Tool", &tools_clone, executor_clone.clone(), ui_weak_tool.clone(), tools_active_tool.clone());
     });
 
     let executor_clone2 = script_executor.clone();
@@ -329,42 +415,42 @@ pub async fn execute() -> Result<()> {
     let ui_weak_script = ui.as_weak();
     ui.on_run_script(move |path| {
         info!("Running script: {}", path);
-        execute_tool_async(&path, "Script", &tools_clone2, executor_clone2.clone(), ui_weak_script.clone());
+        execute_tool_async(&path, "Script", &tools_clone2, executor_clone2.clone(), ui_weak_script.clone(), tools_active.clone());
     });
     
     let ui_weak = ui.as_weak();
     let vnc_manager_clone = vnc_manager.clone();
     ui.on_toggle_vnc(move || {
-        if let Some(ui) = ui_weak.upgrade() {
-            if let Ok(mut vnc) = vnc_manager_clone.lock() {
-                if vnc.is_running() {
-                    info!("Stopping VNC server");
-                    if let Err(e) = vnc.stop_server() {
-                        error!("Failed to stop VNC server: {}", e);
+        if let Some(ui) = ui_weak.upgrade()
+            && let Ok(mut vnc) = vnc_manager_clone.lock()
+        {
+            if vnc.is_running() {
+                info!("Stopping VNC server");
+                if let Err(e) = vnc.stop_server() {
+                    error!("Failed to stop VNC server: {}", e);
+                    let short_err = truncate_error(&e.to_string(), 20);
+                    ui.set_vnc_status(format!("Error: {}", short_err).into());
+                    ui.invoke_show_notification(format!("Failed to stop VNC: {}", e).into(), "error".into());
+                } else {
+                    ui.set_vnc_enabled(false);
+                    ui.set_vnc_status("Disconnected".into());
+                    ui.invoke_show_notification("VNC server stopped".into(), "info".into());
+                }
+            } else {
+                info!("Starting VNC server");
+                match vnc.start_server() {
+                    Ok(_) => {
+                        ui.set_vnc_enabled(true);
+                        let connection_info = vnc.get_connection_info();
+                        let conn_str = connection_info.get_connection_string();
+                        ui.set_vnc_status(format!("Connected ({})", conn_str).into());
+                        ui.invoke_show_notification(format!("VNC server started: {}", conn_str).into(), "success".into());
+                    }
+                    Err(e) => {
+                        error!("Failed to start VNC server: {}", e);
                         let short_err = truncate_error(&e.to_string(), 20);
                         ui.set_vnc_status(format!("Error: {}", short_err).into());
-                        ui.invoke_show_notification(format!("Failed to stop VNC: {}", e).into(), "error".into());
-                    } else {
-                        ui.set_vnc_enabled(false);
-                        ui.set_vnc_status("Disconnected".into());
-                        ui.invoke_show_notification("VNC server stopped".into(), "info".into());
-                    }
-                } else {
-                    info!("Starting VNC server");
-                    match vnc.start_server() {
-                        Ok(_) => {
-                            ui.set_vnc_enabled(true);
-                            let connection_info = vnc.get_connection_info();
-                            let conn_str = connection_info.get_connection_string();
-                            ui.set_vnc_status(format!("Connected ({})", conn_str).into());
-                            ui.invoke_show_notification(format!("VNC server started: {}", conn_str).into(), "success".into());
-                        }
-                        Err(e) => {
-                            error!("Failed to start VNC server: {}", e);
-                            let short_err = truncate_error(&e.to_string(), 20);
-                            ui.set_vnc_status(format!("Error: {}", short_err).into());
-                            ui.invoke_show_notification(format!("Failed to start VNC: {}", e).into(), "error".into());
-                        }
+                        ui.invoke_show_notification(format!("Failed to start VNC: {}", e).into(), "error".into());
                     }
                 }
             }
@@ -396,7 +482,44 @@ pub async fn execute() -> Result<()> {
     Ok(())
 }
 
+/// One row per matched device for the home view's staged driver card
+fn driver_load_items(report: &crate::cli::load_drivers::LoadReport) -> Vec<DriverLoadItem> {
+    use crate::cli::load_drivers::{LoadResult, device_label};
+
+    report
+        .loads
+        .iter()
+        .map(|load| {
+            let (status, detail) = match &load.result {
+                LoadResult::Loaded => ("loaded", String::new()),
+                LoadResult::Planned => ("planned", String::new()),
+                LoadResult::Failed(reason) => ("failed", truncate_error(reason, 40)),
+            };
+            DriverLoadItem {
+                device: device_label(&load.matched.device).into(),
+                inf: load.matched.inf.clone().into(),
+                status: status.into(),
+                detail: detail.into(),
+            }
+        })
+        .collect()
+}
+
 /// Truncate error message safely for UI display (UTF-8 aware)
+/// Lines kept in the tool output panels
+const TOOL_OUTPUT_LINES: usize = 200;
+
+fn append_tool_output(ui: &GhostWinApp, line: SharedString) {
+    let model = ui.get_tool_output();
+    let Some(lines) = model.as_any().downcast_ref::<VecModel<SharedString>>() else {
+        return;
+    };
+    if lines.row_count() >= TOOL_OUTPUT_LINES {
+        lines.remove(0);
+    }
+    lines.push(line);
+}
+
 fn truncate_error(msg: &str, max_len: usize) -> String {
     // Take first line only
     let first_line = msg.lines().next().unwrap_or(msg);
@@ -549,38 +672,40 @@ fn start_windows_installation(
     info!("Validated Windows Setup at: {}", setup_path.display());
 
     // NOW safe to run PE scripts - we know setup.exe exists
-    if automated {
-        if let (Some(executor), Some(config)) = (executor, config) {
-            let detector = ToolDetector::new(&config.tools);
-            let detected_tools = detector.detect_tools(".")?;
-            let phase_tools = crate::utils::resolve_detected_tools(&config.phases.pe_system_setup_paths, &detected_tools);
-
-            info!("Executing configured PE system-setup scripts");
-            let results = executor.execute_pe_autorun_scripts(&phase_tools)?;
-
-            // Check if any scripts failed, collect failure details
-            let mut failed_scripts: Vec<String> = Vec::new();
-            for result in &results {
-                info!("{}", result.summary());
-                if !result.success {
-                    let detail = if result.stderr.is_empty() {
-                        result.tool_name.clone()
-                    } else {
-                        format!("{}: {}", result.tool_name, result.stderr.lines().next().unwrap_or(""))
-                    };
-                    error!("Script failed: {}", detail);
-                    failed_scripts.push(detail);
-                }
-            }
+    if automated
+        && let (Some(executor), Some(config)) = (executor, config)
+    {
+        let detector = ToolDetector::new(&config.tools);
+        let detected_tools = detector.detect_tools(".")?;
+        let phase_tools = crate::utils::resolve_detected_tools(&config.phases.pe_system_setup_paths, &detected_tools);
+
+        let autorun_tools: Vec<_> = phase_tools
+            .into_iter()
+            .filter(|tool| matches!(tool.category, ToolCategory::PEAutoRun) && tool.auto_run)
+            .collect();
+
+        info!("Executing configured PE system-setup scripts");
+        let runtime = tokio::runtime::Runtime::new()?;
+        let report = runtime.block_on(executor.execute_phase(&autorun_tools, false, "PE system-setup script"))?;
+
+        // Check if any scripts failed, collect failure details
+        let mut failed_scripts: Vec<String> = Vec::new();
+        for outcome in report.failed() {
+            let detail = match &outcome.status {
+                crate::executor::graph::NodeStatus::Failed(reason) => format!("{}: {}", outcome.name, reason),
+                _ => outcome.name.clone(),
+            };
+            error!("Script failed: {}", detail);
+            failed_scripts.push(detail);
+        }
 
-            if !failed_scripts.is_empty() {
-                let summary = if failed_scripts.len() == 1 {
-                    format!("PE script failed: {}", failed_scripts[0])
-                } else {
-                    format!("PE scripts failed: {}", failed_scripts.join(", "))
-                };
-                return Err(anyhow::anyhow!("{}", summary));
-            }
+        if !failed_scripts.is_empty() {
+            let summary = if failed_scripts.len() == 1 {
+                format!("PE script failed: {}", failed_scripts[0])
+            } else {
+                format!("PE scripts failed: {}", failed_scripts.join(", "))
+            };
+            return Err(anyhow::anyhow!("{}", summary));
         }
     }
 
@@ -736,8 +861,13 @@ fn install_progress_from_build_progress(progress: &BuildProgressState) -> Instal
 
     // Never set completed=true during progress updates
     // Only finalize_build_progress should mark the build as complete
+    let current_step = match &progress.detail {
+        Some(detail) => format!("{} - {}", progress.current_step, detail),
+        None => progress.current_step.to_string(),
+    };
+
     InstallProgress {
-        current_step: progress.current_step.into(),
+        current_step: current_step.into(),
         progress: fraction,
         completed: false,
         error: "".into(),
@@ -746,7 +876,7 @@ fn install_progress_from_build_progress(progress: &BuildProgressState) -> Instal
 
 #[cfg(test)]
 mod tests {
-    use super::install_progress_from_build_progress;
+    use super::{driver_load_items, install_progress_from_build_progress};
     use crate::cli::build::BuildProgressState;
 
     #[test]
@@ -755,6 +885,7 @@ mod tests {
             current_step: "Step 3: Copying helper files",
             completed_steps: 2,
             total_steps: 8,
+            detail: None,
         };
 
         let install = install_progress_from_build_progress(&progress);
@@ -764,6 +895,19 @@ mod tests {
         assert_eq!(install.error.as_str(), "");
     }
 
+    #[test]
+    fn appends_item_detail_to_step_label() {
+        let progress = BuildProgressState {
+            current_step: "Step 1: Extracting source ISO",
+            completed_steps: 0,
+            total_steps: 8,
+            detail: Some("sources/boot.wim (3/10)".to_string()),
+        };
+
+        let install = install_progress_from_build_progress(&progress);
+        assert_eq!(install.current_step.as_str(), "Step 1: Extracting source ISO - sources/boot.wim (3/10)");
+    }
+
     #[test]
     fn caps_progress_at_95_percent_during_final_step() {
         // Progress updates should never show 100% - that's reserved for finalize_build_progress
@@ -771,6 +915,7 @@ mod tests {
             current_step: "Step 10: Verifying ISO integrity",
             completed_steps: 9,
             total_steps: 10,
+            detail: None,
         };
 
         let install = install_progress_from_build_progress(&progress);
@@ -778,6 +923,46 @@ mod tests {
         assert!(!install.completed); // Never completed during progress updates
     }
 
+    #[test]
+    fn lists_driver_loads_per_device() {
+        use crate::cli::load_drivers::{DeviceLoad, LoadReport, LoadResult};
+        use crate::drivers::inventory::InventoryDevice;
+        use crate::drivers::repository::IndexMatch;
+
+        let load = |name: Option<&str>, result: LoadResult| DeviceLoad {
+            matched: IndexMatch {
+                device: InventoryDevice {
+                    id: r"PCI\VEN_8086&DEV_A77F\3&0".to_string(),
+                    name: name.map(str::to_string),
+                    hardware_ids: vec![r"PCI\VEN_8086&DEV_A77F".to_string()],
+                    compatible_ids: Vec::new(),
+                },
+                inf: "001-Intel_RST/iaStorVD.inf".to_string(),
+                model: "VMD".to_string(),
+                matched_id: r"PCI\VEN_8086&DEV_A77F".to_string(),
+                rank: 0,
+                driver_ver: None,
+            },
+            result,
+        };
+        let report = LoadReport {
+            index: "index.json".into(),
+            devices: 2,
+            loads: vec![
+                load(Some("RAID controller"), LoadResult::Loaded),
+                load(None, LoadResult::Failed("Error: the driver package is not signed.\nmore".to_string())),
+            ],
+        };
+
+        let items = driver_load_items(&report);
+        assert_eq!(items[0].device, "RAID controller");
+        assert_eq!(items[0].status, "loaded");
+        assert_eq!(items[0].inf, "001-Intel_RST/iaStorVD.inf");
+        assert_eq!(items[1].device, r"PCI\VEN_8086&DEV_A77F\3&0");
+        assert_eq!(items[1].status, "failed");
+        assert_eq!(items[1].detail, "Error: the driver package is not signed.");
+    }
+
     #[test]
     fn truncate_error_handles_ascii() {
         use super::truncate_error;
@@ -900,97 +1085,3 @@ mod tests {
         }
     }
 }
-
-#[allow(dead_code)]
-fn launch_tool_process(path: &str) -> Result<()> {
-    info!("Launching tool at: {}", path);
-    
-    let path_buf = std::path::PathBuf::from(path);
-    let extension = path_buf.extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("");
-    
-    match extension {
-        "exe" | "com" => {
-            #[cfg(target_os = "windows")]
-            {
-                Command::new(path).spawn()?;
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                info!("Would launch Windows executable: {}", path);
-            }
-        }
-        "bat" | "cmd" => {
-            #[cfg(target_os = "windows")]
-            {
-                Command::new("cmd")
-                    .args(&["/c", path])
-                    .spawn()?;
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                info!("Would run batch script: {}", path);
-            }
-        }
-        "ps1" => {
-            #[cfg(target_os = "windows")]
-            {
-                Command::new("powershell")
-                    .args(&["-ExecutionPolicy", "Bypass", "-File", path])
-                    .spawn()?;
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                info!("Would run PowerShell script: {}", path);
-            }
-        }
-        "au3" => {
-            // AutoIt script - would need AutoIt3.exe
-            info!("AutoIt script detected: {}", path);
-        }
-        "reg" => {
-            #[cfg(target_os = "windows")]
-            {
-                Command::new("reg")
-                    .args(&["import", path])
-                    .spawn()?;
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                info!("Would import registry file: {}", path);
-            }
-        }
-        _ => {
-            info!("Unknown file type for: {}", path);
-        }
-    }
-    
-    Ok(())
-}
-
-#[allow(dead_code)]
-fn run_script_process(path: &str) -> Result<()> {
-    // Same as launch_tool_process but with different logging
-    info!("Running script: {}", path);
-    launch_tool_process(path)
-}
-
-#[allow(dead_code)]
-fn run_pe_autorun_scripts() -> Result<()> {
-    info!("Running PE autorun scripts");
-    
-    let config = ConfigManager::load_default()?;
-    let detector = ToolDetector::new(&config.tools);
-    let tools = detector.detect_tools(".")?;
-    
-    // Find and run all PEAutoRun tools
-    for tool in tools {
-        if matches!(tool.category, ToolCategory::PEAutoRun) && tool.auto_run {
-            info!("Auto-running: {}", tool.path.display());
-            launch_tool_process(&tool.path.to_string_lossy())?;
-        }
-    }
-    
-    Ok(())
-}
@@ -0,0 +1,232 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use crate::cli::{LoadDriversArgs, OutputFormat};
+use crate::drivers::inventory::{HardwareInventory, InventoryDevice};
+use crate::drivers::repository::{DriverIndex, INDEX_FILE, IndexMatch, REPOSITORY_DIR};
+
+/// What happened to the INF matched for one device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum LoadResult {
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Loaded,
+    Failed(String),
+    /// `--dry-run`: would be passed to drvload
+    Planned,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceLoad {
+    #[serde(flatten)]
+    pub matched: IndexMatch,
+    pub result: LoadResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadReport {
+    pub index: PathBuf,
+    /// Present devices considered
+    pub devices: usize,
+    pub loads: Vec<DeviceLoad>,
+}
+
+impl LoadReport {
+    pub fn failed(&self) -> usize {
+        self.loads
+            .iter()
+            .filter(|load| matches!(load.result, LoadResult::Failed(_)))
+            .count()
+    }
+}
+
+/// Load the staged drivers matching the present devices
+pub async fn execute(args: LoadDriversArgs) -> Result<()> {
+    crate::cli::validate_host_change_mode("load-drivers", args.dry_run, args.force)?;
+
+    let index = args.index.map(PathBuf::from).unwrap_or_else(default_index_path);
+    let report = load_drivers(&index, args.devices.as_deref().map(Path::new), args.dry_run)?;
+    match args.format {
+        OutputFormat::Table => print!("{}", format_load_table(&report)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    let failed = report.failed();
+    if failed > 0 {
+        bail!("{} device(s) did not get their driver loaded", failed);
+    }
+    Ok(())
+}
+
+/// `%SystemDrive%\GhostWin\Drivers\index.json`, which is `X:` in WinPE
+pub fn default_index_path() -> PathBuf {
+    let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "X:".to_string());
+    PathBuf::from(format!("{}\\", drive)).join(REPOSITORY_DIR).join(INDEX_FILE)
+}
+
+/// Match devices against the index and drvload each matched INF once
+///
+/// `devices` replaces enumeration with a hardware inventory export, so the
+/// matching can be previewed on a machine other than the target.
+pub fn load_drivers(index_path: &Path, devices: Option<&Path>, dry_run: bool) -> Result<LoadReport> {
+    let index = DriverIndex::load(index_path)?;
+    let devices = match devices {
+        Some(path) => HardwareInventory::load(path)?.devices,
+        None => present_devices()?,
+    };
+    info!("🔍 Matching {} device(s) against {} staged driver package(s)", devices.len(), index.packages.len());
+
+    let repository = index_path.parent().unwrap_or(Path::new("."));
+    let mut results: BTreeMap<String, LoadResult> = BTreeMap::new();
+    let loads = index
+        .match_devices(&devices)
+        .into_iter()
+        .map(|matched| {
+            let result = results
+                .entry(matched.inf.clone())
+                .or_insert_with(|| {
+                    if dry_run {
+                        info!("Dry run: would load {}", matched.inf);
+                        LoadResult::Planned
+                    } else {
+                        drvload(&repository.join(&matched.inf))
+                    }
+                })
+                .clone();
+            DeviceLoad { matched, result }
+        })
+        .collect();
+
+    Ok(LoadReport {
+        index: index_path.to_path_buf(),
+        devices: devices.len(),
+        loads,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn present_devices() -> Result<Vec<InventoryDevice>> {
+    use anyhow::Context;
+
+    let output = std::process::Command::new("pnputil")
+        .args(["/enum-devices", "/connected", "/ids"])
+        .output()
+        .context("Failed to run pnputil")?;
+    if !output.status.success() {
+        bail!(
+            "pnputil /enum-devices failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+    Ok(crate::drivers::repository::parse_pnputil_devices(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn present_devices() -> Result<Vec<InventoryDevice>> {
+    bail!("Device enumeration needs pnputil on Windows; pass --devices with a hardware inventory JSON instead")
+}
+
+#[cfg(target_os = "windows")]
+fn drvload(inf: &Path) -> LoadResult {
+    info!("💉 Loading {}", inf.display());
+    match std::process::Command::new("drvload").arg(inf).output() {
+        Ok(output) if output.status.success() => LoadResult::Loaded,
+        Ok(output) => {
+            let message = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            warn!("drvload failed for {}: {}", inf.display(), message);
+            LoadResult::Failed(if message.is_empty() { output.status.to_string() } else { message })
+        }
+        Err(error) => {
+            warn!("Failed to run drvload for {}: {}", inf.display(), error);
+            LoadResult::Failed(error.to_string())
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn drvload(inf: &Path) -> LoadResult {
+    warn!("Cannot load {}: drvload is only available on Windows", inf.display());
+    LoadResult::Failed("drvload is only available on Windows".to_string())
+}
+
+pub fn device_label(device: &InventoryDevice) -> &str {
+    device.name.as_deref().unwrap_or(&device.id)
+}
+
+fn format_load_table(report: &LoadReport) -> String {
+    let mut output = format!("{:<8} {:<40} {}\n", "RESULT", "DEVICE", "INF");
+    for load in &report.loads {
+        let (status, reason) = match &load.result {
+            LoadResult::Loaded => ("loaded", None),
+            LoadResult::Planned => ("planned", None),
+            LoadResult::Failed(reason) => ("failed", Some(reason)),
+        };
+        output.push_str(&format!(
+            "{:<8} {:<40} {} ({})\n",
+            status,
+            device_label(&load.matched.device),
+            load.matched.inf,
+            load.matched.matched_id
+        ));
+        if let Some(reason) = reason {
+            output.push_str(&format!("         {}\n", reason));
+        }
+    }
+    output.push_str(&format!(
+        "\n{} of {} device(s) matched a staged driver, {} failed to load\n",
+        report.loads.len(),
+        report.devices,
+        report.failed()
+    ));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadResult, format_load_table, load_drivers};
+    use crate::drivers::inf::Architecture;
+    use crate::drivers::repository::{DriverIndex, IndexedModel, IndexedPackage};
+    use tempfile::tempdir;
+
+    #[test]
+    fn dry_run_matches_inventory_devices_and_loads_each_inf_once() {
+        let temp = tempdir().unwrap();
+        let index = DriverIndex {
+            architecture: Architecture::Amd64,
+            packages: vec![IndexedPackage {
+                inf: "001-Intel_RST/iaStorVD.inf".to_string(),
+                class: Some("SCSIAdapter".to_string()),
+                driver_ver: None,
+                models: vec![IndexedModel {
+                    description: "VMD".to_string(),
+                    hardware_ids: vec![r"PCI\VEN_8086&DEV_A77F".to_string()],
+                }],
+            }],
+        };
+        let index_path = temp.path().join("index.json");
+        index.write(&index_path).unwrap();
+        let devices = temp.path().join("devices.json");
+        std::fs::write(
+            &devices,
+            r#"["PCI\\VEN_8086&DEV_A77F&SUBSYS_0B1A1028\\3&0", "PCI\\VEN_8086&DEV_A77F&SUBSYS_0B1B1028\\3&1", "USB\\VID_FFFF&PID_0001\\1"]"#,
+        )
+        .unwrap();
+
+        let report = load_drivers(&index_path, Some(&devices), true).unwrap();
+        assert_eq!(report.devices, 3);
+        assert_eq!(report.loads.len(), 2);
+        assert!(report.loads.iter().all(|load| load.result == LoadResult::Planned));
+        assert_eq!(report.failed(), 0);
+
+        let table = format_load_table(&report);
+        assert!(table.contains("planned  PCI\\VEN_8086&DEV_A77F&SUBSYS_0B1A1028\\3&0"));
+        assert!(table.contains("2 of 3 device(s) matched a staged driver, 0 failed to load"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["loads"][0]["inf"], "001-Intel_RST/iaStorVD.inf");
+        assert_eq!(json["loads"][0]["result"]["status"], "planned");
+    }
+}
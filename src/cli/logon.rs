@@ -1,5 +1,6 @@
 use anyhow::Result;
 use tracing::{info, warn};
+use std::sync::Arc;
 use crate::tools::ToolManager;
 use crate::executor::ScriptExecutor;
 use crate::config::ConfigManager;
@@ -12,7 +13,7 @@ pub async fn execute(args: LogonArgs) -> Result<()> {
     
     let config = ConfigManager::load_config(None).await?;
     let tool_manager = ToolManager::new(&config.tools);
-    let executor = ScriptExecutor::new(config.clone());
+    let executor = Arc::new(ScriptExecutor::new(config.clone()));
     let detected_tools = tool_manager.scan_tools().await?;
     let logon_tools = crate::utils::resolve_detected_tools(&config.phases.post_install_logon_paths, &detected_tools);
     
@@ -22,7 +23,7 @@ pub async fn execute(args: LogonArgs) -> Result<()> {
     }
     
     info!("Found {} logon script(s) to execute", logon_tools.len());
-    crate::utils::execute_tools_with_dry_run(&logon_tools, &executor, args.dry_run, "logon script").await?;
+    executor.execute_phase(&logon_tools, args.dry_run, "logon script").await?;
     
     info!("Logon script execution completed");
     
@@ -1,13 +1,18 @@
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 pub mod build;
+pub mod cache;
+pub mod drivers;
 pub mod gui;
+pub mod load_drivers;
 pub mod validate;
 pub mod tools;
 pub mod logon;
 pub mod system_setup;
+pub mod wim;
 
 #[derive(Args, Debug, Clone)]
 pub struct BuildArgs {
@@ -42,6 +47,29 @@ pub struct BuildArgs {
     /// Verify ISO integrity after creation
     #[arg(long)]
     pub verify: bool,
+
+    /// Skip steps already completed by a previous run into the same output directory
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Keep the output directory when the build fails (for inspection or --resume)
+    #[arg(long)]
+    pub keep_on_failure: bool,
+
+    /// Print what the build would do (text or json) without touching anything on disk
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "text", value_name = "FORMAT")]
+    pub plan: Option<PlanFormat>,
+
+    /// JSON list of PNP device IDs from the target machines; only matching drivers are injected
+    #[arg(long, value_name = "FILE")]
+    pub hardware_inventory: Option<String>,
+}
+
+/// Output format for `build --plan`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanFormat {
+    Text,
+    Json,
 }
 
 #[derive(Args, Debug, Clone, Default)]
@@ -66,6 +94,152 @@ pub struct SystemSetupArgs {
     pub force: bool,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct LoadDriversArgs {
+    /// Driver index written by the build (default: `%SystemDrive%\GhostWin\Drivers\index.json`)
+    #[arg(long, value_name = "FILE")]
+    pub index: Option<String>,
+
+    /// Match the devices in a hardware inventory JSON instead of the present devices
+    #[arg(long, value_name = "FILE")]
+    pub devices: Option<String>,
+
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+
+    /// Preview which drivers would be loaded
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Load the matched drivers with drvload
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct WimArgs {
+    #[command(subcommand)]
+    pub command: WimCommand,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CacheArgs {
+    /// Configuration file path
+    #[arg(short, long, global = true)]
+    pub config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DriversArgs {
+    /// Configuration file path
+    #[arg(short, long, global = true)]
+    pub config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: DriversCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DriversCommand {
+    /// List the drivers a build would detect, with classification and priority
+    Scan {
+        #[command(flatten)]
+        source: DriverSourceArgs,
+
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Show the parsed contents of an INF file
+    Inspect {
+        /// Path to the .inf file
+        inf: String,
+
+        /// Architecture to verify the catalog for (x86, amd64, arm, arm64); defaults to the build target
+        #[arg(long, value_name = "ARCH")]
+        arch: Option<String>,
+
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Summarize driver risks and fail when a `[drivers.check]` threshold is exceeded
+    Check {
+        #[command(flatten)]
+        source: DriverSourceArgs,
+
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Import downloaded vendor driver packs listed in a local catalog XML
+    Import(DriversImportArgs),
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct DriverSourceArgs {
+    /// Driver directory to scan instead of `drivers.paths` (repeatable)
+    #[arg(long = "path", value_name = "DIR")]
+    pub paths: Vec<String>,
+}
+
+/// Output format for `drivers` subcommands
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DriversImportArgs {
+    /// Dell DriverPackCatalog.xml, HP HPClientDriverPackCatalog.xml or Lenovo catalogv2.xml
+    #[arg(long, value_name = "XML")]
+    pub catalog: String,
+
+    /// Model name, system ID or machine type to select (repeatable)
+    #[arg(long, required = true)]
+    pub model: Vec<String>,
+
+    /// Operating system to select, e.g. win11, win10 or winpe
+    #[arg(long)]
+    pub os: Option<String>,
+
+    /// Folder holding the downloaded pack archives
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    pub downloads: String,
+
+    /// Driver folder to lay the packs out in (defaults to the first `drivers.paths` entry)
+    #[arg(long, value_name = "DIR")]
+    pub output: Option<String>,
+
+    /// Verify and report without copying anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CacheCommand {
+    /// List cached source ISO extractions
+    List,
+    /// Evict least-recently-used extractions above the size cap
+    Prune {
+        /// Override `cache.max_size_gb` for this run
+        #[arg(long)]
+        max_size_gb: Option<u64>,
+    },
+    /// Remove every cached extraction
+    Clear,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum WimCommand {
+    /// List the images stored in a WIM/ESD file
+    Info {
+        /// Path to the .wim or .esd file
+        path: String,
+    },
+}
+
 pub(crate) fn validate_host_change_mode(command_name: &str, dry_run: bool, force: bool) -> Result<()> {
     match (dry_run, force) {
         (true, true) => Err(anyhow::anyhow!(
@@ -87,6 +261,10 @@ pub struct GhostwinConfig {
     pub tools: ToolsConfig,
     pub phases: PhaseConfig,
     pub security: SecurityConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub drivers: DriversConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,6 +274,32 @@ pub struct IsoConfig {
     pub adk_path: Option<String>,
     pub helper_source: Option<String>,
     pub windows_overlay_source: Option<String>,
+    #[serde(default)]
+    pub authoring: IsoAuthoring,
+    #[serde(default)]
+    pub servicing_backend: ServicingBackend,
+}
+
+/// Backend used to author the final bootable ISO
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IsoAuthoring {
+    /// Built-in ISO 9660 + Joliet + UDF writer (works on every host)
+    #[default]
+    Native,
+    /// Windows ADK oscdimg.exe
+    Oscdimg,
+}
+
+/// Tooling used to mount and service `boot.wim`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServicingBackend {
+    /// DISM on Windows, wimlib-imagex elsewhere
+    #[default]
+    Auto,
+    Dism,
+    Wimlib,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,12 +307,86 @@ pub struct WinPEConfig {
     pub packages: Vec<String>,
     pub disable_dpi_scaling: bool,
     pub set_resolution: Option<String>,
+    /// `[[winpe.registry]]` values written into the offline image hives
+    #[serde(default)]
+    pub registry: Vec<RegistryTweak>,
+    /// `.reg` files imported into the offline image hives
+    #[serde(default)]
+    pub registry_files: Vec<String>,
+}
+
+/// One offline registry value baked into the mounted image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryTweak {
+    pub hive: RegistryHive,
+    pub key: String,
+    /// Value name; empty for the key's default value
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    pub value_type: RegistryValueType,
+    pub data: RegistryData,
+}
+
+/// Offline hives that registry tweaks can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RegistryHive {
+    Software,
+    System,
+    Default,
+    /// Default user profile (`Users/Default/NTUSER.DAT`)
+    Ntuser,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryValueType {
+    #[serde(alias = "REG_SZ")]
+    Sz,
+    #[serde(alias = "REG_EXPAND_SZ")]
+    ExpandSz,
+    #[serde(alias = "REG_MULTI_SZ")]
+    MultiSz,
+    #[serde(alias = "REG_DWORD")]
+    Dword,
+    #[serde(alias = "REG_QWORD")]
+    Qword,
+    #[serde(alias = "REG_BINARY")]
+    Binary,
+}
+
+/// Raw `data` as written in config; interpreted according to the tweak's `type`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RegistryData {
+    Integer(i64),
+    Text(String),
+    List(Vec<String>),
+    Bytes(Vec<u8>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolsConfig {
     pub folders: Vec<String>,
     pub auto_detect: bool,
+    /// Extension (without the dot) to interpreter, merged over the built-in types
+    #[serde(default)]
+    pub interpreters: BTreeMap<String, InterpreterConfig>,
+}
+
+/// How tools with one file extension are run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterpreterConfig {
+    /// Program followed by its arguments; `{path}` is replaced by the tool and a
+    /// lone `{args}` by the sidecar `arguments`. Empty disables the type.
+    pub command: Vec<String>,
+    /// Locations tried for the program before looking it up on `PATH`
+    #[serde(default)]
+    pub search_paths: Vec<String>,
+    /// Listed as directly runnable rather than as a script
+    #[serde(default)]
+    pub executable: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +394,163 @@ pub struct PhaseConfig {
     pub pe_system_setup_paths: Vec<String>,
     pub pe_driver_loader_paths: Vec<String>,
     pub post_install_logon_paths: Vec<String>,
+    /// Independent phase scripts run at once; unset runs them one at a time
+    #[serde(default)]
+    pub max_parallel: Option<usize>,
+    /// File every tool output line is appended to
+    #[serde(default)]
+    pub output_log: Option<String>,
+}
+
+/// Content-addressed cache of extracted source ISOs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// Cache root; defaults to the per-user cache directory
+    pub path: Option<String>,
+    /// Least-recently-used extractions are evicted above this size
+    pub max_size_gb: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            max_size_gb: 20,
+        }
+    }
+}
+
+/// Driver detection and injection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriversConfig {
+    /// Source directories scanned for driver packages (working-directory relative)
+    pub paths: Vec<String>,
+    /// Also look for `PEAutoRun\Drivers`, `Helper\Drivers` and `Tools\Drivers` on every drive (Windows)
+    pub scan_drives: bool,
+    /// Globs matched against paths relative to their source directory; empty means everything
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Case-insensitive file name substrings injected first (storage controllers)
+    pub priority: Vec<String>,
+    /// Policy per INF `Class`, e.g. `Display = "stage"`
+    pub class_policy: BTreeMap<String, ClassPolicy>,
+    /// Policy for classes not listed in `class_policy` and for CAB packages that cannot be expanded
+    pub default_policy: ClassPolicy,
+    /// When false, only priority drivers are injected and the rest are staged
+    pub inject_all: bool,
+    /// Accepted for compatibility; drivers are fetched by `scripts/Download-Drivers.ps1`, not by builds
+    pub auto_download: bool,
+    /// Packages to keep over newer duplicates
+    pub pins: Vec<DriverPin>,
+    /// Only inject or stage packages whose files match their catalog; the catalog signature is not verified
+    pub require_signed: bool,
+    /// Thresholds for `ghostwin drivers check`
+    pub check: DriverCheckConfig,
+    /// Drivers also injected into `sources/install.wim` so the installed OS has them on first boot
+    pub install_image: InstallImageConfig,
+}
+
+impl Default for DriversConfig {
+    fn default() -> Self {
+        Self {
+            paths: crate::drivers::DRIVER_SEARCH_DIRS.iter().map(|dir| dir.to_string()).collect(),
+            scan_drives: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            priority: crate::drivers::DEFAULT_PRIORITY_DRIVERS.iter().map(|pattern| pattern.to_string()).collect(),
+            class_policy: BTreeMap::new(),
+            default_policy: ClassPolicy::Inject,
+            inject_all: true,
+            auto_download: false,
+            pins: Vec::new(),
+            require_signed: false,
+            check: DriverCheckConfig::default(),
+            install_image: InstallImageConfig::default(),
+        }
+    }
+}
+
+/// `install.wim` / `install.esd` servicing; boot.wim keeps following `class_policy`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstallImageConfig {
+    /// Images to service, by index or name like `iso.wim_index`; empty leaves the install image untouched
+    pub indexes: Vec<String>,
+    /// INF classes injected into those images (case-insensitive); `"*"` means every class
+    pub classes: Vec<String>,
+    /// Export only the selected images from an install.esd; every other edition is removed from the media
+    pub drop_unselected_editions: bool,
+}
+
+impl InstallImageConfig {
+    pub fn enabled(&self) -> bool {
+        !self.indexes.is_empty()
+    }
+}
+
+impl Default for InstallImageConfig {
+    fn default() -> Self {
+        Self {
+            indexes: Vec::new(),
+            classes: vec!["SCSIAdapter".to_string(), "HDC".to_string()],
+            drop_unselected_editions: false,
+        }
+    }
+}
+
+/// `ghostwin drivers check` fails when a count exceeds its maximum; unset limits are not checked
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriverCheckConfig {
+    /// `.sys` files without an INF that references them
+    pub max_sys_without_inf: Option<usize>,
+    /// CAB packages that could not be expanded
+    pub max_unreadable_cabs: Option<usize>,
+    /// Older duplicate packages dropped in favour of a newer or pinned copy
+    pub max_duplicates: Option<usize>,
+    /// INF packages whose files do not match their catalog
+    pub max_unsigned: Option<usize>,
+    /// Fewest priority storage drivers expected
+    pub min_priority: Option<usize>,
+}
+
+/// What a build does with a driver package of a given class
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassPolicy {
+    /// Add to the boot.wim driver store and stage in `GhostWin/Drivers`
+    #[default]
+    Inject,
+    /// Only stage in `GhostWin/Drivers` for `ghostwin load-drivers` inside WinPE
+    Stage,
+    Skip,
+}
+
+impl std::fmt::Display for ClassPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Inject => "inject",
+            Self::Stage => "stage",
+            Self::Skip => "skip",
+        })
+    }
+}
+
+/// Keep a specific copy of a driver package when several versions are found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriverPin {
+    /// INF file name, e.g. `iaStorVD.inf`
+    pub inf: String,
+    /// `DriverVer` version to keep, e.g. `19.5.2.1049`
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Folder (or INF path) to keep the package from
+    #[serde(default)]
+    pub path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,6 +560,18 @@ pub struct SecurityConfig {
     pub vnc_enabled: bool,
     pub vnc_port: u16,
     pub vnc_password: Option<String>,
+    /// Refuse tools that the signed tool manifest does not vouch for, instead of warning
+    #[serde(default)]
+    pub enforce_tool_manifest: bool,
+    /// Ed25519 private key (PKCS#8 PEM file) the build signs the tool manifest with; keep it off the media
+    #[serde(default)]
+    pub tool_manifest_signing_key: Option<String>,
+    /// Hex Ed25519 public key runs verify the tool manifest against
+    #[serde(default)]
+    pub tool_manifest_public_key: Option<String>,
+    /// Signed tool manifest to verify against; defaults to `%SystemDrive%\Helper\ghostwin-hashes.json`
+    #[serde(default)]
+    pub tool_manifest_path: Option<String>,
 }
 
 impl Default for GhostwinConfig {
@@ -136,6 +583,8 @@ impl Default for GhostwinConfig {
                 adk_path: None,
                 helper_source: Some("concept/windows-setup-helper-master/Helper".to_string()),
                 windows_overlay_source: Some("concept/windows-setup-helper-master/Windows".to_string()),
+                authoring: IsoAuthoring::Native,
+                servicing_backend: ServicingBackend::Auto,
             },
             winpe: WinPEConfig {
                 packages: vec![
@@ -146,15 +595,20 @@ impl Default for GhostwinConfig {
                 ],
                 disable_dpi_scaling: true,
                 set_resolution: Some("1024x768".to_string()),
+                registry: Vec::new(),
+                registry_files: Vec::new(),
             },
             tools: ToolsConfig {
                 folders: vec!["Tools".to_string(), "PEAutoRun".to_string(), "Logon".to_string()],
                 auto_detect: true,
+                interpreters: BTreeMap::new(),
             },
             phases: PhaseConfig {
                 pe_system_setup_paths: vec!["pe_autorun/system_setup".to_string()],
                 pe_driver_loader_paths: vec!["pe_autorun/drivers".to_string()],
                 post_install_logon_paths: vec!["scripts/basic/registry/disable_auto_logon.reg".to_string()],
+                max_parallel: None,
+                output_log: None,
             },
             security: SecurityConfig {
                 password_hash: None,
@@ -162,7 +616,13 @@ impl Default for GhostwinConfig {
                 vnc_enabled: false,
                 vnc_port: 5950,
                 vnc_password: None,
+                enforce_tool_manifest: false,
+                tool_manifest_signing_key: None,
+                tool_manifest_public_key: None,
+                tool_manifest_path: None,
             },
+            cache: CacheConfig::default(),
+            drivers: DriversConfig::default(),
         }
     }
 }
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use tracing::{info, warn};
 use std::path::Path;
+use std::sync::Arc;
 use crate::tools::ToolManager;
 use crate::executor::ScriptExecutor;
 use crate::config::ConfigManager;
@@ -13,12 +14,12 @@ pub async fn execute(args: SystemSetupArgs) -> Result<()> {
     
     let config = ConfigManager::load_config(None).await?;
     let tool_manager = ToolManager::new(&config.tools);
-    let executor = ScriptExecutor::new(config.clone());
+    let executor = Arc::new(ScriptExecutor::new(config.clone()));
     let detected_tools = tool_manager.scan_tools().await?;
     let system_tools = crate::utils::resolve_detected_tools(&config.phases.pe_system_setup_paths, &detected_tools);
     
     info!("Found {} system setup script(s) to execute", system_tools.len());
-    crate::utils::execute_tools_with_dry_run(&system_tools, &executor, args.dry_run, "system setup script").await?;
+    executor.execute_phase(&system_tools, args.dry_run, "system setup script").await?;
     
     // Apply basic system configurations
     apply_system_configurations(args.dry_run).await?;
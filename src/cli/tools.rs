@@ -66,19 +66,19 @@ pub async fn execute() -> Result<()> {
     
     // Load options files
     for tool_dir in &tool_dirs {
-        if let Ok(options) = detector.load_options_file(tool_dir) {
-            if options.check_all || options.collapse_tree || !options.default_checked.is_empty() {
-                println!("\n⚙️  Options for {}:", tool_dir.display());
-                
-                if options.check_all {
-                    println!("  - Check all items by default");
-                }
-                if options.collapse_tree {
-                    println!("  - Collapse tree view by default");
-                }
-                if !options.default_checked.is_empty() {
-                    println!("  - Default checked items: {}", options.default_checked.join(", "));
-                }
+        if let Ok(options) = detector.load_options_file(tool_dir)
+            && (options.check_all || options.collapse_tree || !options.default_checked.is_empty())
+        {
+            println!("\n⚙️  Options for {}:", tool_dir.display());
+
+            if options.check_all {
+                println!("  - Check all items by default");
+            }
+            if options.collapse_tree {
+                println!("  - Collapse tree view by default");
+            }
+            if !options.default_checked.is_empty() {
+                println!("  - Default checked items: {}", options.default_checked.join(", "));
             }
         }
     }
@@ -48,13 +48,18 @@ pub async fn execute() -> Result<()> {
             info!("✅ Configuration loaded successfully");
             
             // Validate ADK path
-            if let Some(ref adk_path) = config.iso.adk_path {
-                if !std::path::Path::new(adk_path).exists() {
-                    warn!("⚠️  Custom ADK path does not exist: {}", adk_path);
-                    warnings += 1;
-                }
+            if let Some(ref adk_path) = config.iso.adk_path
+                && !std::path::Path::new(adk_path).exists()
+            {
+                warn!("⚠️  Custom ADK path does not exist: {}", adk_path);
+                warnings += 1;
             }
             
+            if let Err(e) = crate::cli::build::validate_iso_authoring(&config) {
+                error!("❌ {}", e);
+                errors += 1;
+            }
+
             // Validate tool folders
             for folder in &config.tools.folders {
                 if !std::path::Path::new(folder).exists() {
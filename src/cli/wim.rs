@@ -0,0 +1,68 @@
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+use crate::cli::{WimArgs, WimCommand};
+use crate::utils;
+use crate::wim::metadata::{WimInfo, read_wim_info};
+
+pub async fn execute(args: WimArgs) -> Result<()> {
+    match args.command {
+        WimCommand::Info { path } => {
+            info!("📀 Reading WIM metadata from {}", path);
+            let wim_info = read_wim_info(Path::new(&path))?;
+            print!("{}", format_wim_info(&path, &wim_info));
+        }
+    }
+
+    Ok(())
+}
+
+fn format_wim_info(path: &str, wim_info: &WimInfo) -> String {
+    let mut output = format!(
+        "📀 {}: {} image(s), boot index {}, part {}/{}\n",
+        path, wim_info.image_count, wim_info.boot_index, wim_info.part_number, wim_info.total_parts
+    );
+
+    for image in &wim_info.images {
+        output.push_str(&format!("\n  [{}] {}\n", image.index, image.name));
+        output.push_str(&format!(
+            "      Edition: {}  Arch: {}  Build: {}\n",
+            image.edition.as_deref().unwrap_or("-"),
+            image.architecture.as_deref().unwrap_or("-"),
+            image.build.map(|build| build.to_string()).unwrap_or_else(|| "-".to_string()),
+        ));
+        output.push_str(&format!(
+            "      Size: {} ({} bytes)\n",
+            utils::format_file_size(image.total_bytes),
+            image.total_bytes
+        ));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_wim_info;
+    use crate::wim::metadata::{read_wim_info, write_test_wim};
+    use tempfile::tempdir;
+
+    #[test]
+    fn formats_every_image_with_details() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("boot.wim");
+        write_test_wim(
+            &path,
+            &[
+                ("Microsoft Windows PE (amd64)", "WindowsPE", "9", 26100),
+                ("Microsoft Windows Setup (amd64)", "WindowsPE", "9", 26100),
+            ],
+        );
+
+        let output = format_wim_info("boot.wim", &read_wim_info(&path).unwrap());
+        assert!(output.contains("boot.wim: 2 image(s), boot index 2"));
+        assert!(output.contains("[2] Microsoft Windows Setup (amd64)"));
+        assert!(output.contains("Edition: WindowsPE  Arch: amd64  Build: 26100"));
+        assert!(output.contains("(2000000 bytes)"));
+    }
+}
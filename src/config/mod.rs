@@ -68,25 +68,94 @@ impl ConfigManager {
             return Err(anyhow::anyhow!("WIM index cannot be empty"));
         }
 
-        let wim_index = config.iso.wim_index.trim().parse::<u32>()
-            .map_err(|_| anyhow::anyhow!("WIM index must be a positive numeric image index"))?;
-
-        if wim_index == 0 {
+        // Non-numeric values are image names, resolved against boot.wim at build time
+        if config.iso.wim_index.trim().parse::<u32>() == Ok(0) {
             return Err(anyhow::anyhow!("WIM index must be greater than zero"));
         }
 
-        if let Some(helper_source) = &config.iso.helper_source {
-            if helper_source.trim().is_empty() {
-                return Err(anyhow::anyhow!("Helper source path cannot be empty when configured"));
+        if let Some(helper_source) = &config.iso.helper_source
+            && helper_source.trim().is_empty()
+        {
+            return Err(anyhow::anyhow!("Helper source path cannot be empty when configured"));
+        }
+
+        if let Some(windows_overlay_source) = &config.iso.windows_overlay_source
+            && windows_overlay_source.trim().is_empty()
+        {
+            return Err(anyhow::anyhow!("Windows overlay source path cannot be empty when configured"));
+        }
+        
+        for tweak in &config.winpe.registry {
+            crate::wim::registry::tweak_edit(tweak).context("Invalid [[winpe.registry]] entry")?;
+        }
+
+        if config.winpe.registry_files.iter().any(|path| path.trim().is_empty()) {
+            return Err(anyhow::anyhow!("Registry file paths cannot be empty"));
+        }
+
+        for (extension, interpreter) in &config.tools.interpreters {
+            if extension.trim().trim_start_matches('.').is_empty() {
+                return Err(anyhow::anyhow!("tools.interpreters keys must name a file extension"));
+            }
+            if !interpreter.command.is_empty()
+                && !interpreter.command.iter().any(|element| element.contains(crate::tools::interpreters::PATH_PLACEHOLDER))
+            {
+                return Err(anyhow::anyhow!("Interpreter for .{} must pass the tool as {{path}}", extension));
+            }
+        }
+
+        if config.cache.enabled && config.cache.max_size_gb == 0 {
+            return Err(anyhow::anyhow!("cache.max_size_gb must be greater than zero when the cache is enabled"));
+        }
+
+        if let Some(path) = &config.cache.path
+            && path.trim().is_empty()
+        {
+            return Err(anyhow::anyhow!("Cache path cannot be empty when configured"));
+        }
+
+        if config.drivers.paths.iter().any(|path| path.trim().is_empty()) {
+            return Err(anyhow::anyhow!("drivers.paths entries cannot be empty"));
+        }
+
+        crate::drivers::compile_globs(&config.drivers.include).context("Invalid drivers.include pattern")?;
+        crate::drivers::compile_globs(&config.drivers.exclude).context("Invalid drivers.exclude pattern")?;
+
+        if config.drivers.class_policy.keys().any(|class| class.trim().is_empty()) {
+            return Err(anyhow::anyhow!("drivers.class_policy keys must name a device class"));
+        }
+
+        for pin in &config.drivers.pins {
+            if pin.inf.trim().is_empty() {
+                return Err(anyhow::anyhow!("drivers.pins entries need an inf file name"));
+            }
+            if pin.version.is_none() && pin.path.is_none() {
+                return Err(anyhow::anyhow!("Driver pin for {} needs a version or a path", pin.inf));
             }
         }
 
-        if let Some(windows_overlay_source) = &config.iso.windows_overlay_source {
-            if windows_overlay_source.trim().is_empty() {
-                return Err(anyhow::anyhow!("Windows overlay source path cannot be empty when configured"));
+        let install_image = &config.drivers.install_image;
+        for selector in &install_image.indexes {
+            if selector.trim().is_empty() || selector.trim().parse::<u32>() == Ok(0) {
+                return Err(anyhow::anyhow!(
+                    "drivers.install_image.indexes entries must be an image name or an index greater than zero"
+                ));
             }
         }
-        
+        if install_image.enabled() && install_image.classes.iter().all(|class| class.trim().is_empty()) {
+            return Err(anyhow::anyhow!("drivers.install_image.classes must name at least one device class or \"*\""));
+        }
+
+        if config.security.tool_manifest_signing_key.as_deref().is_some_and(|path| path.trim().is_empty()) {
+            return Err(anyhow::anyhow!("security.tool_manifest_signing_key cannot be empty when configured"));
+        }
+        if let Some(public_key) = &config.security.tool_manifest_public_key {
+            crate::tools::integrity::parse_public_key(public_key)?;
+        }
+        if config.security.enforce_tool_manifest && config.security.tool_manifest_public_key.is_none() {
+            return Err(anyhow::anyhow!("security.enforce_tool_manifest needs a security.tool_manifest_public_key"));
+        }
+
         // Validate VNC port range
         if config.security.vnc_port == 0 {
             return Err(anyhow::anyhow!("VNC port must be between 1 and 65535"));
@@ -176,7 +245,7 @@ impl ConfigManager {
 #[cfg(test)]
 mod tests {
     use super::ConfigManager;
-    use crate::cli::GhostwinConfig;
+    use crate::cli::{ClassPolicy, GhostwinConfig, RegistryData, RegistryHive, RegistryTweak, RegistryValueType};
 
     #[test]
     fn default_config_is_valid() {
@@ -185,12 +254,20 @@ mod tests {
     }
 
     #[test]
-    fn rejects_non_numeric_wim_index() {
+    fn accepts_image_name_as_wim_index() {
         let mut config = GhostwinConfig::default();
-        config.iso.wim_index = "setup".to_string();
+        config.iso.wim_index = "Microsoft Windows Setup (amd64)".to_string();
+
+        ConfigManager::validate_config(&config).unwrap();
+    }
+
+    #[test]
+    fn rejects_zero_wim_index() {
+        let mut config = GhostwinConfig::default();
+        config.iso.wim_index = "0".to_string();
 
         let error = ConfigManager::validate_config(&config).unwrap_err();
-        assert!(error.to_string().contains("WIM index must be a positive numeric image index"));
+        assert!(error.to_string().contains("WIM index must be greater than zero"));
     }
 
     #[test]
@@ -228,4 +305,172 @@ mod tests {
         let error = ConfigManager::validate_config(&config).unwrap_err();
         assert!(error.to_string().contains("Phase script paths cannot be empty"));
     }
+
+    #[test]
+    fn parses_registry_tweaks_from_toml() {
+        let mut value: toml::Value = toml::Value::try_from(GhostwinConfig::default()).unwrap();
+        let tweaks: toml::Value = toml::from_str(
+            r#"
+            registry_files = ["tweaks/pe.reg"]
+
+            [[registry]]
+            hive = "SYSTEM"
+            key = 'CurrentControlSet\Control\Session Manager'
+            name = "Example"
+            type = "dword"
+            data = 1
+
+            [[registry]]
+            hive = "NTUSER"
+            key = 'Control Panel\Desktop'
+            type = "REG_MULTI_SZ"
+            data = ["a", "b"]
+            "#,
+        )
+        .unwrap();
+        value["winpe"]["registry"] = tweaks["registry"].clone();
+        value["winpe"]["registry_files"] = tweaks["registry_files"].clone();
+
+        let config: GhostwinConfig = value.try_into().unwrap();
+        ConfigManager::validate_config(&config).unwrap();
+        assert_eq!(config.winpe.registry.len(), 2);
+        assert_eq!(config.winpe.registry[0].hive, RegistryHive::System);
+        assert_eq!(config.winpe.registry[1].name, "");
+        assert_eq!(config.winpe.registry[1].value_type, RegistryValueType::MultiSz);
+        assert_eq!(config.winpe.registry_files, vec!["tweaks/pe.reg"]);
+    }
+
+    #[test]
+    fn parses_driver_sources_and_policies_from_toml() {
+        let mut value: toml::Value = toml::Value::try_from(GhostwinConfig::default()).unwrap();
+        let drivers: toml::Value = toml::from_str(
+            r#"
+            paths = ["drivers/optiplex-7010"]
+            auto_download = false
+            inject_all = false
+            exclude = ["**/Audio/**"]
+
+            [class_policy]
+            SCSIAdapter = "inject"
+            Display = "stage"
+
+            [check]
+            max_sys_without_inf = 0
+            min_priority = 1
+            "#,
+        )
+        .unwrap();
+        value["drivers"] = drivers;
+
+        let config: GhostwinConfig = value.try_into().unwrap();
+        ConfigManager::validate_config(&config).unwrap();
+        assert_eq!(config.drivers.paths, vec!["drivers/optiplex-7010"]);
+        assert!(!config.drivers.inject_all);
+        assert!(config.drivers.scan_drives);
+        assert_eq!(config.drivers.class_policy["Display"], ClassPolicy::Stage);
+        assert!(!config.drivers.priority.is_empty());
+        assert_eq!(config.drivers.check.max_sys_without_inf, Some(0));
+        assert_eq!(config.drivers.check.min_priority, Some(1));
+        assert_eq!(config.drivers.check.max_unsigned, None);
+    }
+
+    #[test]
+    fn parses_and_validates_tool_interpreters() {
+        let mut value: toml::Value = toml::Value::try_from(GhostwinConfig::default()).unwrap();
+        let interpreters: toml::Value = toml::from_str(
+            r#"
+            [ahk]
+            command = ["AutoHotkey64.exe", "{path}", "{args}"]
+
+            [py]
+            command = []
+            "#,
+        )
+        .unwrap();
+        value["tools"]["interpreters"] = interpreters;
+
+        let mut config: GhostwinConfig = value.try_into().unwrap();
+        ConfigManager::validate_config(&config).unwrap();
+        assert!(!config.tools.interpreters["ahk"].executable);
+        assert!(config.tools.interpreters["py"].command.is_empty());
+
+        config.tools.interpreters.get_mut("ahk").unwrap().command = vec!["AutoHotkey64.exe".to_string()];
+        let error = ConfigManager::validate_config(&config).unwrap_err();
+        assert!(error.to_string().contains("must pass the tool as {path}"));
+    }
+
+    #[test]
+    fn enforcing_the_tool_manifest_needs_a_public_key() {
+        let mut config = GhostwinConfig::default();
+        config.security.enforce_tool_manifest = true;
+        let error = ConfigManager::validate_config(&config).unwrap_err();
+        assert!(error.to_string().contains("security.tool_manifest_public_key"));
+
+        config.security.tool_manifest_public_key = Some("not-hex".to_string());
+        let error = ConfigManager::validate_config(&config).unwrap_err();
+        assert!(error.to_string().contains("must be 64 hex characters"));
+
+        let temp = tempfile::tempdir().unwrap();
+        let public_key = crate::tools::integrity::tests::write_signing_key(&temp.path().join("signing.pem"), 7);
+        config.security.tool_manifest_public_key = Some(public_key);
+        ConfigManager::validate_config(&config).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_driver_globs() {
+        let mut config = GhostwinConfig::default();
+        config.drivers.include = vec!["net/[".to_string()];
+
+        let error = ConfigManager::validate_config(&config).unwrap_err();
+        assert!(format!("{:#}", error).contains("Invalid drivers.include pattern"));
+    }
+
+    #[test]
+    fn validates_install_image_selection() {
+        let mut config = GhostwinConfig::default();
+        config.drivers.install_image.indexes = vec!["Windows 11 Pro".to_string(), "3".to_string()];
+        ConfigManager::validate_config(&config).unwrap();
+
+        config.drivers.install_image.classes = Vec::new();
+        let error = ConfigManager::validate_config(&config).unwrap_err();
+        assert!(error.to_string().contains("drivers.install_image.classes"));
+
+        config.drivers.install_image.classes = vec!["*".to_string()];
+        config.drivers.install_image.indexes = vec!["0".to_string()];
+        let error = ConfigManager::validate_config(&config).unwrap_err();
+        assert!(error.to_string().contains("drivers.install_image.indexes"));
+    }
+
+    #[test]
+    fn rejects_registry_tweak_with_mismatched_data() {
+        let mut config = GhostwinConfig::default();
+        config.winpe.registry = vec![RegistryTweak {
+            hive: RegistryHive::Software,
+            key: r"Policies\Vendor".to_string(),
+            name: "Enabled".to_string(),
+            value_type: RegistryValueType::Dword,
+            data: RegistryData::Text("yes".to_string()),
+        }];
+
+        let error = ConfigManager::validate_config(&config).unwrap_err();
+        assert!(format!("{:#}", error).contains("invalid DWORD 'yes'"));
+    }
+
+    #[test]
+    fn saved_configs_keep_security_secrets() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = GhostwinConfig::default();
+        config.security.password_hash = Some("ab".repeat(32));
+        config.security.access_secret = Some("access-value".to_string());
+        config.security.vnc_password = Some("vncpass".to_string());
+
+        for file in ["ghostwin.toml", "ghostwin.json"] {
+            let path = temp.path().join(file);
+            ConfigManager::save_to_file(&config, &path).unwrap();
+            let loaded = ConfigManager::load_from_file(&path).unwrap();
+            assert_eq!(loaded.security.password_hash, config.security.password_hash, "{}", file);
+            assert_eq!(loaded.security.access_secret, config.security.access_secret, "{}", file);
+            assert_eq!(loaded.security.vnc_password, config.security.vnc_password, "{}", file);
+        }
+    }
 }
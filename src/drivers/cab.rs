@@ -0,0 +1,1349 @@
+use anyhow::{Context, Result, bail};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const CAB_SIGNATURE: &[u8; 4] = b"MSCF";
+const FLAG_PREV_CABINET: u16 = 0x0001;
+const FLAG_NEXT_CABINET: u16 = 0x0002;
+const FLAG_RESERVE_PRESENT: u16 = 0x0004;
+const ATTRIB_NAME_IS_UTF: u16 = 0x0080;
+/// `iFolder` values at or above this continue a folder from/into another cabinet
+const IFOLD_CONTINUED: u16 = 0xFFFD;
+
+/// Uncompressed bytes per CFDATA block, and per LZX frame
+const FRAME_SIZE: usize = 32768;
+/// MSZIP history carried from one CFDATA block into the next
+const MSZIP_WINDOW: usize = 32768;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Mszip,
+    Lzx { window_bits: u8 },
+}
+
+impl Compression {
+    fn from_type(value: u16) -> Result<Self> {
+        match value & 0x000F {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Mszip),
+            2 => bail!("Quantum-compressed CAB folders are not supported"),
+            3 => {
+                let window_bits = ((value >> 8) & 0x1F) as u8;
+                if !(15..=21).contains(&window_bits) {
+                    bail!("Invalid LZX window size 2^{}", window_bits);
+                }
+                Ok(Self::Lzx { window_bits })
+            }
+            other => bail!("Unknown CAB compression type {}", other),
+        }
+    }
+}
+
+/// `CFFOLDER`: a run of CFDATA blocks compressed as one stream
+#[derive(Debug, Clone)]
+pub struct CabFolder {
+    data_offset: u32,
+    data_blocks: u16,
+    pub compression: Compression,
+}
+
+/// `CFFILE`: a file stored at `offset` in its folder's uncompressed stream
+#[derive(Debug, Clone)]
+pub struct CabFile {
+    /// Path inside the cabinet, with `\` separators
+    pub name: String,
+    pub size: u32,
+    folder: u16,
+    offset: u32,
+}
+
+/// A single-volume Microsoft cabinet (MS-CAB)
+#[derive(Debug)]
+pub struct Cabinet {
+    pub path: PathBuf,
+    pub folders: Vec<CabFolder>,
+    pub files: Vec<CabFile>,
+    /// Per-CFDATA reserved bytes (`cbCFData`)
+    data_reserve: u8,
+}
+
+impl Cabinet {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        Self::read_header(path, &mut reader).with_context(|| format!("Invalid cabinet {}", path.display()))
+    }
+
+    fn read_header<R: Read + Seek>(path: &Path, reader: &mut R) -> Result<Self> {
+        let mut signature = [0u8; 4];
+        reader.read_exact(&mut signature)?;
+        if &signature != CAB_SIGNATURE {
+            bail!("missing MSCF signature");
+        }
+        read_u32(reader)?; // reserved1
+        read_u32(reader)?; // cbCabinet
+        read_u32(reader)?; // reserved2
+        let files_offset = read_u32(reader)?;
+        read_u32(reader)?; // reserved3
+        let minor = read_u8(reader)?;
+        let major = read_u8(reader)?;
+        if major != 1 {
+            bail!("unsupported cabinet version {}.{}", major, minor);
+        }
+        let folder_count = read_u16(reader)?;
+        let file_count = read_u16(reader)?;
+        let flags = read_u16(reader)?;
+        read_u16(reader)?; // setID
+        read_u16(reader)?; // iCabinet
+
+        let (mut folder_reserve, mut data_reserve) = (0u8, 0u8);
+        if flags & FLAG_RESERVE_PRESENT != 0 {
+            let header_reserve = read_u16(reader)?;
+            folder_reserve = read_u8(reader)?;
+            data_reserve = read_u8(reader)?;
+            reader.seek(SeekFrom::Current(i64::from(header_reserve)))?;
+        }
+        if flags & (FLAG_PREV_CABINET | FLAG_NEXT_CABINET) != 0 {
+            bail!("multi-volume cabinets are not supported");
+        }
+
+        let mut folders = Vec::with_capacity(usize::from(folder_count));
+        for _ in 0..folder_count {
+            let data_offset = read_u32(reader)?;
+            let data_blocks = read_u16(reader)?;
+            let compression = Compression::from_type(read_u16(reader)?)?;
+            reader.seek(SeekFrom::Current(i64::from(folder_reserve)))?;
+            folders.push(CabFolder { data_offset, data_blocks, compression });
+        }
+
+        reader.seek(SeekFrom::Start(u64::from(files_offset)))?;
+        let mut files = Vec::with_capacity(usize::from(file_count));
+        for _ in 0..file_count {
+            let size = read_u32(reader)?;
+            let offset = read_u32(reader)?;
+            let folder = read_u16(reader)?;
+            read_u16(reader)?; // date
+            read_u16(reader)?; // time
+            let attributes = read_u16(reader)?;
+            let raw_name = read_cstring(reader)?;
+            let name = if attributes & ATTRIB_NAME_IS_UTF != 0 {
+                String::from_utf8_lossy(&raw_name).into_owned()
+            } else {
+                // Code-page names; Latin-1 keeps ASCII intact and never fails
+                raw_name.iter().map(|&byte| char::from(byte)).collect()
+            };
+
+            if folder >= IFOLD_CONTINUED {
+                bail!("{} continues in another cabinet", name);
+            }
+            if usize::from(folder) >= folders.len() {
+                bail!("{} refers to missing folder {}", name, folder);
+            }
+            files.push(CabFile { name, size, folder, offset });
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            folders,
+            files,
+            data_reserve,
+        })
+    }
+
+    /// Extract every file below `destination`, returning the written paths
+    pub fn extract_all(&self, destination: &Path) -> Result<Vec<PathBuf>> {
+        let file = File::open(&self.path).with_context(|| format!("Failed to open {}", self.path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut extracted = Vec::with_capacity(self.files.len());
+
+        for (index, folder) in self.folders.iter().enumerate() {
+            let mut members: Vec<(&CabFile, PathBuf)> = Vec::new();
+            for file in self.files.iter().filter(|file| usize::from(file.folder) == index) {
+                members.push((file, destination.join(safe_relative_path(&file.name)?)));
+            }
+            if members.is_empty() {
+                continue;
+            }
+            members.sort_by_key(|(file, _)| file.offset);
+            extracted.extend(members.iter().map(|(_, path)| path.clone()));
+
+            self.extract_folder(&mut reader, folder, members)
+                .with_context(|| format!("Failed to extract folder {} of {}", index, self.path.display()))?;
+        }
+
+        Ok(extracted)
+    }
+
+    fn extract_folder(
+        &self,
+        reader: &mut BufReader<File>,
+        folder: &CabFolder,
+        members: Vec<(&CabFile, PathBuf)>,
+    ) -> Result<()> {
+        let mut writer = FolderWriter::new(members)?;
+        let mut decoder = FolderDecoder::new(folder.compression);
+        reader.seek(SeekFrom::Start(u64::from(folder.data_offset)))?;
+
+        for block in 0..folder.data_blocks {
+            if writer.is_complete() {
+                break;
+            }
+            read_u32(reader)?; // csum
+            let compressed_size = read_u16(reader)?;
+            let uncompressed_size = read_u16(reader)?;
+            reader.seek(SeekFrom::Current(i64::from(self.data_reserve)))?;
+            if uncompressed_size == 0 {
+                bail!("data block {} continues in another cabinet", block);
+            }
+            if usize::from(uncompressed_size) > FRAME_SIZE {
+                bail!("data block {} expands to more than {} bytes", block, FRAME_SIZE);
+            }
+
+            let mut data = vec![0u8; usize::from(compressed_size)];
+            reader.read_exact(&mut data)?;
+            let output = decoder
+                .decompress(&data, usize::from(uncompressed_size))
+                .with_context(|| format!("Corrupt data block {}", block))?;
+            writer.write(&output)?;
+        }
+
+        writer.finish()
+    }
+}
+
+/// Join a cabinet member name onto the extraction root without escaping it
+fn safe_relative_path(name: &str) -> Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.split(['\\', '/']) {
+        match component {
+            "" | "." => {}
+            ".." => bail!("Cabinet member {} escapes the extraction directory", name),
+            component if component.contains(':') => bail!("Cabinet member {} has an absolute path", name),
+            component => path.push(component),
+        }
+    }
+    if path.as_os_str().is_empty() {
+        bail!("Cabinet member has an empty name");
+    }
+    Ok(path)
+}
+
+/// Spreads a folder's uncompressed stream across the files stored in it
+struct FolderWriter {
+    /// Sorted by folder offset
+    members: Vec<(u64, u64, PathBuf)>,
+    next: usize,
+    open: Vec<(usize, BufWriter<File>)>,
+    position: u64,
+}
+
+impl FolderWriter {
+    fn new(members: Vec<(&CabFile, PathBuf)>) -> Result<Self> {
+        let members = members
+            .into_iter()
+            .map(|(file, path)| (u64::from(file.offset), u64::from(file.size), path))
+            .collect();
+        let mut writer = Self {
+            members,
+            next: 0,
+            open: Vec::new(),
+            position: 0,
+        };
+        writer.open_until(0)?;
+        Ok(writer)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.next == self.members.len() && self.open.is_empty()
+    }
+
+    /// Open members starting at or before `end`; empty ones are created and closed at once
+    fn open_until(&mut self, end: u64) -> Result<()> {
+        while let Some((offset, size, path)) = self.members.get(self.next) {
+            let starts_in_range = *offset < end || (*size == 0 && *offset <= end);
+            if !starts_in_range {
+                break;
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+            if *size > 0 {
+                self.open.push((self.next, BufWriter::new(file)));
+            }
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        let start = self.position;
+        let end = start + chunk.len() as u64;
+        self.open_until(end)?;
+
+        let mut index = 0;
+        while index < self.open.len() {
+            let member = self.open[index].0;
+            let (offset, size, _) = &self.members[member];
+            let member_end = offset + size;
+            let from = (*offset).max(start);
+            let to = member_end.min(end);
+            if from < to {
+                let slice = &chunk[(from - start) as usize..(to - start) as usize];
+                self.open[index].1.write_all(slice)?;
+            }
+
+            if member_end <= end {
+                let (member, mut file) = self.open.remove(index);
+                file.flush()
+                    .with_context(|| format!("Failed to write {}", self.members[member].2.display()))?;
+            } else {
+                index += 1;
+            }
+        }
+
+        self.position = end;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.open_until(self.position)?;
+        if let Some((member, _)) = self.open.first() {
+            bail!("Cabinet data ended before {} was complete", self.members[*member].2.display());
+        }
+        if let Some((_, _, path)) = self.members.get(self.next) {
+            bail!("Cabinet data ended before {} started", path.display());
+        }
+        Ok(())
+    }
+}
+
+enum FolderDecoder {
+    Stored,
+    Mszip(Vec<u8>),
+    Lzx(Box<LzxDecoder>),
+}
+
+impl FolderDecoder {
+    fn new(compression: Compression) -> Self {
+        match compression {
+            Compression::None => Self::Stored,
+            Compression::Mszip => Self::Mszip(Vec::new()),
+            Compression::Lzx { window_bits } => Self::Lzx(Box::new(LzxDecoder::new(window_bits))),
+        }
+    }
+
+    /// Decode one CFDATA block into exactly `size` bytes
+    fn decompress(&mut self, data: &[u8], size: usize) -> Result<Vec<u8>> {
+        let output = match self {
+            Self::Stored => data.to_vec(),
+            Self::Mszip(history) => inflate_mszip_block(history, data, size)?,
+            Self::Lzx(decoder) => decoder.decompress_frame(data, size)?,
+        };
+        if output.len() != size {
+            bail!("block decoded to {} bytes, expected {}", output.len(), size);
+        }
+        Ok(output)
+    }
+}
+
+/// Canonical Huffman decoding table, indexed by the next `bits` input bits
+struct Huffman {
+    /// `symbol << 5 | code length`; zero marks an unused code
+    table: Vec<u32>,
+    bits: u32,
+}
+
+impl Huffman {
+    /// `lsb_first` builds bit-reversed lookups for deflate's LSB-first bit order
+    fn new(lengths: &[u8], lsb_first: bool) -> Result<Self> {
+        let max = u32::from(lengths.iter().copied().max().unwrap_or(0));
+        if max > 16 {
+            bail!("Huffman code length {} exceeds 16", max);
+        }
+
+        let mut count = [0u32; 17];
+        for &length in lengths {
+            count[usize::from(length)] += 1;
+        }
+        count[0] = 0;
+        let mut next_code = [0u32; 17];
+        let mut code = 0;
+        for length in 1..17 {
+            code = (code + count[length - 1]) << 1;
+            next_code[length] = code;
+        }
+
+        let mut table = vec![0u32; 1 << max];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+            let length = u32::from(length);
+            let code = next_code[length as usize];
+            next_code[length as usize] += 1;
+            if code >= 1 << length {
+                bail!("over-subscribed Huffman code");
+            }
+
+            let entry = (symbol as u32) << 5 | length;
+            let fill = 1u32 << (max - length);
+            if lsb_first {
+                let reversed = code.reverse_bits() >> (32 - length);
+                for high in 0..fill {
+                    table[(reversed | high << length) as usize] = entry;
+                }
+            } else {
+                let base = code << (max - length);
+                for low in 0..fill {
+                    table[(base | low) as usize] = entry;
+                }
+            }
+        }
+
+        Ok(Self { table, bits: max })
+    }
+
+    fn entry(&self, index: u32) -> Result<(u16, u32)> {
+        match self.table[index as usize] {
+            0 => bail!("invalid Huffman code"),
+            entry => Ok(((entry >> 5) as u16, entry & 0x1F)),
+        }
+    }
+
+    fn decode_lsb(&self, bits: &mut LsbBits) -> Result<u16> {
+        let (symbol, length) = self.entry(bits.peek(self.bits))?;
+        bits.consume(length);
+        Ok(symbol)
+    }
+
+    fn decode_msb(&self, bits: &mut MsbBits) -> Result<u16> {
+        bits.ensure(self.bits);
+        let (symbol, length) = self.entry(bits.peek(self.bits))?;
+        bits.consume(length);
+        Ok(symbol)
+    }
+}
+
+/// Deflate bit reader; reads past the end return zero bits
+struct LsbBits<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl<'a> LsbBits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0, buffer: 0, count: 0 }
+    }
+
+    fn peek(&mut self, bits: u32) -> u32 {
+        while self.count < bits {
+            let byte = self.data.get(self.position).copied().unwrap_or(0);
+            self.position += 1;
+            self.buffer |= u64::from(byte) << self.count;
+            self.count += 8;
+        }
+        (self.buffer & ((1u64 << bits) - 1)) as u32
+    }
+
+    fn consume(&mut self, bits: u32) {
+        self.buffer >>= bits;
+        self.count -= bits;
+    }
+
+    fn read(&mut self, bits: u32) -> u32 {
+        let value = self.peek(bits);
+        self.consume(bits);
+        value
+    }
+
+    fn align_to_byte(&mut self) {
+        self.consume(self.count % 8);
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097,
+    6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Inflate one MSZIP block: `CK` followed by deflate blocks up to the final one
+///
+/// Back-references may reach into the previous block, so `history` keeps
+/// the last 32 KiB of output between calls.
+fn inflate_mszip_block(history: &mut Vec<u8>, data: &[u8], size: usize) -> Result<Vec<u8>> {
+    let Some(deflate) = data.strip_prefix(b"CK") else {
+        bail!("MSZIP block is missing its CK signature");
+    };
+    let mut bits = LsbBits::new(deflate);
+    let mut buffer = std::mem::take(history);
+    let start = buffer.len();
+    let limit = start + size;
+
+    loop {
+        let last = bits.read(1) == 1;
+        match bits.read(2) {
+            0 => {
+                bits.align_to_byte();
+                let length = bits.read(16);
+                if length != !bits.read(16) & 0xFFFF {
+                    bail!("stored deflate block length check failed");
+                }
+                for _ in 0..length {
+                    buffer.push(bits.read(8) as u8);
+                }
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths, true)?;
+                let distances = Huffman::new(&[5; 30], true)?;
+                inflate_codes(&mut bits, &literals, &distances, &mut buffer, limit)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_trees(&mut bits)?;
+                inflate_codes(&mut bits, &literals, &distances, &mut buffer, limit)?;
+            }
+            _ => bail!("invalid deflate block type"),
+        }
+        if buffer.len() > limit {
+            bail!("MSZIP block overflows its {} byte frame", size);
+        }
+        if last {
+            break;
+        }
+    }
+
+    let output = buffer[start..].to_vec();
+    let keep_from = buffer.len().saturating_sub(MSZIP_WINDOW);
+    buffer.drain(..keep_from);
+    *history = buffer;
+    Ok(output)
+}
+
+fn read_dynamic_trees(bits: &mut LsbBits) -> Result<(Huffman, Huffman)> {
+    let literal_count = bits.read(5) as usize + 257;
+    let distance_count = bits.read(5) as usize + 1;
+    let code_length_count = bits.read(4) as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = bits.read(3) as u8;
+    }
+    let code_length_tree = Huffman::new(&code_lengths, true)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let (value, repeat) = match code_length_tree.decode_lsb(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let Some(&previous) = index.checked_sub(1).and_then(|previous| lengths.get(previous)) else {
+                    bail!("deflate length repeat with no previous length");
+                };
+                (previous, bits.read(2) as usize + 3)
+            }
+            17 => (0, bits.read(3) as usize + 3),
+            _ => (0, bits.read(7) as usize + 11),
+        };
+        if index + repeat > lengths.len() {
+            bail!("deflate code lengths overflow");
+        }
+        lengths[index..index + repeat].fill(value);
+        index += repeat;
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count], true)?,
+        Huffman::new(&lengths[literal_count..], true)?,
+    ))
+}
+
+fn inflate_codes(
+    bits: &mut LsbBits,
+    literals: &Huffman,
+    distances: &Huffman,
+    buffer: &mut Vec<u8>,
+    limit: usize,
+) -> Result<()> {
+    loop {
+        let symbol = usize::from(literals.decode_lsb(bits)?);
+        if symbol < 256 {
+            buffer.push(symbol as u8);
+        } else if symbol == 256 {
+            return Ok(());
+        } else {
+            let index = symbol - 257;
+            if index >= LENGTH_BASE.len() {
+                bail!("invalid deflate length symbol {}", symbol);
+            }
+            let length = usize::from(LENGTH_BASE[index]) + bits.read(u32::from(LENGTH_EXTRA[index])) as usize;
+
+            let index = usize::from(distances.decode_lsb(bits)?);
+            if index >= DISTANCE_BASE.len() {
+                bail!("invalid deflate distance symbol {}", index);
+            }
+            let distance =
+                usize::from(DISTANCE_BASE[index]) + bits.read(u32::from(DISTANCE_EXTRA[index])) as usize;
+            if distance > buffer.len() {
+                bail!("deflate distance {} reaches before the start of the folder", distance);
+            }
+            let from = buffer.len() - distance;
+            for offset in 0..length {
+                let byte = buffer[from + offset];
+                buffer.push(byte);
+            }
+        }
+
+        if buffer.len() > limit {
+            bail!("MSZIP block overflows its frame");
+        }
+    }
+}
+
+/// LZX bit reader: 16-bit little-endian words consumed most significant bit
+/// first; reads past the end return zero bits
+struct MsbBits<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    left: u32,
+}
+
+impl<'a> MsbBits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0, buffer: 0, left: 0 }
+    }
+
+    /// Buffer at least `bits` (at most 17) bits
+    fn ensure(&mut self, bits: u32) {
+        while self.left < bits {
+            let low = self.data.get(self.position).copied().unwrap_or(0);
+            let high = self.data.get(self.position + 1).copied().unwrap_or(0);
+            self.position += 2;
+            self.buffer |= u32::from(u16::from_le_bytes([low, high])) << (16 - self.left);
+            self.left += 16;
+        }
+    }
+
+    fn peek(&self, bits: u32) -> u32 {
+        if bits == 0 { 0 } else { self.buffer >> (32 - bits) }
+    }
+
+    fn consume(&mut self, bits: u32) {
+        self.buffer = self.buffer.checked_shl(bits).unwrap_or(0);
+        self.left -= bits;
+    }
+
+    fn read(&mut self, bits: u32) -> u32 {
+        self.ensure(bits);
+        let value = self.peek(bits);
+        self.consume(bits);
+        value
+    }
+
+    /// Switch to byte reads for an uncompressed block: drop the rest of the
+    /// current 16-bit word, or a whole padding word when already aligned
+    fn align_for_bytes(&mut self) {
+        if self.left == 0 {
+            self.ensure(16);
+        }
+        self.buffer = 0;
+        self.left = 0;
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let Some(&byte) = self.data.get(self.position) else {
+            bail!("LZX uncompressed block is truncated");
+        };
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn u32_le(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes([self.byte()?, self.byte()?, self.byte()?, self.byte()?]))
+    }
+}
+
+const LZX_NUM_CHARS: usize = 256;
+const LZX_PRETREE_SYMBOLS: usize = 20;
+const LZX_LENGTH_SYMBOLS: usize = 249;
+const LZX_MIN_MATCH: usize = 2;
+const LZX_NUM_PRIMARY_LENGTHS: usize = 7;
+
+const LZX_BLOCK_VERBATIM: u32 = 1;
+const LZX_BLOCK_ALIGNED: u32 = 2;
+const LZX_BLOCK_UNCOMPRESSED: u32 = 3;
+
+/// Verbatim bits following each position slot
+const LZX_EXTRA_BITS: [u8; 52] = {
+    let mut table = [0u8; 52];
+    let mut slot = 0;
+    let mut bits = 0;
+    while slot < 52 {
+        table[slot] = bits;
+        table[slot + 1] = bits;
+        if slot != 0 && bits < 17 {
+            bits += 1;
+        }
+        slot += 2;
+    }
+    table
+};
+
+/// First match offset of each position slot
+const LZX_POSITION_BASE: [u32; 52] = {
+    let mut table = [0u32; 52];
+    let mut slot = 0;
+    let mut base = 0;
+    while slot < 52 {
+        table[slot] = base;
+        base += 1 << LZX_EXTRA_BITS[slot];
+        slot += 1;
+    }
+    table
+};
+
+/// LZX decoder state carried across the frames of one folder
+struct LzxDecoder {
+    window: Vec<u8>,
+    /// Bytes decoded so far; may run past the current frame when a match crosses it
+    total: u64,
+    frame_start: u64,
+    frames: u32,
+    main_lengths: Vec<u8>,
+    length_lengths: Vec<u8>,
+    main_tree: Huffman,
+    length_tree: Huffman,
+    aligned_tree: Huffman,
+    block_type: u32,
+    block_length: u32,
+    block_remaining: u32,
+    /// Repeated match offsets R0..R2
+    repeats: [u32; 3],
+    header_read: bool,
+    intel_file_size: i32,
+    intel_started: bool,
+}
+
+impl LzxDecoder {
+    fn new(window_bits: u8) -> Self {
+        let position_slots = match window_bits {
+            20 => 42,
+            21 => 50,
+            bits => usize::from(bits) * 2,
+        };
+        let main_elements = LZX_NUM_CHARS + position_slots * 8;
+        let empty = || Huffman { table: vec![0], bits: 0 };
+
+        Self {
+            window: vec![0; 1 << window_bits],
+            total: 0,
+            frame_start: 0,
+            frames: 0,
+            main_lengths: vec![0; main_elements],
+            length_lengths: vec![0; LZX_LENGTH_SYMBOLS],
+            main_tree: empty(),
+            length_tree: empty(),
+            aligned_tree: empty(),
+            block_type: 0,
+            block_length: 0,
+            block_remaining: 0,
+            repeats: [1, 1, 1],
+            header_read: false,
+            intel_file_size: 0,
+            intel_started: false,
+        }
+    }
+
+    /// Decode one CFDATA block, which holds exactly one LZX frame
+    fn decompress_frame(&mut self, data: &[u8], size: usize) -> Result<Vec<u8>> {
+        let mut bits = MsbBits::new(data);
+        if !self.header_read {
+            if bits.read(1) == 1 {
+                let high = bits.read(16);
+                let low = bits.read(16);
+                self.intel_file_size = (high << 16 | low) as i32;
+            }
+            self.header_read = true;
+        }
+
+        let frame_end = self.frame_start + size as u64;
+        while self.total < frame_end {
+            if self.block_remaining == 0 {
+                self.read_block_header(&mut bits)?;
+                continue;
+            }
+
+            if self.block_type == LZX_BLOCK_UNCOMPRESSED {
+                let run = u64::from(self.block_remaining).min(frame_end - self.total) as u32;
+                for _ in 0..run {
+                    let byte = bits.byte()?;
+                    self.push(byte);
+                }
+                self.block_remaining -= run;
+            } else {
+                let length = self.decode_element(&mut bits)?;
+                if length > self.block_remaining as usize {
+                    bail!("LZX match runs past the end of its block");
+                }
+                self.block_remaining -= length as u32;
+            }
+        }
+
+        let window_size = self.window.len() as u64;
+        let start = (self.frame_start % window_size) as usize;
+        let mut output = self.window[start..start + size].to_vec();
+        self.translate_e8(&mut output);
+
+        self.frame_start = frame_end;
+        self.frames += 1;
+        Ok(output)
+    }
+
+    fn read_block_header(&mut self, bits: &mut MsbBits) -> Result<()> {
+        if self.block_type == LZX_BLOCK_UNCOMPRESSED && self.block_length & 1 == 1 {
+            bits.byte()?; // padding after an odd-sized uncompressed block
+        }
+
+        self.block_type = bits.read(3);
+        let high = bits.read(16);
+        let low = bits.read(8);
+        self.block_length = high << 8 | low;
+        self.block_remaining = self.block_length;
+
+        match self.block_type {
+            LZX_BLOCK_ALIGNED => {
+                let mut lengths = [0u8; 8];
+                for length in &mut lengths {
+                    *length = bits.read(3) as u8;
+                }
+                self.aligned_tree = Huffman::new(&lengths, false)?;
+                self.read_main_and_length_trees(bits)
+            }
+            LZX_BLOCK_VERBATIM => self.read_main_and_length_trees(bits),
+            LZX_BLOCK_UNCOMPRESSED => {
+                self.intel_started = true;
+                bits.align_for_bytes();
+                for repeat in &mut self.repeats {
+                    *repeat = bits.u32_le()?;
+                }
+                Ok(())
+            }
+            other => bail!("invalid LZX block type {}", other),
+        }
+    }
+
+    fn read_main_and_length_trees(&mut self, bits: &mut MsbBits) -> Result<()> {
+        let main_elements = self.main_lengths.len();
+        read_tree_lengths(bits, &mut self.main_lengths[..LZX_NUM_CHARS])?;
+        read_tree_lengths(bits, &mut self.main_lengths[LZX_NUM_CHARS..main_elements])?;
+        self.main_tree = Huffman::new(&self.main_lengths, false)?;
+        if self.main_lengths[0xE8] != 0 {
+            self.intel_started = true;
+        }
+
+        read_tree_lengths(bits, &mut self.length_lengths)?;
+        self.length_tree = Huffman::new(&self.length_lengths, false)?;
+        Ok(())
+    }
+
+    /// Decode a literal or match from a verbatim or aligned block, returning its length
+    fn decode_element(&mut self, bits: &mut MsbBits) -> Result<usize> {
+        let symbol = usize::from(self.main_tree.decode_msb(bits)?);
+        if symbol < LZX_NUM_CHARS {
+            self.push(symbol as u8);
+            return Ok(1);
+        }
+
+        let element = symbol - LZX_NUM_CHARS;
+        let mut length = element & 7;
+        if length == LZX_NUM_PRIMARY_LENGTHS {
+            length += usize::from(self.length_tree.decode_msb(bits)?);
+        }
+        length += LZX_MIN_MATCH;
+
+        let slot = element >> 3;
+        let offset = match slot {
+            0 => self.repeats[0],
+            1 => {
+                self.repeats.swap(0, 1);
+                self.repeats[0]
+            }
+            2 => {
+                self.repeats.swap(0, 2);
+                self.repeats[0]
+            }
+            _ => {
+                let extra = u32::from(LZX_EXTRA_BITS[slot]);
+                let base = LZX_POSITION_BASE[slot] - 2;
+                let offset = if self.block_type == LZX_BLOCK_ALIGNED && extra >= 3 {
+                    let verbatim = bits.read(extra - 3) << 3;
+                    base + verbatim + u32::from(self.aligned_tree.decode_msb(bits)?)
+                } else {
+                    base + bits.read(extra)
+                };
+                self.repeats = [offset, self.repeats[0], self.repeats[1]];
+                offset
+            }
+        };
+
+        let offset = u64::from(offset);
+        if offset == 0 || offset > self.total || offset > self.window.len() as u64 {
+            bail!("LZX match offset {} is outside the window", offset);
+        }
+        let window_size = self.window.len() as u64;
+        for _ in 0..length {
+            let byte = self.window[((self.total - offset) % window_size) as usize];
+            self.push(byte);
+        }
+        Ok(length)
+    }
+
+    fn push(&mut self, byte: u8) {
+        let index = (self.total % self.window.len() as u64) as usize;
+        self.window[index] = byte;
+        self.total += 1;
+    }
+
+    /// Undo the encoder's x86 `CALL` (E8) absolute-address translation
+    fn translate_e8(&self, frame: &mut [u8]) {
+        let applies = self.intel_started && self.intel_file_size != 0 && self.frames < 32768 && frame.len() > 10;
+        if !applies {
+            return;
+        }
+
+        let file_size = self.intel_file_size;
+        let mut position = self.frame_start as i32;
+        let end = frame.len() - 10;
+        let mut index = 0;
+        while index < end {
+            if frame[index] != 0xE8 {
+                index += 1;
+                position = position.wrapping_add(1);
+                continue;
+            }
+
+            let operand = &mut frame[index + 1..index + 5];
+            let absolute = i32::from_le_bytes([operand[0], operand[1], operand[2], operand[3]]);
+            if absolute >= position.wrapping_neg() && absolute < file_size {
+                let relative = if absolute >= 0 {
+                    absolute.wrapping_sub(position)
+                } else {
+                    absolute.wrapping_add(file_size)
+                };
+                operand.copy_from_slice(&relative.to_le_bytes());
+            }
+            index += 5;
+            position = position.wrapping_add(5);
+        }
+    }
+}
+
+/// Read pretree-coded code lengths as deltas against the previous block's lengths
+fn read_tree_lengths(bits: &mut MsbBits, lengths: &mut [u8]) -> Result<()> {
+    let mut pretree_lengths = [0u8; LZX_PRETREE_SYMBOLS];
+    for length in &mut pretree_lengths {
+        *length = bits.read(4) as u8;
+    }
+    let pretree = Huffman::new(&pretree_lengths, false)?;
+
+    let delta = |previous: u8, symbol: u16| -> Result<u8> {
+        if symbol > 16 {
+            bail!("invalid LZX pretree delta {}", symbol);
+        }
+        Ok(((u16::from(previous) + 17 - symbol) % 17) as u8)
+    };
+
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = pretree.decode_msb(bits)?;
+        let (value, run) = match symbol {
+            17 => (0, bits.read(4) as usize + 4),
+            18 => (0, bits.read(5) as usize + 20),
+            19 => {
+                let run = bits.read(1) as usize + 4;
+                let symbol = pretree.decode_msb(bits)?;
+                (delta(lengths[index], symbol)?, run)
+            }
+            symbol => (delta(lengths[index], symbol)?, 1),
+        };
+        if index + run > lengths.len() {
+            bail!("LZX code lengths overflow their tree");
+        }
+        lengths[index..index + run].fill(value);
+        index += run;
+    }
+    Ok(())
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8> {
+    let mut bytes = [0u8; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_cstring<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    loop {
+        match read_u8(reader)? {
+            0 => return Ok(bytes),
+            byte if bytes.len() < 1024 => bytes.push(byte),
+            _ => bail!("file name is not terminated"),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{Cabinet, safe_relative_path};
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    /// One folder for `build_cab`: `typeCompress` and (compressed, uncompressed size) blocks
+    pub(crate) struct TestFolder {
+        pub compression: u16,
+        pub blocks: Vec<(Vec<u8>, u16)>,
+    }
+
+    /// Assemble a cabinet; `files` are (name, folder, offset, size)
+    pub(crate) fn build_cab(folders: &[TestFolder], files: &[(&str, u16, u32, u32)]) -> Vec<u8> {
+        let files_offset = 36 + 8 * folders.len();
+        let files_size: usize = files.iter().map(|(name, ..)| 17 + name.len()).sum();
+        let mut data_offset = files_offset + files_size;
+
+        let mut cab = Vec::new();
+        cab.extend(b"MSCF");
+        cab.extend(0u32.to_le_bytes());
+        cab.extend(0u32.to_le_bytes()); // cbCabinet, patched below
+        cab.extend(0u32.to_le_bytes());
+        cab.extend((files_offset as u32).to_le_bytes());
+        cab.extend(0u32.to_le_bytes());
+        cab.extend([3, 1]);
+        cab.extend((folders.len() as u16).to_le_bytes());
+        cab.extend((files.len() as u16).to_le_bytes());
+        cab.extend([0u8; 6]); // flags, setID, iCabinet
+
+        for folder in folders {
+            cab.extend((data_offset as u32).to_le_bytes());
+            cab.extend((folder.blocks.len() as u16).to_le_bytes());
+            cab.extend(folder.compression.to_le_bytes());
+            data_offset += folder.blocks.iter().map(|(data, _)| 8 + data.len()).sum::<usize>();
+        }
+        for (name, folder, offset, size) in files {
+            cab.extend(size.to_le_bytes());
+            cab.extend(offset.to_le_bytes());
+            cab.extend(folder.to_le_bytes());
+            cab.extend([0x21, 0x59, 0x00, 0x60, 0x20, 0x00]); // date, time, attributes
+            cab.extend(name.as_bytes());
+            cab.push(0);
+        }
+        for folder in folders {
+            for (data, size) in &folder.blocks {
+                cab.extend(0u32.to_le_bytes());
+                cab.extend((data.len() as u16).to_le_bytes());
+                cab.extend(size.to_le_bytes());
+                cab.extend(data);
+            }
+        }
+
+        let length = cab.len() as u32;
+        cab[8..12].copy_from_slice(&length.to_le_bytes());
+        cab
+    }
+
+    /// Uncompressed single-folder cabinet holding `files`
+    pub(crate) fn stored_cab(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        let mut entries = Vec::new();
+        for (name, data) in files {
+            entries.push((*name, 0, stream.len() as u32, data.len() as u32));
+            stream.extend(*data);
+        }
+        let blocks = stream.chunks(32768).map(|chunk| (chunk.to_vec(), chunk.len() as u16)).collect();
+        build_cab(&[TestFolder { compression: 0, blocks }], &entries)
+    }
+
+    /// Deflate-order bit writer: values LSB first, Huffman codes MSB first
+    #[derive(Default)]
+    struct DeflateWriter {
+        out: Vec<u8>,
+        buffer: u32,
+        count: u32,
+    }
+
+    impl DeflateWriter {
+        fn bits(&mut self, value: u32, count: u32) {
+            for bit in 0..count {
+                self.buffer |= (value >> bit & 1) << self.count;
+                self.count += 1;
+                if self.count == 8 {
+                    self.out.push(self.buffer as u8);
+                    self.buffer = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        fn code(&mut self, code: u32, length: u32) {
+            for bit in (0..length).rev() {
+                self.bits(code >> bit & 1, 1);
+            }
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            if self.count > 0 {
+                self.bits(0, 8 - self.count);
+            }
+            self.out
+        }
+    }
+
+    /// LZX-order bit writer: MSB first into 16-bit little-endian words
+    #[derive(Default)]
+    struct LzxWriter {
+        out: Vec<u8>,
+        buffer: u16,
+        count: u32,
+    }
+
+    impl LzxWriter {
+        fn bits(&mut self, value: u32, count: u32) {
+            for bit in (0..count).rev() {
+                self.buffer = self.buffer << 1 | (value >> bit & 1) as u16;
+                self.count += 1;
+                if self.count == 16 {
+                    self.out.extend(self.buffer.to_le_bytes());
+                    self.buffer = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        fn align(&mut self) {
+            if self.count > 0 {
+                self.bits(0, 16 - self.count);
+            }
+        }
+
+        fn block_header(&mut self, block_type: u32, length: u32) {
+            self.bits(block_type, 3);
+            self.bits(length >> 8, 16);
+            self.bits(length & 0xFF, 8);
+        }
+
+        /// Pretree giving `first` and `second` one-bit codes, then one code per length
+        fn tree(&mut self, first: u32, second: u32, codes: impl IntoIterator<Item = u32>) {
+            for symbol in 0..20 {
+                self.bits(u32::from(symbol == first || symbol == second), 4);
+            }
+            for code in codes {
+                self.bits(code, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn extracts_stored_files_into_nested_directories() {
+        let temp = tempdir().unwrap();
+        let sys: Vec<u8> = (0..40000u32).map(|index| (index % 251) as u8).collect();
+        let cab_path = temp.path().join("pack.cab");
+        std::fs::write(
+            &cab_path,
+            stored_cab(&[
+                ("x64\\net.inf", b"[Version]\r\nClass=Net\r\n"),
+                ("empty.txt", b""),
+                ("x64\\net.sys", &sys),
+                ("readme.txt", b"notes"),
+            ]),
+        )
+        .unwrap();
+
+        let cabinet = Cabinet::open(&cab_path).unwrap();
+        assert_eq!(cabinet.files.len(), 4);
+        let out = temp.path().join("out");
+        let extracted = cabinet.extract_all(&out).unwrap();
+        assert_eq!(extracted.len(), 4);
+
+        assert_eq!(std::fs::read(out.join("x64").join("net.inf")).unwrap(), b"[Version]\r\nClass=Net\r\n");
+        assert_eq!(std::fs::read(out.join("x64").join("net.sys")).unwrap(), sys);
+        assert_eq!(std::fs::read(out.join("readme.txt")).unwrap(), b"notes");
+        assert!(std::fs::read(out.join("empty.txt")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_members_that_escape_the_destination() {
+        assert!(safe_relative_path("..\\evil.inf").is_err());
+        assert!(safe_relative_path("C:\\Windows\\evil.inf").is_err());
+        assert_eq!(safe_relative_path("\\x64/./net.inf").unwrap(), std::path::Path::new("x64").join("net.inf"));
+
+        let temp = tempdir().unwrap();
+        let cab_path = temp.path().join("evil.cab");
+        std::fs::write(&cab_path, stored_cab(&[("..\\evil.inf", b"x")])).unwrap();
+        let out = temp.path().join("out");
+        assert!(Cabinet::open(&cab_path).unwrap().extract_all(&out).is_err());
+        assert!(!temp.path().join("evil.inf").exists());
+
+        std::fs::write(&cab_path, b"not a cabinet").unwrap();
+        assert!(format!("{:#}", Cabinet::open(&cab_path).unwrap_err()).contains("MSCF"));
+    }
+
+    #[test]
+    fn inflates_mszip_blocks_with_shared_history() {
+        let first: Vec<u8> = (0..32768u32).map(|index| (index * 7 % 251) as u8).collect();
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&first).unwrap();
+        let mut first_block = b"CK".to_vec();
+        first_block.extend(encoder.finish().unwrap());
+
+        // Fixed-Huffman block: "xy" then a 10 byte match reaching back into the first block
+        let mut writer = DeflateWriter::default();
+        writer.bits(1, 1);
+        writer.bits(1, 2);
+        writer.code(0x30 + u32::from(b'x'), 8);
+        writer.code(0x30 + u32::from(b'y'), 8);
+        writer.code(264 - 256, 7);
+        writer.code(29, 5);
+        writer.bits(32000 - 24577, 13);
+        writer.code(0, 7);
+        let mut second_block = b"CK".to_vec();
+        second_block.extend(writer.finish());
+
+        let temp = tempdir().unwrap();
+        let cab_path = temp.path().join("mszip.cab");
+        std::fs::write(
+            &cab_path,
+            build_cab(
+                &[TestFolder {
+                    compression: 1,
+                    blocks: vec![(first_block, 32768), (second_block, 12)],
+                }],
+                &[("first.bin", 0, 0, 32770), ("second.bin", 0, 32770, 10)],
+            ),
+        )
+        .unwrap();
+
+        let out = temp.path().join("out");
+        Cabinet::open(&cab_path).unwrap().extract_all(&out).unwrap();
+        let mut expected_first = first.clone();
+        expected_first.extend(b"xy");
+        assert_eq!(std::fs::read(out.join("first.bin")).unwrap(), expected_first);
+        assert_eq!(std::fs::read(out.join("second.bin")).unwrap(), &first[770..780]);
+    }
+
+    #[test]
+    fn decodes_lzx_frames_across_blocks() {
+        // Main tree: A-D, a slot 5 match (offset 4, length 8) and a repeated-offset match with a
+        // length footer, all with 3-bit codes; length tree codes footers 121 and 248
+        const MATCH_OFFSET_4: u32 = 256 + 5 * 8 + 6;
+        const REPEAT_MATCH: u32 = 256 + 7;
+        let main_code = |symbol: u32| match symbol {
+            65..=68 => symbol - 65,
+            REPEAT_MATCH => 4,
+            MATCH_OFFSET_4 => 5,
+            _ => unreachable!(),
+        };
+        let main_elements = 256 + 30 * 8;
+
+        let mut frame1 = LzxWriter::default();
+        frame1.bits(1, 1); // E8 translation with a 1 MiB file size
+        frame1.bits(0x0010, 16);
+        frame1.bits(0, 16);
+        frame1.block_header(1, 12 + 127 * 257 + 130);
+        let used = |symbol: u32| matches!(symbol, 65..=68 | REPEAT_MATCH | MATCH_OFFSET_4);
+        frame1.tree(0, 14, (0..256).map(|symbol| u32::from(used(symbol))));
+        frame1.tree(0, 14, (256..main_elements).map(|symbol| u32::from(used(symbol))));
+        frame1.tree(0, 16, (0..249).map(|footer| u32::from(footer == 121 || footer == 248)));
+        for symbol in b"ABCD" {
+            frame1.bits(main_code(u32::from(*symbol)), 3);
+        }
+        frame1.bits(main_code(MATCH_OFFSET_4), 3);
+        frame1.bits(0, 1);
+        for _ in 0..127 {
+            frame1.bits(main_code(REPEAT_MATCH), 3);
+            frame1.bits(1, 1);
+        }
+        // Runs 13 bytes into the second frame
+        frame1.bits(main_code(REPEAT_MATCH), 3);
+        frame1.bits(0, 1);
+        frame1.align();
+
+        // Uncompressed block of odd length holding a translated CALL at frame offset 15
+        let mut raw = vec![0x11, 0x22, 0xE8];
+        raw.extend((0x100i32 + 32768 + 15).to_le_bytes());
+        raw.extend([0x33; 14]);
+        let mut frame2 = LzxWriter::default();
+        frame2.block_header(3, raw.len() as u32);
+        // Uncompressed blocks start at the next word, skipping a whole word when already aligned
+        if frame2.count == 0 {
+            frame2.bits(0, 16);
+        } else {
+            frame2.align();
+        }
+        frame2.out.extend(4u32.to_le_bytes());
+        frame2.out.extend(1u32.to_le_bytes());
+        frame2.out.extend(1u32.to_le_bytes());
+        frame2.out.extend(&raw);
+        frame2.out.push(0); // padding
+        frame2.block_header(1, 4);
+        frame2.tree(0, 1, (0..256).map(|_| 0));
+        frame2.tree(0, 1, (256..main_elements).map(|_| 0));
+        frame2.tree(0, 1, (0..249).map(|_| 0));
+        for symbol in b"ABCD" {
+            frame2.bits(main_code(u32::from(*symbol)), 3);
+        }
+        frame2.align();
+
+        let mut expected: Vec<u8> = b"ABCD".iter().copied().cycle().take(32768 + 13).collect();
+        expected.extend([0x11, 0x22, 0xE8]);
+        expected.extend(0x100i32.to_le_bytes());
+        expected.extend([0x33; 14]);
+        expected.extend(b"ABCD");
+
+        let temp = tempdir().unwrap();
+        let cab_path = temp.path().join("lzx.cab");
+        let second_size = expected.len() - 32768;
+        std::fs::write(
+            &cab_path,
+            build_cab(
+                &[TestFolder {
+                    compression: 3 | 15 << 8,
+                    blocks: vec![(frame1.out, 32768), (frame2.out, second_size as u16)],
+                }],
+                &[("code.bin", 0, 0, expected.len() as u32)],
+            ),
+        )
+        .unwrap();
+
+        let out = temp.path().join("out");
+        Cabinet::open(&cab_path).unwrap().extract_all(&out).unwrap();
+        assert_eq!(std::fs::read(out.join("code.bin")).unwrap(), expected);
+    }
+}
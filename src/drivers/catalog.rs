@@ -0,0 +1,615 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use super::inf::{Architecture, InfFile};
+
+// DER-encoded OID bodies
+const OID_SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+const OID_CTL: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x0A, 0x01];
+const OID_SPC_INDIRECT_DATA: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x04];
+const OID_SPC_PE_IMAGE_DATA: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x0F];
+const OID_CAT_NAMEVALUE: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x0C, 0x02, 0x01];
+const OID_SHA1: &[u8] = &[0x2B, 0x0E, 0x03, 0x02, 0x1A];
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_BMP_STRING: u8 = 0x1E;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xA0;
+
+/// Outcome of checking a driver package against its catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogStatus {
+    /// The catalog has a SignerInfo and lists a matching hash for every referenced file
+    ///
+    /// Consistency only: neither the signature nor the signer's trust is checked.
+    CatalogConsistent,
+    /// No `CatalogFile`, the `.cat` is absent, unreadable, or has no signer
+    MissingCatalog,
+    /// A referenced file is missing or its hash is not in the catalog
+    HashMismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageVerification {
+    pub inf_file: PathBuf,
+    pub catalog: Option<PathBuf>,
+    pub status: CatalogStatus,
+    pub files_checked: usize,
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+}
+
+/// One catalog member: a file hash, optionally tagged with its file name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogMember {
+    pub algorithm: HashAlgorithm,
+    pub digest: Vec<u8>,
+    /// Authenticode PE image hash rather than a flat file hash
+    pub pe_image: bool,
+    pub file_name: Option<String>,
+}
+
+/// Parsed PKCS#7 security catalog (`.cat`)
+///
+/// Only the structure is read: `signers` counts SignerInfos, but their signatures,
+/// `messageDigest` attributes and certificate chains are left to Windows, which
+/// validates them when the driver is installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    pub members: Vec<CatalogMember>,
+    pub signers: usize,
+}
+
+impl Catalog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read catalog {}", path.display()))?;
+        Self::parse(&bytes).with_context(|| format!("Invalid catalog {}", path.display()))
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let (content_info, _) = Der::read(bytes)?;
+        let mut content_info = content_info.expect(TAG_SEQUENCE)?.children();
+        if content_info.next_tagged(TAG_OID)?.content != OID_SIGNED_DATA {
+            bail!("not a PKCS#7 SignedData structure");
+        }
+
+        let mut signed_data = content_info
+            .next_tagged(TAG_CONTEXT_0)?
+            .children()
+            .next_tagged(TAG_SEQUENCE)?
+            .children();
+        signed_data.next_tagged(TAG_INTEGER)?;
+        signed_data.next_tagged(TAG_SET)?;
+
+        let mut encapsulated = signed_data.next_tagged(TAG_SEQUENCE)?.children();
+        if encapsulated.next_tagged(TAG_OID)?.content != OID_CTL {
+            bail!("signed content is not a certificate trust list");
+        }
+        let ctl = encapsulated
+            .next_tagged(TAG_CONTEXT_0)?
+            .children()
+            .next_tagged(TAG_SEQUENCE)?;
+
+        // Certificates ([0]) and CRLs ([1]) are optional; signerInfos is the trailing SET
+        let signers = signed_data
+            .filter_map(|element| element.ok())
+            .filter(|element| element.tag == TAG_SET)
+            .last()
+            .map(|signer_infos| signer_infos.children().count())
+            .unwrap_or(0);
+
+        // subjectUsage, [listIdentifier], [sequenceNumber], thisUpdate, [nextUpdate],
+        // subjectAlgorithm, [trustedSubjects]: the third SEQUENCE holds the members
+        let trusted_subjects = ctl
+            .children()
+            .filter_map(|element| element.ok())
+            .filter(|element| element.tag == TAG_SEQUENCE)
+            .nth(2);
+
+        let mut members = Vec::new();
+        for subject in trusted_subjects.iter().flat_map(|subjects| subjects.children()) {
+            if let Some(member) = parse_member(&subject?)? {
+                members.push(member);
+            }
+        }
+
+        Ok(Self { members, signers })
+    }
+
+    /// Whether `data` (the contents of `file_name`) has a matching catalog entry
+    pub fn check_file(&self, file_name: &str, data: &[u8]) -> FileCheck {
+        let mut named = false;
+        for member in &self.members {
+            let names_file = member
+                .file_name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(file_name));
+            named |= names_file;
+
+            let ranges = if member.pe_image {
+                match authenticode_ranges(data) {
+                    Some(ranges) => ranges,
+                    None => continue,
+                }
+            } else {
+                std::iter::once(0..data.len()).collect()
+            };
+            if digest_ranges(member.algorithm, data, &ranges) == member.digest {
+                return FileCheck::Listed;
+            }
+        }
+
+        if named { FileCheck::Tampered } else { FileCheck::NotListed }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCheck {
+    Listed,
+    /// The catalog names the file, but with a different hash
+    Tampered,
+    NotListed,
+}
+
+fn parse_member(subject: &Der) -> Result<Option<CatalogMember>> {
+    let mut fields = subject.expect(TAG_SEQUENCE)?.children();
+    fields.next_tagged(TAG_OCTET_STRING)?;
+    let Some(attributes) = fields.next().transpose()? else {
+        return Ok(None);
+    };
+
+    let mut digest = None;
+    let mut file_name = None;
+    for attribute in attributes.children() {
+        let mut attribute = attribute?.children();
+        let kind = attribute.next_tagged(TAG_OID)?.content;
+        let Some(value) = attribute.next_tagged(TAG_SET)?.children().next().transpose()? else {
+            continue;
+        };
+
+        if kind == OID_SPC_INDIRECT_DATA {
+            // SpcIndirectDataContent { data { type, value }, DigestInfo { algorithm, digest } }
+            let mut indirect = value.children();
+            let data_type = indirect.next_tagged(TAG_SEQUENCE)?.children().next_tagged(TAG_OID)?.content;
+            let mut digest_info = indirect.next_tagged(TAG_SEQUENCE)?.children();
+            let algorithm = match digest_info.next_tagged(TAG_SEQUENCE)?.children().next_tagged(TAG_OID)?.content {
+                OID_SHA1 => HashAlgorithm::Sha1,
+                OID_SHA256 => HashAlgorithm::Sha256,
+                _ => return Ok(None),
+            };
+            let member_digest = digest_info.next_tagged(TAG_OCTET_STRING)?.content.to_vec();
+            digest = Some((algorithm, member_digest, data_type == OID_SPC_PE_IMAGE_DATA));
+        } else if kind == OID_CAT_NAMEVALUE {
+            // CatNameValue { tag BMPString, flags INTEGER, value OCTET STRING (UTF-16LE) }
+            let mut name_value = value.children();
+            let tag = utf16_be(name_value.next_tagged(TAG_BMP_STRING)?.content);
+            name_value.next_tagged(TAG_INTEGER)?;
+            let content = name_value.next_tagged(TAG_OCTET_STRING)?.content;
+            if tag.eq_ignore_ascii_case("File") {
+                file_name = Some(utf16_le(content));
+            }
+        }
+    }
+
+    Ok(digest.map(|(algorithm, digest, pe_image)| CatalogMember {
+        algorithm,
+        digest,
+        pe_image,
+        file_name,
+    }))
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn utf16_le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units).trim_end_matches('\0').to_string()
+}
+
+/// A DER element; only definite lengths are supported, as catalogs are DER
+#[derive(Debug, Clone, Copy)]
+struct Der<'a> {
+    tag: u8,
+    content: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    fn read(input: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let [tag, first, rest @ ..] = input else {
+            bail!("truncated DER element");
+        };
+        if tag & 0x1F == 0x1F {
+            bail!("multi-byte DER tags are not supported");
+        }
+
+        let (length, rest) = match *first {
+            length if length < 0x80 => (length as usize, rest),
+            0x80 => bail!("indefinite-length encoding is not DER"),
+            marker => {
+                let count = (marker & 0x7F) as usize;
+                if count > 4 || rest.len() < count {
+                    bail!("invalid DER length");
+                }
+                let length = rest[..count].iter().fold(0_usize, |length, byte| (length << 8) | *byte as usize);
+                (length, &rest[count..])
+            }
+        };
+        if rest.len() < length {
+            bail!("DER element overruns its container");
+        }
+
+        Ok((Self { tag: *tag, content: &rest[..length] }, &rest[length..]))
+    }
+
+    fn expect(self, tag: u8) -> Result<Self> {
+        if self.tag != tag {
+            bail!("expected DER tag {:#04x}, found {:#04x}", tag, self.tag);
+        }
+        Ok(self)
+    }
+
+    fn children(&self) -> DerChildren<'a> {
+        DerChildren { rest: self.content }
+    }
+}
+
+struct DerChildren<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> DerChildren<'a> {
+    fn next_tagged(&mut self, tag: u8) -> Result<Der<'a>> {
+        match self.next() {
+            Some(element) => element?.expect(tag),
+            None => bail!("missing DER element {:#04x}", tag),
+        }
+    }
+}
+
+impl<'a> Iterator for DerChildren<'a> {
+    type Item = Result<Der<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        match Der::read(self.rest) {
+            Ok((element, rest)) => {
+                self.rest = rest;
+                Some(Ok(element))
+            }
+            Err(error) => {
+                self.rest = &[];
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Byte ranges covered by the Authenticode PE image hash
+///
+/// Everything except the optional header checksum, the certificate table
+/// directory entry, and the certificate table itself.
+fn authenticode_ranges(data: &[u8]) -> Option<Vec<Range<usize>>> {
+    let read_u16 = |offset: usize| data.get(offset..offset + 2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]));
+    let read_u32 = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+
+    if data.get(..2) != Some(b"MZ") {
+        return None;
+    }
+    let pe = read_u32(0x3C)?;
+    if data.get(pe..pe + 4) != Some(b"PE\0\0") {
+        return None;
+    }
+    let optional_header = pe + 24;
+    let directories = match read_u16(optional_header)? {
+        0x10B => optional_header + 96,
+        0x20B => optional_header + 112,
+        _ => return None,
+    };
+    let checksum = optional_header + 64;
+
+    if read_u32(directories - 4)? < 5 {
+        return Some(vec![0..checksum, checksum + 4..data.len()]);
+    }
+    let certificate_entry = directories + 4 * 8;
+    let certificate_offset = read_u32(certificate_entry)?;
+    let certificate_size = read_u32(certificate_entry + 4)?;
+
+    let mut ranges = vec![0..checksum, checksum + 4..certificate_entry];
+    if certificate_size > 0 && certificate_offset >= certificate_entry + 8 && certificate_offset <= data.len() {
+        let certificate_end = (certificate_offset + certificate_size).min(data.len());
+        ranges.push(certificate_entry + 8..certificate_offset);
+        ranges.push(certificate_end..data.len());
+    } else {
+        ranges.push(certificate_entry + 8..data.len());
+    }
+    Some(ranges)
+}
+
+fn digest_ranges(algorithm: HashAlgorithm, data: &[u8], ranges: &[Range<usize>]) -> Vec<u8> {
+    fn hash<D: Digest>(data: &[u8], ranges: &[Range<usize>]) -> Vec<u8> {
+        let mut hasher = D::new();
+        for range in ranges {
+            hasher.update(&data[range.clone()]);
+        }
+        hasher.finalize().to_vec()
+    }
+
+    match algorithm {
+        HashAlgorithm::Sha1 => hash::<Sha1>(data, ranges),
+        HashAlgorithm::Sha256 => hash::<Sha256>(data, ranges),
+    }
+}
+
+/// Check a driver package's files against the catalog its INF names
+pub fn verify_package(inf: &InfFile, architecture: Architecture) -> PackageVerification {
+    let mut verification = PackageVerification {
+        inf_file: inf.path.clone(),
+        catalog: None,
+        status: CatalogStatus::MissingCatalog,
+        files_checked: 0,
+        problems: Vec::new(),
+    };
+    let package_dir = inf.path.parent().unwrap_or(Path::new("."));
+
+    let Some(catalog_name) = inf.catalog_for(architecture) else {
+        verification.problems.push("INF has no CatalogFile entry".to_string());
+        return verification;
+    };
+    let Some(catalog_path) = find_file(package_dir, None, catalog_name) else {
+        verification.problems.push(format!("catalog {} not found", catalog_name));
+        return verification;
+    };
+    verification.catalog = Some(catalog_path.clone());
+
+    let catalog = match Catalog::open(&catalog_path) {
+        Ok(catalog) => catalog,
+        Err(error) => {
+            verification.problems.push(format!("{:#}", error));
+            return verification;
+        }
+    };
+    if catalog.signers == 0 {
+        verification.problems.push(format!("catalog {} has no signer", catalog_name));
+        return verification;
+    }
+
+    // The INF itself, every file the target architecture ships, and CopyFiles targets present on disk
+    let mut files: Vec<(String, Option<PathBuf>, bool)> = vec![(
+        inf.path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        Some(inf.path.clone()),
+        true,
+    )];
+    for source in inf.source_files.iter().filter(|source| source.architecture.is_none_or(|arch| arch == architecture)) {
+        files.push((source.name.clone(), find_file(package_dir, source.subdir.as_deref(), &source.name), true));
+    }
+    for name in &inf.copy_files {
+        files.push((name.clone(), find_file(package_dir, None, name), false));
+    }
+
+    let mut seen: Vec<String> = Vec::new();
+    for (name, path, required) in files {
+        if seen.iter().any(|existing| existing.eq_ignore_ascii_case(&name)) {
+            continue;
+        }
+        let Some(path) = path else {
+            if required {
+                seen.push(name.clone());
+                verification.problems.push(format!("{} is referenced but missing", name));
+            }
+            continue;
+        };
+        seen.push(name.clone());
+
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(error) => {
+                verification.problems.push(format!("{} could not be read: {}", name, error));
+                continue;
+            }
+        };
+        verification.files_checked += 1;
+        match catalog.check_file(&name, &data) {
+            FileCheck::Listed => {}
+            FileCheck::Tampered => verification.problems.push(format!("{} does not match its catalog hash", name)),
+            FileCheck::NotListed => verification.problems.push(format!("{} is not listed in {}", name, catalog_name)),
+        }
+    }
+
+    verification.status = if verification.problems.is_empty() {
+        CatalogStatus::CatalogConsistent
+    } else {
+        CatalogStatus::HashMismatch
+    };
+    verification
+}
+
+/// Resolve a package-relative file, ignoring case in the final component
+fn find_file(package_dir: &Path, subdir: Option<&str>, name: &str) -> Option<PathBuf> {
+    let dir = match subdir {
+        Some(subdir) => package_dir.join(subdir.replace('\\', "/")),
+        None => package_dir.to_path_buf(),
+    };
+    let exact = dir.join(name);
+    if exact.is_file() {
+        return Some(exact);
+    }
+    std::fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .is_some_and(|file_name| file_name.to_string_lossy().eq_ignore_ascii_case(name))
+        })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{
+        Catalog, CatalogStatus, HashAlgorithm, OID_CAT_NAMEVALUE, OID_CTL, OID_SHA1, OID_SHA256, OID_SIGNED_DATA,
+        OID_SPC_INDIRECT_DATA, OID_SPC_PE_IMAGE_DATA, authenticode_ranges, digest_ranges, verify_package,
+    };
+    use crate::drivers::inf::{Architecture, InfFile};
+    use tempfile::tempdir;
+
+    /// Flat-file hash members use the SPC_CAB_DATA type
+    const OID_SPC_CAB_DATA: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x19];
+
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![tag];
+        match content.len() {
+            length if length < 0x80 => encoded.push(length as u8),
+            length if length <= 0xFF => encoded.extend([0x81, length as u8]),
+            length => encoded.extend([0x82, (length >> 8) as u8, length as u8]),
+        }
+        encoded.extend(content);
+        encoded
+    }
+
+    fn seq(parts: &[Vec<u8>]) -> Vec<u8> {
+        der(0x30, &parts.concat())
+    }
+
+    /// Minimal signed catalog with one member per (file name, data) pair
+    pub(crate) fn build_catalog(files: &[(&str, &[u8])], signed: bool) -> Vec<u8> {
+        let subjects: Vec<Vec<u8>> = files
+            .iter()
+            .map(|(name, data)| {
+                let pe_image = authenticode_ranges(data).is_some();
+                let ranges = authenticode_ranges(data).unwrap_or_else(|| std::iter::once(0..data.len()).collect());
+                let digest = digest_ranges(HashAlgorithm::Sha256, data, &ranges);
+                let data_type = if pe_image { OID_SPC_PE_IMAGE_DATA } else { OID_SPC_CAB_DATA };
+                let indirect = seq(&[
+                    seq(&[der(0x06, data_type)]),
+                    seq(&[seq(&[der(0x06, OID_SHA256), der(0x05, &[])]), der(0x04, &digest)]),
+                ]);
+                let name_utf16: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).chain([0, 0]).collect();
+                let tag: Vec<u8> = "File".encode_utf16().flat_map(u16::to_be_bytes).collect();
+                let name_value = seq(&[der(0x1E, &tag), der(0x02, &[0x10]), der(0x04, &name_utf16)]);
+                seq(&[
+                    der(0x04, &digest[..20]),
+                    der(
+                        0x31,
+                        &[
+                            seq(&[der(0x06, OID_SPC_INDIRECT_DATA), der(0x31, &indirect)]),
+                            seq(&[der(0x06, OID_CAT_NAMEVALUE), der(0x31, &name_value)]),
+                        ]
+                        .concat(),
+                    ),
+                ])
+            })
+            .collect();
+
+        let ctl = seq(&[
+            seq(&[der(0x06, &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x0C, 0x01, 0x01])]),
+            der(0x04, b"ghostwin-test"),
+            der(0x17, b"250101000000Z"),
+            seq(&[der(0x06, &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x0C, 0x01, 0x02])]),
+            seq(&subjects),
+        ]);
+        let signer_infos = if signed { der(0x31, &seq(&[der(0x02, &[1])])) } else { der(0x31, &[]) };
+        let signed_data = seq(&[
+            der(0x02, &[1]),
+            der(0x31, &seq(&[der(0x06, OID_SHA1)])),
+            seq(&[der(0x06, OID_CTL), der(0xA0, &ctl)]),
+            signer_infos,
+        ]);
+        seq(&[der(0x06, OID_SIGNED_DATA), der(0xA0, &signed_data)])
+    }
+
+    /// PE32+ image with a checksum and an attached certificate table
+    pub(crate) fn build_pe(body: &[u8], checksum: u32, certificate: &[u8]) -> Vec<u8> {
+        let mut image = vec![0_u8; 0x200];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3C..0x40].copy_from_slice(&0x80_u32.to_le_bytes());
+        image[0x80..0x84].copy_from_slice(b"PE\0\0");
+        let optional_header = 0x80 + 24;
+        image[optional_header..optional_header + 2].copy_from_slice(&0x20B_u16.to_le_bytes());
+        image[optional_header + 64..optional_header + 68].copy_from_slice(&checksum.to_le_bytes());
+        image[optional_header + 108..optional_header + 112].copy_from_slice(&16_u32.to_le_bytes());
+        image.extend(body);
+
+        let certificate_entry = optional_header + 112 + 32;
+        let offset = image.len() as u32;
+        image[certificate_entry..certificate_entry + 4].copy_from_slice(&offset.to_le_bytes());
+        image[certificate_entry + 4..certificate_entry + 8].copy_from_slice(&(certificate.len() as u32).to_le_bytes());
+        image.extend(certificate);
+        image
+    }
+
+    #[test]
+    fn authenticode_hash_ignores_checksum_and_certificate() {
+        let original = build_pe(b"driver code", 0x1111, b"signature-a");
+        let resigned = build_pe(b"driver code", 0x2222, b"signature-b-longer");
+        let patched = build_pe(b"DRIVER code", 0x1111, b"signature-a");
+
+        let hash = |image: &[u8]| digest_ranges(HashAlgorithm::Sha256, image, &authenticode_ranges(image).unwrap());
+        assert_eq!(hash(&original), hash(&resigned));
+        assert_ne!(hash(&original), hash(&patched));
+        assert!(authenticode_ranges(b"not a pe file").is_none());
+    }
+
+    #[test]
+    fn parses_catalog_members() {
+        let catalog = Catalog::parse(&build_catalog(&[("net.inf", b"[Version]")], true)).unwrap();
+        assert_eq!(catalog.signers, 1);
+        assert_eq!(catalog.members.len(), 1);
+        assert_eq!(catalog.members[0].algorithm, HashAlgorithm::Sha256);
+        assert_eq!(catalog.members[0].file_name.as_deref(), Some("net.inf"));
+        assert!(Catalog::parse(b"\x30\x80garbage").is_err());
+    }
+
+    #[test]
+    fn classifies_packages_by_catalog_consistency() {
+        let temp = tempdir().unwrap();
+        let inf_text = "[Version]\nClass=Net\nCatalogFile=net.cat\n[Manufacturer]\nContoso=Models,NTamd64\n\
+                        [Models.NTamd64]\nNIC=Install,PCI\\VEN_1234\n[Install]\nCopyFiles=@net.sys\n\
+                        [SourceDisksFiles]\nnet.sys=1\n";
+        let driver = build_pe(b"driver code", 0, b"sig");
+        let inf_path = temp.path().join("net.inf");
+        std::fs::write(&inf_path, inf_text).unwrap();
+        std::fs::write(temp.path().join("NET.SYS"), &driver).unwrap();
+
+        let verify = || verify_package(&InfFile::open(&inf_path).unwrap(), Architecture::Amd64);
+        let missing = verify();
+        assert_eq!(missing.status, CatalogStatus::MissingCatalog);
+        assert_eq!(missing.problems, vec!["catalog net.cat not found"]);
+
+        let catalog = build_catalog(&[("net.inf", inf_text.as_bytes()), ("net.sys", &driver)], true);
+        std::fs::write(temp.path().join("net.cat"), &catalog).unwrap();
+        let consistent = verify();
+        assert_eq!(consistent.status, CatalogStatus::CatalogConsistent, "{:?}", consistent.problems);
+        assert_eq!(consistent.files_checked, 2);
+
+        std::fs::write(temp.path().join("NET.SYS"), build_pe(b"patched code", 0, b"sig")).unwrap();
+        let tampered = verify();
+        assert_eq!(tampered.status, CatalogStatus::HashMismatch);
+        assert_eq!(tampered.problems, vec!["net.sys does not match its catalog hash"]);
+
+        std::fs::write(temp.path().join("net.cat"), build_catalog(&[("net.inf", inf_text.as_bytes())], false)).unwrap();
+        assert_eq!(verify().problems, vec!["catalog net.cat has no signer"]);
+    }
+}
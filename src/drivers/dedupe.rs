@@ -0,0 +1,263 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use crate::cli::DriverPin;
+use super::inf::{Architecture, InfFile};
+use super::{DetectedDriver, DriverType};
+
+/// A driver package dropped because another copy of the same driver was kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedDuplicate {
+    pub name: String,
+    pub path: PathBuf,
+    pub driver_ver: Option<String>,
+    pub kept: PathBuf,
+    pub reason: String,
+}
+
+/// Packages that may install the same driver: provider, class and INF file name match
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GroupKey {
+    provider: String,
+    class: String,
+    inf_name: String,
+}
+
+impl GroupKey {
+    fn new(inf: &InfFile) -> Self {
+        Self {
+            provider: inf.provider.as_deref().unwrap_or_default().to_ascii_lowercase(),
+            class: inf.class.as_deref().unwrap_or_default().to_ascii_lowercase(),
+            inf_name: inf
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Split same-key packages into groups whose hardware IDs overlap, directly or through
+/// another member, so a newer release that adds device IDs still replaces the old one
+fn overlapping_groups(packages: Vec<(usize, HashSet<String>)>) -> Vec<Vec<usize>> {
+    let mut groups: Vec<(Vec<usize>, HashSet<String>)> = Vec::new();
+    for (index, hardware_ids) in packages {
+        let mut members = vec![index];
+        let mut merged_ids = hardware_ids;
+        let mut position = 0;
+        while position < groups.len() {
+            if groups[position].1.is_disjoint(&merged_ids) {
+                position += 1;
+            } else {
+                let (group_members, group_ids) = groups.swap_remove(position);
+                members.extend(group_members);
+                merged_ids.extend(group_ids);
+            }
+        }
+        groups.push((members, merged_ids));
+    }
+
+    groups
+        .into_iter()
+        .map(|(mut members, _)| {
+            members.sort_unstable();
+            members
+        })
+        .collect()
+}
+
+/// Keep one INF package per group: the pinned one, otherwise the newest `DriverVer`
+///
+/// Ties keep the earlier package, so priority ordering from detection is
+/// preserved. `.sys` entries linked to a dropped INF are dropped with it.
+pub fn dedupe_drivers(
+    drivers: Vec<DetectedDriver>,
+    pins: &[DriverPin],
+    architecture: Architecture,
+) -> (Vec<DetectedDriver>, Vec<SkippedDuplicate>) {
+    let mut candidates: HashMap<GroupKey, Vec<(usize, HashSet<String>)>> = HashMap::new();
+    for (index, driver) in drivers.iter().enumerate() {
+        if driver.driver_type == DriverType::Inf
+            && let Some(inf) = &driver.metadata
+        {
+            let hardware_ids: HashSet<String> = inf.hardware_ids(architecture).map(str::to_ascii_lowercase).collect();
+            if !hardware_ids.is_empty() {
+                candidates.entry(GroupKey::new(inf)).or_default().push((index, hardware_ids));
+            }
+        }
+    }
+    let groups: Vec<Vec<usize>> = candidates.into_values().flat_map(overlapping_groups).collect();
+
+    for pin in pins {
+        if !drivers.iter().any(|driver| pin_matches(pin, driver)) {
+            warn!("Driver pin for {} matched no detected package", pin.inf);
+        }
+    }
+
+    let mut dropped: HashMap<usize, SkippedDuplicate> = HashMap::new();
+    for members in groups.iter().filter(|members| members.len() > 1) {
+        let pinned = members
+            .iter()
+            .copied()
+            .find(|index| pins.iter().any(|pin| pin_matches(pin, &drivers[*index])));
+
+        let kept = pinned.unwrap_or_else(|| {
+            members
+                .iter()
+                .copied()
+                .reduce(|best, candidate| {
+                    if driver_ver(&drivers[candidate]) > driver_ver(&drivers[best]) { candidate } else { best }
+                })
+                .unwrap_or(members[0])
+        });
+
+        for index in members.iter().copied().filter(|index| *index != kept) {
+            let driver = &drivers[index];
+            let reason = match pinned {
+                Some(_) => "pinned to another package".to_string(),
+                None => match driver_ver(&drivers[kept]) {
+                    Some(newer) if driver_ver(driver) < Some(newer) => format!("newer version {} kept", newer),
+                    _ => "same version already selected".to_string(),
+                },
+            };
+            dropped.insert(
+                index,
+                SkippedDuplicate {
+                    name: driver.name.clone(),
+                    path: driver.path.clone(),
+                    driver_ver: driver_ver(driver).map(|version| version.to_string()),
+                    kept: drivers[kept].path.clone(),
+                    reason,
+                },
+            );
+        }
+    }
+
+    let dropped_infs: Vec<PathBuf> = dropped.values().map(|skipped| skipped.path.clone()).collect();
+    let mut skipped: Vec<(usize, SkippedDuplicate)> = dropped.into_iter().collect();
+    skipped.sort_by_key(|(index, _)| *index);
+
+    let kept = drivers
+        .into_iter()
+        .enumerate()
+        .filter(|(index, driver)| {
+            let dropped_inf = skipped.iter().any(|(skipped_index, _)| skipped_index == index);
+            let linked_to_dropped_inf = driver.driver_type == DriverType::Sys
+                && driver.inf_file.as_ref().is_some_and(|inf| dropped_infs.contains(inf));
+            !dropped_inf && !linked_to_dropped_inf
+        })
+        .map(|(_, driver)| driver)
+        .collect();
+
+    (kept, skipped.into_iter().map(|(_, skipped)| skipped).collect())
+}
+
+/// Whether `pin` names this package by INF file name plus version or location
+fn pin_matches(pin: &DriverPin, driver: &DetectedDriver) -> bool {
+    let Some(inf) = &driver.metadata else {
+        return false;
+    };
+    let names_inf = inf
+        .path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(pin.inf.trim()));
+    if !names_inf {
+        return false;
+    }
+
+    let version_matches = pin.version.as_deref().is_none_or(|version| {
+        inf.driver_ver.is_some_and(|driver_ver| driver_ver.version_string() == version.trim())
+    });
+    let path_matches = pin.path.as_deref().is_none_or(|path| {
+        let pinned = Path::new(path.trim());
+        inf.path == pinned || inf.path.parent() == Some(pinned) || inf.path.starts_with(pinned)
+    });
+    version_matches && path_matches
+}
+
+fn driver_ver(driver: &DetectedDriver) -> Option<super::inf::DriverVer> {
+    driver.metadata.as_ref().and_then(|inf| inf.driver_ver)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::DriverPin;
+    use crate::drivers::DriverManager;
+    use tempfile::tempdir;
+
+    fn write_package(root: &std::path::Path, folder: &str, driver_ver: &str, hardware_ids: &str) {
+        let dir = root.join(folder);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("iaStorVD.inf"),
+            format!(
+                "[Version]\nClass=SCSIAdapter\nProvider=Intel\nDriverVer={}\n\
+                 [Manufacturer]\nIntel=Models,NTamd64\n[Models.NTamd64]\nVMD=Install,{}\n\
+                 [Install]\nCopyFiles=@iaStorVD.sys\n",
+                driver_ver, hardware_ids
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.join("iaStorVD.sys"), "binary").unwrap();
+    }
+
+    #[test]
+    fn keeps_newest_package_per_group_unless_pinned() {
+        let temp = tempdir().unwrap();
+        write_package(temp.path(), "rst_19_0", "01/10/2023,19.0.0.1000", r"PCI\VEN_8086&DEV_A77F");
+        write_package(temp.path(), "rst_19_5", "05/14/2024,19.5.2.1049", r"PCI\VEN_8086&DEV_A77F");
+        // Different hardware IDs are a different driver, even with the same INF name
+        write_package(temp.path(), "rst_raid", "01/10/2022,17.0.0.1000", r"PCI\VEN_8086&DEV_2822");
+
+        let mut manager = DriverManager::new();
+        manager.driver_paths.push(temp.path().to_path_buf());
+        let drivers = manager.detect_drivers().unwrap();
+        assert_eq!(drivers.iter().filter(|driver| driver.path.ends_with("iaStorVD.inf")).count(), 2);
+        assert_eq!(drivers.iter().filter(|driver| driver.path.ends_with("iaStorVD.sys")).count(), 2);
+        assert!(drivers.iter().all(|driver| !driver.path.starts_with(temp.path().join("rst_19_0"))));
+
+        let summary = manager.summarize_driver_risks(&drivers);
+        assert_eq!(summary.duplicates_skipped, 1);
+        assert_eq!(summary.duplicates[0].path, temp.path().join("rst_19_0").join("iaStorVD.inf"));
+        assert!(summary.duplicates[0].reason.contains("19.5.2.1049"));
+
+        manager.set_pins(vec![DriverPin {
+            inf: "IASTORVD.INF".to_string(),
+            version: Some("19.0.0.1000".to_string()),
+            path: None,
+        }]);
+        let drivers = manager.detect_drivers().unwrap();
+        assert!(drivers.iter().any(|driver| driver.path.starts_with(temp.path().join("rst_19_0"))));
+        assert!(drivers.iter().all(|driver| !driver.path.starts_with(temp.path().join("rst_19_5"))));
+        let skipped = manager.summarize_driver_risks(&drivers).duplicates;
+        assert_eq!(skipped[0].reason, "pinned to another package");
+    }
+
+    #[test]
+    fn groups_packages_with_overlapping_hardware_ids() {
+        let temp = tempdir().unwrap();
+        write_package(temp.path(), "rst_19_0", "01/10/2023,19.0.0.1000", r"PCI\VEN_8086&DEV_A77F");
+        // The newer release adds a device ID on top of the old one
+        write_package(
+            temp.path(),
+            "rst_19_5",
+            "05/14/2024,19.5.2.1049",
+            r"PCI\VEN_8086&DEV_A77F, PCI\VEN_8086&DEV_7D0B",
+        );
+        write_package(temp.path(), "rst_raid", "01/10/2022,17.0.0.1000", r"PCI\VEN_8086&DEV_2822");
+
+        let mut manager = DriverManager::new();
+        manager.driver_paths.push(temp.path().to_path_buf());
+        let drivers = manager.detect_drivers().unwrap();
+        let infs: Vec<_> = drivers.iter().filter(|driver| driver.path.ends_with("iaStorVD.inf")).collect();
+        assert_eq!(infs.len(), 2);
+        assert!(infs.iter().any(|driver| driver.path.starts_with(temp.path().join("rst_19_5"))));
+        assert!(infs.iter().any(|driver| driver.path.starts_with(temp.path().join("rst_raid"))));
+
+        let duplicates = manager.summarize_driver_risks(&drivers).duplicates;
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].path, temp.path().join("rst_19_0").join("iaStorVD.inf"));
+        assert_eq!(duplicates[0].kept, temp.path().join("rst_19_5").join("iaStorVD.inf"));
+    }
+}
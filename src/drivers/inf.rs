@@ -0,0 +1,708 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Processor architecture named by an INF `NT<arch>` decoration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Architecture {
+    X86,
+    Amd64,
+    Arm,
+    Arm64,
+    Ia64,
+}
+
+impl Architecture {
+    /// Parse the platform part of a decoration such as `NTamd64.10.0...16299`
+    ///
+    /// Returns `Some(None)` for an undecorated or bare `NT` target, which Windows
+    /// only honours on x86.
+    fn from_decoration(decoration: &str) -> Option<Option<Self>> {
+        let platform = decoration.split('.').next().unwrap_or("").to_ascii_lowercase();
+        let platform = platform.strip_prefix("nt")?;
+        match platform {
+            "" => Some(None),
+            "x86" => Some(Some(Self::X86)),
+            "amd64" => Some(Some(Self::Amd64)),
+            "arm" => Some(Some(Self::Arm)),
+            "arm64" => Some(Some(Self::Arm64)),
+            "ia64" => Some(Some(Self::Ia64)),
+            _ => None,
+        }
+    }
+
+    /// `SourceDisksFiles.<arch>` suffix
+    fn from_platform_suffix(suffix: &str) -> Option<Self> {
+        match suffix.to_ascii_lowercase().as_str() {
+            "x86" => Some(Self::X86),
+            "amd64" => Some(Self::Amd64),
+            "arm" => Some(Self::Arm),
+            "arm64" => Some(Self::Arm64),
+            "ia64" => Some(Self::Ia64),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for Architecture {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Self::from_platform_suffix(value.trim())
+            .with_context(|| format!("Unknown architecture {} (expected x86, amd64, arm, arm64 or ia64)", value))
+    }
+}
+
+impl fmt::Display for Architecture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::X86 => "x86",
+            Self::Amd64 => "amd64",
+            Self::Arm => "arm",
+            Self::Arm64 => "arm64",
+            Self::Ia64 => "ia64",
+        })
+    }
+}
+
+/// `DriverVer = mm/dd/yyyy[,w.x.y.z]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DriverVer {
+    // Field order gives the derived ordering Windows uses: date first, then version
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub version: [u32; 4],
+}
+
+impl DriverVer {
+    fn parse(value: &[String]) -> Option<Self> {
+        let mut date = value.first()?.split(['/', '-']);
+        let month = date.next()?.trim().parse().ok()?;
+        let day = date.next()?.trim().parse().ok()?;
+        let year = date.next()?.trim().parse().ok()?;
+
+        let mut version = [0_u32; 4];
+        if let Some(text) = value.get(1) {
+            for (slot, part) in version.iter_mut().zip(text.split('.')) {
+                *slot = part.trim().parse().ok()?;
+            }
+        }
+
+        Some(Self { year, month, day, version })
+    }
+}
+
+impl DriverVer {
+    /// `w.x.y.z`
+    pub fn version_string(&self) -> String {
+        self.version.map(|part| part.to_string()).join(".")
+    }
+}
+
+impl fmt::Display for DriverVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}/{:02}/{:04},{}", self.month, self.day, self.year, self.version_string())
+    }
+}
+
+/// One `[Manufacturer]` line and the model sections it points at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InfManufacturer {
+    pub name: String,
+    pub models_section: String,
+    /// Target decorations as written, e.g. `NTamd64.10.0...16299`
+    pub decorations: Vec<String>,
+}
+
+/// A device line from a models section
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InfModel {
+    pub description: String,
+    pub install_section: String,
+    /// Hardware ID followed by any compatible IDs
+    pub hardware_ids: Vec<String>,
+    /// `None` for undecorated (x86-only) model sections
+    pub architecture: Option<Architecture>,
+}
+
+impl InfModel {
+    /// Undecorated model sections only install on x86
+    pub fn applies_to(&self, architecture: Architecture) -> bool {
+        self.architecture.map_or(architecture == Architecture::X86, |candidate| candidate == architecture)
+    }
+}
+
+/// A `[SourceDisksFiles]` entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InfSourceFile {
+    pub name: String,
+    /// Directory relative to the INF, when the entry names one
+    pub subdir: Option<String>,
+    pub architecture: Option<Architecture>,
+}
+
+/// Parsed driver INF
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InfFile {
+    pub path: PathBuf,
+    pub class: Option<String>,
+    pub class_guid: Option<String>,
+    pub provider: Option<String>,
+    pub driver_ver: Option<DriverVer>,
+    /// Undecorated `CatalogFile`
+    pub catalog_file: Option<String>,
+    /// `CatalogFile.NT<arch>` entries
+    pub arch_catalog_files: Vec<(Architecture, String)>,
+    pub manufacturers: Vec<InfManufacturer>,
+    pub models: Vec<InfModel>,
+    /// Destination file names pulled in by every `CopyFiles` directive
+    pub copy_files: Vec<String>,
+    pub source_files: Vec<InfSourceFile>,
+    /// Architectures named by `[DefaultInstall.NT<arch>]` sections (primitive drivers)
+    default_install_architectures: Vec<Option<Architecture>>,
+}
+
+impl InfFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read INF {}", path.display()))?;
+        Ok(Self::parse(path, &decode_inf_text(&bytes)))
+    }
+
+    pub fn parse(path: &Path, text: &str) -> Self {
+        let sections = Sections::parse(text);
+        let version = sections.get("Version");
+
+        let mut inf = Self {
+            path: path.to_path_buf(),
+            class: version.and_then(|lines| first_value(lines, "Class")),
+            class_guid: version.and_then(|lines| first_value(lines, "ClassGuid")),
+            provider: version.and_then(|lines| first_value(lines, "Provider")),
+            driver_ver: version
+                .and_then(|lines| lines.iter().find(|line| line.is_key("DriverVer")))
+                .and_then(|line| DriverVer::parse(&line.values)),
+            catalog_file: version.and_then(|lines| first_value(lines, "CatalogFile")),
+            arch_catalog_files: Vec::new(),
+            manufacturers: Vec::new(),
+            models: Vec::new(),
+            copy_files: Vec::new(),
+            source_files: Vec::new(),
+            default_install_architectures: Vec::new(),
+        };
+
+        for line in version.into_iter().flatten() {
+            if let Some(key) = &line.key
+                && let Some((name, decoration)) = key.split_once('.')
+                && name.eq_ignore_ascii_case("CatalogFile")
+                && let Some(Some(architecture)) = Architecture::from_decoration(decoration)
+                && let Some(file) = line.values.first()
+            {
+                inf.arch_catalog_files.push((architecture, file.clone()));
+            }
+        }
+
+        for line in sections.get("Manufacturer").into_iter().flatten() {
+            let Some(models_section) = line.values.first().filter(|value| !value.is_empty()) else {
+                continue;
+            };
+            inf.manufacturers.push(InfManufacturer {
+                name: line.key.clone().unwrap_or_default(),
+                models_section: models_section.clone(),
+                decorations: line.values[1..].iter().filter(|value| !value.is_empty()).cloned().collect(),
+            });
+        }
+
+        for manufacturer in &inf.manufacturers {
+            let mut targets: Vec<(String, Option<Architecture>)> = manufacturer
+                .decorations
+                .iter()
+                .filter_map(|decoration| {
+                    Architecture::from_decoration(decoration).map(|architecture| {
+                        (format!("{}.{}", manufacturer.models_section, decoration), architecture)
+                    })
+                })
+                .collect();
+            targets.push((manufacturer.models_section.clone(), None));
+
+            for (section, architecture) in targets {
+                for line in sections.get(&section).into_iter().flatten() {
+                    let Some(install_section) = line.values.first() else {
+                        continue;
+                    };
+                    inf.models.push(InfModel {
+                        description: line.key.clone().unwrap_or_default(),
+                        install_section: install_section.clone(),
+                        hardware_ids: line.values[1..].iter().filter(|id| !id.is_empty()).cloned().collect(),
+                        architecture,
+                    });
+                }
+            }
+        }
+
+        for (name, lines) in &sections.sections {
+            if let Some(suffix) = name.strip_prefix("defaultinstall") {
+                let architecture = match suffix.strip_prefix('.') {
+                    Some(decoration) => Architecture::from_decoration(decoration).unwrap_or(None),
+                    None => None,
+                };
+                inf.default_install_architectures.push(architecture);
+            }
+
+            if name == "sourcedisksfiles" || name.starts_with("sourcedisksfiles.") {
+                let architecture = name
+                    .strip_prefix("sourcedisksfiles.")
+                    .and_then(Architecture::from_platform_suffix);
+                for line in lines {
+                    let Some(file) = line.key.as_ref().or(line.values.first()) else {
+                        continue;
+                    };
+                    let subdir = line
+                        .key
+                        .as_ref()
+                        .and(line.values.get(1))
+                        .filter(|subdir| !subdir.is_empty())
+                        .cloned();
+                    inf.source_files.push(InfSourceFile {
+                        name: file.clone(),
+                        subdir,
+                        architecture,
+                    });
+                }
+            }
+
+            for line in lines.iter().filter(|line| line.is_key("CopyFiles")) {
+                for target in &line.values {
+                    if let Some(file) = target.strip_prefix('@') {
+                        inf.copy_files.push(file.trim().to_string());
+                        continue;
+                    }
+                    for file_line in sections.get(target).into_iter().flatten() {
+                        // File-list lines are `destination[,source][,,flags]`
+                        if let Some(file) = file_line.values.first().or(file_line.key.as_ref())
+                            && !file.is_empty()
+                        {
+                            inf.copy_files.push(file.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        inf.copy_files.sort_by_key(|file| file.to_ascii_lowercase());
+        inf.copy_files.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        inf
+    }
+
+    /// Whether the INF installs or ships `file_name` (case-insensitive)
+    pub fn references_file(&self, file_name: &str) -> bool {
+        self.copy_files.iter().any(|file| file.eq_ignore_ascii_case(file_name))
+            || self.source_files.iter().any(|file| file.name.eq_ignore_ascii_case(file_name))
+    }
+
+    /// Architectures with installable models; empty when the INF names none
+    pub fn architectures(&self) -> Vec<Option<Architecture>> {
+        let mut architectures: Vec<Option<Architecture>> = if self.models.is_empty() {
+            self.default_install_architectures.clone()
+        } else {
+            self.models.iter().map(|model| model.architecture).collect()
+        };
+        architectures.sort_by_key(|architecture| architecture.map(|arch| arch as u8 + 1).unwrap_or(0));
+        architectures.dedup();
+        architectures
+    }
+
+    /// Whether Windows on `architecture` would install anything from this INF
+    ///
+    /// Undecorated sections only apply to x86; INFs without any model or
+    /// `DefaultInstall` section are treated as architecture-neutral.
+    pub fn supports(&self, architecture: Architecture) -> bool {
+        let architectures = self.architectures();
+        architectures.is_empty()
+            || architectures.iter().any(|candidate| match candidate {
+                Some(candidate) => *candidate == architecture,
+                None => architecture == Architecture::X86,
+            })
+    }
+
+    /// Catalog for `architecture`, preferring the decorated entry
+    pub fn catalog_for(&self, architecture: Architecture) -> Option<&str> {
+        self.arch_catalog_files
+            .iter()
+            .find(|(candidate, _)| *candidate == architecture)
+            .map(|(_, file)| file.as_str())
+            .or(self.catalog_file.as_deref())
+    }
+
+    pub fn hardware_ids(&self, architecture: Architecture) -> impl Iterator<Item = &str> {
+        self.models
+            .iter()
+            .filter(move |model| model.applies_to(architecture))
+            .flat_map(|model| model.hardware_ids.iter().map(String::as_str))
+    }
+}
+
+/// INF text is usually UTF-16LE with a BOM, otherwise ANSI/UTF-8
+pub fn decode_inf_text(bytes: &[u8]) -> String {
+    let utf16 = |rest: &[u8], from: fn([u8; 2]) -> u16| {
+        String::from_utf16_lossy(
+            &rest
+                .chunks_exact(2)
+                .map(|pair| from([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InfLine {
+    key: Option<String>,
+    values: Vec<String>,
+}
+
+impl InfLine {
+    fn is_key(&self, wanted: &str) -> bool {
+        self.key.as_deref().is_some_and(|key| key.eq_ignore_ascii_case(wanted))
+    }
+}
+
+/// Sections keyed by lowercase name; repeated sections are merged
+struct Sections {
+    sections: Vec<(String, Vec<InfLine>)>,
+}
+
+impl Sections {
+    fn parse(text: &str) -> Self {
+        let logical_lines = join_continuations(text);
+
+        // First pass: raw lines per section, so [Strings] can be applied everywhere
+        let mut raw: Vec<(String, Vec<String>)> = Vec::new();
+        let mut current = None;
+        for line in logical_lines {
+            let line = strip_comment(&line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.split_once(']')).map(|(name, _)| name) {
+                let name = name.trim().to_ascii_lowercase();
+                current = Some(match raw.iter().position(|(existing, _)| *existing == name) {
+                    Some(index) => index,
+                    None => {
+                        raw.push((name, Vec::new()));
+                        raw.len() - 1
+                    }
+                });
+                continue;
+            }
+            if let Some(index) = current {
+                raw[index].1.push(line.to_string());
+            }
+        }
+
+        let mut strings = HashMap::new();
+        // Localized tables fill gaps left by the base [Strings] section
+        let mut string_sections: Vec<&(String, Vec<String>)> = raw
+            .iter()
+            .filter(|(name, _)| name == "strings" || name.starts_with("strings."))
+            .collect();
+        string_sections.sort_by_key(|(name, _)| name != "strings");
+        for (_, lines) in string_sections {
+            for line in lines {
+                if let Some((key, value)) = line.split_once('=') {
+                    let value = split_values(value).into_iter().next().unwrap_or_default();
+                    strings.entry(key.trim().to_ascii_lowercase()).or_insert(value);
+                }
+            }
+        }
+
+        let sections = raw
+            .iter()
+            .map(|(name, lines)| {
+                let parsed = lines
+                    .iter()
+                    .map(|line| {
+                        let (key, values) = match split_key(line) {
+                            Some((key, values)) => (Some(substitute(&unquote(key), &strings)), values),
+                            None => (None, line.as_str()),
+                        };
+                        InfLine {
+                            key,
+                            values: split_values(values)
+                                .into_iter()
+                                .map(|value| substitute(&value, &strings))
+                                .collect(),
+                        }
+                    })
+                    .collect();
+                (name.clone(), parsed)
+            })
+            .collect();
+
+        Self { sections }
+    }
+
+    fn get(&self, name: &str) -> Option<&Vec<InfLine>> {
+        let name = name.trim().to_ascii_lowercase();
+        self.sections
+            .iter()
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, lines)| lines)
+    }
+}
+
+fn first_value(lines: &[InfLine], key: &str) -> Option<String> {
+    lines
+        .iter()
+        .find(|line| line.is_key(key))
+        .and_then(|line| line.values.first().cloned())
+        .filter(|value| !value.is_empty())
+}
+
+/// Join lines ending in `\` (outside comments) with the following line
+fn join_continuations(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending = String::new();
+    for line in text.lines() {
+        let content = strip_comment(line);
+        match content.trim_end().strip_suffix('\\') {
+            Some(head) => {
+                pending.push_str(head);
+                pending.push(' ');
+            }
+            None => {
+                pending.push_str(line);
+                lines.push(std::mem::take(&mut pending));
+            }
+        }
+    }
+    if !pending.is_empty() {
+        lines.push(pending);
+    }
+    lines
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let mut in_quotes = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => in_quotes = !in_quotes,
+            '=' if !in_quotes => return Some((&line[..index], &line[index + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Comma-separated fields with quotes removed (`""` inside quotes is a literal quote)
+fn split_values(text: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut characters = text.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '"' if in_quotes && characters.peek() == Some(&'"') => {
+                current.push('"');
+                characters.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => values.push(std::mem::take(&mut current).trim().to_string()),
+            _ => current.push(character),
+        }
+    }
+    values.push(current.trim().to_string());
+    values
+}
+
+fn unquote(text: &str) -> String {
+    split_values(text).into_iter().next().unwrap_or_default()
+}
+
+/// Replace `%token%` with its [Strings] value; `%%` is a literal percent sign
+fn substitute(text: &str, strings: &HashMap<String, String>) -> String {
+    if !text.contains('%') {
+        return text.to_string();
+    }
+
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        let token = &after[..end];
+        if token.is_empty() {
+            output.push('%');
+        } else {
+            match strings.get(&token.to_ascii_lowercase()) {
+                Some(value) => output.push_str(value),
+                None => {
+                    output.push('%');
+                    output.push_str(token);
+                    output.push('%');
+                }
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Architecture, DriverVer, InfFile, decode_inf_text};
+    use std::path::Path;
+
+    const STORAGE_INF: &str = r#"
+; Intel RST VMD controller
+[Version]
+Signature   = "$WINDOWS NT$"
+Class       = SCSIAdapter
+ClassGUID   = {4D36E97B-E325-11CE-BFC1-08002BE10318}
+Provider    = %INTEL%
+CatalogFile = iaStorVD.cat
+CatalogFile.NTarm64 = iaStorVD_arm64.cat
+DriverVer   = 05/14/2024,19.5.2.1049
+
+[Manufacturer]
+%INTEL% = INTEL_HDC, NTamd64.10.0...16299, NTarm64
+
+[INTEL_HDC.NTamd64.10.0...16299]
+%VMD.DeviceDesc% = iaStorVD_Inst, PCI\VEN_8086&DEV_A77F&CC_0104, \
+    PCI\VEN_8086&CC_0104
+
+[INTEL_HDC.NTarm64]
+%VMD.DeviceDesc% = iaStorVD_Inst, PCI\VEN_8086&DEV_ABCD
+
+[iaStorVD_Inst]
+CopyFiles = iaStorVD_Files, @iaStorVD.ini
+
+[iaStorVD_Files]
+iaStorVD.sys,,,0x100 ; kernel driver
+
+[SourceDisksFiles.amd64]
+iaStorVD.sys = 1, x64
+
+[Strings]
+INTEL = "Intel Corporation"
+VMD.DeviceDesc = "Intel RST VMD Controller ""A77F"""
+
+[Strings.0409]
+INTEL = "Ignored localized name"
+"#;
+
+    #[test]
+    fn parses_version_models_and_files() {
+        let inf = InfFile::parse(Path::new("iaStorVD.inf"), STORAGE_INF);
+
+        assert_eq!(inf.class.as_deref(), Some("SCSIAdapter"));
+        assert_eq!(inf.class_guid.as_deref(), Some("{4D36E97B-E325-11CE-BFC1-08002BE10318}"));
+        assert_eq!(inf.provider.as_deref(), Some("Intel Corporation"));
+        assert_eq!(
+            inf.driver_ver,
+            Some(DriverVer {
+                year: 2024,
+                month: 5,
+                day: 14,
+                version: [19, 5, 2, 1049],
+            })
+        );
+        assert_eq!(inf.driver_ver.unwrap().to_string(), "05/14/2024,19.5.2.1049");
+        assert_eq!(inf.catalog_for(Architecture::Amd64), Some("iaStorVD.cat"));
+        assert_eq!(inf.catalog_for(Architecture::Arm64), Some("iaStorVD_arm64.cat"));
+
+        assert_eq!(inf.manufacturers[0].decorations, vec!["NTamd64.10.0...16299", "NTarm64"]);
+        assert_eq!(inf.models.len(), 2);
+        assert_eq!(inf.models[0].description, "Intel RST VMD Controller \"A77F\"");
+        assert_eq!(inf.models[0].architecture, Some(Architecture::Amd64));
+        assert_eq!(
+            inf.hardware_ids(Architecture::Amd64).collect::<Vec<_>>(),
+            vec![r"PCI\VEN_8086&DEV_A77F&CC_0104", r"PCI\VEN_8086&CC_0104"]
+        );
+
+        assert!(inf.references_file("IASTORVD.SYS"));
+        assert!(inf.references_file("iaStorVD.ini"));
+        assert!(!inf.references_file("other.sys"));
+        assert_eq!(inf.source_files[0].subdir.as_deref(), Some("x64"));
+        assert_eq!(inf.source_files[0].architecture, Some(Architecture::Amd64));
+
+        assert!(inf.supports(Architecture::Amd64));
+        assert!(inf.supports(Architecture::Arm64));
+        assert!(!inf.supports(Architecture::X86));
+    }
+
+    #[test]
+    fn undecorated_models_only_apply_to_x86() {
+        let inf = InfFile::parse(
+            Path::new("legacy.inf"),
+            "[Version]\nClass=Net\n[Manufacturer]\nContoso=Models\n[Models]\nNIC=Install,PCI\\VEN_1234\n",
+        );
+        assert!(inf.supports(Architecture::X86));
+        assert!(!inf.supports(Architecture::Amd64));
+
+        let primitive = InfFile::parse(
+            Path::new("filter.inf"),
+            "[Version]\nClass=System\n[DefaultInstall.NTamd64]\nCopyFiles=@filter.sys\n",
+        );
+        assert!(primitive.supports(Architecture::Amd64));
+        assert!(!primitive.supports(Architecture::Arm64));
+        assert!(primitive.references_file("filter.sys"));
+
+        let neutral = InfFile::parse(Path::new("neutral.inf"), "[Version]\nClass=Media\n");
+        assert!(neutral.supports(Architecture::Arm64));
+    }
+
+    #[test]
+    fn merges_repeated_sections() {
+        let inf = InfFile::parse(
+            Path::new("split.inf"),
+            "[Version]\nClass=Net\n[Strings]\nContoso=\"Contoso Ltd\"\n\
+             [Manufacturer]\n%Contoso%=Models,NTamd64\n[version]\nProvider=%Contoso%\nDriverVer=01/02/2023,1.2.3.4\n\
+             [Models.NTamd64]\n%Nic%=Install,PCI\\VEN_1234\n[STRINGS]\nNic=\"Contoso NIC\"\n",
+        );
+        assert_eq!(inf.class.as_deref(), Some("Net"));
+        assert_eq!(inf.provider.as_deref(), Some("Contoso Ltd"));
+        assert_eq!(inf.driver_ver.unwrap().to_string(), "01/02/2023,1.2.3.4");
+        assert_eq!(inf.manufacturers.len(), 1);
+        assert_eq!(inf.models.len(), 1);
+        assert_eq!(inf.models[0].description, "Contoso NIC");
+        assert_eq!(inf.hardware_ids(Architecture::Amd64).collect::<Vec<_>>(), vec![r"PCI\VEN_1234"]);
+    }
+
+    #[test]
+    fn decodes_utf16_inf_files() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "[Version]\r\nClass=Net\r\n".encode_utf16() {
+            bytes.extend(unit.to_le_bytes());
+        }
+        let inf = InfFile::parse(Path::new("net.inf"), &decode_inf_text(&bytes));
+        assert_eq!(inf.class.as_deref(), Some("Net"));
+    }
+}
@@ -0,0 +1,368 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use super::inf::{Architecture, DriverVer};
+use super::{DetectedDriver, DriverType};
+
+/// PNP devices exported from the target machines
+///
+/// Accepts a JSON array (or `{"devices": [...]}`) whose entries are either
+/// device instance / hardware ID strings, or objects as produced by
+/// `Get-PnpDevice | Select-Object InstanceId,FriendlyName,HardwareID,CompatibleID | ConvertTo-Json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HardwareInventory {
+    pub path: PathBuf,
+    pub devices: Vec<InventoryDevice>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InventoryDevice {
+    /// Instance ID when exported, otherwise the first hardware ID
+    pub id: String,
+    pub name: Option<String>,
+    /// Most specific first
+    pub hardware_ids: Vec<String>,
+    pub compatible_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InventoryFile {
+    Devices(Vec<InventoryEntry>),
+    Wrapped { devices: Vec<InventoryEntry> },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InventoryEntry {
+    Id(String),
+    Device(DeviceEntry),
+}
+
+#[derive(Deserialize)]
+struct DeviceEntry {
+    #[serde(default, alias = "InstanceId", alias = "instance_id", alias = "DeviceID")]
+    id: Option<String>,
+    #[serde(default, alias = "FriendlyName", alias = "Name", alias = "name")]
+    friendly_name: Option<String>,
+    #[serde(default, alias = "HardwareID", alias = "hardware_ids")]
+    hardware_id: StringOrList,
+    #[serde(default, alias = "CompatibleID", alias = "compatible_ids")]
+    compatible_id: StringOrList,
+}
+
+/// PowerShell collapses single-element arrays to a plain string
+#[derive(Deserialize, Default)]
+#[serde(untagged)]
+enum StringOrList {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl StringOrList {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::None => Vec::new(),
+            Self::One(value) => vec![value],
+            Self::Many(values) => values,
+        }
+    }
+}
+
+impl HardwareInventory {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read hardware inventory {}", path.display()))?;
+        Self::parse(path, content.trim_start_matches('\u{feff}'))
+            .with_context(|| format!("Invalid hardware inventory {}", path.display()))
+    }
+
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let entries = match serde_json::from_str(content)? {
+            InventoryFile::Devices(entries) | InventoryFile::Wrapped { devices: entries } => entries,
+        };
+
+        let mut devices: Vec<InventoryDevice> = Vec::new();
+        for entry in entries {
+            let device = match entry {
+                InventoryEntry::Id(id) => InventoryDevice {
+                    hardware_ids: hardware_ids_from_instance(&id),
+                    id,
+                    name: None,
+                    compatible_ids: Vec::new(),
+                },
+                InventoryEntry::Device(entry) => {
+                    let mut hardware_ids = entry.hardware_id.into_vec();
+                    if hardware_ids.is_empty()
+                        && let Some(id) = &entry.id
+                    {
+                        hardware_ids = hardware_ids_from_instance(id);
+                    }
+                    let Some(id) = entry.id.or_else(|| hardware_ids.first().cloned()) else {
+                        continue;
+                    };
+                    InventoryDevice {
+                        id,
+                        name: entry.friendly_name,
+                        hardware_ids,
+                        compatible_ids: entry.compatible_id.into_vec(),
+                    }
+                }
+            };
+
+            // The same device appears once per exported machine
+            if !device.hardware_ids.is_empty()
+                && !devices.iter().any(|existing| existing.hardware_ids == device.hardware_ids)
+            {
+                devices.push(device);
+            }
+        }
+
+        if devices.is_empty() {
+            bail!("no devices with hardware IDs");
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            devices,
+        })
+    }
+}
+
+/// Hardware IDs Windows would report for an instance ID, most specific first
+///
+/// `PCI\VEN_8086&DEV_A77F&SUBSYS_0B1A1028&REV_01\3&11583659&0&B8` yields the
+/// full ID without the instance suffix, then progressively shorter prefixes
+/// down to `PCI\VEN_8086&DEV_A77F`.
+fn hardware_ids_from_instance(instance_id: &str) -> Vec<String> {
+    let mut parts = instance_id.trim().splitn(3, '\\');
+    let enumerator = parts.next().unwrap_or("");
+    let Some(device) = parts.next().filter(|device| !device.is_empty()) else {
+        return vec![instance_id.trim().to_string()];
+    };
+
+    let components: Vec<&str> = device.split('&').collect();
+    let mut ids: Vec<String> = (1..=components.len())
+        .rev()
+        .filter(|count| *count >= components.len().min(2))
+        .map(|count| format!("{}\\{}", enumerator, components[..count].join("&")))
+        .collect();
+
+    // PCI also reports VEN&DEV&REV without the subsystem
+    if let Some(revision) = components.iter().find(|component| component.to_ascii_uppercase().starts_with("REV_"))
+        && components.len() > 3
+    {
+        ids.insert(ids.len() - 1, format!("{}\\{}&{}&{}", enumerator, components[0], components[1], revision));
+    }
+    ids
+}
+
+/// One inventory device and the driver chosen for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceMatch {
+    pub device: InventoryDevice,
+    pub inf_file: PathBuf,
+    pub model: String,
+    pub matched_id: String,
+    /// Windows-style match rank; lower is better
+    pub rank: u32,
+    pub driver_ver: Option<DriverVer>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DriverSelection {
+    /// INF packages to inject, in detection order
+    pub drivers: Vec<DetectedDriver>,
+    pub matches: Vec<DeviceMatch>,
+    pub unmatched: Vec<InventoryDevice>,
+    /// CAB packages that could not be expanded, so their hardware IDs are unknown
+    pub unmatched_cabs: Vec<PathBuf>,
+}
+
+/// Pick the best-ranked INF for every inventory device
+///
+/// Ranking follows Windows: a device hardware ID beats a device compatible ID,
+/// an INF hardware ID beats an INF compatible ID, and earlier (more specific)
+/// IDs beat later ones. Ties go to the newer `DriverVer`, then detection order.
+pub fn select_drivers(
+    drivers: &[DetectedDriver],
+    inventory: &HardwareInventory,
+    architecture: Architecture,
+) -> DriverSelection {
+    let mut selection = DriverSelection::default();
+    let mut chosen = vec![false; drivers.len()];
+
+    for device in &inventory.devices {
+        let mut best: Option<(usize, DeviceMatch)> = None;
+
+        for (index, driver) in drivers.iter().enumerate() {
+            if driver.driver_type != DriverType::Inf {
+                continue;
+            }
+            let Some(inf) = &driver.metadata else {
+                continue;
+            };
+
+            for model in inf.models.iter().filter(|model| model.applies_to(architecture)) {
+                let Some((rank, matched_id)) = rank_model(device, &model.hardware_ids) else {
+                    continue;
+                };
+                let better = match &best {
+                    None => true,
+                    Some((_, current)) => {
+                        rank < current.rank || (rank == current.rank && inf.driver_ver > current.driver_ver)
+                    }
+                };
+                if better {
+                    best = Some((
+                        index,
+                        DeviceMatch {
+                            device: device.clone(),
+                            inf_file: inf.path.clone(),
+                            model: model.description.clone(),
+                            matched_id: matched_id.to_string(),
+                            rank,
+                            driver_ver: inf.driver_ver,
+                        },
+                    ));
+                }
+            }
+        }
+
+        match best {
+            Some((index, device_match)) => {
+                chosen[index] = true;
+                selection.matches.push(device_match);
+            }
+            None => selection.unmatched.push(device.clone()),
+        }
+    }
+
+    selection.drivers = drivers
+        .iter()
+        .zip(&chosen)
+        .filter(|(_, chosen)| **chosen)
+        .map(|(driver, _)| driver.clone())
+        .collect();
+    selection.unmatched_cabs = drivers
+        .iter()
+        .filter(|driver| driver.driver_type == DriverType::Cab)
+        .map(|driver| driver.path.clone())
+        .collect();
+    selection
+}
+
+pub(super) fn rank_model<'a>(device: &InventoryDevice, model_ids: &'a [String]) -> Option<(u32, &'a str)> {
+    let device_ids = device
+        .hardware_ids
+        .iter()
+        .enumerate()
+        .map(|(position, id)| (0x0000, position, id))
+        .chain(device.compatible_ids.iter().enumerate().map(|(position, id)| (0x2000, position, id)));
+
+    device_ids
+        .flat_map(|(device_base, device_position, device_id)| {
+            model_ids
+                .iter()
+                .enumerate()
+                .filter(move |(_, model_id)| model_id.eq_ignore_ascii_case(device_id))
+                .map(move |(model_position, model_id)| {
+                    let inf_base = if model_position == 0 { 0x0000 } else { 0x1000 };
+                    (device_base + inf_base + device_position.min(0xFFF) as u32, model_id.as_str())
+                })
+        })
+        .min_by_key(|(rank, _)| *rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HardwareInventory, hardware_ids_from_instance, select_drivers};
+    use crate::drivers::DriverManager;
+    use crate::drivers::inf::Architecture;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn expands_instance_ids_into_hardware_ids() {
+        assert_eq!(
+            hardware_ids_from_instance(r"PCI\VEN_8086&DEV_A77F&SUBSYS_0B1A1028&REV_01\3&11583659&0&B8"),
+            vec![
+                r"PCI\VEN_8086&DEV_A77F&SUBSYS_0B1A1028&REV_01",
+                r"PCI\VEN_8086&DEV_A77F&SUBSYS_0B1A1028",
+                r"PCI\VEN_8086&DEV_A77F&REV_01",
+                r"PCI\VEN_8086&DEV_A77F",
+            ]
+        );
+        assert_eq!(hardware_ids_from_instance(r"ACPI\PNP0A08\0"), vec![r"ACPI\PNP0A08"]);
+    }
+
+    #[test]
+    fn parses_powershell_exports_and_plain_id_lists() {
+        let inventory = HardwareInventory::parse(
+            Path::new("optiplex.json"),
+            r#"[
+                {"InstanceId": "PCI\\VEN_8086&DEV_A77F\\3&0", "FriendlyName": "VMD", "HardwareID": ["PCI\\VEN_8086&DEV_A77F"], "CompatibleID": "PCI\\CC_0104"},
+                "USB\\VID_0BDA&PID_8153&REV_3000\\000001",
+                "PCI\\VEN_8086&DEV_A77F\\3&1"
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(inventory.devices.len(), 2);
+        assert_eq!(inventory.devices[0].name.as_deref(), Some("VMD"));
+        assert_eq!(inventory.devices[0].compatible_ids, vec![r"PCI\CC_0104"]);
+        assert_eq!(inventory.devices[1].hardware_ids[1], r"USB\VID_0BDA&PID_8153");
+
+        assert!(HardwareInventory::parse(Path::new("empty.json"), r#"{"devices": []}"#).is_err());
+    }
+
+    #[test]
+    fn selects_best_ranked_driver_per_device() {
+        let temp = tempdir().unwrap();
+        let inf = |name: &str, driver_ver: &str, ids: &str| {
+            std::fs::write(
+                temp.path().join(name),
+                format!(
+                    "[Version]\nClass=SCSIAdapter\nDriverVer={}\n[Manufacturer]\nIntel=Models,NTamd64\n[Models.NTamd64]\nDevice=Install,{}\n",
+                    driver_ver, ids
+                ),
+            )
+            .unwrap();
+        };
+        // Generic class driver only matches through a compatible ID
+        inf("generic.inf", "01/01/2025,1.0.0.0", r"PCI\VEN_1234&DEV_0000,PCI\CC_0104");
+        inf("vmd_old.inf", "01/01/2023,19.0.0.0", r"PCI\VEN_8086&DEV_A77F");
+        inf("vmd_new.inf", "01/01/2024,19.5.0.0", r"PCI\VEN_8086&DEV_A77F");
+        inf("audio.inf", "01/01/2024,1.0.0.0", r"HDAUDIO\FUNC_01&VEN_10EC");
+
+        let mut manager = DriverManager::new();
+        manager.driver_paths.push(temp.path().to_path_buf());
+        let drivers = manager.detect_drivers().unwrap();
+
+        let inventory = HardwareInventory::parse(
+            Path::new("inventory.json"),
+            r#"[
+                {"InstanceId": "PCI\\VEN_8086&DEV_A77F\\3&0", "HardwareID": ["PCI\\VEN_8086&DEV_A77F"], "CompatibleID": ["PCI\\CC_0104"]},
+                {"InstanceId": "PCI\\VEN_1B21&DEV_9999\\1", "HardwareID": ["PCI\\VEN_1B21&DEV_9999"], "CompatibleID": ["PCI\\CC_0104"]},
+                "USB\\VID_FFFF&PID_0001\\1"
+            ]"#,
+        )
+        .unwrap();
+
+        let selection = select_drivers(&drivers, &inventory, Architecture::Amd64);
+        let mut names: Vec<&str> = selection.drivers.iter().map(|driver| driver.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["generic.inf", "vmd_new.inf"]);
+        assert_eq!(selection.matches[0].inf_file.file_name().unwrap(), "vmd_new.inf");
+        assert_eq!(selection.matches[0].rank, 0);
+        assert_eq!(selection.matches[1].inf_file.file_name().unwrap(), "generic.inf");
+        assert_eq!(selection.matches[1].rank, 0x3000);
+        assert_eq!(selection.unmatched.len(), 1);
+        assert_eq!(selection.unmatched[0].id, r"USB\VID_FFFF&PID_0001\1");
+
+        // Wrong architecture never matches
+        let selection = select_drivers(&drivers, &inventory, Architecture::Arm64);
+        assert!(selection.drivers.is_empty());
+    }
+}
@@ -1,18 +1,98 @@
-#[cfg(target_os = "windows")]
-use anyhow::{Context, Result, bail};
-
-#[cfg(not(target_os = "windows"))]
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
+use serde::Serialize;
 use walkdir::WalkDir;
 use tracing::{info, debug, warn};
 use crate::wim::WimManager;
 
+pub mod cab;
+pub mod catalog;
+pub mod dedupe;
+pub mod inf;
+pub mod inventory;
+pub mod packs;
+pub mod repository;
+
+use crate::cli::{ClassPolicy, DriverPin, DriversConfig};
+use glob::{MatchOptions, Pattern};
+use std::collections::BTreeMap;
+use catalog::{CatalogStatus, PackageVerification};
+use dedupe::SkippedDuplicate;
+use inf::{Architecture, InfFile};
+use inventory::{DriverSelection, HardwareInventory};
+use repository::DriverIndex;
+use tempfile::TempDir;
+
+/// Working-directory-relative folders scanned for drivers to inject
+pub const DRIVER_SEARCH_DIRS: &[&str] = &[
+    "PEAutoRun/Drivers",
+    "pe_autorun/drivers",
+    "Tools/Drivers",
+    "tools/drivers",
+    "Drivers",
+    "drivers",
+];
+
+/// Default `drivers.priority` patterns: storage controllers WinPE needs to see disks
+/// Includes Dell Optiplex, 15th Gen Intel (Arrow Lake) and modern NVMe hardware
+pub const DEFAULT_PRIORITY_DRIVERS: &[&str] = &[
+    // Intel VMD/RapidStorage drivers (critical for Dell Optiplex)
+    "iastorac",         // Intel Rapid Storage Technology
+    "iastorv",          // Intel VMD Controller
+    "iaStorAC",         // Intel Rapid Storage Technology AHCI
+    "iaStorAVC",        // Intel Rapid Storage Technology VMD (15th gen support)
+    "iastorav",         // Intel Rapid Storage Technology AV
+    "iastore",          // Intel Storage Enhanced
+    "vmd",              // VMD Controller
+    "vroc",             // Intel VROC (Virtual RAID on CPU)
+
+    // Modern NVMe drivers
+    "stornvme",         // Windows Standard NVMe Driver
+    "nvme",             // Generic NVMe
+
+    // Micron NVMe specific (2200/2300/3400 series - Dell 15th gen common)
+    "micron",           // Micron NVMe drivers
+    "mtfd",             // Micron storage drivers
+    "mtfd3400",         // Micron 3400 NVMe (latest, common in Dell 15th gen)
+    "mtfd2300",         // Micron 2300 NVMe
+    "mtfd7450",         // Micron 7450 PRO NVMe
+
+    // Samsung NVMe specific
+    "samsung",          // Samsung NVMe drivers
+    "nvmexpresssam",    // Samsung NVMe Express
+    "samclass",         // Samsung Class Driver
+
+    // Dell specific storage drivers
+    "dell",             // Dell storage controllers
+    "bossstornvme",     // Dell BOSS-S1 Controller
+
+    // Common storage controllers
+    "storahci",         // AHCI Storage Controller
+    "msahci",           // Microsoft AHCI
+];
+
 /// Driver injection manager for WinPE environments
 /// Supports Intel VMD/RapidStorage, Dell Optiplex, and modern NVMe drivers
 pub struct DriverManager {
     driver_paths: Vec<PathBuf>,
     priority_drivers: Vec<String>,
+    /// `drivers.paths`: candidates for `driver_paths`
+    source_dirs: Vec<PathBuf>,
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    scan_drives: bool,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    class_policies: BTreeMap<String, ClassPolicy>,
+    default_policy: ClassPolicy,
+    inject_all: bool,
+    /// Drivers whose INF has no models for this architecture are skipped
+    target_architecture: Architecture,
+    /// `[[drivers.pins]]`: packages kept over newer duplicates
+    pins: Vec<DriverPin>,
+    /// Duplicates dropped by the last `detect_drivers` call
+    skipped_duplicates: Vec<SkippedDuplicate>,
+    /// CAB packages expanded during detection; the directories live as long as the manager
+    expanded_cabs: Vec<(PathBuf, TempDir)>,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +101,10 @@ pub struct DetectedDriver {
     pub path: PathBuf,
     pub driver_type: DriverType,
     pub inf_file: Option<PathBuf>,
+    /// Parsed `inf_file`, when it could be read
+    pub metadata: Option<InfFile>,
+    /// CAB package this file was expanded from
+    pub source_cab: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,62 +116,102 @@ pub enum DriverType {
     Unknown,
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
+impl DriverType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Inf => "inf",
+            Self::Cab => "cab",
+            Self::Sys => "sys",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Drivers grouped by `drivers.class_policy`
+#[derive(Debug, Clone, Default)]
+pub struct PolicyOutcome {
+    pub inject: Vec<DetectedDriver>,
+    pub stage: Vec<DetectedDriver>,
+    pub skipped: Vec<DriverInjection>,
+}
+
+/// Outcome of injecting one driver package, as recorded in the build report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DriverInjection {
+    pub name: String,
+    pub inf_file: Option<PathBuf>,
+    /// `Class` from the INF `[Version]` section
+    pub class: Option<String>,
+    pub result: InjectionResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum InjectionResult {
+    Injected,
+    /// Copied into the image for loading in WinPE, not added to the driver store
+    Staged,
+    Failed(String),
+    Skipped(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct DriverRiskSummary {
     pub total: usize,
     pub priority: usize,
     pub missing_inf_for_sys: usize,
+    /// CAB packages that could not be expanded
     pub cab_packages: usize,
+    pub cabs_expanded: usize,
+    pub duplicates_skipped: usize,
+    pub duplicates: Vec<SkippedDuplicate>,
 }
 
 impl DriverManager {
     pub fn new() -> Self {
-        // Priority drivers for Dell Optiplex and modern NVMe support
-        // Includes support for 15th Gen Intel (Arrow Lake) and latest hardware
-        let priority_drivers = vec![
-            // Intel VMD/RapidStorage drivers (critical for Dell Optiplex)
-            "iastorac".to_string(),        // Intel Rapid Storage Technology
-            "iastorv".to_string(),         // Intel VMD Controller
-            "iaStorAC".to_string(),        // Intel Rapid Storage Technology AHCI
-            "iaStorAVC".to_string(),       // Intel Rapid Storage Technology VMD (15th gen support)
-            "iastorav".to_string(),        // Intel Rapid Storage Technology AV
-            "iastore".to_string(),         // Intel Storage Enhanced
-            "vmd".to_string(),             // VMD Controller
-            "vroc".to_string(),            // Intel VROC (Virtual RAID on CPU)
-
-            // Modern NVMe drivers
-            "stornvme".to_string(),        // Windows Standard NVMe Driver
-            "nvme".to_string(),            // Generic NVMe
-
-            // Micron NVMe specific (2200/2300/3400 series - Dell 15th gen common)
-            "micron".to_string(),          // Micron NVMe drivers
-            "mtfd".to_string(),            // Micron storage drivers
-            "mtfd3400".to_string(),        // Micron 3400 NVMe (latest, common in Dell 15th gen)
-            "mtfd2300".to_string(),        // Micron 2300 NVMe
-            "mtfd7450".to_string(),        // Micron 7450 PRO NVMe
-
-            // Samsung NVMe specific
-            "samsung".to_string(),         // Samsung NVMe drivers
-            "nvmexpresssam".to_string(),   // Samsung NVMe Express
-            "samclass".to_string(),        // Samsung Class Driver
-
-            // Dell specific storage drivers
-            "dell".to_string(),            // Dell storage controllers
-            "bossstornvme".to_string(),    // Dell BOSS-S1 Controller
-
-            // Common storage controllers
-            "storahci".to_string(),        // AHCI Storage Controller
-            "msahci".to_string(),          // Microsoft AHCI
-        ];
+        Self::from_config(&DriversConfig::default()).expect("default driver config is valid")
+    }
 
-        Self {
+    pub fn from_config(config: &DriversConfig) -> Result<Self> {
+        Ok(Self {
             driver_paths: Vec::new(),
-            priority_drivers,
-        }
+            priority_drivers: config.priority.clone(),
+            source_dirs: config.paths.iter().map(PathBuf::from).collect(),
+            scan_drives: config.scan_drives,
+            include: compile_globs(&config.include)?,
+            exclude: compile_globs(&config.exclude)?,
+            class_policies: config
+                .class_policy
+                .iter()
+                .map(|(class, policy)| (class.to_ascii_lowercase(), *policy))
+                .collect(),
+            default_policy: config.default_policy,
+            inject_all: config.inject_all,
+            // WinPE media built by Ghostwin is x64
+            target_architecture: Architecture::Amd64,
+            pins: config.pins.clone(),
+            skipped_duplicates: Vec::new(),
+            expanded_cabs: Vec::new(),
+        })
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn set_pins(&mut self, pins: Vec<DriverPin>) {
+        self.pins = pins;
+    }
+
+    /// Architecture builds inject drivers for
+    pub fn target_architecture(&self) -> Architecture {
+        self.target_architecture
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn set_target_architecture(&mut self, architecture: Architecture) {
+        self.target_architecture = architecture;
     }
 
     /// Check if driver is a priority driver (NVMe/VMD/RapidStorage)
-    fn is_priority_driver(&self, driver_name: &str) -> bool {
+    pub fn is_priority_driver(&self, driver_name: &str) -> bool {
         let name_lower = driver_name.to_lowercase();
         self.priority_drivers.iter().any(|priority| {
             name_lower.contains(&priority.to_lowercase())
@@ -98,26 +222,18 @@ impl DriverManager {
     pub fn scan_driver_directories(&mut self) -> Result<Vec<PathBuf>> {
         info!("🔍 Scanning for driver directories");
 
-        let search_patterns = vec![
-            "PEAutoRun/Drivers",
-            "pe_autorun/drivers",
-            "Tools/Drivers",
-            "tools/drivers",
-            "Drivers",
-            "drivers",
-        ];
-
-        for pattern in search_patterns {
-            let path = PathBuf::from(pattern);
-            if path.exists() && path.is_dir() {
+        for path in self.source_dirs.clone() {
+            if path.is_dir() {
                 info!("Found driver directory: {}", path.display());
                 self.driver_paths.push(path);
+            } else {
+                debug!("Driver directory not present: {}", path.display());
             }
         }
 
         // Also scan all drives on Windows
         #[cfg(target_os = "windows")]
-        {
+        if self.scan_drives {
             self.scan_all_drives_for_drivers()?;
         }
 
@@ -156,50 +272,212 @@ impl DriverManager {
     }
 
     /// Detect all drivers in configured directories
-    /// Prioritizes Intel VMD/RapidStorage and NVMe drivers
-    pub fn detect_drivers(&self) -> Result<Vec<DetectedDriver>> {
+    /// Prioritizes Intel VMD/RapidStorage and NVMe drivers and drops older duplicate packages
+    pub fn detect_drivers(&mut self) -> Result<Vec<DetectedDriver>> {
         info!("🔍 Detecting drivers in {} directories", self.driver_paths.len());
         let mut drivers = Vec::new();
         let mut priority_drivers = Vec::new();
 
-        for driver_dir in &self.driver_paths {
+        let mut found = Vec::new();
+        for driver_dir in self.driver_paths.clone() {
             debug!("Scanning directory: {}", driver_dir.display());
 
-            for entry in WalkDir::new(driver_dir).max_depth(5) {
-                let entry = entry?;
-                let path = entry.path();
-
-                if path.is_file() {
-                    if let Some(driver) = self.classify_driver(path)? {
-                        let is_priority = self.is_priority_driver(&driver.name);
+            for driver in self.scan_directory(&driver_dir, 5)? {
+                if driver.driver_type != DriverType::Cab {
+                    found.push(driver);
+                    continue;
+                }
 
-                        if is_priority {
-                            info!("🔥 PRIORITY driver detected: {} ({})", driver.name, driver.path.display());
-                            priority_drivers.push(driver);
-                        } else {
-                            info!("Detected driver: {} ({})", driver.name, driver.path.display());
-                            drivers.push(driver);
+                // Expanded packages go through matching, dedupe and verification like loose INFs
+                match self.expand_cab(&driver.path) {
+                    Ok(root) => {
+                        for mut expanded in self.scan_directory(&root, usize::MAX)? {
+                            expanded.source_cab = Some(driver.path.clone());
+                            found.push(expanded);
                         }
                     }
+                    Err(error) => {
+                        warn!("Could not expand CAB package {}: {:#}", driver.path.display(), error);
+                        found.push(driver);
+                    }
                 }
             }
         }
 
+        for driver in found {
+            if let Some(metadata) = &driver.metadata
+                && !metadata.supports(self.target_architecture)
+            {
+                info!(
+                    "Skipping driver {} (no {} models in {})",
+                    driver.name,
+                    self.target_architecture,
+                    metadata.path.display(),
+                );
+                continue;
+            }
+
+            let is_priority = self.is_priority_driver(&driver.name);
+
+            if is_priority {
+                info!("🔥 PRIORITY driver detected: {} ({})", driver.name, driver.path.display());
+                priority_drivers.push(driver);
+            } else {
+                info!("Detected driver: {} ({})", driver.name, driver.path.display());
+                drivers.push(driver);
+            }
+        }
+
         // Priority drivers go first (critical for Dell Optiplex boot)
-        let priority_count = priority_drivers.len();
-        let total_count = priority_count + drivers.len();
         priority_drivers.extend(drivers);
 
-        info!("✅ Detected {} drivers ({} priority storage drivers)",
-              total_count,
-              priority_count);
+        let (drivers, skipped) = dedupe::dedupe_drivers(priority_drivers, &self.pins, self.target_architecture);
+        for duplicate in &skipped {
+            info!("Skipping duplicate driver {} ({}): {}", duplicate.name, duplicate.path.display(), duplicate.reason);
+        }
+        self.skipped_duplicates = skipped;
+
+        let priority_count = drivers.iter().filter(|driver| self.is_priority_driver(&driver.name)).count();
+        info!("✅ Detected {} drivers ({} priority storage drivers, {} duplicates skipped)",
+              drivers.len(),
+              priority_count,
+              self.skipped_duplicates.len());
 
-        Ok(priority_drivers)
+        Ok(drivers)
+    }
+
+    /// Classify the files below `root` that pass `drivers.include` / `drivers.exclude`
+    fn scan_directory(&self, root: &Path, max_depth: usize) -> Result<Vec<DetectedDriver>> {
+        let mut drivers = Vec::new();
+        for entry in WalkDir::new(root).max_depth(max_depth) {
+            let entry = entry?;
+            let path = entry.path();
+
+            if path.is_file()
+                && self.matches_filters(root, path)
+                && let Some(driver) = self.classify_driver(path)?
+            {
+                drivers.push(driver);
+            }
+        }
+        Ok(drivers)
+    }
+
+    /// Expand a CAB package once per manager, returning its extraction directory
+    fn expand_cab(&mut self, cab_path: &Path) -> Result<PathBuf> {
+        if let Some((_, directory)) = self.expanded_cabs.iter().find(|(path, _)| path == cab_path) {
+            return Ok(directory.path().to_path_buf());
+        }
+
+        let directory = extract_cab(cab_path)?;
+        let root = directory.path().to_path_buf();
+        self.expanded_cabs.push((cab_path.to_path_buf(), directory));
+        Ok(root)
+    }
+
+    /// `drivers.include` / `drivers.exclude`, matched against the path below its source directory
+    fn matches_filters(&self, driver_dir: &Path, path: &Path) -> bool {
+        let relative = path
+            .strip_prefix(driver_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+
+        let included = self.include.is_empty() || self.include.iter().any(|glob| glob.matches_with(&relative, options));
+        let excluded = self.exclude.iter().any(|glob| glob.matches_with(&relative, options));
+        if included && excluded {
+            debug!("Excluded by drivers.exclude: {}", path.display());
+        }
+        included && !excluded
+    }
+
+    /// Policy for a driver: its INF class policy, demoted to staging for
+    /// non-priority drivers when `inject_all` is off
+    pub fn policy_for(&self, driver: &DetectedDriver) -> ClassPolicy {
+        let policy = driver
+            .metadata
+            .as_ref()
+            .and_then(|inf| inf.class.as_deref())
+            .and_then(|class| self.class_policies.get(&class.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(self.default_policy);
+
+        if policy == ClassPolicy::Inject && !self.inject_all && !self.is_priority_driver(&driver.name) {
+            ClassPolicy::Stage
+        } else {
+            policy
+        }
+    }
+
+    /// Split drivers into those injected into the driver store, those only staged, and skipped ones
+    pub fn apply_class_policies(&self, drivers: Vec<DetectedDriver>) -> PolicyOutcome {
+        let mut outcome = PolicyOutcome::default();
+        for driver in drivers {
+            match self.policy_for(&driver) {
+                ClassPolicy::Inject => outcome.inject.push(driver),
+                ClassPolicy::Stage => {
+                    debug!("Staging {} without injecting it", driver.name);
+                    outcome.stage.push(driver);
+                }
+                ClassPolicy::Skip => {
+                    info!("Skipping {} (drivers.class_policy)", driver.name);
+                    outcome.skipped.push(skipped_injection(&driver, "class policy: skip"));
+                }
+            }
+        }
+        outcome
+    }
+
+    /// Keep only the INF packages that best match a device in `inventory`
+    pub fn select_for_inventory(&self, drivers: &[DetectedDriver], inventory: &HardwareInventory) -> DriverSelection {
+        info!(
+            "🎯 Matching {} drivers against {} inventory devices from {}",
+            drivers.len(),
+            inventory.devices.len(),
+            inventory.path.display()
+        );
+        let selection = inventory::select_drivers(drivers, inventory, self.target_architecture);
+
+        for device_match in &selection.matches {
+            debug!(
+                "{} -> {} ({}, rank {:#06x})",
+                device_match.device.id,
+                device_match.inf_file.display(),
+                device_match.matched_id,
+                device_match.rank
+            );
+        }
+        for device in &selection.unmatched {
+            warn!(
+                "No driver matches {}{}",
+                device.id,
+                device.name.as_deref().map(|name| format!(" ({})", name)).unwrap_or_default()
+            );
+        }
+        for cab in &selection.unmatched_cabs {
+            warn!("Skipping CAB package {}: it could not be expanded to match hardware IDs", cab.display());
+        }
+
+        info!(
+            "✅ Selected {} of {} drivers for {} of {} devices",
+            selection.drivers.len(),
+            drivers.len(),
+            selection.matches.len(),
+            inventory.devices.len()
+        );
+        selection
     }
 
     pub fn summarize_driver_risks(&self, drivers: &[DetectedDriver]) -> DriverRiskSummary {
         let mut summary = DriverRiskSummary {
             total: drivers.len(),
+            duplicates_skipped: self.skipped_duplicates.len(),
+            duplicates: self.skipped_duplicates.clone(),
+            cabs_expanded: self.expanded_cabs.len(),
             ..DriverRiskSummary::default()
         };
 
@@ -226,11 +504,13 @@ impl DriverManager {
         let summary = self.summarize_driver_risks(drivers);
 
         info!(
-            "Driver summary: {} total, {} priority storage, {} CAB packages, {} SYS without INF",
+            "Driver summary: {} total, {} priority storage, {} CAB packages expanded, {} CAB packages unreadable, {} SYS without INF, {} duplicates skipped",
             summary.total,
             summary.priority,
+            summary.cabs_expanded,
             summary.cab_packages,
             summary.missing_inf_for_sys,
+            summary.duplicates_skipped,
         );
 
         if summary.missing_inf_for_sys > 0 {
@@ -242,12 +522,77 @@ impl DriverManager {
 
         if summary.cab_packages > 0 {
             warn!(
-                "{} CAB driver package(s) require extraction and INF discovery during injection",
+                "{} CAB driver package(s) could not be expanded; their INFs were not matched or verified",
                 summary.cab_packages
             );
         }
     }
 
+    /// Check each INF package's files against its catalog
+    pub fn verify_drivers(&self, drivers: &[DetectedDriver]) -> Vec<PackageVerification> {
+        let mut verifications: Vec<PackageVerification> = Vec::new();
+        for inf in drivers.iter().filter_map(|driver| driver.metadata.as_ref()) {
+            if verifications.iter().any(|verification| verification.inf_file == inf.path) {
+                continue;
+            }
+
+            let verification = catalog::verify_package(inf, self.target_architecture);
+            match verification.status {
+                CatalogStatus::CatalogConsistent => debug!(
+                    "🔏 {} matches {} ({} files)",
+                    inf.path.display(),
+                    verification.catalog.as_deref().unwrap_or(Path::new("-")).display(),
+                    verification.files_checked
+                ),
+                status => warn!(
+                    "{} failed catalog verification ({:?}): {}",
+                    inf.path.display(),
+                    status,
+                    verification.problems.join("; ")
+                ),
+            }
+            verifications.push(verification);
+        }
+
+        let consistent = verifications
+            .iter()
+            .filter(|verification| verification.status == CatalogStatus::CatalogConsistent)
+            .count();
+        info!("🔏 {} of {} driver packages match their catalogs", consistent, verifications.len());
+        verifications
+    }
+
+    /// Split out packages without a consistent catalog (`drivers.require_signed`)
+    pub fn refuse_unsigned(
+        &self,
+        drivers: Vec<DetectedDriver>,
+        verifications: &[PackageVerification],
+    ) -> (Vec<DetectedDriver>, Vec<DriverInjection>) {
+        let mut allowed = Vec::new();
+        let mut refused = Vec::new();
+
+        for driver in drivers {
+            let verification = driver
+                .inf_file
+                .as_ref()
+                .and_then(|inf| verifications.iter().find(|verification| verification.inf_file == *inf));
+            let reason = match verification.map(|verification| verification.status) {
+                Some(CatalogStatus::CatalogConsistent) => {
+                    allowed.push(driver);
+                    continue;
+                }
+                Some(CatalogStatus::MissingCatalog) => "unsigned: missing catalog",
+                Some(CatalogStatus::HashMismatch) => "unsigned: catalog hash mismatch",
+                None if driver.driver_type == DriverType::Cab => "unsigned: CAB package could not be expanded",
+                None => "unsigned: no INF to verify",
+            };
+            warn!("Refusing to inject or stage {} ({})", driver.name, reason);
+            refused.push(skipped_injection(&driver, reason));
+        }
+
+        (allowed, refused)
+    }
+
     /// Classify a file as a driver
     fn classify_driver(&self, path: &Path) -> Result<Option<DetectedDriver>> {
         let extension = path.extension()
@@ -267,7 +612,7 @@ impl DriverManager {
             .to_string_lossy()
             .to_string();
 
-        // For .sys files, look for associated .inf file
+        // For .sys files, look for the .inf that actually installs it
         let inf_file = if driver_type == DriverType::Sys {
             self.find_inf_for_sys(path)?
         } else if driver_type == DriverType::Inf {
@@ -276,23 +621,57 @@ impl DriverManager {
             None
         };
 
+        let metadata = match &inf_file {
+            Some(inf_path) => match InfFile::open(inf_path) {
+                Ok(metadata) => Some(metadata),
+                Err(error) => {
+                    warn!("Could not parse {}: {:#}", inf_path.display(), error);
+                    None
+                }
+            },
+            None => None,
+        };
+
         Ok(Some(DetectedDriver {
             name,
             path: path.to_path_buf(),
             driver_type,
             inf_file,
+            metadata,
+            source_cab: None,
         }))
     }
 
-    /// Find .inf file associated with a .sys driver
+    /// Find the .inf that references a .sys driver via `CopyFiles` or `SourceDisksFiles`
+    ///
+    /// Looks in the driver's own directory and its parent, since packages often
+    /// keep binaries in an architecture subfolder next to the INF.
     fn find_inf_for_sys(&self, sys_path: &Path) -> Result<Option<PathBuf>> {
-        if let Some(parent) = sys_path.parent() {
-            for entry in std::fs::read_dir(parent)? {
-                let entry = entry?;
-                let path = entry.path();
+        let Some(sys_name) = sys_path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+            return Ok(None);
+        };
 
-                if path.extension().and_then(|ext| ext.to_str()) == Some("inf") {
-                    return Ok(Some(path));
+        // A relative driver folder's parent is the empty path, which `read_dir` rejects
+        let search_dirs = sys_path
+            .parent()
+            .into_iter()
+            .chain(sys_path.parent().and_then(Path::parent))
+            .filter(|dir| !dir.as_os_str().is_empty());
+        for dir in search_dirs {
+            let mut inf_paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.is_file()
+                        && path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("inf"))
+                })
+                .collect();
+            inf_paths.sort();
+
+            for inf_path in inf_paths {
+                match InfFile::open(&inf_path) {
+                    Ok(inf) if inf.references_file(&sys_name) => return Ok(Some(inf_path)),
+                    Ok(_) => {}
+                    Err(error) => debug!("Ignoring unreadable INF {}: {:#}", inf_path.display(), error),
                 }
             }
         }
@@ -300,15 +679,16 @@ impl DriverManager {
         Ok(None)
     }
 
-    /// Inject drivers into mounted WIM image
+    /// Inject drivers into mounted WIM image, returning one outcome per driver package
     pub async fn inject_drivers_to_wim(
         &self,
         wim_manager: &WimManager,
         drivers: &[DetectedDriver],
-    ) -> Result<()> {
+    ) -> Result<Vec<DriverInjection>> {
+        let mut injections = Vec::new();
         if drivers.is_empty() {
             info!("No drivers to inject");
-            return Ok(());
+            return Ok(injections);
         }
 
         info!("💉 Injecting {} drivers into WIM", drivers.len());
@@ -316,10 +696,10 @@ impl DriverManager {
         for driver in drivers {
             match driver.driver_type {
                 DriverType::Inf => {
-                    self.inject_inf_driver(wim_manager, driver).await?;
+                    injections.push(self.inject_inf_driver(wim_manager, driver).await);
                 }
                 DriverType::Cab => {
-                    self.inject_cab_driver(wim_manager, driver).await?;
+                    injections.extend(self.inject_cab_driver(wim_manager, driver).await?);
                 }
                 DriverType::Sys => {
                     if let Some(ref inf_file) = driver.inf_file {
@@ -329,154 +709,182 @@ impl DriverManager {
                             path: inf_file.clone(),
                             driver_type: DriverType::Inf,
                             inf_file: Some(inf_file.clone()),
+                            metadata: driver.metadata.clone(),
+                            source_cab: driver.source_cab.clone(),
                         };
-                        self.inject_inf_driver(wim_manager, &inf_driver).await?;
+                        injections.push(self.inject_inf_driver(wim_manager, &inf_driver).await);
                     } else {
                         warn!("Skipping .sys driver without .inf file: {}", driver.path.display());
+                        injections.push(skipped_injection(driver, "no matching .inf file"));
                     }
                 }
                 DriverType::Unknown => {
                     warn!("Unknown driver type, skipping: {}", driver.path.display());
+                    injections.push(skipped_injection(driver, "unknown driver type"));
                 }
             }
         }
 
         info!("✅ Driver injection completed");
-        Ok(())
+        Ok(injections)
     }
 
     /// Inject a .inf driver package
     async fn inject_inf_driver(
         &self,
-        #[allow(unused_variables)] wim_manager: &WimManager,
+        wim_manager: &WimManager,
         driver: &DetectedDriver,
-    ) -> Result<()> {
+    ) -> DriverInjection {
         info!("Injecting INF driver: {}", driver.name);
 
-        #[cfg(target_os = "windows")]
-        {
-            let mount_path = wim_manager.mount_path();
-
-            let output = tokio::process::Command::new("dism")
-                .args([
-                    &format!("/Image:{}", mount_path.display()),
-                    "/Add-Driver",
-                    &format!("/Driver:{}", driver.path.display()),
-                ])
-                .output()
-                .await
-                .context("Failed to run DISM add-driver command")?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                warn!("Driver injection warning for {}: {}", driver.name, stderr);
-                // Don't fail the whole process for one driver
-            } else {
+        let result = match wim_manager.add_driver(&driver.path).await {
+            Ok(()) => {
                 info!("✅ Injected INF driver: {}", driver.name);
+                InjectionResult::Injected
             }
-        }
+            // Don't fail the whole process for one driver
+            Err(error) => {
+                warn!("Driver injection warning for {}: {}", driver.name, error);
+                InjectionResult::Failed(error.to_string())
+            }
+        };
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would inject INF driver: {} (not on Windows)", driver.name);
+        DriverInjection {
+            name: driver.name.clone(),
+            inf_file: Some(driver.path.clone()),
+            class: driver.metadata.as_ref().and_then(|inf| inf.class.clone()).or_else(|| read_inf_class(&driver.path)),
+            result,
         }
-
-        Ok(())
     }
 
-    /// Inject a .cab driver package
+    /// Inject a .cab driver package that was not expanded during detection
     async fn inject_cab_driver(
         &self,
-        #[allow(unused_variables)] wim_manager: &WimManager,
+        wim_manager: &WimManager,
         driver: &DetectedDriver,
-    ) -> Result<()> {
+    ) -> Result<Vec<DriverInjection>> {
         info!("Injecting CAB driver: {}", driver.name);
 
-        #[cfg(target_os = "windows")]
-        {
-            // First extract the CAB to a temp location
-            let temp_dir = std::env::temp_dir().join(format!("ghostwin_driver_{}", driver.name));
-            let driver_path = driver.path.to_string_lossy().into_owned();
-            let temp_dir_path = temp_dir.to_string_lossy().into_owned();
-            std::fs::create_dir_all(&temp_dir)?;
-
-            // Extract CAB
-            let extract_output = tokio::process::Command::new("expand")
-                .args([
-                    driver_path.as_str(),
-                    "-F:*",
-                    temp_dir_path.as_str(),
-                ])
-                .output()
-                .await?;
-
-            if !extract_output.status.success() {
-                bail!("Failed to extract CAB driver: {}", driver.name);
-            }
-
-            // Find .inf files in extracted directory
-            let mut found_inf = false;
-            for entry in WalkDir::new(&temp_dir).max_depth(2) {
-                let entry = entry?;
-                if entry.path().extension().and_then(|ext| ext.to_str()) == Some("inf") {
-                    found_inf = true;
-                    let inf_driver = DetectedDriver {
-                        name: entry.file_name().to_string_lossy().to_string(),
-                        path: entry.path().to_path_buf(),
-                        driver_type: DriverType::Inf,
-                        inf_file: Some(entry.path().to_path_buf()),
-                    };
-                    self.inject_inf_driver(wim_manager, &inf_driver).await?;
-                }
+        let directory = match extract_cab(&driver.path) {
+            Ok(directory) => directory,
+            Err(error) => {
+                warn!("Failed to extract CAB driver {}: {:#}", driver.name, error);
+                return Ok(vec![DriverInjection {
+                    result: InjectionResult::Failed(format!("{:#}", error)),
+                    ..skipped_injection(driver, "")
+                }]);
             }
+        };
 
-            if !found_inf {
-                warn!("No .inf files found in CAB: {}", driver.name);
+        let mut injections = Vec::new();
+        for inf_driver in self.scan_directory(directory.path(), usize::MAX)? {
+            let supported = inf_driver
+                .metadata
+                .as_ref()
+                .is_none_or(|metadata| metadata.supports(self.target_architecture));
+            if inf_driver.driver_type == DriverType::Inf && supported {
+                injections.push(self.inject_inf_driver(wim_manager, &inf_driver).await);
             }
-
-            // Cleanup
-            let _ = std::fs::remove_dir_all(&temp_dir);
         }
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would inject CAB driver: {} (not on Windows)", driver.name);
+        if injections.is_empty() {
+            warn!("No {} .inf files found in CAB: {}", self.target_architecture, driver.name);
+            injections.push(skipped_injection(driver, "no .inf files in CAB"));
         }
 
-        Ok(())
+        Ok(injections)
     }
 
-    /// Copy drivers to WIM for manual installation during WinPE boot
-    pub async fn copy_drivers_to_wim(
+    /// Stage driver packages in the WIM's driver repository for `ghostwin load-drivers`
+    ///
+    /// Only `drivers` are indexed; with `drivers.require_signed`, pass just the packages `refuse_unsigned` allowed
+    pub async fn stage_drivers_to_wim(
         &self,
         wim_manager: &WimManager,
         drivers: &[DetectedDriver],
-    ) -> Result<()> {
-        info!("📋 Copying {} drivers to WIM for manual installation", drivers.len());
+    ) -> Result<DriverIndex> {
+        info!("📋 Staging {} drivers in {}", drivers.len(), repository::REPOSITORY_DIR);
+
+        let staging = repository::plan_staging(drivers);
+        for directory in &staging {
+            debug!("Staging driver directory {} as {}", directory.source.display(), directory.name);
+            wim_manager
+                .copy_to_mount(&directory.source, &format!("{}/{}", repository::REPOSITORY_DIR, directory.name))
+                .await?;
+        }
 
-        // Group drivers by their parent directory
-        use std::collections::HashMap;
-        let mut drivers_by_dir: HashMap<PathBuf, Vec<&DetectedDriver>> = HashMap::new();
+        let index = DriverIndex::build(drivers, &staging, self.target_architecture);
+        let repository = wim_manager.mount_path().join(repository::REPOSITORY_DIR);
+        std::fs::create_dir_all(&repository)
+            .with_context(|| format!("Failed to create driver repository {}", repository.display()))?;
+        index.write(&repository.join(repository::INDEX_FILE))?;
+        info!("✅ Staged {} package directories, {} indexed INF packages", staging.len(), index.packages.len());
 
-        for driver in drivers {
-            if let Some(parent) = driver.path.parent() {
-                drivers_by_dir.entry(parent.to_path_buf())
-                    .or_default()
-                    .push(driver);
-            }
-        }
+        Ok(index)
+    }
+}
 
-        // Copy each driver directory to the WIM
-        for (driver_dir, dir_drivers) in drivers_by_dir {
-            info!("Copying driver directory: {}", driver_dir.display());
-            wim_manager.copy_to_mount(&driver_dir, "Windows/System32/Drivers").await?;
-            info!("✅ Copied {} drivers from {}", dir_drivers.len(), driver_dir.display());
-        }
+/// Drivers for `drivers.install_image`: packages whose INF class is listed, or all with `"*"`
+pub fn install_image_drivers(drivers: &[DetectedDriver], classes: &[String]) -> Vec<DetectedDriver> {
+    let every_class = classes.iter().any(|class| class.trim() == "*");
+    drivers
+        .iter()
+        .filter(|driver| {
+            driver
+                .metadata
+                .as_ref()
+                .and_then(|inf| inf.class.as_deref())
+                .is_some_and(|class| every_class || classes.iter().any(|listed| listed.trim().eq_ignore_ascii_case(class)))
+        })
+        .cloned()
+        .collect()
+}
 
-        Ok(())
+pub fn staged_injection(driver: &DetectedDriver) -> DriverInjection {
+    DriverInjection {
+        result: InjectionResult::Staged,
+        ..skipped_injection(driver, "")
+    }
+}
+
+/// Expand a CAB package into its own `ghostwin_driver_<name>_*` temp directory
+pub fn extract_cab(cab_path: &Path) -> Result<TempDir> {
+    let stem = cab_path.file_stem().unwrap_or_default().to_string_lossy();
+    let directory = tempfile::Builder::new()
+        .prefix(&format!("ghostwin_driver_{}_", stem))
+        .tempdir()
+        .context("Failed to create CAB extraction directory")?;
+
+    let cabinet = cab::Cabinet::open(cab_path)?;
+    let files = cabinet.extract_all(directory.path())?;
+    info!("📦 Expanded {} ({} files) into {}", cab_path.display(), files.len(), directory.path().display());
+    Ok(directory)
+}
+
+/// `drivers.include` / `drivers.exclude` globs
+pub fn compile_globs(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|error| anyhow::anyhow!("Invalid driver glob '{}': {}", pattern, error))
+        })
+        .collect()
+}
+
+fn skipped_injection(driver: &DetectedDriver, reason: &str) -> DriverInjection {
+    DriverInjection {
+        name: driver.name.clone(),
+        inf_file: driver.inf_file.clone(),
+        class: driver.metadata.as_ref().and_then(|inf| inf.class.clone()),
+        result: InjectionResult::Skipped(reason.to_string()),
     }
 }
 
+/// `Class=` from an INF's `[Version]` section
+pub fn read_inf_class(inf_path: &Path) -> Option<String> {
+    InfFile::open(inf_path).ok()?.class
+}
+
 impl Default for DriverManager {
     fn default() -> Self {
         Self::new()
@@ -485,7 +893,8 @@ impl Default for DriverManager {
 
 #[cfg(test)]
 mod tests {
-    use super::{DriverManager, DriverType};
+    use super::{DriverManager, DriverType, InjectionResult, read_inf_class};
+    use super::inf::Architecture;
     use tempfile::tempdir;
 
     #[test]
@@ -495,7 +904,7 @@ mod tests {
         let sys_path = temp.path().join("iastorac.sys");
         let cab_path = temp.path().join("vendor.cab");
 
-        std::fs::write(&inf_path, "[Version]").unwrap();
+        std::fs::write(&inf_path, "[Version]\n[Install]\nCopyFiles=@IASTORAC.SYS\n").unwrap();
         std::fs::write(&sys_path, "binary").unwrap();
         std::fs::write(&cab_path, "cab").unwrap();
 
@@ -514,6 +923,237 @@ mod tests {
         assert!(cab_driver.inf_file.is_none());
     }
 
+    #[test]
+    fn sys_lookup_handles_relative_driver_folders() {
+        let manager = DriverManager::new();
+        let sys_driver = manager.classify_driver(std::path::Path::new("stray.sys")).unwrap().unwrap();
+        assert_eq!(sys_driver.driver_type, DriverType::Sys);
+        assert!(sys_driver.inf_file.is_none());
+    }
+
+    #[test]
+    fn links_sys_only_to_the_inf_that_references_it() {
+        let temp = tempdir().unwrap();
+        let package = temp.path().join("vmd");
+        let binaries = package.join("x64");
+        std::fs::create_dir_all(&binaries).unwrap();
+
+        let unrelated_inf = package.join("audio.inf");
+        let storage_inf = package.join("iaStorVD.inf");
+        let sys_path = binaries.join("iaStorVD.sys");
+        std::fs::write(&unrelated_inf, "[Version]\nClass=Media\n").unwrap();
+        std::fs::write(
+            &storage_inf,
+            "[Version]\nClass=SCSIAdapter\n[SourceDisksFiles.amd64]\niaStorVD.sys=1,x64\n",
+        )
+        .unwrap();
+        std::fs::write(&sys_path, "binary").unwrap();
+
+        let manager = DriverManager::new();
+        let sys_driver = manager.classify_driver(&sys_path).unwrap().unwrap();
+        assert_eq!(sys_driver.inf_file.as_deref(), Some(storage_inf.as_path()));
+        assert_eq!(sys_driver.metadata.unwrap().class.as_deref(), Some("SCSIAdapter"));
+
+        std::fs::remove_file(&storage_inf).unwrap();
+        let orphan = manager.classify_driver(&sys_path).unwrap().unwrap();
+        assert!(orphan.inf_file.is_none());
+    }
+
+    #[test]
+    fn skips_drivers_for_other_architectures() {
+        let temp = tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("arm_only.inf"),
+            "[Version]\nClass=Net\n[Manufacturer]\nContoso=Models,NTarm64\n[Models.NTarm64]\nNIC=Install,PCI\\VEN_1234\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("x64.inf"),
+            "[Version]\nClass=Net\n[Manufacturer]\nContoso=Models,NTamd64\n[Models.NTamd64]\nNIC=Install,PCI\\VEN_1234\n",
+        )
+        .unwrap();
+
+        let mut manager = DriverManager::new();
+        manager.driver_paths.push(temp.path().to_path_buf());
+
+        let detected = manager.detect_drivers().unwrap();
+        assert_eq!(detected.iter().map(|driver| driver.name.as_str()).collect::<Vec<_>>(), vec!["x64.inf"]);
+
+        manager.set_target_architecture(Architecture::Arm64);
+        let detected = manager.detect_drivers().unwrap();
+        assert_eq!(detected.iter().map(|driver| driver.name.as_str()).collect::<Vec<_>>(), vec!["arm_only.inf"]);
+    }
+
+    #[test]
+    fn refuses_packages_without_consistent_catalogs() {
+        let temp = tempdir().unwrap();
+        let signed_dir = temp.path().join("signed");
+        let unsigned_dir = temp.path().join("unsigned");
+        std::fs::create_dir_all(&signed_dir).unwrap();
+        std::fs::create_dir_all(&unsigned_dir).unwrap();
+
+        let signed_inf = "[Version]\nClass=Net\nCatalogFile=signed.cat\n";
+        std::fs::write(signed_dir.join("signed.inf"), signed_inf).unwrap();
+        std::fs::write(
+            signed_dir.join("signed.cat"),
+            super::catalog::tests::build_catalog(&[("signed.inf", signed_inf.as_bytes())], true),
+        )
+        .unwrap();
+        std::fs::write(unsigned_dir.join("unsigned.inf"), "[Version]\nClass=Net\n").unwrap();
+        std::fs::write(temp.path().join("vendor.cab"), "cab").unwrap();
+
+        let mut manager = DriverManager::new();
+        manager.driver_paths.push(temp.path().to_path_buf());
+        let drivers = manager.detect_drivers().unwrap();
+        let verifications = manager.verify_drivers(&drivers);
+        assert_eq!(verifications.len(), 2);
+
+        let (allowed, refused) = manager.refuse_unsigned(drivers, &verifications);
+        assert_eq!(allowed.iter().map(|driver| driver.name.as_str()).collect::<Vec<_>>(), vec!["signed.inf"]);
+        let mut reasons: Vec<String> = refused
+            .into_iter()
+            .map(|injection| match injection.result {
+                InjectionResult::Skipped(reason) => format!("{}: {}", injection.name, reason),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        reasons.sort();
+        assert_eq!(
+            reasons,
+            vec![
+                "unsigned.inf: unsigned: missing catalog",
+                "vendor.cab: unsigned: CAB package could not be expanded",
+            ]
+        );
+    }
+
+    #[test]
+    fn selects_install_image_drivers_by_class() {
+        let temp = tempdir().unwrap();
+        for (name, class) in [("iastorvd.inf", "SCSIAdapter"), ("stornvme.inf", "hdc"), ("e1d.inf", "Net")] {
+            std::fs::write(temp.path().join(name), format!("[Version]\nClass={}\n", class)).unwrap();
+        }
+        std::fs::write(temp.path().join("noclass.inf"), "[Version]\n").unwrap();
+
+        let mut manager = DriverManager::new();
+        manager.driver_paths = vec![temp.path().to_path_buf()];
+        let drivers = manager.detect_drivers().unwrap();
+
+        let names = |classes: &[&str]| {
+            let classes: Vec<String> = classes.iter().map(|class| class.to_string()).collect();
+            let mut names: Vec<String> =
+                super::install_image_drivers(&drivers, &classes).into_iter().map(|driver| driver.name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&["SCSIAdapter", "HDC"]), ["iastorvd.inf", "stornvme.inf"]);
+        assert_eq!(names(&["*"]), ["e1d.inf", "iastorvd.inf", "stornvme.inf"]);
+        assert!(names(&["Display"]).is_empty());
+    }
+
+    #[test]
+    fn applies_config_filters_and_class_policies() {
+        let temp = tempdir().unwrap();
+        for (folder, name, class) in [
+            ("storage", "iastorvd.inf", "SCSIAdapter"),
+            ("network", "e1d.inf", "Net"),
+            ("display", "igdlh.inf", "Display"),
+            ("audio", "realtek.inf", "Media"),
+            ("storage/old", "iastora.inf", "SCSIAdapter"),
+        ] {
+            let dir = temp.path().join(folder);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(name), format!("[Version]\nClass={}\n", class)).unwrap();
+        }
+
+        let config: crate::cli::DriversConfig = toml::from_str(
+            r#"
+            exclude = ["STORAGE/OLD/**"]
+            default_policy = "skip"
+
+            [class_policy]
+            scsiadapter = "inject"
+            Net = "inject"
+            Display = "stage"
+            "#,
+        )
+        .unwrap();
+        let mut manager = DriverManager::from_config(&config).unwrap();
+        manager.driver_paths.push(temp.path().to_path_buf());
+
+        let drivers = manager.detect_drivers().unwrap();
+        assert_eq!(drivers.len(), 4);
+        assert!(drivers.iter().all(|driver| driver.name != "iastora.inf"));
+
+        let names = |drivers: &[super::DetectedDriver]| {
+            let mut names: Vec<String> = drivers.iter().map(|driver| driver.name.clone()).collect();
+            names.sort();
+            names
+        };
+        let outcome = manager.apply_class_policies(drivers.clone());
+        assert_eq!(names(&outcome.inject), vec!["e1d.inf", "iastorvd.inf"]);
+        assert_eq!(names(&outcome.stage), vec!["igdlh.inf"]);
+        assert_eq!(outcome.skipped.len(), 1);
+        assert_eq!(outcome.skipped[0].name, "realtek.inf");
+
+        // Without inject_all only priority drivers reach the driver store
+        let config = crate::cli::DriversConfig { inject_all: false, ..config };
+        let manager = DriverManager::from_config(&config).unwrap();
+        let outcome = manager.apply_class_policies(drivers);
+        assert_eq!(names(&outcome.inject), vec!["iastorvd.inf"]);
+        assert_eq!(names(&outcome.stage), vec!["e1d.inf", "igdlh.inf"]);
+
+        let config = crate::cli::DriversConfig { include: vec!["[".to_string()], ..Default::default() };
+        assert!(DriverManager::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn expands_cab_packages_into_separate_directories() {
+        let temp = tempdir().unwrap();
+        for (model, device) in [("7010", "1234"), ("7020", "5678")] {
+            let inf = format!(
+                "[Version]\nClass=Net\n[Manufacturer]\nContoso=Models,NTamd64\n[Models.NTamd64]\nNIC=Install,PCI\\VEN_{}\n",
+                device
+            );
+            let dir = temp.path().join(model);
+            std::fs::create_dir_all(&dir).unwrap();
+            let cab = super::cab::tests::stored_cab(&[(r"x64\network\e1d.inf", inf.as_bytes()), ("readme.txt", b"notes")]);
+            std::fs::write(dir.join("drivers.cab"), cab).unwrap();
+        }
+
+        let mut manager = DriverManager::new();
+        manager.driver_paths.push(temp.path().to_path_buf());
+        let drivers = manager.detect_drivers().unwrap();
+        assert_eq!(drivers.len(), 2);
+        assert!(drivers.iter().all(|driver| driver.driver_type == DriverType::Inf));
+        assert!(drivers.iter().all(|driver| driver.metadata.as_ref().unwrap().class.as_deref() == Some("Net")));
+        assert_ne!(drivers[0].path.parent(), drivers[1].path.parent());
+        let mut sources: Vec<_> = drivers.iter().map(|driver| driver.source_cab.clone().unwrap()).collect();
+        sources.sort();
+        assert_eq!(sources, vec![temp.path().join("7010").join("drivers.cab"), temp.path().join("7020").join("drivers.cab")]);
+        assert_eq!(manager.summarize_driver_risks(&drivers).cabs_expanded, 2);
+
+        // CAB entries handed straight to injection are expanded on the spot
+        let cab_driver = manager.classify_driver(&temp.path().join("7010").join("drivers.cab")).unwrap().unwrap();
+        let config = crate::cli::GhostwinConfig {
+            iso: crate::cli::IsoConfig {
+                mount_path: Some(temp.path().join("mount").display().to_string()),
+                ..crate::cli::GhostwinConfig::default().iso
+            },
+            ..crate::cli::GhostwinConfig::default()
+        };
+        let backend = crate::wim::backend::RecordingBackend::default();
+        let mut wim_manager = crate::wim::WimManager::with_backend(&config, Box::new(backend.clone())).unwrap();
+        wim_manager.mount(&temp.path().join("boot.wim"), 1).await.unwrap();
+
+        let injections = manager.inject_drivers_to_wim(&wim_manager, &[cab_driver]).await.unwrap();
+        assert_eq!(injections.len(), 1);
+        assert_eq!(injections[0].name, "e1d.inf");
+        assert_eq!(injections[0].result, InjectionResult::Injected);
+        assert!(backend.calls().contains(&"add_driver e1d.inf".to_string()));
+        wim_manager.unmount_and_discard().await.unwrap();
+    }
+
     #[test]
     fn prioritizes_storage_drivers_before_generic_ones() {
         let temp = tempdir().unwrap();
@@ -558,4 +1198,53 @@ mod tests {
         assert_eq!(summary.missing_inf_for_sys, 1);
         assert_eq!(summary.cab_packages, 1);
     }
+
+    #[tokio::test]
+    async fn reports_injection_outcome_per_driver() {
+        let temp = tempdir().unwrap();
+        let inf_path = temp.path().join("iastorac.inf");
+        let orphan_sys_dir = temp.path().join("orphan-sys");
+        let sys_without_inf = orphan_sys_dir.join("mystery.sys");
+        std::fs::create_dir_all(&orphan_sys_dir).unwrap();
+        std::fs::write(&inf_path, "[Version]\r\nSignature=\"$WINDOWS NT$\"\r\nClass = SCSIAdapter ; storage\r\n").unwrap();
+        std::fs::write(&sys_without_inf, "binary").unwrap();
+
+        let manager = DriverManager::new();
+        let drivers = vec![
+            manager.classify_driver(&inf_path).unwrap().unwrap(),
+            manager.classify_driver(&sys_without_inf).unwrap().unwrap(),
+        ];
+
+        let config = crate::cli::GhostwinConfig {
+            iso: crate::cli::IsoConfig {
+                mount_path: Some(temp.path().join("mount").display().to_string()),
+                ..crate::cli::GhostwinConfig::default().iso
+            },
+            ..crate::cli::GhostwinConfig::default()
+        };
+        let backend = crate::wim::backend::RecordingBackend::default();
+        let mut wim_manager = crate::wim::WimManager::with_backend(&config, Box::new(backend.clone())).unwrap();
+        wim_manager.mount(&temp.path().join("boot.wim"), 1).await.unwrap();
+
+        let injections = manager.inject_drivers_to_wim(&wim_manager, &drivers).await.unwrap();
+        assert_eq!(injections.len(), 2);
+        assert_eq!(injections[0].class.as_deref(), Some("SCSIAdapter"));
+        assert_eq!(injections[0].result, InjectionResult::Injected);
+        assert!(matches!(injections[1].result, InjectionResult::Skipped(_)));
+        assert!(backend.calls().contains(&"add_driver iastorac.inf".to_string()));
+        wim_manager.unmount_and_discard().await.unwrap();
+    }
+
+    #[test]
+    fn reads_class_from_utf16_inf() {
+        let temp = tempdir().unwrap();
+        let inf_path = temp.path().join("net.inf");
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "[Strings]\r\nClass=Wrong\r\n[version]\r\nClass=\"Net\"\r\n".encode_utf16() {
+            bytes.extend(unit.to_le_bytes());
+        }
+        std::fs::write(&inf_path, bytes).unwrap();
+
+        assert_eq!(read_inf_class(&inf_path).as_deref(), Some("Net"));
+    }
 }
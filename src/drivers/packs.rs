@@ -0,0 +1,664 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::Digest;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PackVendor {
+    Dell,
+    Hp,
+    Lenovo,
+}
+
+impl fmt::Display for PackVendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dell => "Dell",
+            Self::Hp => "HP",
+            Self::Lenovo => "Lenovo",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackHash {
+    pub algorithm: DigestAlgorithm,
+    /// Lowercase hex
+    pub value: String,
+}
+
+/// One downloadable driver pack from a vendor catalog
+#[derive(Debug, Clone, Serialize)]
+pub struct DriverPack {
+    pub vendor: PackVendor,
+    pub name: String,
+    pub version: Option<String>,
+    /// Model names, system IDs and machine types the pack supports; the first is the display name
+    pub models: Vec<String>,
+    /// Operating systems as the catalog spells them
+    pub operating_systems: Vec<String>,
+    pub url: Option<String>,
+    /// Archive file name expected in the downloads folder
+    pub file_name: String,
+    pub size: Option<u64>,
+    pub hashes: Vec<PackHash>,
+}
+
+impl DriverPack {
+    /// Strongest hash that can be checked (SHA-256, then SHA-1)
+    pub fn verifiable_hash(&self) -> Option<&PackHash> {
+        self.hashes
+            .iter()
+            .filter(|hash| hash.algorithm != DigestAlgorithm::Md5)
+            .max_by_key(|hash| hash.algorithm)
+    }
+
+    fn matches_model(&self, query: &str) -> bool {
+        let query = tokens(query);
+        !query.is_empty()
+            && self.models.iter().any(|model| {
+                let model = tokens(model);
+                model.windows(query.len()).any(|window| window == query.as_slice())
+            })
+    }
+
+    fn matches_os(&self, os: &str) -> bool {
+        let wanted = os_key(os);
+        self.operating_systems.is_empty()
+            || self.operating_systems.iter().any(|candidate| os_key(candidate) == wanted)
+    }
+
+    /// `<vendor>/<model>/<os>` below the driver folder
+    pub fn layout_dir(&self, root: &Path) -> PathBuf {
+        let model = self.models.first().map(String::as_str).unwrap_or(&self.name);
+        let os = self
+            .operating_systems
+            .iter()
+            .find_map(|os| os_key(os))
+            .unwrap_or_else(|| "any".to_string());
+        root.join(self.vendor.to_string()).join(slug(model)).join(os)
+    }
+}
+
+/// A vendor driver pack catalog read from disk
+#[derive(Debug, Clone)]
+pub struct PackCatalog {
+    pub vendor: PackVendor,
+    pub path: PathBuf,
+    pub packs: Vec<DriverPack>,
+}
+
+impl PackCatalog {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read driver pack catalog {}", path.display()))?;
+        let text = super::inf::decode_inf_text(&bytes);
+        Self::parse(path, &text).with_context(|| format!("Failed to parse driver pack catalog {}", path.display()))
+    }
+
+    pub fn parse(path: &Path, xml: &str) -> Result<Self> {
+        let document = roxmltree::Document::parse(xml).context("Invalid catalog XML")?;
+        let root = document.root_element();
+        let (vendor, packs) = match root.tag_name().name() {
+            "DriverPackManifest" => (PackVendor::Dell, parse_dell(root)),
+            "NewDataSet" | "HPClientDriverPackCatalog" => (PackVendor::Hp, parse_hp(root)),
+            "ModelList" => (PackVendor::Lenovo, parse_lenovo(root)),
+            other => bail!(
+                "Unrecognized catalog root <{}>; expected a Dell DriverPackCatalog, HP HPClientDriverPackCatalog or Lenovo catalogv2",
+                other
+            ),
+        };
+
+        Ok(Self {
+            vendor,
+            path: path.to_path_buf(),
+            packs,
+        })
+    }
+
+    /// Packs supporting any of `models` and, when given, the operating system `os`
+    pub fn select(&self, models: &[String], os: Option<&str>) -> Vec<&DriverPack> {
+        self.packs
+            .iter()
+            .filter(|pack| models.iter().any(|model| pack.matches_model(model)))
+            .filter(|pack| os.is_none_or(|os| pack.matches_os(os)))
+            .collect()
+    }
+}
+
+/// Dell `DriverPackCatalog.xml`
+fn parse_dell(root: roxmltree::Node) -> Vec<DriverPack> {
+    let base = root.attribute("baseLocation").map(|base| base.trim_end_matches('/'));
+    let mut packs = Vec::new();
+
+    for package in root.children().filter(|node| node.has_tag_name("DriverPackage")) {
+        let Some(path) = package.attribute("path") else {
+            continue;
+        };
+
+        let mut models = Vec::new();
+        let mut system_ids = Vec::new();
+        for brand in descendants(package, "Brand") {
+            let brand_name = display_text(brand).unwrap_or_default();
+            for model in children(brand, "Model") {
+                let name = display_text(model).or_else(|| model.attribute("name").map(str::to_string));
+                if let Some(name) = name {
+                    models.push(format!("{} {}", brand_name, name).trim().to_string());
+                }
+                system_ids.extend(model.attribute("systemID").map(str::to_string));
+            }
+        }
+        models.extend(system_ids);
+
+        let mut hashes: Vec<PackHash> = descendants(package, "Hash")
+            .filter_map(|hash| {
+                let algorithm = digest_algorithm(hash.attribute("algorithm")?)?;
+                Some(PackHash { algorithm, value: hash.text()?.trim().to_ascii_lowercase() })
+            })
+            .collect();
+        if let Some(md5) = package.attribute("hashMD5")
+            && !hashes.iter().any(|hash| hash.algorithm == DigestAlgorithm::Md5)
+        {
+            hashes.push(PackHash { algorithm: DigestAlgorithm::Md5, value: md5.to_ascii_lowercase() });
+        }
+
+        let operating_systems = descendants(package, "OperatingSystem")
+            .filter_map(|os| os.attribute("osCode").map(str::to_string).or_else(|| display_text(os)))
+            .collect();
+
+        packs.push(DriverPack {
+            vendor: PackVendor::Dell,
+            name: children(package, "Name")
+                .next()
+                .and_then(display_text)
+                .unwrap_or_else(|| file_name(path)),
+            version: package.attribute("dellVersion").or(package.attribute("vendorVersion")).map(str::to_string),
+            models,
+            operating_systems,
+            url: base.map(|base| {
+                let base = if base.contains("://") { base.to_string() } else { format!("https://{}", base) };
+                format!("{}/{}", base, path.trim_start_matches('/'))
+            }),
+            file_name: file_name(path),
+            size: package.attribute("size").and_then(|size| size.parse().ok()),
+            hashes,
+        });
+    }
+
+    packs
+}
+
+/// HP `HPClientDriverPackCatalog.xml`: SoftPaqs plus a product/OS table pointing at them
+fn parse_hp(root: roxmltree::Node) -> Vec<DriverPack> {
+    let mut products: BTreeMap<String, (Vec<String>, Vec<String>)> = BTreeMap::new();
+    for product in descendants(root, "ProductOSDriverPack") {
+        let Some(softpaq) = child_text(product, "SoftPaqId") else {
+            continue;
+        };
+        let (models, operating_systems) = products.entry(softpaq.to_ascii_lowercase()).or_default();
+        for value in [child_text(product, "SystemName"), child_text(product, "SystemId")].into_iter().flatten() {
+            if !models.contains(&value) {
+                models.push(value);
+            }
+        }
+        if let Some(os) = child_text(product, "OSName")
+            && !operating_systems.contains(&os)
+        {
+            operating_systems.push(os);
+        }
+    }
+
+    let mut packs = Vec::new();
+    for softpaq in descendants(root, "SoftPaq") {
+        let (Some(id), Some(url)) = (child_text(softpaq, "Id"), child_text(softpaq, "Url")) else {
+            continue;
+        };
+        let Some((models, operating_systems)) = products.get(&id.to_ascii_lowercase()) else {
+            continue;
+        };
+
+        let hashes = [("SHA256", DigestAlgorithm::Sha256), ("MD5", DigestAlgorithm::Md5)]
+            .into_iter()
+            .filter_map(|(element, algorithm)| {
+                Some(PackHash { algorithm, value: child_text(softpaq, element)?.to_ascii_lowercase() })
+            })
+            .collect();
+
+        packs.push(DriverPack {
+            vendor: PackVendor::Hp,
+            name: child_text(softpaq, "Name").unwrap_or_else(|| id.clone()),
+            version: child_text(softpaq, "Version"),
+            models: models.clone(),
+            operating_systems: operating_systems.clone(),
+            file_name: file_name(&url),
+            url: Some(url),
+            size: child_text(softpaq, "Size").and_then(|size| size.parse().ok()),
+            hashes,
+        });
+    }
+
+    packs
+}
+
+/// Lenovo `catalogv2.xml`: one SCCM driver pack per model and OS
+fn parse_lenovo(root: roxmltree::Node) -> Vec<DriverPack> {
+    let mut packs = Vec::new();
+    for model in children(root, "Model") {
+        let name = model.attribute("name").unwrap_or_default().to_string();
+        let mut models = vec![name.clone()];
+        models.extend(descendants(model, "Type").filter_map(|node| node.text()).map(|text| text.trim().to_string()));
+
+        for sccm in children(model, "SCCM") {
+            let Some(url) = sccm.text().map(str::trim).filter(|url| !url.is_empty()) else {
+                continue;
+            };
+            // catalogv2 publishes the SHA-256 of the pack in `crc`
+            let hashes = sccm
+                .attribute("crc")
+                .filter(|crc| crc.len() == 64 && crc.chars().all(|c| c.is_ascii_hexdigit()))
+                .map(|crc| PackHash { algorithm: DigestAlgorithm::Sha256, value: crc.to_ascii_lowercase() })
+                .into_iter()
+                .collect();
+            let os = sccm.attribute("os").unwrap_or_default();
+
+            packs.push(DriverPack {
+                vendor: PackVendor::Lenovo,
+                name: format!("{} {} {}", name, os, sccm.attribute("version").unwrap_or_default()).trim().to_string(),
+                version: sccm.attribute("version").map(str::to_string),
+                models: models.clone(),
+                operating_systems: vec![os.to_string()].into_iter().filter(|os| !os.is_empty()).collect(),
+                url: Some(url.to_string()),
+                file_name: file_name(url),
+                size: None,
+                hashes,
+            });
+        }
+    }
+    packs
+}
+
+/// What `drivers import` did with one selected pack
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PackImport {
+    Imported { destination: PathBuf },
+    /// Destination already holds an archive with the catalog hash
+    AlreadyPresent { destination: PathBuf },
+    /// `--dry-run`: verified and would be copied here
+    Planned { destination: PathBuf },
+    NotDownloaded { expected: PathBuf, url: Option<String> },
+    HashMismatch { archive: PathBuf, algorithm: DigestAlgorithm, expected: String, actual: String },
+    /// The catalog lists no SHA-256 or SHA-1 hash for the pack
+    Unverifiable { archive: PathBuf },
+}
+
+impl PackImport {
+    pub fn is_problem(&self) -> bool {
+        matches!(self, Self::NotDownloaded { .. } | Self::HashMismatch { .. } | Self::Unverifiable { .. })
+    }
+}
+
+/// Verify a downloaded pack against the catalog and copy it into `<root>/<vendor>/<model>/<os>`
+pub fn import_pack(pack: &DriverPack, downloads: &Path, root: &Path, dry_run: bool) -> Result<PackImport> {
+    let Some(archive) = find_download(downloads, &pack.file_name)? else {
+        return Ok(PackImport::NotDownloaded {
+            expected: downloads.join(&pack.file_name),
+            url: pack.url.clone(),
+        });
+    };
+    let Some(hash) = pack.verifiable_hash() else {
+        return Ok(PackImport::Unverifiable { archive });
+    };
+
+    let actual = digest_file(hash.algorithm, &archive)?;
+    if actual != hash.value {
+        return Ok(PackImport::HashMismatch {
+            archive,
+            algorithm: hash.algorithm,
+            expected: hash.value.clone(),
+            actual,
+        });
+    }
+
+    let destination = pack.layout_dir(root).join(&pack.file_name);
+    if dry_run {
+        return Ok(PackImport::Planned { destination });
+    }
+    if destination.is_file() && digest_file(hash.algorithm, &destination)? == hash.value {
+        return Ok(PackImport::AlreadyPresent { destination });
+    }
+
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::copy(&archive, &destination)
+        .with_context(|| format!("Failed to copy {} to {}", archive.display(), destination.display()))?;
+    Ok(PackImport::Imported { destination })
+}
+
+/// Case-insensitive lookup, since catalog and download names often differ in case
+fn find_download(downloads: &Path, name: &str) -> Result<Option<PathBuf>> {
+    let exact = downloads.join(name);
+    if exact.is_file() {
+        return Ok(Some(exact));
+    }
+    if !downloads.is_dir() {
+        bail!("Downloads folder {} does not exist", downloads.display());
+    }
+
+    for entry in std::fs::read_dir(downloads).with_context(|| format!("Failed to read {}", downloads.display()))? {
+        let path = entry?.path();
+        if path.is_file() && path.file_name().is_some_and(|candidate| candidate.to_string_lossy().eq_ignore_ascii_case(name)) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+fn digest_file(algorithm: DigestAlgorithm, path: &Path) -> Result<String> {
+    match algorithm {
+        DigestAlgorithm::Sha256 => crate::utils::sha256_file(path),
+        DigestAlgorithm::Sha1 => {
+            let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {} for hashing", path.display()))?;
+            let mut hasher = Sha1::new();
+            let mut buffer = vec![0_u8; 1024 * 1024];
+            loop {
+                let read = file.read(&mut buffer).with_context(|| format!("Failed to read {} for hashing", path.display()))?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+            Ok(format!("{:x}", hasher.finalize()))
+        }
+        DigestAlgorithm::Md5 => bail!("MD5 hashes cannot be verified"),
+    }
+}
+
+fn digest_algorithm(name: &str) -> Option<DigestAlgorithm> {
+    match name.to_ascii_uppercase().replace('-', "").as_str() {
+        "SHA256" => Some(DigestAlgorithm::Sha256),
+        "SHA1" => Some(DigestAlgorithm::Sha1),
+        "MD5" => Some(DigestAlgorithm::Md5),
+        _ => None,
+    }
+}
+
+/// Normalized OS family: `win10`, `win11` or `winpe`
+fn os_key(value: &str) -> Option<String> {
+    let compact: String = value
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if compact.starts_with("winpe") {
+        return Some("winpe".to_string());
+    }
+
+    let rest = compact.strip_prefix("windows").or_else(|| compact.strip_prefix("win"))?;
+    let version: String = rest.chars().take_while(char::is_ascii_digit).take(2).collect();
+    if version.is_empty() { None } else { Some(format!("win{}", version)) }
+}
+
+fn tokens(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn slug(value: &str) -> String {
+    tokens(value).join("-")
+}
+
+/// Last path segment of a catalog path or URL
+fn file_name(path: &str) -> String {
+    path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+fn children<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(move |child| child.has_tag_name(name))
+}
+
+fn descendants<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.descendants().filter(move |child| child.has_tag_name(name))
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Dell `<Display lang="en">` text, preferring English
+fn display_text(node: roxmltree::Node) -> Option<String> {
+    let displays: Vec<roxmltree::Node> = children(node, "Display").collect();
+    displays
+        .iter()
+        .find(|display| display.attribute("lang").is_some_and(|lang| lang.eq_ignore_ascii_case("en")))
+        .or(displays.first())
+        .and_then(|display| display.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DigestAlgorithm, PackCatalog, PackImport, PackVendor, import_pack, os_key};
+    use std::path::Path;
+
+    const DELL: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<DriverPackManifest baseLocation="downloads.dell.com" version="2024.01">
+  <DriverPackage dellVersion="A05" path="FOLDER01/OptiPlex-7010-WIN11-A05.CAB" size="11" type="win">
+    <Name><Display lang="en"><![CDATA[OptiPlex 7010 Windows 11 Driver Pack]]></Display></Name>
+    <Cryptography>
+      <Hash algorithm="MD5">00000000000000000000000000000000</Hash>
+      <Hash algorithm="SHA256">HASH256</Hash>
+    </Cryptography>
+    <SupportedSystems>
+      <Brand key="1" prefix="OP">
+        <Display lang="en"><![CDATA[OptiPlex]]></Display>
+        <Model name="7010" systemID="0B1E"><Display lang="en"><![CDATA[7010]]></Display></Model>
+      </Brand>
+    </SupportedSystems>
+    <SupportedOperatingSystems>
+      <OperatingSystem osArch="x64" osCode="Windows11"><Display lang="en"><![CDATA[Windows 11 x64]]></Display></OperatingSystem>
+    </SupportedOperatingSystems>
+  </DriverPackage>
+  <DriverPackage dellVersion="A10" path="FOLDER02/OptiPlex-7010-WIN10-A10.CAB" hashMD5="11111111111111111111111111111111">
+    <Name><Display lang="en">OptiPlex 7010 Windows 10 Driver Pack</Display></Name>
+    <SupportedSystems>
+      <Brand><Display lang="en">OptiPlex</Display><Model name="7010" systemID="0B1E" /></Brand>
+    </SupportedSystems>
+    <SupportedOperatingSystems><OperatingSystem osCode="Windows10" /></SupportedOperatingSystems>
+  </DriverPackage>
+  <DriverPackage path="FOLDER03/OptiPlex-70100-WIN11-A01.CAB">
+    <SupportedSystems>
+      <Brand><Display lang="en">OptiPlex</Display><Model name="70100" systemID="0C11" /></Brand>
+    </SupportedSystems>
+  </DriverPackage>
+</DriverPackManifest>"#;
+
+    const HP: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<NewDataSet>
+  <HPClientDriverPackCatalog>
+    <SoftPaqList>
+      <SoftPaq>
+        <Id>SP143749</Id>
+        <Name>HP EliteBook 840 G8 Windows 11 Driver Pack</Name>
+        <Version>1.00 A 1</Version>
+        <Url>https://ftp.hp.com/pub/softpaq/sp143501-144000/sp143749.exe</Url>
+        <Size>1024</Size>
+        <MD5>abcdef</MD5>
+        <SHA256>0123ABCD</SHA256>
+      </SoftPaq>
+      <SoftPaq><Id>SP1</Id><Url>https://ftp.hp.com/pub/softpaq/sp1.exe</Url></SoftPaq>
+    </SoftPaqList>
+    <ProductOSDriverPackList>
+      <ProductOSDriverPack>
+        <ProductType>Notebook</ProductType>
+        <SystemId>880D, 8AB8</SystemId>
+        <SystemName>HP EliteBook 840 G8 Notebook PC</SystemName>
+        <OSName>Windows 11 64-bit, 22H2</OSName>
+        <SoftPaqId>SP143749</SoftPaqId>
+      </ProductOSDriverPack>
+      <ProductOSDriverPack>
+        <SystemId>880D</SystemId>
+        <SystemName>HP EliteBook 840 G8 Notebook PC</SystemName>
+        <OSName>Windows 11 64-bit, 23H2</OSName>
+        <SoftPaqId>SP143749</SoftPaqId>
+      </ProductOSDriverPack>
+    </ProductOSDriverPackList>
+  </HPClientDriverPackCatalog>
+</NewDataSet>"#;
+
+    const LENOVO: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<ModelList>
+  <Model name="ThinkPad T14 Gen 3 (Type 21AH, 21AJ)">
+    <Types><Type>21AH</Type><Type>21AJ</Type></Types>
+    <SCCM os="win10" version="21H2" crc="AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA">https://download.lenovo.com/pccbbs/mobiles/tp_t14_w1064_202212.exe</SCCM>
+    <SCCM os="win11" version="22H2" crc="not-a-hash">https://download.lenovo.com/pccbbs/mobiles/tp_t14_w11_202301.exe</SCCM>
+  </Model>
+</ModelList>"#;
+
+    fn models(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_dell_catalog_and_selects_by_model_and_os() {
+        let catalog = PackCatalog::parse(Path::new("DriverPackCatalog.xml"), DELL).unwrap();
+        assert_eq!(catalog.vendor, PackVendor::Dell);
+        assert_eq!(catalog.packs.len(), 3);
+
+        let pack = &catalog.packs[0];
+        assert_eq!(pack.name, "OptiPlex 7010 Windows 11 Driver Pack");
+        assert_eq!(pack.version.as_deref(), Some("A05"));
+        assert_eq!(pack.models, vec!["OptiPlex 7010", "0B1E"]);
+        assert_eq!(pack.file_name, "OptiPlex-7010-WIN11-A05.CAB");
+        assert_eq!(pack.url.as_deref(), Some("https://downloads.dell.com/FOLDER01/OptiPlex-7010-WIN11-A05.CAB"));
+        assert_eq!(pack.size, Some(11));
+        assert_eq!(pack.verifiable_hash().unwrap().algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(catalog.packs[1].hashes[0].algorithm, DigestAlgorithm::Md5);
+        assert!(catalog.packs[1].verifiable_hash().is_none());
+
+        // "7010" must not match the 70100 by substring
+        let selected = catalog.select(&models(&["optiplex 7010"]), None);
+        assert_eq!(selected.len(), 2);
+        let selected = catalog.select(&models(&["0b1e"]), Some("Windows 11"));
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].file_name, "OptiPlex-7010-WIN11-A05.CAB");
+        assert_eq!(
+            selected[0].layout_dir(Path::new("drivers")),
+            Path::new("drivers").join("Dell").join("optiplex-7010").join("win11")
+        );
+    }
+
+    #[test]
+    fn parses_hp_catalog_grouping_products_by_softpaq() {
+        let catalog = PackCatalog::parse(Path::new("HPClientDriverPackCatalog.xml"), HP).unwrap();
+        assert_eq!(catalog.vendor, PackVendor::Hp);
+        assert_eq!(catalog.packs.len(), 1);
+
+        let pack = &catalog.packs[0];
+        assert_eq!(pack.file_name, "sp143749.exe");
+        assert_eq!(pack.models, vec!["HP EliteBook 840 G8 Notebook PC", "880D, 8AB8", "880D"]);
+        assert_eq!(pack.operating_systems.len(), 2);
+        assert_eq!(pack.verifiable_hash().unwrap().value, "0123abcd");
+
+        assert_eq!(catalog.select(&models(&["8AB8"]), Some("win11")).len(), 1);
+        assert_eq!(catalog.select(&models(&["EliteBook 840 G8"]), Some("win10")).len(), 0);
+    }
+
+    #[test]
+    fn parses_lenovo_catalog_with_machine_types() {
+        let catalog = PackCatalog::parse(Path::new("catalogv2.xml"), LENOVO).unwrap();
+        assert_eq!(catalog.vendor, PackVendor::Lenovo);
+        assert_eq!(catalog.packs.len(), 2);
+
+        let selected = catalog.select(&models(&["21aj"]), Some("win10"));
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].file_name, "tp_t14_w1064_202212.exe");
+        assert_eq!(selected[0].verifiable_hash().unwrap().value, "a".repeat(64));
+        // crc values that are not SHA-256 digests are ignored
+        assert!(catalog.packs[1].hashes.is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_catalogs() {
+        let error = PackCatalog::parse(Path::new("x.xml"), "<Catalog />").unwrap_err();
+        assert!(error.to_string().contains("Unrecognized catalog root <Catalog>"));
+    }
+
+    #[test]
+    fn normalizes_operating_system_names() {
+        assert_eq!(os_key("Windows11").as_deref(), Some("win11"));
+        assert_eq!(os_key("Windows 10 64-bit, 22H2").as_deref(), Some("win10"));
+        assert_eq!(os_key("win1064").as_deref(), Some("win10"));
+        assert_eq!(os_key("WinPE10.0").as_deref(), Some("winpe"));
+        assert_eq!(os_key("Linux"), None);
+    }
+
+    #[test]
+    fn imports_verified_packs_and_refuses_mismatches() {
+        let temp = tempfile::tempdir().unwrap();
+        let downloads = temp.path().join("downloads");
+        let output = temp.path().join("drivers");
+        std::fs::create_dir(&downloads).unwrap();
+        // Downloaded with different case than the catalog path
+        std::fs::write(downloads.join("optiplex-7010-win11-a05.cab"), b"driver pack").unwrap();
+        let digest = crate::utils::sha256_file(&downloads.join("optiplex-7010-win11-a05.cab")).unwrap();
+
+        let mut catalog = PackCatalog::parse(Path::new("DriverPackCatalog.xml"), &DELL.replace("HASH256", &digest)).unwrap();
+        let pack = catalog.packs[0].clone();
+
+        let planned = import_pack(&pack, &downloads, &output, true).unwrap();
+        assert!(matches!(planned, PackImport::Planned { .. }));
+        assert!(!output.exists());
+
+        let PackImport::Imported { destination } = import_pack(&pack, &downloads, &output, false).unwrap() else {
+            panic!("expected import");
+        };
+        assert_eq!(destination, output.join("Dell/optiplex-7010/win11/OptiPlex-7010-WIN11-A05.CAB"));
+        assert_eq!(std::fs::read(&destination).unwrap(), b"driver pack");
+        assert!(matches!(
+            import_pack(&pack, &downloads, &output, false).unwrap(),
+            PackImport::AlreadyPresent { .. }
+        ));
+
+        catalog.packs[0].hashes[1].value = "0".repeat(64);
+        let mismatch = import_pack(&catalog.packs[0], &downloads, &output, false).unwrap();
+        assert!(matches!(mismatch, PackImport::HashMismatch { algorithm: DigestAlgorithm::Sha256, .. }));
+        assert!(mismatch.is_problem());
+
+        let md5_only = import_pack(&catalog.packs[1], &downloads, &output, false).unwrap();
+        assert!(matches!(md5_only, PackImport::NotDownloaded { .. }));
+        std::fs::write(downloads.join("OptiPlex-7010-WIN10-A10.CAB"), b"other").unwrap();
+        let md5_only = import_pack(&catalog.packs[1], &downloads, &output, false).unwrap();
+        assert!(matches!(md5_only, PackImport::Unverifiable { .. }));
+    }
+}
@@ -0,0 +1,301 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use super::inf::{Architecture, DriverVer};
+use super::inventory::{InventoryDevice, rank_model};
+use super::{DetectedDriver, DriverType};
+
+/// Staged driver repository inside the boot image (`X:\GhostWin\Drivers` in WinPE)
+pub const REPOSITORY_DIR: &str = "GhostWin/Drivers";
+/// Hardware ID index written next to the staged packages
+pub const INDEX_FILE: &str = "index.json";
+
+/// Build-time map from hardware IDs to the staged INF files, read by `ghostwin load-drivers`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriverIndex {
+    pub architecture: Architecture,
+    pub packages: Vec<IndexedPackage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedPackage {
+    /// INF path relative to the repository, `/`-separated
+    pub inf: String,
+    pub class: Option<String>,
+    pub driver_ver: Option<DriverVer>,
+    /// Models for `architecture` only
+    pub models: Vec<IndexedModel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedModel {
+    pub description: String,
+    /// Hardware ID followed by any compatible IDs
+    pub hardware_ids: Vec<String>,
+}
+
+/// Staged package directory for a driver, relative to the repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedDirectory {
+    pub source: PathBuf,
+    pub name: String,
+}
+
+/// Assign each driver's package directory a unique folder in the repository
+///
+/// Folders are numbered in driver order so same-named vendor folders never collide;
+/// packages expanded from a CAB are named after the CAB.
+pub fn plan_staging(drivers: &[DetectedDriver]) -> Vec<StagedDirectory> {
+    let mut staged: Vec<StagedDirectory> = Vec::new();
+    for driver in drivers {
+        let Some(source) = driver.path.parent() else {
+            continue;
+        };
+        if staged.iter().any(|directory| directory.source == source) {
+            continue;
+        }
+        let label = driver
+            .source_cab
+            .as_deref()
+            .and_then(Path::file_stem)
+            .or_else(|| source.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "drivers".to_string());
+        staged.push(StagedDirectory {
+            source: source.to_path_buf(),
+            name: format!("{:03}-{}", staged.len() + 1, label),
+        });
+    }
+    staged
+}
+
+impl DriverIndex {
+    /// Index the INF packages among `drivers` as they will appear once `staging` is copied
+    pub fn build(drivers: &[DetectedDriver], staging: &[StagedDirectory], architecture: Architecture) -> Self {
+        let mut packages = Vec::new();
+        for driver in drivers.iter().filter(|driver| driver.driver_type == DriverType::Inf) {
+            let (Some(inf), Some(file_name)) = (&driver.metadata, driver.path.file_name()) else {
+                continue;
+            };
+            let Some(directory) = driver.path.parent().and_then(|parent| staging.iter().find(|staged| staged.source == parent))
+            else {
+                continue;
+            };
+            let models: Vec<IndexedModel> = inf
+                .models
+                .iter()
+                .filter(|model| model.applies_to(architecture) && !model.hardware_ids.is_empty())
+                .map(|model| IndexedModel {
+                    description: model.description.clone(),
+                    hardware_ids: model.hardware_ids.clone(),
+                })
+                .collect();
+            if models.is_empty() {
+                continue;
+            }
+            packages.push(IndexedPackage {
+                inf: format!("{}/{}", directory.name, file_name.to_string_lossy()),
+                class: inf.class.clone(),
+                driver_ver: inf.driver_ver,
+                models,
+            });
+        }
+        Self { architecture, packages }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read driver index {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid driver index {}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write driver index {}", path.display()))
+    }
+
+    /// Best-ranked package for every device, with the same ranking as `build --hardware-inventory`
+    pub fn match_devices(&self, devices: &[InventoryDevice]) -> Vec<IndexMatch> {
+        let mut matches = Vec::new();
+        for device in devices {
+            let mut best: Option<IndexMatch> = None;
+            for package in &self.packages {
+                for model in &package.models {
+                    let Some((rank, matched_id)) = rank_model(device, &model.hardware_ids) else {
+                        continue;
+                    };
+                    let better = best.as_ref().is_none_or(|current| {
+                        rank < current.rank || (rank == current.rank && package.driver_ver > current.driver_ver)
+                    });
+                    if better {
+                        best = Some(IndexMatch {
+                            device: device.clone(),
+                            inf: package.inf.clone(),
+                            model: model.description.clone(),
+                            matched_id: matched_id.to_string(),
+                            rank,
+                            driver_ver: package.driver_ver,
+                        });
+                    }
+                }
+            }
+            matches.extend(best);
+        }
+        matches
+    }
+}
+
+/// A present device and the staged INF chosen for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexMatch {
+    pub device: InventoryDevice,
+    pub inf: String,
+    pub model: String,
+    pub matched_id: String,
+    /// Windows-style match rank; lower is better
+    pub rank: u32,
+    pub driver_ver: Option<DriverVer>,
+}
+
+/// Present devices from `pnputil /enum-devices /connected /ids`
+///
+/// Devices are separated by blank lines; multi-valued fields continue on
+/// indented lines without a label.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn parse_pnputil_devices(output: &str) -> Vec<InventoryDevice> {
+    let mut devices = Vec::new();
+    let mut fields: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+
+    let mut finish = |fields: &mut BTreeMap<String, Vec<String>>| {
+        let mut take = |key: &str| fields.remove(key).unwrap_or_default();
+        let id = take("instance id").into_iter().next();
+        let name = take("device description").into_iter().next();
+        let hardware_ids = take("hardware ids");
+        let compatible_ids = take("compatible ids");
+        fields.clear();
+        if let Some(id) = id
+            && !hardware_ids.is_empty()
+        {
+            devices.push(InventoryDevice {
+                id,
+                name,
+                hardware_ids,
+                compatible_ids,
+            });
+        }
+    };
+
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            finish(&mut fields);
+            current = None;
+            continue;
+        }
+        let labelled = !line.starts_with(char::is_whitespace);
+        match line.split_once(':') {
+            Some((label, value)) if labelled => {
+                let label = label.trim().to_ascii_lowercase();
+                if label == "instance id" && fields.contains_key("instance id") {
+                    finish(&mut fields);
+                }
+                let values = fields.entry(label.clone()).or_default();
+                if !value.trim().is_empty() {
+                    values.push(value.trim().to_string());
+                }
+                current = Some(label);
+            }
+            _ => {
+                if let Some(label) = &current {
+                    fields.entry(label.clone()).or_default().push(line.trim().to_string());
+                }
+            }
+        }
+    }
+    finish(&mut fields);
+    devices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DriverIndex, parse_pnputil_devices, plan_staging};
+    use crate::drivers::DriverManager;
+    use crate::drivers::inf::Architecture;
+    use tempfile::tempdir;
+
+    #[test]
+    fn indexes_staged_packages_and_matches_present_devices() {
+        let temp = tempdir().unwrap();
+        for (folder, name, driver_ver, models) in [
+            ("Intel_RST", "iaStorVD.inf", "01/01/2024,19.5.0.0", r"[Models.NTamd64]
+VMD=Install,PCI\VEN_8086&DEV_A77F"),
+            ("Intel_RST_Old", "iaStorVD.inf", "01/01/2023,19.0.0.0", r"[Models.NTamd64]
+VMD=Install,PCI\VEN_8086&DEV_A77F"),
+            ("Generic", "storahci.inf", "01/01/2025,1.0.0.0", r"[Models.NTamd64]
+AHCI=Install,PCI\VEN_1234&DEV_0000,PCI\CC_0106"),
+            ("Arm", "arm.inf", "01/01/2025,1.0.0.0", r"[Models.NTarm64]
+NIC=Install,PCI\VEN_10EC&DEV_8168"),
+        ] {
+            let dir = temp.path().join(folder);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join(name),
+                format!(
+                    "[Version]\nClass=SCSIAdapter\nDriverVer={}\n[Manufacturer]\nVendor=Models,NTamd64,NTarm64\n{}\n",
+                    driver_ver, models
+                ),
+            )
+            .unwrap();
+        }
+        let mut manager = DriverManager::new();
+        manager.driver_paths.push(temp.path().to_path_buf());
+        manager.set_target_architecture(Architecture::Amd64);
+        let drivers = manager.detect_drivers().unwrap();
+
+        let staging = plan_staging(&drivers);
+        assert_eq!(staging.len(), drivers.len());
+        assert!(staging.iter().enumerate().all(|(position, staged)| staged.name.starts_with(&format!("{:03}-", position + 1))));
+
+        let index = DriverIndex::build(&drivers, &staging, Architecture::Amd64);
+        assert!(index.packages.iter().all(|package| !package.inf.ends_with("arm.inf")));
+        let path = temp.path().join("index.json");
+        index.write(&path).unwrap();
+        assert_eq!(DriverIndex::load(&path).unwrap(), index);
+
+        let devices = parse_pnputil_devices(
+            r"Microsoft PnP Utility
+
+Instance ID:                PCI\VEN_8086&DEV_A77F&SUBSYS_0B1A1028&REV_01\3&11583659&0&B8
+Device Description:         PCI standard RAID controller
+Class Name:                 System
+Status:                     Problem
+Hardware IDs:               PCI\VEN_8086&DEV_A77F&SUBSYS_0B1A1028&REV_01
+                            PCI\VEN_8086&DEV_A77F&SUBSYS_0B1A1028
+                            PCI\VEN_8086&DEV_A77F
+Compatible IDs:             PCI\VEN_8086&CC_010400
+                            PCI\CC_0104
+
+Instance ID:                PCI\VEN_1B21&DEV_0612\3&0
+Device Description:         SATA controller
+Hardware IDs:               PCI\VEN_1B21&DEV_0612
+Compatible IDs:             PCI\CC_0106
+Instance ID:                ROOT\BASICDISPLAY\0000
+Device Description:         Microsoft Basic Display Driver
+Hardware IDs:               ROOT\BasicDisplay
+",
+        );
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[0].name.as_deref(), Some("PCI standard RAID controller"));
+        assert_eq!(devices[0].hardware_ids.len(), 3);
+        assert_eq!(devices[0].compatible_ids, vec![r"PCI\VEN_8086&CC_010400", r"PCI\CC_0104"]);
+        assert_eq!(devices[2].id, r"ROOT\BASICDISPLAY\0000");
+
+        let matches = index.match_devices(&devices);
+        assert_eq!(matches.len(), 2);
+        assert!(matches[0].inf.ends_with("-Intel_RST/iaStorVD.inf"));
+        assert_eq!(matches[0].matched_id, r"PCI\VEN_8086&DEV_A77F");
+        assert!(matches[1].inf.ends_with("-Generic/storahci.inf"));
+        assert_eq!(matches[1].rank, 0x3000);
+    }
+}
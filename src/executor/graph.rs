@@ -0,0 +1,507 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::task::{self, JoinError, JoinSet};
+use tracing::{debug, error, info, warn};
+use crate::tools::{DetectedTool, FailurePolicy};
+use super::{ExecutionResult, ScriptExecutor};
+use super::process::CancellationToken;
+
+/// Phase scripts with their `after`/`requires` edges resolved to node indexes
+#[derive(Debug, Clone)]
+pub struct ExecutionGraph {
+    nodes: Vec<GraphNode>,
+}
+
+#[derive(Debug, Clone)]
+struct GraphNode {
+    tool: DetectedTool,
+    /// Must finish first, whatever the outcome
+    after: Vec<usize>,
+    /// Must succeed first
+    requires: Vec<usize>,
+    /// `requires` entries that matched no tool in the phase
+    missing: Vec<String>,
+}
+
+/// Final state of one script in a phase run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum NodeStatus {
+    Succeeded,
+    Failed(String),
+    Skipped(String),
+    /// `--dry-run`: would have been executed
+    Planned,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeOutcome {
+    pub name: String,
+    pub path: std::path::PathBuf,
+    /// Display names of the `after` and `requires` dependencies
+    pub depends_on: Vec<String>,
+    pub status: NodeStatus,
+    pub attempts: u32,
+    /// Result of the last attempt
+    pub result: Option<ExecutionResult>,
+}
+
+/// Per-script outcomes of a phase, in completion order
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionReport {
+    pub outcomes: Vec<NodeOutcome>,
+    /// Script whose `abort` policy stopped the run
+    pub aborted_by: Option<String>,
+}
+
+/// Outcome nested under the first dependency it waited for
+#[derive(Debug, Clone, Serialize)]
+pub struct OutcomeTree<'a> {
+    #[serde(flatten)]
+    pub outcome: &'a NodeOutcome,
+    pub dependents: Vec<OutcomeTree<'a>>,
+}
+
+impl ExecutionGraph {
+    /// Resolve dependencies between `tools`, which are tried in `order` and then list order
+    pub fn build(tools: &[DetectedTool]) -> Result<Self> {
+        let mut tools = tools.to_vec();
+        crate::tools::sort_by_order(&mut tools);
+
+        let resolve = |index: usize, reference: &str| -> Vec<usize> {
+            tools
+                .iter()
+                .enumerate()
+                .filter(|(other, tool)| *other != index && tool.is_referenced_by(reference))
+                .map(|(other, _)| other)
+                .collect()
+        };
+
+        let mut nodes = Vec::with_capacity(tools.len());
+        for (index, tool) in tools.iter().enumerate() {
+            let mut after = Vec::new();
+            for reference in &tool.metadata.after {
+                let matched = resolve(index, reference);
+                if matched.is_empty() {
+                    debug!("{}: ignoring `after` on {}, which is not part of this phase", tool.display_name(), reference);
+                }
+                after.extend(matched);
+            }
+            let mut requires = Vec::new();
+            let mut missing = Vec::new();
+            for reference in &tool.metadata.requires {
+                let matched = resolve(index, reference);
+                if matched.is_empty() {
+                    missing.push(reference.clone());
+                }
+                requires.extend(matched);
+            }
+            nodes.push(GraphNode {
+                tool: tool.clone(),
+                after,
+                requires,
+                missing,
+            });
+        }
+
+        let graph = Self { nodes };
+        graph.check_acyclic()?;
+        Ok(graph)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn dependencies(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let node = &self.nodes[index];
+        node.after.iter().chain(&node.requires).copied()
+    }
+
+    fn check_acyclic(&self) -> Result<()> {
+        let mut remaining: Vec<usize> = (0..self.nodes.len()).map(|index| self.dependencies(index).count()).collect();
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for index in 0..self.nodes.len() {
+            for dependency in self.dependencies(index) {
+                dependents[dependency].push(index);
+            }
+        }
+        let mut ready: Vec<usize> = (0..self.nodes.len()).filter(|index| remaining[*index] == 0).collect();
+        let mut visited = 0;
+        while let Some(index) = ready.pop() {
+            visited += 1;
+            for &dependent in &dependents[index] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+        if visited < self.nodes.len() {
+            let cycle: Vec<&str> = (0..self.nodes.len())
+                .filter(|index| remaining[*index] > 0)
+                .map(|index| self.nodes[index].tool.display_name())
+                .collect();
+            bail!("Phase scripts have a dependency cycle between {}", cycle.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Run every script once its dependencies are done, at most `max_parallel` at a time
+    ///
+    /// Scripts whose `requires` failed or were skipped are skipped; an `abort`
+    /// failure lets running scripts finish and skips everything not yet started.
+    /// Cancelling `cancel` stops the running scripts and skips the rest.
+    pub async fn run(
+        &self,
+        executor: Arc<ScriptExecutor>,
+        cancel: CancellationToken,
+        max_parallel: usize,
+        dry_run: bool,
+    ) -> ExecutionReport {
+        let count = self.nodes.len();
+        let mut outcomes: Vec<Option<NodeOutcome>> = vec![None; count];
+        let mut started = vec![false; count];
+        let mut completed = Vec::with_capacity(count);
+        let mut aborted_by: Option<String> = None;
+        let mut running = JoinSet::new();
+        let mut tasks: HashMap<task::Id, usize> = HashMap::new();
+
+        loop {
+            // Skip dependents of failures until nothing changes, since dependencies may come later in the list
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for index in 0..count {
+                    if started[index] {
+                        continue;
+                    }
+                    let node = &self.nodes[index];
+                    let reason = node
+                        .missing
+                        .first()
+                        .map(|missing| format!("required {} is not part of this phase", missing))
+                        .or_else(|| {
+                            node.requires.iter().find_map(|&dependency| {
+                                let name = self.nodes[dependency].tool.display_name();
+                                match outcomes[dependency].as_ref().map(|outcome| &outcome.status) {
+                                    Some(NodeStatus::Failed(_)) => Some(format!("required {} failed", name)),
+                                    Some(NodeStatus::Skipped(_)) => Some(format!("required {} was skipped", name)),
+                                    _ => None,
+                                }
+                            })
+                        })
+                        .or_else(|| aborted_by.as_ref().map(|name| format!("aborted after {} failed", name)))
+                        .or_else(|| cancel.is_cancelled().then(|| "cancelled".to_string()));
+                    if let Some(reason) = reason {
+                        warn!("⏭️ Skipping {}: {}", node.tool.display_name(), reason);
+                        started[index] = true;
+                        completed.push(index);
+                        outcomes[index] = Some(self.outcome(index, NodeStatus::Skipped(reason), 0, None));
+                        changed = true;
+                    }
+                }
+            }
+
+            for index in 0..count {
+                if running.len() >= max_parallel.max(1) {
+                    break;
+                }
+                if started[index] || !self.dependencies(index).all(|dependency| outcomes[dependency].is_some()) {
+                    continue;
+                }
+                started[index] = true;
+                let tool = self.nodes[index].tool.clone();
+                if dry_run {
+                    info!("Dry run: would execute {}", tool.path.display());
+                    completed.push(index);
+                    outcomes[index] = Some(self.outcome(index, NodeStatus::Planned, 0, None));
+                    continue;
+                }
+                info!("▶️ Starting {}", tool.display_name());
+                let executor = executor.clone();
+                let cancel = cancel.clone();
+                let handle = running.spawn(async move {
+                    let (result, attempts) = execute_with_retries(&executor, &tool, &cancel).await;
+                    (index, result, attempts)
+                });
+                tasks.insert(handle.id(), index);
+            }
+
+            if dry_run && completed.len() < count && running.is_empty() {
+                continue;
+            }
+            let Some(joined) = running.join_next_with_id().await else {
+                break;
+            };
+            let Some((index, result, attempts)) = self.joined_result(joined, &mut tasks) else {
+                continue;
+            };
+            let tool = &self.nodes[index].tool;
+            let status = if result.success {
+                info!("{}", result.summary());
+                NodeStatus::Succeeded
+            } else {
+                error!("{}", result.summary());
+                if tool.metadata.on_failure == FailurePolicy::Abort && aborted_by.is_none() {
+                    error!("🛑 {} failed with on_failure = \"abort\"; starting no further scripts", tool.display_name());
+                    aborted_by = Some(tool.display_name().to_string());
+                }
+                NodeStatus::Failed(failure_reason(&result))
+            };
+            completed.push(index);
+            outcomes[index] = Some(self.outcome(index, status, attempts, Some(result)));
+        }
+
+        ExecutionReport {
+            outcomes: completed.into_iter().filter_map(|index| outcomes[index].take()).collect(),
+            aborted_by,
+        }
+    }
+
+    /// Map a finished task back to its script; a panicked or aborted task counts as a failed run
+    fn joined_result(
+        &self,
+        joined: Result<(task::Id, ScriptRun), JoinError>,
+        tasks: &mut HashMap<task::Id, usize>,
+    ) -> Option<ScriptRun> {
+        match joined {
+            Ok((id, finished)) => {
+                tasks.remove(&id);
+                Some(finished)
+            }
+            Err(error) => {
+                error!("Phase script task failed: {}", error);
+                let index = tasks.remove(&error.id())?;
+                let reason = if error.is_panic() { "panicked" } else { "was aborted" };
+                let result = ExecutionResult {
+                    tool_name: self.nodes[index].tool.name.clone(),
+                    success: false,
+                    exit_code: None,
+                    stdout: String::new(),
+                    stderr: format!("Script task {}", reason),
+                    execution_time_ms: 0,
+                };
+                Some((index, result, 1))
+            }
+        }
+    }
+
+    fn outcome(&self, index: usize, status: NodeStatus, attempts: u32, result: Option<ExecutionResult>) -> NodeOutcome {
+        let tool = &self.nodes[index].tool;
+        NodeOutcome {
+            name: tool.display_name().to_string(),
+            path: tool.path.clone(),
+            depends_on: self.dependencies(index).map(|dependency| self.nodes[dependency].tool.display_name().to_string()).collect(),
+            status,
+            attempts,
+            result,
+        }
+    }
+}
+
+/// Node index, final result and attempt count of one script task
+type ScriptRun = (usize, ExecutionResult, u32);
+
+/// Run `tool`, retrying per its `on_failure` policy; errors become failed results
+async fn execute_with_retries(executor: &ScriptExecutor, tool: &DetectedTool, cancel: &CancellationToken) -> (ExecutionResult, u32) {
+    let retries = match tool.metadata.on_failure {
+        FailurePolicy::Retry(retries) => retries,
+        FailurePolicy::Abort | FailurePolicy::Continue => 0,
+    };
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = executor.execute_tool_with(tool, cancel).await.unwrap_or_else(|e| ExecutionResult {
+            tool_name: tool.name.clone(),
+            success: false,
+            exit_code: Some(-1),
+            stdout: String::new(),
+            stderr: format!("Execution failed: {}", e),
+            execution_time_ms: 0,
+        });
+        if result.success || attempts > retries || cancel.is_cancelled() {
+            return (result, attempts);
+        }
+        warn!("🔁 Retrying {} (attempt {} of {})", tool.display_name(), attempts + 1, retries + 1);
+    }
+}
+
+fn failure_reason(result: &ExecutionResult) -> String {
+    // Last line, so a timeout or cancellation appended after the tool's own output wins
+    match result.stderr.lines().map(str::trim).rfind(|line| !line.is_empty()) {
+        Some(line) => line.to_string(),
+        None => match result.exit_code {
+            Some(code) => format!("exit code {}", code),
+            None => "terminated".to_string(),
+        },
+    }
+}
+
+impl ExecutionReport {
+    pub fn failed(&self) -> impl Iterator<Item = &NodeOutcome> {
+        self.outcomes.iter().filter(|outcome| matches!(outcome.status, NodeStatus::Failed(_)))
+    }
+
+    /// Outcomes nested under their first dependency; scripts without one are roots
+    pub fn tree(&self) -> Vec<OutcomeTree<'_>> {
+        self.subtree(None)
+    }
+
+    fn subtree(&self, parent: Option<&str>) -> Vec<OutcomeTree<'_>> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.depends_on.first().map(String::as_str) == parent)
+            .map(|outcome| OutcomeTree {
+                outcome,
+                dependents: self.subtree(Some(&outcome.name)),
+            })
+            .collect()
+    }
+
+    pub fn format_tree(&self) -> String {
+        fn write(output: &mut String, nodes: &[OutcomeTree<'_>], depth: usize) {
+            for node in nodes {
+                let outcome = node.outcome;
+                let (icon, detail) = match &outcome.status {
+                    NodeStatus::Succeeded => ("✅", None),
+                    NodeStatus::Failed(reason) => ("❌", Some(reason.as_str())),
+                    NodeStatus::Skipped(reason) => ("⏭️", Some(reason.as_str())),
+                    NodeStatus::Planned => ("📝", None),
+                };
+                output.push_str(&format!("{}{} {}", "  ".repeat(depth), icon, outcome.name));
+                if outcome.attempts > 1 {
+                    output.push_str(&format!(" ({} attempts)", outcome.attempts));
+                }
+                if let Some(detail) = detail {
+                    output.push_str(&format!(": {}", detail));
+                }
+                output.push('\n');
+                write(output, &node.dependents, depth + 1);
+            }
+        }
+
+        let mut output = String::new();
+        write(&mut output, &self.tree(), 0);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExecutionGraph, NodeStatus};
+    use crate::cli::GhostwinConfig;
+    use crate::executor::ScriptExecutor;
+    use crate::executor::process::CancellationToken;
+    use crate::tools::{DetectedTool, FailurePolicy, ToolCategory, ToolMetadata};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use tokio::task::JoinSet;
+
+    fn tool(path: &str, metadata: ToolMetadata) -> DetectedTool {
+        DetectedTool {
+            name: PathBuf::from(path).file_name().unwrap().to_string_lossy().to_string(),
+            path: PathBuf::from(path),
+            category: ToolCategory::PEAutoRun,
+            executable: true,
+            hidden: false,
+            auto_run: true,
+            metadata,
+        }
+    }
+
+    #[test]
+    fn rejects_dependency_cycles() {
+        let tools = vec![
+            tool("PEAutoRun/a.cmd", ToolMetadata { after: vec!["b.cmd".into()], ..Default::default() }),
+            tool("PEAutoRun/b.cmd", ToolMetadata { requires: vec!["PEAutoRun/a.cmd".into()], ..Default::default() }),
+            tool("PEAutoRun/c.cmd", ToolMetadata::default()),
+        ];
+        let error = ExecutionGraph::build(&tools).unwrap_err();
+        assert_eq!(error.to_string(), "Phase scripts have a dependency cycle between a.cmd, b.cmd");
+    }
+
+    #[tokio::test]
+    async fn runs_dependencies_first_and_skips_dependents_of_failures() {
+        let tools = vec![
+            tool("PEAutoRun/mount-share.cmd", ToolMetadata { requires: vec!["network.cmd".into()], ..Default::default() }),
+            tool("PEAutoRun/network.cmd", ToolMetadata::default()),
+            tool("PEAutoRun/broken.txt", ToolMetadata { on_failure: FailurePolicy::Retry(2), ..Default::default() }),
+            tool("PEAutoRun/after-broken.cmd", ToolMetadata { requires: vec!["broken.txt".into()], ..Default::default() }),
+            tool("PEAutoRun/cleanup.cmd", ToolMetadata {
+                after: vec!["broken.txt".into(), "missing.cmd".into()],
+                order: Some(10),
+                ..Default::default()
+            }),
+            tool("PEAutoRun/needs-missing.cmd", ToolMetadata { requires: vec!["missing.cmd".into()], ..Default::default() }),
+        ];
+        let graph = ExecutionGraph::build(&tools).unwrap();
+        let executor = Arc::new(ScriptExecutor::new(GhostwinConfig::default()));
+
+        let report = graph.run(executor.clone(), CancellationToken::new(), 2, false).await;
+        let status = |name: &str| &report.outcomes.iter().find(|outcome| outcome.name == name).unwrap().status;
+        let position = |name: &str| report.outcomes.iter().position(|outcome| outcome.name == name).unwrap();
+
+        assert_eq!(report.outcomes.len(), 6);
+        assert_eq!(*status("network.cmd"), NodeStatus::Succeeded);
+        assert_eq!(*status("mount-share.cmd"), NodeStatus::Succeeded);
+        assert!(position("network.cmd") < position("mount-share.cmd"));
+        assert_eq!(*status("broken.txt"), NodeStatus::Failed("Execution failed: Unsupported file type: txt".to_string()));
+        assert_eq!(report.outcomes[position("broken.txt")].attempts, 3);
+        assert_eq!(*status("after-broken.cmd"), NodeStatus::Skipped("required broken.txt failed".to_string()));
+        assert_eq!(*status("needs-missing.cmd"), NodeStatus::Skipped("required missing.cmd is not part of this phase".to_string()));
+        assert_eq!(*status("cleanup.cmd"), NodeStatus::Succeeded);
+        assert!(position("broken.txt") < position("cleanup.cmd"));
+        assert_eq!(report.failed().count(), 1);
+        assert!(report.aborted_by.is_none());
+
+        let tree = report.format_tree();
+        assert!(tree.contains("✅ network.cmd\n  ✅ mount-share.cmd\n"));
+        assert!(tree.contains("❌ broken.txt (3 attempts): Execution failed: Unsupported file type: txt\n"));
+        assert!(tree.contains("  ⏭️ after-broken.cmd: required broken.txt failed\n"));
+        assert!(tree.contains("  ✅ cleanup.cmd\n"));
+
+        let planned = graph.run(executor, CancellationToken::new(), 1, true).await;
+        assert!(planned.outcomes.iter().filter(|outcome| outcome.name != "needs-missing.cmd").all(|outcome| outcome.status == NodeStatus::Planned));
+        let planned_position = |name: &str| planned.outcomes.iter().position(|outcome| outcome.name == name).unwrap();
+        assert!(planned_position("network.cmd") < planned_position("mount-share.cmd"));
+    }
+
+    #[tokio::test]
+    async fn abort_policy_skips_scripts_not_yet_started() {
+        let tools = vec![
+            tool("Logon/first.cmd", ToolMetadata::default()),
+            tool("Logon/fatal.txt", ToolMetadata { on_failure: FailurePolicy::Abort, ..Default::default() }),
+            tool("Logon/last.cmd", ToolMetadata::default()),
+        ];
+        let graph = ExecutionGraph::build(&tools).unwrap();
+        let report = graph.run(Arc::new(ScriptExecutor::new(GhostwinConfig::default())), CancellationToken::new(), 1, false).await;
+
+        let statuses: Vec<&NodeStatus> = report.outcomes.iter().map(|outcome| &outcome.status).collect();
+        assert_eq!(statuses[0], &NodeStatus::Succeeded);
+        assert!(matches!(statuses[1], NodeStatus::Failed(_)));
+        assert_eq!(statuses[2], &NodeStatus::Skipped("aborted after fatal.txt failed".to_string()));
+        assert_eq!(report.aborted_by.as_deref(), Some("fatal.txt"));
+    }
+
+    #[tokio::test]
+    async fn panicked_tasks_are_recorded_as_failures() {
+        let tools = vec![tool("PEAutoRun/crash.cmd", ToolMetadata::default())];
+        let graph = ExecutionGraph::build(&tools).unwrap();
+
+        let mut running = JoinSet::new();
+        let mut tasks = HashMap::new();
+        let handle = running.spawn(async { panic!("script task crashed") });
+        tasks.insert(handle.id(), 0);
+        let joined = running.join_next_with_id().await.unwrap();
+
+        let (index, result, attempts) = graph.joined_result(joined, &mut tasks).unwrap();
+        assert_eq!((index, attempts), (0, 1));
+        assert!(!result.success);
+        assert_eq!(result.stderr, "Script task panicked");
+        assert!(tasks.is_empty());
+    }
+}
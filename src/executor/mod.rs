@@ -1,370 +1,181 @@
+#[cfg(target_os = "windows")]
+use anyhow::Context;
 use anyhow::Result;
+use serde::Serialize;
 use std::path::Path;
-use tracing::{info, error, debug};
-use crate::tools::{DetectedTool, ToolCategory};
+use std::sync::{Arc, Mutex};
+use tracing::{info, error, debug, warn};
+use crate::tools::{DetectedTool, ToolMetadata};
+use crate::tools::integrity::ToolVerifier;
+use crate::tools::interpreters::InterpreterRegistry;
 use crate::cli::GhostwinConfig;
 
+pub mod graph;
+// Tools are only spawned on Windows; other hosts simulate them
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub mod process;
+
+use graph::{ExecutionGraph, ExecutionReport};
+use process::{CancellationToken, OutputCallback};
+
 #[cfg(target_os = "windows")]
-use std::process::{Command, Stdio};
+use tokio::process::Command;
 
 pub struct ScriptExecutor {
-    #[allow(dead_code)]
     config: GhostwinConfig,
+    /// Receive every output line of every tool run
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    subscribers: Mutex<Vec<Arc<OutputCallback>>>,
+    /// Shared by the runs started since the last `cancel`
+    cancellation: Mutex<CancellationToken>,
+    interpreters: InterpreterRegistry,
+    /// Signed tool manifest check run before every tool
+    verifier: ToolVerifier,
 }
 
 impl ScriptExecutor {
     pub fn new(config: GhostwinConfig) -> Self {
-        Self { config }
-    }
-    
-    pub fn execute_tool(&self, tool: &DetectedTool) -> Result<ExecutionResult> {
-        info!("Executing tool: {} at {}", tool.name, tool.path.display());
-        
-        let path_str = tool.path.to_string_lossy();
-        let extension = tool.path.extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-        
-        match extension.as_str() {
-            "exe" | "com" => self.execute_executable(&path_str),
-            "bat" | "cmd" => self.execute_batch_script(&path_str),
-            "ps1" => self.execute_powershell_script(&path_str),
-            "au3" => self.execute_autoit_script(&path_str),
-            "reg" => self.execute_registry_file(&path_str),
-            "vbs" => self.execute_vbscript(&path_str),
-            _ => {
-                error!("Unsupported file type: {}", extension);
-                Err(anyhow::anyhow!("Unsupported file type: {}", extension))
+        let mut subscribers = vec![process::tracing_output()];
+        if let Some(log) = &config.phases.output_log {
+            match process::log_file_output(Path::new(log)) {
+                Ok(subscriber) => subscribers.push(subscriber),
+                Err(e) => warn!("⚠️ Tool output will not be logged to a file: {:#}", e),
             }
         }
-    }
-    
-    pub fn execute_pe_autorun_scripts(&self, tools: &[DetectedTool]) -> Result<Vec<ExecutionResult>> {
-        info!("Executing PE autorun scripts");
-        let mut results = Vec::new();
-        
-        for tool in tools {
-            if matches!(tool.category, ToolCategory::PEAutoRun) && tool.auto_run {
-                info!("Auto-running: {}", tool.path.display());
-                match self.execute_tool(tool) {
-                    Ok(result) => results.push(result),
-                    Err(e) => {
-                        error!("Failed to execute autorun tool {}: {}", tool.name, e);
-                        results.push(ExecutionResult {
-                            tool_name: tool.name.clone(),
-                            success: false,
-                            exit_code: Some(-1),
-                            stdout: String::new(),
-                            stderr: format!("Execution failed: {}", e),
-                            execution_time_ms: 0,
-                        });
-                    }
-                }
-            }
+        Self {
+            interpreters: InterpreterRegistry::new(&config.tools.interpreters),
+            verifier: ToolVerifier::from_config(&config.security),
+            config,
+            subscribers: Mutex::new(subscribers),
+            cancellation: Mutex::new(CancellationToken::new()),
         }
-        
-        Ok(results)
     }
-    
-    pub async fn execute_script(&self, script_path: &Path) -> Result<String> {
-        let path_str = script_path.to_string_lossy();
-        let extension = script_path.extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-        
-        let result = match extension.as_str() {
-            "exe" | "com" => self.execute_executable(&path_str)?,
-            "bat" | "cmd" => self.execute_batch_script(&path_str)?,
-            "ps1" => self.execute_powershell_script(&path_str)?,
-            "reg" => self.execute_registry_file(&path_str)?,
-            "vbs" => self.execute_vbscript(&path_str)?,
-            _ => return Err(anyhow::anyhow!("Unsupported file type: {}", extension)),
-        };
-        
-        Ok(result.stdout)
+
+    pub fn subscribe(&self, subscriber: Arc<OutputCallback>) {
+        self.subscribers.lock().expect("subscriber list poisoned").push(subscriber);
     }
-    
-    fn execute_executable(&self, path: &str) -> Result<ExecutionResult> {
-        debug!("Executing executable: {}", path);
-        
-        #[cfg(target_os = "windows")]
-        {
-            let start_time = std::time::Instant::now();
-            let output = Command::new(path)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            
-            let execution_time = start_time.elapsed().as_millis() as u64;
-            
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: output.status.success(),
-                exit_code: output.status.code(),
-                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                execution_time_ms: execution_time,
-            })
-        }
-        
-        #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would execute Windows executable: {}", path);
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: true,
-                exit_code: Some(0),
-                stdout: "Simulated execution (not on Windows)".to_string(),
-                stderr: String::new(),
-                execution_time_ms: 0,
-            })
-        }
+
+    /// Token the next runs will observe
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.lock().expect("cancellation token poisoned").clone()
     }
-    
-    fn execute_batch_script(&self, path: &str) -> Result<ExecutionResult> {
-        debug!("Executing batch script: {}", path);
-        
-        #[cfg(target_os = "windows")]
-        {
-            let start_time = std::time::Instant::now();
-            let output = Command::new("cmd")
-                .args(["/c", path])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            
-            let execution_time = start_time.elapsed().as_millis() as u64;
-            
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: output.status.success(),
-                exit_code: output.status.code(),
-                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                execution_time_ms: execution_time,
-            })
-        }
-        
-        #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would execute batch script: {}", path);
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: true,
-                exit_code: Some(0),
-                stdout: "Simulated batch execution (not on Windows)".to_string(),
-                stderr: String::new(),
-                execution_time_ms: 0,
-            })
-        }
+
+    /// Stop every running tool and phase; later runs get a fresh token
+    pub fn cancel(&self) {
+        let mut token = self.cancellation.lock().expect("cancellation token poisoned");
+        info!("⏹️ Cancelling running tools");
+        token.cancel();
+        *token = CancellationToken::new();
     }
     
-    fn execute_powershell_script(&self, path: &str) -> Result<ExecutionResult> {
-        debug!("Executing PowerShell script: {}", path);
-        
-        #[cfg(target_os = "windows")]
-        {
-            let start_time = std::time::Instant::now();
-            let output = Command::new("powershell")
-                .args(["-ExecutionPolicy", "Bypass", "-File", path])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            
-            let execution_time = start_time.elapsed().as_millis() as u64;
-            
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: output.status.success(),
-                exit_code: output.status.code(),
-                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                execution_time_ms: execution_time,
-            })
-        }
-        
-        #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would execute PowerShell script: {}", path);
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: true,
-                exit_code: Some(0),
-                stdout: "Simulated PowerShell execution (not on Windows)".to_string(),
-                stderr: String::new(),
+    pub async fn execute_tool(&self, tool: &DetectedTool) -> Result<ExecutionResult> {
+        let cancel = self.cancellation_token();
+        self.execute_tool_with(tool, &cancel).await
+    }
+
+    pub async fn execute_tool_with(&self, tool: &DetectedTool, cancel: &CancellationToken) -> Result<ExecutionResult> {
+        info!("Executing tool: {} at {}", tool.display_name(), tool.path.display());
+        if cancel.is_cancelled() {
+            return Ok(ExecutionResult {
+                tool_name: tool.name.clone(),
+                success: false,
+                exit_code: None,
+                stdout: String::new(),
+                stderr: "Cancelled".to_string(),
                 execution_time_ms: 0,
-            })
+            });
         }
+        let command_line = self.interpreters.command_line(&tool.path, &tool.metadata.arguments).inspect_err(|e| error!("{}", e))?;
+        debug!("Command line: {}", command_line.join(" "));
+        self.verifier.check(tool)?;
+        self.prepare_tool(tool)?;
+        self.run(&tool.path, command_line, &tool.metadata, cancel).await
     }
     
-    fn execute_autoit_script(&self, path: &str) -> Result<ExecutionResult> {
-        debug!("Executing AutoIt script: {}", path);
+    /// Run one phase's scripts as a dependency graph and log the outcome tree
+    ///
+    /// Fails only for a dependency cycle or when a script with `on_failure = "abort"` failed.
+    pub async fn execute_phase(self: &Arc<Self>, tools: &[DetectedTool], dry_run: bool, action_label: &str) -> Result<ExecutionReport> {
+        let graph = ExecutionGraph::build(tools)?;
+        let max_parallel = self.config.phases.max_parallel.unwrap_or(1);
+        info!("Executing {} {}(s), up to {} at a time", graph.len(), action_label, max_parallel.max(1));
 
-        // Look for AutoIt executable
-        #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
-        let autoit_paths = [
-            "C:\\Program Files (x86)\\AutoIt3\\AutoIt3.exe",
-            "C:\\Program Files\\AutoIt3\\AutoIt3.exe",
-            "autoit3.exe",
-        ];
-        
-        #[cfg(target_os = "windows")]
-        {
-            for autoit_path in &autoit_paths {
-                if Path::new(autoit_path).exists() {
-                    let start_time = std::time::Instant::now();
-                    let output = Command::new(autoit_path)
-                        .arg(path)
-                        .stdout(Stdio::piped())
-                        .stderr(Stdio::piped())
-                        .output()?;
-                    
-                    let execution_time = start_time.elapsed().as_millis() as u64;
-                    
-                    return Ok(ExecutionResult {
-                        tool_name: Path::new(path).file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string(),
-                        success: output.status.success(),
-                        exit_code: output.status.code(),
-                        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                        execution_time_ms: execution_time,
-                    });
-                }
-            }
-            
-            Err(anyhow::anyhow!("AutoIt3.exe not found in standard locations"))
+        let report = graph.run(self.clone(), self.cancellation_token(), max_parallel, dry_run).await;
+        for line in report.format_tree().lines() {
+            info!("{}", line);
         }
-        
-        #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would execute AutoIt script: {}", path);
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: true,
-                exit_code: Some(0),
-                stdout: "Simulated AutoIt execution (not on Windows)".to_string(),
-                stderr: String::new(),
-                execution_time_ms: 0,
-            })
+        if let Some(name) = &report.aborted_by {
+            anyhow::bail!("{} execution aborted after {} failed", action_label, name);
         }
+        let failed = report.failed().count();
+        if failed > 0 {
+            warn!("{} {}(s) failed; their dependents were skipped", failed, action_label);
+        }
+        Ok(report)
     }
     
-    fn execute_registry_file(&self, path: &str) -> Result<ExecutionResult> {
-        debug!("Importing registry file: {}", path);
-        
-        #[cfg(target_os = "windows")]
-        {
-            let start_time = std::time::Instant::now();
-            let output = Command::new("reg")
-                .args(["import", path])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            
-            let execution_time = start_time.elapsed().as_millis() as u64;
-            
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: output.status.success(),
-                exit_code: output.status.code(),
-                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                execution_time_ms: execution_time,
-            })
+    /// Enforce the sidecar's `elevated` and `network` requirements before running
+    fn prepare_tool(&self, tool: &DetectedTool) -> Result<()> {
+        if tool.metadata.elevated {
+            crate::utils::ensure_admin_privileges()
+                .map_err(|e| anyhow::anyhow!("{} requires elevation: {}", tool.display_name(), e))?;
         }
-        
-        #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would import registry file: {}", path);
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: true,
-                exit_code: Some(0),
-                stdout: "Simulated registry import (not on Windows)".to_string(),
-                stderr: String::new(),
-                execution_time_ms: 0,
-            })
+        if tool.metadata.network {
+            initialize_network();
         }
+        Ok(())
     }
-    
-    fn execute_vbscript(&self, path: &str) -> Result<ExecutionResult> {
-        debug!("Executing VBScript: {}", path);
-        
+
+    #[cfg(target_os = "windows")]
+    async fn run(&self, path: &Path, command_line: Vec<String>, metadata: &ToolMetadata, cancel: &CancellationToken) -> Result<ExecutionResult> {
+        let (program, arguments) = command_line.split_first().context("Interpreter command is empty")?;
+        let mut command = Command::new(program);
+        command.args(arguments);
+        let subscribers = self.subscribers.lock().expect("subscriber list poisoned").clone();
+        process::run_command(command, &path.to_string_lossy(), metadata, cancel, &subscribers).await
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    async fn run(&self, path: &Path, command_line: Vec<String>, _metadata: &ToolMetadata, _cancel: &CancellationToken) -> Result<ExecutionResult> {
+        info!("Would execute: {}", command_line.join(" "));
+        Ok(ExecutionResult {
+            tool_name: path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            success: true,
+            exit_code: Some(0),
+            stdout: "Simulated execution (not on Windows)".to_string(),
+            stderr: String::new(),
+            execution_time_ms: 0,
+        })
+    }
+}
+
+/// Start WinPE networking once for tools that declare `network = true`
+fn initialize_network() {
+    static NETWORK: std::sync::Once = std::sync::Once::new();
+    NETWORK.call_once(|| {
         #[cfg(target_os = "windows")]
         {
-            let start_time = std::time::Instant::now();
-            let output = Command::new("cscript")
-                .args(["/nologo", path])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            
-            let execution_time = start_time.elapsed().as_millis() as u64;
-            
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: output.status.success(),
-                exit_code: output.status.code(),
-                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                execution_time_ms: execution_time,
-            })
+            let in_winpe = std::env::var("SystemDrive").is_ok_and(|drive| drive.eq_ignore_ascii_case("X:"));
+            if !in_winpe {
+                return;
+            }
+            info!("🌐 Initializing WinPE network");
+            match std::process::Command::new("wpeutil").arg("InitializeNetwork").status() {
+                Ok(status) if status.success() => {}
+                Ok(status) => warn!("wpeutil InitializeNetwork failed ({})", status),
+                Err(e) => warn!("Failed to run wpeutil: {}", e),
+            }
         }
-        
+
         #[cfg(not(target_os = "windows"))]
-        {
-            info!("Would execute VBScript: {}", path);
-            Ok(ExecutionResult {
-                tool_name: Path::new(path).file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                success: true,
-                exit_code: Some(0),
-                stdout: "Simulated VBScript execution (not on Windows)".to_string(),
-                stderr: String::new(),
-                execution_time_ms: 0,
-            })
-        }
-    }
+        info!("Would initialize WinPE network");
+    });
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ExecutionResult {
     pub tool_name: String,
     pub success: bool,
@@ -0,0 +1,363 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write as _;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use crate::tools::ToolMetadata;
+use super::ExecutionResult;
+
+/// How long to keep reading a tool's pipes after it exits or is stopped, in case a detached grandchild still holds them
+const PIPE_DRAIN_GRACE: Duration = Duration::from_secs(5);
+
+/// Cloneable flag that stops every tool run holding it
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancelState>,
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` has been called
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // Register before checking so a cancel between the check and the await is not missed
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line a running tool wrote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutputLine {
+    pub tool: String,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+pub type OutputCallback = dyn Fn(&OutputLine) + Send + Sync;
+
+/// Log tool output through tracing, stderr as warnings
+pub fn tracing_output() -> Arc<OutputCallback> {
+    Arc::new(|output: &OutputLine| match output.stream {
+        OutputStream::Stdout => info!("[{}] {}", output.tool, output.line),
+        OutputStream::Stderr => warn!("[{}] {}", output.tool, output.line),
+    })
+}
+
+/// Append tool output to `path`, one `[tool] [stream] line` per line
+pub fn log_file_output(path: &Path) -> Result<Arc<OutputCallback>> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open tool output log {}", path.display()))?;
+    let file = Mutex::new(file);
+    Ok(Arc::new(move |output: &OutputLine| {
+        let stream = match output.stream {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        };
+        if let Ok(mut file) = file.lock() {
+            let _ = writeln!(file, "[{}] [{}] {}", output.tool, stream, output.line);
+        }
+    }))
+}
+
+enum Stopped {
+    TimedOut(u64),
+    Cancelled,
+}
+
+/// Run `command` with the sidecar's working directory and timeout, streaming each output line to `subscribers`
+///
+/// A timeout or cancellation kills the whole process tree and reports a failed result,
+/// with the reason appended to whatever the tool had written to stderr.
+pub async fn run_command(
+    mut command: Command,
+    path: &str,
+    metadata: &ToolMetadata,
+    cancel: &CancellationToken,
+    subscribers: &[Arc<OutputCallback>],
+) -> Result<ExecutionResult> {
+    if let Some(working_dir) = &metadata.working_dir {
+        command.current_dir(working_dir);
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Own process group so the whole tree can be killed
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let tool_name = Path::new(path).file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let start_time = Instant::now();
+    let mut child = command.spawn().with_context(|| format!("Failed to start {}", path))?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .map(|pipe| stream_lines(pipe, tool_name.clone(), OutputStream::Stdout, subscribers.to_vec()));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| stream_lines(pipe, tool_name.clone(), OutputStream::Stderr, subscribers.to_vec()));
+
+    let timeout = async {
+        match metadata.timeout_secs {
+            Some(seconds) => tokio::time::sleep(Duration::from_secs(seconds)).await,
+            None => std::future::pending().await,
+        }
+    };
+    let outcome = tokio::select! {
+        status = child.wait() => Ok(status?),
+        _ = timeout => Err(Stopped::TimedOut(metadata.timeout_secs.unwrap_or_default())),
+        _ = cancel.cancelled() => Err(Stopped::Cancelled),
+    };
+
+    let status = match outcome {
+        Ok(status) => status,
+        Err(stopped) => {
+            match stopped {
+                Stopped::TimedOut(seconds) => warn!("⏱️ {} exceeded its {}s timeout, stopping it", tool_name, seconds),
+                Stopped::Cancelled => warn!("⏹️ Cancelling {}", tool_name),
+            }
+            kill_process_tree(&mut child).await;
+            let (stdout, mut stderr) = tokio::join!(collect(stdout), collect(stderr));
+            if !stderr.is_empty() && !stderr.ends_with('\n') {
+                stderr.push('\n');
+            }
+            stderr.push_str(&match stopped {
+                Stopped::TimedOut(seconds) => format!("Timed out after {}s", seconds),
+                Stopped::Cancelled => "Cancelled".to_string(),
+            });
+            return Ok(ExecutionResult {
+                stdout,
+                stderr,
+                tool_name,
+                success: false,
+                exit_code: None,
+                execution_time_ms: start_time.elapsed().as_millis() as u64,
+            });
+        }
+    };
+
+    let (stdout, stderr) = tokio::join!(collect(stdout), collect(stderr));
+    Ok(ExecutionResult {
+        tool_name,
+        success: status.success(),
+        exit_code: status.code(),
+        stdout,
+        stderr,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+/// Output read from one pipe so far, kept by `collect` even if the reader never finishes
+struct PipeReader {
+    collected: Arc<Mutex<String>>,
+    task: JoinHandle<()>,
+}
+
+/// Forward each line of `pipe` to the subscribers and accumulate everything read
+///
+/// Tools often write in the OEM code page, so invalid UTF-8 is replaced rather than ending the stream.
+fn stream_lines<R>(pipe: R, tool: String, stream: OutputStream, subscribers: Vec<Arc<OutputCallback>>) -> PipeReader
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let collected: Arc<Mutex<String>> = Arc::default();
+    let output_buffer = collected.clone();
+    let task = tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let text = String::from_utf8_lossy(&buffer);
+            if let Ok(mut collected) = output_buffer.lock() {
+                collected.push_str(&text);
+            }
+            let output = OutputLine {
+                tool: tool.clone(),
+                stream,
+                line: text.trim_end_matches(['\r', '\n']).to_string(),
+            };
+            for subscriber in &subscribers {
+                subscriber(&output);
+            }
+        }
+    });
+    PipeReader { collected, task }
+}
+
+/// Everything `reader` read, waiting up to `PIPE_DRAIN_GRACE` for the pipe to close
+async fn collect(reader: Option<PipeReader>) -> String {
+    let Some(mut reader) = reader else {
+        return String::new();
+    };
+    if tokio::time::timeout(PIPE_DRAIN_GRACE, &mut reader.task).await.is_err() {
+        // A grandchild still holds the pipe; keep what was read and stop waiting for it
+        reader.task.abort();
+    }
+    reader.collected.lock().map(|collected| collected.clone()).unwrap_or_default()
+}
+
+/// Kill the tool and everything it started; `Child::kill` alone leaves grandchildren running
+async fn kill_process_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        #[cfg(target_os = "windows")]
+        let killed = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .output()
+            .await;
+        #[cfg(not(target_os = "windows"))]
+        let killed = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .output()
+            .await;
+        if let Err(e) = killed {
+            warn!("Failed to kill the process tree of {}: {}", pid, e);
+        }
+    }
+    let _ = child.kill().await;
+}
+
+// The tests drive unix shell commands
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::{CancellationToken, OutputLine, OutputStream, log_file_output, run_command};
+    use crate::tools::ToolMetadata;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tempfile::tempdir;
+    use tokio::process::Command;
+
+    #[tokio::test]
+    async fn streams_lines_in_the_manifest_working_dir_and_enforces_timeouts() {
+        let temp = tempdir().unwrap();
+        let mut metadata = ToolMetadata {
+            working_dir: Some(temp.path().to_path_buf()),
+            timeout_secs: Some(5),
+            ..Default::default()
+        };
+        let lines: Arc<Mutex<Vec<OutputLine>>> = Arc::default();
+        let recorded = lines.clone();
+        let log = temp.path().join("logs/tools.log");
+        let subscribers = vec![
+            Arc::new(move |line: &OutputLine| recorded.lock().unwrap().push(line.clone())) as Arc<_>,
+            log_file_output(&log).unwrap(),
+        ];
+        let cancel = CancellationToken::new();
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "pwd; printf 'bad \\377\\r\\n' >&2; exit 3"]);
+        let result = run_command(command, "tool.sh", &metadata, &cancel, &subscribers).await.unwrap();
+        assert_eq!(result.tool_name, "tool.sh");
+        assert!(!result.success);
+        assert_eq!(result.exit_code, Some(3));
+        assert_eq!(
+            std::path::Path::new(result.stdout.trim()).canonicalize().unwrap(),
+            temp.path().canonicalize().unwrap()
+        );
+        assert_eq!(result.stderr, "bad \u{FFFD}\r\n");
+
+        let lines = lines.lock().unwrap().clone();
+        assert_eq!(lines.len(), 2);
+        let error_line = lines.iter().find(|line| line.stream == OutputStream::Stderr).unwrap();
+        assert_eq!(error_line.tool, "tool.sh");
+        assert_eq!(error_line.line, "bad \u{FFFD}");
+        assert!(std::fs::read_to_string(&log).unwrap().contains("[tool.sh] [stderr] bad \u{FFFD}\n"));
+
+        metadata.timeout_secs = Some(1);
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo 'waiting for network' >&2; sleep 30 & sleep 30"]);
+        let result = run_command(command, "hang.sh", &metadata, &cancel, &subscribers).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.exit_code, None);
+        assert_eq!(result.stderr, "waiting for network\nTimed out after 1s");
+        assert!(result.execution_time_ms < 5_000);
+    }
+
+    #[tokio::test]
+    async fn keeps_output_when_a_background_child_holds_the_pipe() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo started; echo warming up >&2; sleep 8 &"]);
+        let cancel = CancellationToken::new();
+        let started = std::time::Instant::now();
+        let result = run_command(command, "start.sh", &ToolMetadata::default(), &cancel, &[]).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.stdout, "started\n");
+        assert_eq!(result.stderr, "warming up\n");
+        // Both pipes share one grace period
+        assert!(started.elapsed() < Duration::from_secs(8));
+    }
+
+    #[tokio::test]
+    async fn cancellation_stops_running_commands() {
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            trigger.cancel();
+        });
+
+        let mut command = Command::new("sleep");
+        command.arg("30");
+        let result = run_command(command, "sleep", &ToolMetadata::default(), &cancel, &[]).await.unwrap();
+        assert!(cancel.is_cancelled());
+        assert!(!result.success);
+        assert_eq!(result.stderr, "Cancelled");
+        assert!(result.execution_time_ms < 5_000);
+
+        // Already cancelled tokens resolve immediately
+        tokio::time::timeout(Duration::from_secs(1), cancel.cancelled()).await.unwrap();
+    }
+}
@@ -0,0 +1,465 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+use crate::cli::CacheConfig;
+use crate::iso::IsoReader;
+use crate::iso::reader::IsoEntry;
+use crate::utils;
+
+const ENTRY_FILE: &str = "entry.json";
+const TREE_DIR: &str = "tree";
+const STAGING_PREFIX: &str = ".staging-";
+/// Abandoned staging trees older than this are removed by `prune`
+const STAGING_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Metadata stored alongside each cached extraction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub sha256: String,
+    /// Source ISO the entry was first extracted from; with size and mtime this
+    /// lets a later build reuse the hash instead of re-reading the whole ISO
+    pub source_path: PathBuf,
+    pub source_size: u64,
+    pub source_modified: u64,
+    pub files: usize,
+    pub size_bytes: u64,
+    /// Unix seconds
+    pub created_at: u64,
+    pub last_used: u64,
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl CacheEntry {
+    pub fn tree(&self) -> PathBuf {
+        self.dir.join(TREE_DIR)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PopulateSummary {
+    pub linked: usize,
+    pub copied: usize,
+}
+
+/// Extracted source ISO trees keyed by the ISO's SHA-256
+pub struct IsoCache {
+    root: PathBuf,
+    max_bytes: u64,
+}
+
+impl IsoCache {
+    pub fn new(root: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            root: root.into(),
+            max_bytes,
+        }
+    }
+
+    pub fn from_config(config: &CacheConfig) -> Self {
+        let root = config
+            .path
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(default_cache_root);
+        Self::new(root, config.max_size_gb.saturating_mul(1024 * 1024 * 1024))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Every readable entry, most recently used first
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        if !self.root.exists() {
+            return Ok(entries);
+        }
+
+        for dir_entry in std::fs::read_dir(&self.root)
+            .with_context(|| format!("Failed to read cache directory {}", self.root.display()))?
+        {
+            let dir = dir_entry?.path();
+            let metadata_path = dir.join(ENTRY_FILE);
+            if !metadata_path.is_file() {
+                continue;
+            }
+
+            match read_entry(&dir) {
+                Ok(entry) => entries.push(entry),
+                Err(error) => warn!("Ignoring unreadable cache entry {}: {:#}", dir.display(), error),
+            }
+        }
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_used));
+        Ok(entries)
+    }
+
+    /// SHA-256 of `source_iso`, reused from a cache entry with the same path, size and mtime
+    pub fn source_sha256(&self, source_iso: &Path) -> Result<String> {
+        let (size, modified) = source_identity(source_iso)?;
+        let known = self.entries()?.into_iter().find(|entry| {
+            entry.source_path == source_iso && entry.source_size == size && entry.source_modified == modified
+        });
+        match known {
+            Some(entry) => Ok(entry.sha256),
+            None => {
+                info!("Hashing source ISO {}", source_iso.display());
+                utils::sha256_file(source_iso)
+            }
+        }
+    }
+
+    /// Return the cached tree for `source_iso` (hashed as `sha256`), extracting it on a miss
+    pub fn get_or_extract(
+        &self,
+        source_iso: &Path,
+        sha256: &str,
+        on_file: impl FnMut(&IsoEntry, usize, usize),
+    ) -> Result<CacheEntry> {
+        let (size, modified) = source_identity(source_iso)?;
+        let entries = self.entries()?;
+
+        let mut entry = match entries.into_iter().find(|entry| entry.sha256 == sha256) {
+            Some(entry) => {
+                info!("♻️ Using cached extraction {} ({} files)", short_hash(&entry.sha256), entry.files);
+                entry
+            }
+            None => self.insert(source_iso, sha256, size, modified, on_file)?,
+        };
+
+        entry.last_used = unix_now();
+        write_entry(&entry)?;
+        Ok(entry)
+    }
+
+    fn insert(
+        &self,
+        source_iso: &Path,
+        sha256: &str,
+        source_size: u64,
+        source_modified: u64,
+        on_file: impl FnMut(&IsoEntry, usize, usize),
+    ) -> Result<CacheEntry> {
+        std::fs::create_dir_all(&self.root)
+            .with_context(|| format!("Failed to create cache directory {}", self.root.display()))?;
+
+        // Extract into a private staging directory so concurrent builds never see half a tree
+        let staging = self
+            .root
+            .join(format!("{}{}-{}", STAGING_PREFIX, short_hash(sha256), std::process::id()));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+
+        let reader = IsoReader::open(source_iso)?;
+        info!("Caching source ISO through its {:?} file system", reader.file_system());
+        let summary = reader.extract_to(&staging.join(TREE_DIR), on_file)?;
+
+        let now = unix_now();
+        let mut entry = CacheEntry {
+            sha256: sha256.to_string(),
+            source_path: source_iso.to_path_buf(),
+            source_size,
+            source_modified,
+            files: summary.files,
+            size_bytes: summary.bytes,
+            created_at: now,
+            last_used: now,
+            dir: staging.clone(),
+        };
+        write_entry(&entry)?;
+
+        let final_dir = self.root.join(sha256);
+        if let Err(error) = std::fs::rename(&staging, &final_dir) {
+            // Another build finished the same ISO first; keep theirs
+            let _ = std::fs::remove_dir_all(&staging);
+            if !final_dir.join(ENTRY_FILE).is_file() {
+                return Err(error)
+                    .with_context(|| format!("Failed to move extraction into {}", final_dir.display()));
+            }
+            return read_entry(&final_dir);
+        }
+        entry.dir = final_dir;
+
+        info!("✅ Cached {} files from {} as {}", entry.files, source_iso.display(), short_hash(sha256));
+        if let Err(error) = self.prune(self.max_bytes, Some(sha256)) {
+            warn!("Failed to enforce cache size cap: {:#}", error);
+        }
+        Ok(entry)
+    }
+
+    /// Recreate the cached tree under `destination` with hardlinks
+    ///
+    /// Files listed in `copy_paths` (relative, `/`-separated) are rewritten in place
+    /// by the build, so they are copied instead; on copy-on-write file systems the
+    /// OS copy routine turns that into a reflink. Links across volumes fall back to copies.
+    pub fn populate(&self, entry: &CacheEntry, destination: &Path, copy_paths: &[&str]) -> Result<PopulateSummary> {
+        let tree = entry.tree();
+        let mut summary = PopulateSummary::default();
+        std::fs::create_dir_all(destination)
+            .with_context(|| format!("Failed to create {}", destination.display()))?;
+
+        for dir_entry in WalkDir::new(&tree).min_depth(1) {
+            let dir_entry = dir_entry.with_context(|| format!("Failed to walk {}", tree.display()))?;
+            let relative = dir_entry.path().strip_prefix(&tree).unwrap_or(dir_entry.path());
+            let target = destination.join(relative);
+
+            if dir_entry.file_type().is_dir() {
+                std::fs::create_dir_all(&target)
+                    .with_context(|| format!("Failed to create directory {}", target.display()))?;
+                continue;
+            }
+
+            // Never write through an existing file: it may itself be a link into the cache
+            if target.exists() {
+                std::fs::remove_file(&target)
+                    .with_context(|| format!("Failed to replace {}", target.display()))?;
+            }
+
+            let relative_name = relative.to_string_lossy().replace('\\', "/");
+            let must_copy = copy_paths
+                .iter()
+                .any(|path| path.eq_ignore_ascii_case(&relative_name));
+            if !must_copy && std::fs::hard_link(dir_entry.path(), &target).is_ok() {
+                summary.linked += 1;
+                continue;
+            }
+
+            copy_with_mtime(dir_entry.path(), &target)?;
+            summary.copied += 1;
+        }
+
+        debug!(
+            "Populated {} from cache ({} linked, {} copied)",
+            destination.display(),
+            summary.linked,
+            summary.copied
+        );
+        Ok(summary)
+    }
+
+    /// Evict least-recently-used entries until the cache fits in `max_bytes`
+    pub fn prune(&self, max_bytes: u64, keep: Option<&str>) -> Result<Vec<CacheEntry>> {
+        self.remove_stale_staging()?;
+
+        let entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|entry| entry.size_bytes).sum();
+        let mut removed = Vec::new();
+
+        // Oldest first
+        for entry in entries.into_iter().rev() {
+            if total <= max_bytes {
+                break;
+            }
+            if keep == Some(entry.sha256.as_str()) {
+                continue;
+            }
+
+            info!("🧹 Evicting cached extraction {} ({})", short_hash(&entry.sha256), entry.source_path.display());
+            std::fs::remove_dir_all(&entry.dir)
+                .with_context(|| format!("Failed to remove {}", entry.dir.display()))?;
+            total = total.saturating_sub(entry.size_bytes);
+            removed.push(entry);
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove every entry; returns how many were removed
+    pub fn clear(&self) -> Result<usize> {
+        let entries = self.entries()?;
+        for entry in &entries {
+            std::fs::remove_dir_all(&entry.dir)
+                .with_context(|| format!("Failed to remove {}", entry.dir.display()))?;
+        }
+        self.remove_stale_staging()?;
+        Ok(entries.len())
+    }
+
+    fn remove_stale_staging(&self) -> Result<()> {
+        if !self.root.exists() {
+            return Ok(());
+        }
+
+        for dir_entry in std::fs::read_dir(&self.root)? {
+            let dir_entry = dir_entry?;
+            if !dir_entry.file_name().to_string_lossy().starts_with(STAGING_PREFIX) {
+                continue;
+            }
+            let age = dir_entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .unwrap_or_default();
+            if age > STAGING_MAX_AGE {
+                debug!("Removing abandoned cache staging {}", dir_entry.path().display());
+                std::fs::remove_dir_all(dir_entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per-user cache location: `%LOCALAPPDATA%\GhostWin\cache` or `$XDG_CACHE_HOME/ghostwin`
+pub fn default_cache_root() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("LOCALAPPDATA")
+            .map(|base| PathBuf::from(base).join("GhostWin").join("cache"))
+            .unwrap_or_else(|| std::env::temp_dir().join("ghostwin-cache"))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .map(|base| base.join("ghostwin"))
+            .unwrap_or_else(|| std::env::temp_dir().join("ghostwin-cache"))
+    }
+}
+
+pub fn short_hash(sha256: &str) -> &str {
+    &sha256[..sha256.len().min(12)]
+}
+
+fn source_identity(source_iso: &Path) -> Result<(u64, u64)> {
+    let metadata = std::fs::metadata(source_iso)
+        .with_context(|| format!("Failed to read {}", source_iso.display()))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+    Ok((metadata.len(), modified))
+}
+
+fn read_entry(dir: &Path) -> Result<CacheEntry> {
+    let path = dir.join(ENTRY_FILE);
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut entry: CacheEntry =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    entry.dir = dir.to_path_buf();
+    Ok(entry)
+}
+
+fn write_entry(entry: &CacheEntry) -> Result<()> {
+    let path = entry.dir.join(ENTRY_FILE);
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, serde_json::to_string_pretty(entry)?)
+        .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+    std::fs::rename(&temp_path, &path).with_context(|| format!("Failed to update {}", path.display()))?;
+    Ok(())
+}
+
+fn copy_with_mtime(source: &Path, target: &Path) -> Result<()> {
+    std::fs::copy(source, target)
+        .with_context(|| format!("Failed to copy {} to {}", source.display(), target.display()))?;
+    if let Ok(modified) = std::fs::metadata(source).and_then(|metadata| metadata.modified())
+        && let Err(error) = File::options()
+            .write(true)
+            .open(target)
+            .and_then(|file| file.set_modified(modified))
+    {
+        debug!("Could not set timestamp on {}: {}", target.display(), error);
+    }
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsoCache;
+    use crate::iso::{IsoOptions, IsoWriter};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn write_iso(root: &Path, name: &str, payload: &str) -> std::path::PathBuf {
+        let media = root.join(format!("{}-media", name));
+        crate::iso::test_support::write_test_media(&media, &[]);
+        std::fs::write(media.join("sources/boot.wim"), payload).unwrap();
+        let iso = root.join(format!("{}.iso", name));
+        IsoWriter::new(IsoOptions::windows_media("CACHE")).write(&media, &iso).unwrap();
+        iso
+    }
+
+    #[test]
+    fn reuses_extraction_and_copies_mutable_files() {
+        let temp = tempdir().unwrap();
+        let iso = write_iso(temp.path(), "source", "pristine");
+        let cache = IsoCache::new(temp.path().join("cache"), u64::MAX);
+
+        let mut extracted = 0;
+        let sha256 = cache.source_sha256(&iso).unwrap();
+        assert_eq!(sha256, crate::utils::sha256_file(&iso).unwrap());
+        let entry = cache.get_or_extract(&iso, &sha256, |_, _, _| extracted += 1).unwrap();
+        assert_eq!(extracted, 5);
+        assert_eq!(entry.sha256, sha256);
+
+        // An unchanged path, size and mtime reuses the recorded hash without reading the ISO
+        let mut recorded = entry.clone();
+        recorded.sha256 = "0".repeat(64);
+        super::write_entry(&recorded).unwrap();
+        assert_eq!(cache.source_sha256(&iso).unwrap(), recorded.sha256);
+        super::write_entry(&entry).unwrap();
+
+        let again = cache.get_or_extract(&iso, &sha256, |_, _, _| panic!("should be a cache hit")).unwrap();
+        assert_eq!(again.sha256, entry.sha256);
+
+        let output = temp.path().join("build");
+        let summary = cache.populate(&again, &output, &["sources/boot.wim"]).unwrap();
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.linked, 4);
+        assert_eq!(std::fs::read_to_string(output.join("bootmgr")).unwrap(), "bootmgr");
+
+        // Servicing rewrites boot.wim in the output tree; the cache must stay pristine
+        std::fs::write(output.join("sources/boot.wim"), "serviced").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(again.tree().join("sources/boot.wim")).unwrap(),
+            "pristine"
+        );
+
+        // Repopulating replaces the stale tree instead of writing through it
+        cache.populate(&again, &output, &["sources/boot.wim"]).unwrap();
+        assert_eq!(std::fs::read_to_string(output.join("sources/boot.wim")).unwrap(), "pristine");
+    }
+
+    #[test]
+    fn prunes_least_recently_used_entries_over_the_cap() {
+        let temp = tempdir().unwrap();
+        let first = write_iso(temp.path(), "first", "one");
+        let second = write_iso(temp.path(), "second", "two");
+        let cache = IsoCache::new(temp.path().join("cache"), u64::MAX);
+
+        let old = cache.get_or_extract(&first, &cache.source_sha256(&first).unwrap(), |_, _, _| {}).unwrap();
+        let mut newer = cache.get_or_extract(&second, &cache.source_sha256(&second).unwrap(), |_, _, _| {}).unwrap();
+        newer.last_used = old.last_used + 10;
+        super::write_entry(&newer).unwrap();
+        assert_eq!(cache.entries().unwrap().len(), 2);
+
+        let removed = cache.prune(newer.size_bytes, None).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].sha256, old.sha256);
+        assert_eq!(cache.entries().unwrap()[0].sha256, newer.sha256);
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.entries().unwrap().is_empty());
+    }
+}
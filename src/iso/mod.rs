@@ -0,0 +1,155 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub mod cache;
+pub mod reader;
+pub mod writer;
+#[cfg(test)]
+pub(crate) mod test_support;
+
+pub use reader::IsoReader;
+pub use writer::{IsoOptions, IsoWriter};
+
+pub const SECTOR_SIZE: usize = 2048;
+pub(crate) const SECTOR_SIZE_U64: u64 = SECTOR_SIZE as u64;
+
+/// Boot images referenced by the El Torito catalog on Windows installation media
+pub const BIOS_BOOT_IMAGE: &str = "boot/etfsboot.com";
+pub const UEFI_BOOT_IMAGE: &str = "efi/microsoft/boot/efisys.bin";
+
+/// Fixed sector positions shared by the writer and reader
+pub(crate) const PRIMARY_DESCRIPTOR_SECTOR: u64 = 16;
+pub(crate) const ANCHOR_SECTOR: u64 = 256;
+
+pub(crate) const EL_TORITO_ID: &[u8] = b"EL TORITO SPECIFICATION";
+
+/// UDF (ECMA-167) descriptor tag identifiers
+pub(crate) const TAG_PRIMARY_VOLUME: u16 = 1;
+pub(crate) const TAG_ANCHOR: u16 = 2;
+pub(crate) const TAG_IMPLEMENTATION_USE: u16 = 4;
+pub(crate) const TAG_PARTITION: u16 = 5;
+pub(crate) const TAG_LOGICAL_VOLUME: u16 = 6;
+pub(crate) const TAG_UNALLOCATED_SPACE: u16 = 7;
+pub(crate) const TAG_TERMINATING: u16 = 8;
+pub(crate) const TAG_INTEGRITY: u16 = 9;
+pub(crate) const TAG_FILE_SET: u16 = 256;
+pub(crate) const TAG_FILE_IDENTIFIER: u16 = 257;
+pub(crate) const TAG_FILE_ENTRY: u16 = 261;
+
+pub(crate) fn sectors_for(bytes: u64) -> u64 {
+    bytes.div_ceil(SECTOR_SIZE_U64)
+}
+
+/// Broken-down UTC timestamp as stored in ISO 9660 and UDF records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+        let days = seconds.div_euclid(86_400);
+        let remainder = seconds.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: (remainder / 3600) as u8,
+            minute: (remainder % 3600 / 60) as u8,
+            second: (remainder % 60) as u8,
+        }
+    }
+
+    pub fn to_system_time(self) -> SystemTime {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let seconds = days * 86_400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second);
+
+        if seconds <= 0 {
+            UNIX_EPOCH
+        } else {
+            UNIX_EPOCH + Duration::from_secs(seconds as u64)
+        }
+    }
+}
+
+// Howard Hinnant's proleptic Gregorian conversions.
+fn civil_from_days(days: i64) -> (i32, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+    (year, month, day)
+}
+
+fn days_from_civil(year: i32, month: u8, day: u8) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// CRC-ITU-T (polynomial 0x1021, zero seed) used by UDF descriptor tags
+pub(crate) fn udf_crc(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+pub(crate) fn udf_tag_checksum(tag: &[u8]) -> u8 {
+    tag[..16]
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != 4)
+        .fold(0u8, |sum, (_, byte)| sum.wrapping_add(*byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DateTime, udf_crc};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn converts_system_time_to_calendar_fields_and_back() {
+        // 2024-02-29 13:45:30 UTC
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_214_330);
+        let date = DateTime::from_system_time(time);
+
+        assert_eq!(
+            date,
+            DateTime { year: 2024, month: 2, day: 29, hour: 13, minute: 45, second: 30 }
+        );
+        assert_eq!(date.to_system_time(), time);
+    }
+
+    #[test]
+    fn computes_udf_crc_itu() {
+        // ECMA-167 7.2.6 reference value
+        assert_eq!(udf_crc(&[0x70, 0x6A, 0x77]), 0x3299);
+    }
+}
@@ -0,0 +1,814 @@
+use anyhow::{Context, Result, bail};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+use super::{
+    ANCHOR_SECTOR, DateTime, PRIMARY_DESCRIPTOR_SECTOR, SECTOR_SIZE, SECTOR_SIZE_U64, TAG_ANCHOR,
+    TAG_FILE_ENTRY, TAG_FILE_IDENTIFIER, TAG_FILE_SET, TAG_LOGICAL_VOLUME, TAG_PARTITION,
+    TAG_TERMINATING, udf_tag_checksum,
+};
+
+const TAG_ALLOCATION_EXTENT: u16 = 258;
+const TAG_EXTENDED_FILE_ENTRY: u16 = 266;
+
+/// Upper bound on the descriptor area scanned for volume/recognition descriptors
+const DESCRIPTOR_SCAN_END: u64 = 64;
+
+/// File system used to enumerate an ISO image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsoFileSystem {
+    Udf,
+    Joliet,
+    Iso9660,
+}
+
+/// File or directory recorded in an ISO image
+#[derive(Debug, Clone)]
+pub struct IsoEntry {
+    /// Media-relative path using `/` separators
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: SystemTime,
+    extents: Vec<Extent>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Extent {
+    /// Absolute byte offset in the image
+    offset: u64,
+    length: u64,
+    /// Allocated-but-unrecorded extents read back as zeros
+    recorded: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractSummary {
+    pub files: usize,
+    pub directories: usize,
+    pub bytes: u64,
+}
+
+/// In-process ISO 9660 / Joliet / UDF reader
+pub struct IsoReader {
+    file: File,
+    file_system: IsoFileSystem,
+    entries: Vec<IsoEntry>,
+}
+
+impl IsoReader {
+    /// Open an image, preferring UDF (needed for >4 GiB install.wim), then Joliet, then ISO 9660
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open ISO {}", path.display()))?;
+        let mut reader = Self {
+            file,
+            file_system: IsoFileSystem::Iso9660,
+            entries: Vec::new(),
+        };
+
+        let udf = if reader.has_udf_recognition()? {
+            match reader.read_udf_tree() {
+                Ok(entries) => Some(entries),
+                Err(error) => {
+                    warn!("UDF file system unreadable, falling back to ISO 9660: {:#}", error);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        match udf {
+            Some(entries) => {
+                reader.file_system = IsoFileSystem::Udf;
+                reader.entries = entries;
+            }
+            None => {
+                let (file_system, entries) = reader.read_iso9660_tree()?;
+                reader.file_system = file_system;
+                reader.entries = entries;
+            }
+        }
+
+        debug!(
+            "Opened {} as {:?} ({} entries)",
+            path.display(),
+            reader.file_system,
+            reader.entries.len()
+        );
+        Ok(reader)
+    }
+
+    pub fn file_system(&self) -> IsoFileSystem {
+        self.file_system
+    }
+
+    /// Every directory and file, parents listed before their children
+    pub fn entries(&self) -> &[IsoEntry] {
+        &self.entries
+    }
+
+    /// Case-insensitive lookup accepting `/` or `\` separators
+    pub fn find(&self, path: &str) -> Option<&IsoEntry> {
+        let wanted = path.replace('\\', "/");
+        let wanted = wanted.trim_matches('/');
+        self.entries
+            .iter()
+            .find(|entry| entry.path.eq_ignore_ascii_case(wanted))
+    }
+
+    /// Extract the whole image into `destination`, calling `on_file(entry, index, total)` before each file
+    pub fn extract_to(
+        &self,
+        destination: &Path,
+        mut on_file: impl FnMut(&IsoEntry, usize, usize),
+    ) -> Result<ExtractSummary> {
+        std::fs::create_dir_all(destination)
+            .with_context(|| format!("Failed to create {}", destination.display()))?;
+
+        let total_files = self.entries.iter().filter(|entry| !entry.is_dir).count();
+        let mut summary = ExtractSummary::default();
+        let mut directories = Vec::new();
+
+        for entry in &self.entries {
+            let target = destination.join(safe_relative_path(&entry.path)?);
+
+            if entry.is_dir {
+                std::fs::create_dir_all(&target)
+                    .with_context(|| format!("Failed to create directory {}", target.display()))?;
+                directories.push((target, entry.modified));
+                summary.directories += 1;
+                continue;
+            }
+
+            summary.files += 1;
+            on_file(entry, summary.files, total_files);
+            self.extract_file(entry, &target)?;
+            summary.bytes += entry.size;
+        }
+
+        // Directory times last, since creating children bumps them
+        for (directory, modified) in directories.iter().rev() {
+            if let Err(error) = File::open(directory).and_then(|handle| handle.set_modified(*modified)) {
+                debug!("Could not set timestamp on {}: {}", directory.display(), error);
+            }
+        }
+
+        info!(
+            "Extracted {} files ({} directories, {} bytes)",
+            summary.files, summary.directories, summary.bytes
+        );
+        Ok(summary)
+    }
+
+    /// Copy a single file entry to `target`, preserving its timestamp
+    pub fn extract_file(&self, entry: &IsoEntry, target: &Path) -> Result<()> {
+        let mut output = File::create(target)
+            .with_context(|| format!("Failed to create {}", target.display()))?;
+
+        let mut written = 0u64;
+        for extent in &entry.extents {
+            let copied = if extent.recorded {
+                let mut source = &self.file;
+                source.seek(SeekFrom::Start(extent.offset))?;
+                std::io::copy(&mut source.take(extent.length), &mut output)
+            } else {
+                std::io::copy(&mut std::io::repeat(0).take(extent.length), &mut output)
+            }
+            .with_context(|| format!("Failed to extract {}", entry.path))?;
+
+            if copied != extent.length {
+                bail!("ISO is truncated: {} ends early", entry.path);
+            }
+            written += copied;
+        }
+
+        if written != entry.size {
+            bail!("Extent lengths for {} do not match its size", entry.path);
+        }
+
+        output
+            .set_modified(entry.modified)
+            .with_context(|| format!("Failed to set timestamp on {}", target.display()))?;
+        Ok(())
+    }
+
+    fn read_at(&self, offset: u64, length: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; length];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buffer)
+            .with_context(|| format!("Failed to read {} bytes at offset {}", length, offset))?;
+        Ok(buffer)
+    }
+
+    fn read_sector(&self, sector: u64) -> Result<Vec<u8>> {
+        self.read_at(sector * SECTOR_SIZE_U64, SECTOR_SIZE)
+    }
+
+    fn read_extents(&self, extents: &[Extent]) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        for extent in extents {
+            if extent.recorded {
+                data.extend_from_slice(&self.read_at(extent.offset, extent.length as usize)?);
+            } else {
+                data.resize(data.len() + extent.length as usize, 0);
+            }
+        }
+        Ok(data)
+    }
+
+    fn has_udf_recognition(&self) -> Result<bool> {
+        let image_sectors = self.file.metadata()?.len() / SECTOR_SIZE_U64;
+        for sector in PRIMARY_DESCRIPTOR_SECTOR..DESCRIPTOR_SCAN_END.min(image_sectors) {
+            let descriptor = self.read_sector(sector)?;
+            if matches!(&descriptor[1..6], b"NSR02" | b"NSR03") {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn read_iso9660_tree(&self) -> Result<(IsoFileSystem, Vec<IsoEntry>)> {
+        let mut primary = None;
+        let mut joliet = None;
+
+        for sector in PRIMARY_DESCRIPTOR_SECTOR..DESCRIPTOR_SCAN_END {
+            let descriptor = self.read_sector(sector)?;
+            if &descriptor[1..6] != b"CD001" {
+                break;
+            }
+            match descriptor[0] {
+                1 => primary = primary.or(Some(descriptor)),
+                2 if matches!(&descriptor[88..91], b"%/@" | b"%/C" | b"%/E") => {
+                    joliet = joliet.or(Some(descriptor))
+                }
+                255 => break,
+                _ => {}
+            }
+        }
+
+        let (file_system, descriptor) = match (joliet, primary) {
+            (Some(descriptor), _) => (IsoFileSystem::Joliet, descriptor),
+            (None, Some(descriptor)) => (IsoFileSystem::Iso9660, descriptor),
+            (None, None) => bail!("No ISO 9660 or UDF file system found"),
+        };
+
+        let block_size = u64::from(u16::from_le_bytes([descriptor[128], descriptor[129]]));
+        if block_size != SECTOR_SIZE_U64 {
+            bail!("Unsupported ISO 9660 logical block size: {}", block_size);
+        }
+
+        let root = &descriptor[156..190];
+        let joliet = file_system == IsoFileSystem::Joliet;
+        let mut entries = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(String::new(), le_u32(root, 2), le_u32(root, 10))];
+
+        while let Some((prefix, extent, size)) = stack.pop() {
+            if !visited.insert(extent) {
+                bail!("Directory loop detected at sector {}", extent);
+            }
+
+            let data = self.read_at(u64::from(extent) * SECTOR_SIZE_U64, size as usize)?;
+            let children = parse_iso_directory(&data, &prefix, joliet)?;
+            let first_child = entries.len();
+            entries.extend(children.into_iter().map(|(entry, _)| entry));
+
+            // Depth-first so each directory's children follow it in the listing
+            let mut subdirectories = Vec::new();
+            for entry in &entries[first_child..] {
+                if entry.is_dir {
+                    let extent = entry.extents[0];
+                    subdirectories.push((entry.path.clone(), (extent.offset / SECTOR_SIZE_U64) as u32, extent.length as u32));
+                }
+            }
+            stack.extend(subdirectories.into_iter().rev());
+        }
+
+        Ok((file_system, entries))
+    }
+
+    fn read_udf_tree(&self) -> Result<Vec<IsoEntry>> {
+        let anchor = self.read_sector(ANCHOR_SECTOR)?;
+        check_udf_tag(&anchor, TAG_ANCHOR, "Anchor Volume Descriptor Pointer")?;
+        let vds_length = le_u32(&anchor, 16);
+        let vds_start = u64::from(le_u32(&anchor, 20));
+
+        let mut partition_start = None;
+        let mut file_set = None;
+
+        for sector in vds_start..vds_start + sectors(vds_length) {
+            let descriptor = self.read_sector(sector)?;
+            match le_u16(&descriptor, 0) {
+                TAG_PARTITION => partition_start = Some(u64::from(le_u32(&descriptor, 188))),
+                TAG_LOGICAL_VOLUME => {
+                    let block_size = le_u32(&descriptor, 212);
+                    if u64::from(block_size) != SECTOR_SIZE_U64 {
+                        bail!("Unsupported UDF logical block size: {}", block_size);
+                    }
+                    if descriptor[440] != 1 {
+                        bail!("Unsupported UDF partition map type {}", descriptor[440]);
+                    }
+                    file_set = Some(le_u32(&descriptor, 252));
+                }
+                TAG_TERMINATING => break,
+                _ => {}
+            }
+        }
+
+        let partition_start = partition_start.context("UDF partition descriptor missing")?;
+        let file_set = file_set.context("UDF logical volume descriptor missing")?;
+        let volume = UdfVolume { reader: self, partition_start };
+
+        let file_set = volume.read_block(file_set)?;
+        check_udf_tag(&file_set, TAG_FILE_SET, "File Set Descriptor")?;
+        let root = volume.read_node(le_u32(&file_set, 404))?;
+        if !root.is_dir {
+            bail!("UDF root ICB is not a directory");
+        }
+
+        let mut entries = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(String::new(), root)];
+
+        while let Some((prefix, directory)) = stack.pop() {
+            let data = self.read_extents(&directory.extents)?;
+            let mut subdirectories = Vec::new();
+
+            for (name, lbn) in parse_file_identifiers(&data)? {
+                let node = volume.read_node(lbn)?;
+                if node.is_dir && !visited.insert(lbn) {
+                    bail!("UDF directory loop detected at block {}", lbn);
+                }
+                let path = join_path(&prefix, &name);
+                entries.push(IsoEntry {
+                    path: path.clone(),
+                    is_dir: node.is_dir,
+                    size: if node.is_dir { 0 } else { node.size },
+                    modified: node.modified,
+                    extents: if node.is_dir { Vec::new() } else { node.extents.clone() },
+                });
+                if node.is_dir {
+                    subdirectories.push((path, node));
+                }
+            }
+
+            stack.extend(subdirectories.into_iter().rev());
+        }
+
+        Ok(entries)
+    }
+}
+
+struct UdfNode {
+    is_dir: bool,
+    size: u64,
+    modified: SystemTime,
+    extents: Vec<Extent>,
+}
+
+struct UdfVolume<'a> {
+    reader: &'a IsoReader,
+    partition_start: u64,
+}
+
+impl UdfVolume<'_> {
+    fn block_offset(&self, lbn: u32) -> u64 {
+        (self.partition_start + u64::from(lbn)) * SECTOR_SIZE_U64
+    }
+
+    fn read_block(&self, lbn: u32) -> Result<Vec<u8>> {
+        self.reader.read_at(self.block_offset(lbn), SECTOR_SIZE)
+    }
+
+    fn read_node(&self, lbn: u32) -> Result<UdfNode> {
+        let entry = self.read_block(lbn)?;
+        let (modified_at, ea_length_at, ad_start) = match le_u16(&entry, 0) {
+            TAG_FILE_ENTRY => (84, 168, 176),
+            TAG_EXTENDED_FILE_ENTRY => (92, 208, 216),
+            other => bail!("Unexpected UDF descriptor tag {} at block {}", other, lbn),
+        };
+
+        let file_type = entry[27];
+        let ad_type = le_u16(&entry, 34) & 0x07;
+        let size = le_u64(&entry, 56);
+        let ad_offset = ad_start + le_u32(&entry, ea_length_at) as usize;
+        let ad_length = le_u32(&entry, ea_length_at + 4) as usize;
+        if ad_offset + ad_length > entry.len() {
+            bail!("UDF file entry at block {} has invalid descriptor lengths", lbn);
+        }
+
+        let extents = match ad_type {
+            // Data embedded directly in the file entry
+            3 => vec![Extent {
+                offset: self.block_offset(lbn) + ad_offset as u64,
+                length: ad_length as u64,
+                recorded: true,
+            }],
+            0 | 1 => self.allocation_extents(&entry[ad_offset..ad_offset + ad_length], ad_type)?,
+            other => bail!("Unsupported UDF allocation descriptor type {}", other),
+        };
+
+        Ok(UdfNode {
+            is_dir: file_type == 4,
+            size,
+            modified: udf_timestamp(&entry[modified_at..modified_at + 12]),
+            extents: truncate_extents(extents, size),
+        })
+    }
+
+    fn allocation_extents(&self, descriptors: &[u8], ad_type: u16) -> Result<Vec<Extent>> {
+        let ad_size = if ad_type == 0 { 8 } else { 16 };
+        let mut extents = Vec::new();
+        let mut pending = descriptors.to_vec();
+        let mut hops = 0;
+
+        'chain: loop {
+            for descriptor in pending.chunks_exact(ad_size) {
+                let raw_length = le_u32(descriptor, 0);
+                let length = u64::from(raw_length & 0x3FFF_FFFF);
+                let position = le_u32(descriptor, 4);
+                if length == 0 {
+                    break;
+                }
+
+                match raw_length >> 30 {
+                    0 => extents.push(Extent { offset: self.block_offset(position), length, recorded: true }),
+                    1 | 2 => extents.push(Extent { offset: 0, length, recorded: false }),
+                    _ => {
+                        // Continuation into an Allocation Extent Descriptor
+                        hops += 1;
+                        if hops > 1024 {
+                            bail!("UDF allocation descriptor chain too long");
+                        }
+                        let block = self.read_block(position)?;
+                        check_udf_tag(&block, TAG_ALLOCATION_EXTENT, "Allocation Extent Descriptor")?;
+                        let length = (le_u32(&block, 20) as usize).min(SECTOR_SIZE - 24);
+                        pending = block[24..24 + length].to_vec();
+                        continue 'chain;
+                    }
+                }
+            }
+            break;
+        }
+
+        Ok(extents)
+    }
+}
+
+fn parse_iso_directory(data: &[u8], prefix: &str, joliet: bool) -> Result<Vec<(IsoEntry, bool)>> {
+    let mut children: Vec<(IsoEntry, bool)> = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let length = data[offset] as usize;
+        if length == 0 {
+            // Records never straddle sectors; skip the zero padding
+            offset = (offset / SECTOR_SIZE + 1) * SECTOR_SIZE;
+            continue;
+        }
+        if length < 34 || offset + length > data.len() {
+            bail!("Malformed ISO 9660 directory record");
+        }
+
+        let record = &data[offset..offset + length];
+        offset += length;
+
+        let name_length = record[32] as usize;
+        let identifier = &record[33..(33 + name_length).min(record.len())];
+        let flags = record[25];
+        if identifier == [0] || identifier == [1] || flags & 0x04 != 0 {
+            continue;
+        }
+
+        let extent = Extent {
+            offset: u64::from(le_u32(record, 2)) * SECTOR_SIZE_U64,
+            length: u64::from(le_u32(record, 10)),
+            recorded: true,
+        };
+        let name = iso_record_name(identifier, joliet);
+
+        // Multi-extent files repeat the record with flag 0x80 on all but the last part
+        if let Some((previous, continues)) = children.last_mut()
+            && *continues
+            && previous.path == join_path(prefix, &name)
+        {
+            previous.size += extent.length;
+            previous.extents.push(extent);
+            *continues = flags & 0x80 != 0;
+            continue;
+        }
+
+        let is_dir = flags & 0x02 != 0;
+        children.push((
+            IsoEntry {
+                path: join_path(prefix, &name),
+                is_dir,
+                size: if is_dir { 0 } else { extent.length },
+                modified: iso_timestamp(&record[18..25]),
+                extents: vec![extent],
+            },
+            flags & 0x80 != 0,
+        ));
+    }
+
+    Ok(children)
+}
+
+fn iso_record_name(identifier: &[u8], joliet: bool) -> String {
+    let name = if joliet {
+        let units: Vec<u16> = identifier
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(identifier).into_owned()
+    };
+
+    let name = match name.rsplit_once(';') {
+        Some((stem, version)) if version.chars().all(|c| c.is_ascii_digit()) => stem.to_string(),
+        _ => name,
+    };
+    name.strip_suffix('.').map(str::to_string).unwrap_or(name)
+}
+
+fn parse_file_identifiers(data: &[u8]) -> Result<Vec<(String, u32)>> {
+    let mut children = Vec::new();
+    let mut offset = 0;
+
+    while offset + 38 <= data.len() {
+        let descriptor = &data[offset..];
+        if le_u16(descriptor, 0) != TAG_FILE_IDENTIFIER {
+            bail!("Expected UDF File Identifier Descriptor at directory offset {}", offset);
+        }
+
+        let characteristics = descriptor[18];
+        let name_length = descriptor[19] as usize;
+        let icb = le_u32(descriptor, 24);
+        let implementation_length = le_u16(descriptor, 36) as usize;
+        let name_start = 38 + implementation_length;
+        let length = (name_start + name_length).next_multiple_of(4);
+        if offset + name_start + name_length > data.len() {
+            bail!("Truncated UDF File Identifier Descriptor");
+        }
+        offset += length;
+
+        // Skip parent and deleted entries
+        if characteristics & 0x0C != 0 {
+            continue;
+        }
+
+        let name = decode_osta_name(&descriptor[name_start..name_start + name_length]);
+        children.push((name, icb));
+    }
+
+    Ok(children)
+}
+
+/// OSTA compressed unicode: compression id 8 (one byte per char) or 16 (UCS-2 BE)
+fn decode_osta_name(encoded: &[u8]) -> String {
+    match encoded.split_first() {
+        Some((8, bytes)) => bytes.iter().map(|&byte| char::from(byte)).collect(),
+        Some((16, bytes)) => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::new(),
+    }
+}
+
+fn iso_timestamp(field: &[u8]) -> SystemTime {
+    let date = DateTime {
+        year: 1900 + i32::from(field[0]),
+        month: field[1],
+        day: field[2],
+        hour: field[3],
+        minute: field[4],
+        second: field[5],
+    };
+    // GMT offset in 15 minute intervals
+    apply_utc_offset(date, i32::from(field[6] as i8) * 15)
+}
+
+fn udf_timestamp(field: &[u8]) -> SystemTime {
+    let type_and_zone = le_u16(field, 0);
+    let mut zone = i32::from(type_and_zone & 0x0FFF);
+    if zone & 0x0800 != 0 {
+        zone -= 0x1000;
+    }
+    // -2047 means "no time zone specified"
+    let offset_minutes = if type_and_zone >> 12 == 1 && zone != -2047 { zone } else { 0 };
+
+    let date = DateTime {
+        year: i32::from(le_u16(field, 2) as i16),
+        month: field[4],
+        day: field[5],
+        hour: field[6],
+        minute: field[7],
+        second: field[8],
+    };
+    apply_utc_offset(date, offset_minutes)
+}
+
+fn apply_utc_offset(date: DateTime, offset_minutes: i32) -> SystemTime {
+    if date.month == 0 || date.day == 0 {
+        return std::time::UNIX_EPOCH;
+    }
+
+    let local = date.to_system_time();
+    let offset = Duration::from_secs(u64::from(offset_minutes.unsigned_abs()) * 60);
+    if offset_minutes >= 0 {
+        local.checked_sub(offset).unwrap_or(local)
+    } else {
+        local + offset
+    }
+}
+
+fn truncate_extents(extents: Vec<Extent>, size: u64) -> Vec<Extent> {
+    let mut remaining = size;
+    extents
+        .into_iter()
+        .filter_map(|mut extent| {
+            if remaining == 0 {
+                return None;
+            }
+            extent.length = extent.length.min(remaining);
+            remaining -= extent.length;
+            Some(extent)
+        })
+        .collect()
+}
+
+fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+/// Reject names that would escape the extraction directory
+fn safe_relative_path(path: &str) -> Result<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.split('/') {
+        if component.is_empty()
+            || component == "."
+            || component == ".."
+            || component.contains(['\\', ':', '\0'])
+        {
+            bail!("Refusing to extract unsafe ISO path: {}", path);
+        }
+        relative.push(component);
+    }
+    Ok(relative)
+}
+
+fn check_udf_tag(descriptor: &[u8], expected: u16, label: &str) -> Result<()> {
+    if le_u16(descriptor, 0) != expected || descriptor[4] != udf_tag_checksum(descriptor) {
+        bail!("Invalid UDF {}", label);
+    }
+    Ok(())
+}
+
+fn sectors(bytes: u32) -> u64 {
+    u64::from(bytes).div_ceil(SECTOR_SIZE_U64)
+}
+
+fn le_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn le_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from(le_u32(data, offset)) | (u64::from(le_u32(data, offset + 4)) << 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IsoFileSystem, IsoReader, safe_relative_path};
+    use crate::iso::{ANCHOR_SECTOR, IsoOptions, IsoWriter, SECTOR_SIZE};
+    use std::io::{Seek, SeekFrom, Write};
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::tempdir;
+
+    fn authored_iso(root: &std::path::Path) -> std::path::PathBuf {
+        let media = root.join("media");
+        crate::iso::test_support::write_test_media(&media, &[]);
+        std::fs::create_dir_all(media.join("sources/Long Directory Name")).unwrap();
+        std::fs::write(media.join("sources/boot.wim"), (0..10_000u32).flat_map(|n| n.to_le_bytes()).collect::<Vec<_>>()).unwrap();
+        std::fs::write(media.join("sources/Long Directory Name/readme file.txt"), b"hello").unwrap();
+        std::fs::write(media.join("sources/empty.txt"), b"").unwrap();
+
+        let stamp = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(media.join("sources/boot.wim"))
+            .unwrap()
+            .set_modified(stamp)
+            .unwrap();
+
+        let iso = root.join("source.iso");
+        IsoWriter::new(IsoOptions::windows_media("TEST")).write(&media, &iso).unwrap();
+        iso
+    }
+
+    fn zero_sector(iso: &std::path::Path, sector: u64) {
+        let mut file = std::fs::File::options().write(true).open(iso).unwrap();
+        file.seek(SeekFrom::Start(sector * SECTOR_SIZE as u64)).unwrap();
+        file.write_all(&[0u8; SECTOR_SIZE]).unwrap();
+    }
+
+    #[test]
+    fn lists_udf_tree_with_original_names() {
+        let temp = tempdir().unwrap();
+        let reader = IsoReader::open(&authored_iso(temp.path())).unwrap();
+
+        assert_eq!(reader.file_system(), IsoFileSystem::Udf);
+        assert!(reader.find("sources/Long Directory Name/readme file.txt").is_some());
+        assert!(reader.find("BOOT\\BCD").is_some());
+        assert_eq!(reader.find("sources/boot.wim").unwrap().size, 40_000);
+        assert!(reader.find("efi/microsoft").unwrap().is_dir);
+    }
+
+    #[test]
+    fn extracts_files_with_contents_and_timestamps() {
+        let temp = tempdir().unwrap();
+        let iso = authored_iso(temp.path());
+        let output = temp.path().join("out");
+
+        let mut seen = Vec::new();
+        let summary = IsoReader::open(&iso)
+            .unwrap()
+            .extract_to(&output, |entry, index, total| seen.push((entry.path.clone(), index, total)))
+            .unwrap();
+
+        assert_eq!(summary.files, 7);
+        assert_eq!(seen.len(), 7);
+        assert_eq!(seen.last().unwrap().1, 7);
+        assert!(seen.iter().all(|(_, _, total)| *total == 7));
+
+        assert_eq!(
+            std::fs::read(output.join("sources/boot.wim")).unwrap(),
+            std::fs::read(temp.path().join("media/sources/boot.wim")).unwrap()
+        );
+        assert_eq!(std::fs::read(output.join("sources/Long Directory Name/readme file.txt")).unwrap(), b"hello");
+        assert!(std::fs::read(output.join("sources/empty.txt")).unwrap().is_empty());
+
+        let modified = std::fs::metadata(output.join("sources/boot.wim")).unwrap().modified().unwrap();
+        assert_eq!(modified, UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    }
+
+    #[test]
+    fn falls_back_to_joliet_then_iso9660() {
+        let temp = tempdir().unwrap();
+        let iso = authored_iso(temp.path());
+
+        zero_sector(&iso, ANCHOR_SECTOR);
+        let reader = IsoReader::open(&iso).unwrap();
+        assert_eq!(reader.file_system(), IsoFileSystem::Joliet);
+        assert!(reader.find("sources/Long Directory Name/readme file.txt").is_some());
+        assert_eq!(reader.find("sources/boot.wim").unwrap().size, 40_000);
+
+        // Primary, boot record, then Joliet: drop the Joliet escape sequence
+        let mut file = std::fs::File::options().write(true).open(&iso).unwrap();
+        file.seek(SeekFrom::Start(18 * SECTOR_SIZE as u64 + 88)).unwrap();
+        file.write_all(&[0, 0, 0]).unwrap();
+
+        let reader = IsoReader::open(&iso).unwrap();
+        assert_eq!(reader.file_system(), IsoFileSystem::Iso9660);
+        assert!(reader.find("sources/BOOT.WIM").is_some());
+        assert!(reader.find("sources/LONG_DIRECTORY_NAME/README_FILE.TXT").is_some());
+    }
+
+    #[test]
+    fn rejects_non_iso_input() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("random.iso");
+        std::fs::write(&path, vec![0u8; 64 * SECTOR_SIZE]).unwrap();
+
+        let error = IsoReader::open(&path).err().unwrap();
+        assert!(error.to_string().contains("No ISO 9660 or UDF file system found"));
+    }
+
+    #[test]
+    fn refuses_path_traversal() {
+        assert!(safe_relative_path("sources/../../etc").is_err());
+        assert!(safe_relative_path("C:/Windows").is_err());
+        assert_eq!(
+            safe_relative_path("sources/boot.wim").unwrap(),
+            std::path::PathBuf::from("sources").join("boot.wim")
+        );
+    }
+}
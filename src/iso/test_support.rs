@@ -0,0 +1,15 @@
+use std::path::Path;
+use super::{BIOS_BOOT_IMAGE, UEFI_BOOT_IMAGE};
+
+/// Bootable media layout (boot sectors, boot manager, BCD and a `sources/boot.wim`
+/// holding `boot_images`) that tests author ISOs from
+pub(crate) fn write_test_media(media: &Path, boot_images: &[(&str, &str, &str, u32)]) {
+    std::fs::create_dir_all(media.join("boot")).unwrap();
+    std::fs::create_dir_all(media.join("efi/microsoft/boot")).unwrap();
+    std::fs::create_dir_all(media.join("sources")).unwrap();
+    std::fs::write(media.join("bootmgr"), "bootmgr").unwrap();
+    std::fs::write(media.join("boot/bcd"), "bcd").unwrap();
+    std::fs::write(media.join(BIOS_BOOT_IMAGE), vec![0xEB_u8; 2048]).unwrap();
+    std::fs::write(media.join(UEFI_BOOT_IMAGE), vec![0xEF_u8; 2048]).unwrap();
+    crate::wim::metadata::write_test_wim(&media.join("sources/boot.wim"), boot_images);
+}
@@ -0,0 +1,1359 @@
+use anyhow::{Context, Result, bail};
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use super::{
+    ANCHOR_SECTOR, BIOS_BOOT_IMAGE, DateTime, EL_TORITO_ID, PRIMARY_DESCRIPTOR_SECTOR, SECTOR_SIZE,
+    SECTOR_SIZE_U64, TAG_ANCHOR, TAG_FILE_ENTRY, TAG_FILE_IDENTIFIER, TAG_FILE_SET,
+    TAG_IMPLEMENTATION_USE, TAG_INTEGRITY, TAG_LOGICAL_VOLUME, TAG_PARTITION, TAG_PRIMARY_VOLUME,
+    TAG_TERMINATING, TAG_UNALLOCATED_SPACE, UEFI_BOOT_IMAGE, sectors_for, udf_crc,
+    udf_tag_checksum,
+};
+
+const VRS_START_SECTOR: u64 = 20;
+const MAIN_VDS_SECTOR: u64 = 32;
+const RESERVE_VDS_SECTOR: u64 = 48;
+const VDS_LENGTH_SECTORS: u32 = 16;
+const INTEGRITY_SECTOR: u64 = 64;
+const PARTITION_START: u64 = ANCHOR_SECTOR + 1;
+
+/// Largest sector-aligned extent a 32-bit ISO 9660 length can describe
+const MAX_ISO_EXTENT: u64 = 0xFFFF_F800;
+/// UDF extent lengths must stay below 2^30 bytes
+const MAX_UDF_EXTENT: u64 = 0x3FFF_F800;
+
+const ISO_FILE_NAME_LIMIT: usize = 30;
+const ISO_DIR_NAME_LIMIT: usize = 31;
+const JOLIET_NAME_LIMIT: usize = 64;
+
+const UDF_REVISION: u16 = 0x0102;
+const UDF_DOMAIN: &str = "*OSTA UDF Compliant";
+const IMPLEMENTATION_ID: &str = "*GhostWin";
+const APPLICATION_ID: &str = "GHOSTWIN";
+
+/// Options controlling the layout of an authored ISO image
+#[derive(Debug, Clone)]
+pub struct IsoOptions {
+    pub volume_id: String,
+    /// Media-relative path of the BIOS no-emulation boot image
+    pub bios_boot_image: Option<PathBuf>,
+    /// Media-relative path of the UEFI (platform 0xEF) boot image
+    pub uefi_boot_image: Option<PathBuf>,
+    /// Root-level file names left out of the image (build bookkeeping files)
+    pub exclude: Vec<String>,
+}
+
+impl IsoOptions {
+    /// Dual BIOS/UEFI El Torito layout used by Windows installation media
+    pub fn windows_media(volume_id: &str) -> Self {
+        Self {
+            volume_id: volume_id.to_string(),
+            bios_boot_image: Some(PathBuf::from(BIOS_BOOT_IMAGE)),
+            uefi_boot_image: Some(PathBuf::from(UEFI_BOOT_IMAGE)),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsoSummary {
+    pub total_sectors: u64,
+    pub files: usize,
+    pub directories: usize,
+    pub boot_catalog_sector: Option<u32>,
+}
+
+/// Native ISO 9660 + Joliet + UDF 1.02 authoring, replacing oscdimg's
+/// `-m -o -u2 -udfver102 -bootdata:2#...` layout
+pub struct IsoWriter {
+    options: IsoOptions,
+}
+
+impl IsoWriter {
+    pub fn new(options: IsoOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn write(&self, source_root: &Path, output: &Path) -> Result<IsoSummary> {
+        if !source_root.is_dir() {
+            bail!("ISO source is not a directory: {}", source_root.display());
+        }
+
+        info!("Authoring ISO {} from {}", output.display(), source_root.display());
+
+        let mut image = Image::scan(source_root, &self.options.exclude)?;
+        let boot = self.resolve_boot_images(&image)?;
+        let layout = image.allocate(boot.is_some());
+
+        let mut file = File::create(output)
+            .with_context(|| format!("Failed to create ISO file {}", output.display()))?;
+        file.set_len(layout.total_sectors * SECTOR_SIZE_U64)
+            .context("Failed to size ISO file")?;
+
+        let mut out = SectorWriter { file: &mut file };
+        image.write_volume_descriptors(&mut out, &layout, &self.options, boot.as_ref())?;
+        image.write_udf_volume(&mut out, &layout, &self.options)?;
+        if let Some(boot) = &boot {
+            out.write_at(layout.boot_catalog, &image.boot_catalog(boot))?;
+        }
+        image.write_iso_trees(&mut out, &layout)?;
+        image.write_file_data(&mut out)?;
+        file.sync_all().context("Failed to flush ISO file")?;
+
+        let summary = IsoSummary {
+            total_sectors: layout.total_sectors,
+            files: image.entries.iter().filter(|entry| !entry.is_dir()).count(),
+            directories: image.entries.iter().filter(|entry| entry.is_dir()).count(),
+            boot_catalog_sector: boot.map(|_| layout.boot_catalog as u32),
+        };
+
+        info!(
+            "ISO authored: {} files, {} directories, {} sectors",
+            summary.files, summary.directories, summary.total_sectors
+        );
+
+        Ok(summary)
+    }
+
+    fn resolve_boot_images(&self, image: &Image) -> Result<Option<BootImages>> {
+        let bios = self
+            .options
+            .bios_boot_image
+            .as_deref()
+            .map(|path| image.find(path).ok_or_else(|| anyhow::anyhow!("BIOS boot image not found in media: {}", path.display())))
+            .transpose()?;
+        let uefi = self
+            .options
+            .uefi_boot_image
+            .as_deref()
+            .map(|path| image.find(path).ok_or_else(|| anyhow::anyhow!("UEFI boot image not found in media: {}", path.display())))
+            .transpose()?;
+
+        if bios.is_none() && uefi.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(BootImages { bios, uefi }))
+    }
+}
+
+struct BootImages {
+    bios: Option<usize>,
+    uefi: Option<usize>,
+}
+
+struct SectorWriter<'a> {
+    file: &'a mut File,
+}
+
+impl SectorWriter<'_> {
+    fn write_at(&mut self, sector: u64, data: &[u8]) -> Result<()> {
+        self.file
+            .seek(SeekFrom::Start(sector * SECTOR_SIZE_U64))
+            .context("Failed to seek in ISO file")?;
+        self.file.write_all(data).context("Failed to write ISO sector data")?;
+        Ok(())
+    }
+}
+
+enum EntryKind {
+    Directory {
+        children: Vec<usize>,
+    },
+    File {
+        source: PathBuf,
+        size: u64,
+    },
+}
+
+struct Entry {
+    name: String,
+    parent: usize,
+    modified: DateTime,
+    kind: EntryKind,
+    iso_name: String,
+    joliet_name: Vec<u16>,
+    // Absolute sector assignments, filled in by `Image::allocate`
+    udf_entry: u64,
+    udf_directory: (u64, u64),
+    iso_directory: (u64, u64),
+    joliet_directory: (u64, u64),
+    data: u64,
+}
+
+impl Entry {
+    fn is_dir(&self) -> bool {
+        matches!(self.kind, EntryKind::Directory { .. })
+    }
+
+    fn size(&self) -> u64 {
+        match self.kind {
+            EntryKind::File { size, .. } => size,
+            EntryKind::Directory { .. } => 0,
+        }
+    }
+
+    fn children(&self) -> &[usize] {
+        match &self.kind {
+            EntryKind::Directory { children } => children,
+            EntryKind::File { .. } => &[],
+        }
+    }
+}
+
+struct Layout {
+    total_sectors: u64,
+    boot_catalog: u64,
+    iso_path_tables: (u64, u64, u64),
+    joliet_path_tables: (u64, u64, u64),
+    last_anchor: u64,
+}
+
+/// In-memory directory tree with ISO, Joliet, and UDF sector assignments
+struct Image {
+    entries: Vec<Entry>,
+    iso_order: Vec<usize>,
+    joliet_order: Vec<usize>,
+}
+
+struct DirRecord {
+    identifier: Vec<u8>,
+    extent: u32,
+    size: u32,
+    flags: u8,
+    modified: DateTime,
+}
+
+impl Image {
+    fn scan(source_root: &Path, exclude: &[String]) -> Result<Self> {
+        let metadata = std::fs::metadata(source_root)
+            .with_context(|| format!("Failed to read {}", source_root.display()))?;
+        let root = Entry::new(String::new(), 0, &metadata, EntryKind::Directory { children: Vec::new() });
+        let mut image = Self {
+            entries: vec![root],
+            iso_order: Vec::new(),
+            joliet_order: Vec::new(),
+        };
+
+        image.scan_dir(source_root, 0, exclude)?;
+        image.assign_names();
+        image.iso_order = image.directory_order(|entry| entry.iso_name.as_bytes().to_vec());
+        image.joliet_order = image.directory_order(|entry| joliet_sort_key(&entry.joliet_name));
+        Ok(image)
+    }
+
+    fn scan_dir(&mut self, path: &Path, index: usize, exclude: &[String]) -> Result<()> {
+        let mut dir_entries = std::fs::read_dir(path)
+            .with_context(|| format!("Failed to read directory {}", path.display()))?
+            .collect::<std::io::Result<Vec<_>>>()?;
+        dir_entries.retain(|entry| {
+            index != 0
+                || !exclude
+                    .iter()
+                    .any(|name| entry.file_name().to_string_lossy().eq_ignore_ascii_case(name))
+        });
+        dir_entries.sort_by_key(|entry| entry.file_name());
+
+        for dir_entry in dir_entries {
+            let child_path = dir_entry.path();
+            let metadata = std::fs::metadata(&child_path)
+                .with_context(|| format!("Failed to read {}", child_path.display()))?;
+            let name = dir_entry.file_name().to_string_lossy().into_owned();
+
+            let kind = if metadata.is_dir() {
+                EntryKind::Directory { children: Vec::new() }
+            } else {
+                EntryKind::File {
+                    source: child_path.clone(),
+                    size: metadata.len(),
+                }
+            };
+
+            let child = self.entries.len();
+            self.entries.push(Entry::new(name, index, &metadata, kind));
+            if let EntryKind::Directory { children } = &mut self.entries[index].kind {
+                children.push(child);
+            }
+
+            if metadata.is_dir() {
+                self.scan_dir(&child_path, child, &[])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Assign unique ISO 9660 and Joliet identifiers per directory and sort children
+    fn assign_names(&mut self) {
+        for index in 0..self.entries.len() {
+            let children = self.entries[index].children().to_vec();
+            if children.is_empty() {
+                continue;
+            }
+
+            let mut used_iso = HashSet::new();
+            let mut used_joliet = HashSet::new();
+            for &child in &children {
+                let iso_name = unique_name(iso_name(&self.entries[child]), &mut used_iso);
+                let joliet_name = unique_name(joliet_name(&self.entries[child].name), &mut used_joliet);
+                self.entries[child].iso_name = iso_name;
+                self.entries[child].joliet_name = joliet_name.encode_utf16().collect();
+            }
+        }
+    }
+
+    fn find(&self, relative: &Path) -> Option<usize> {
+        let mut current = 0;
+        for component in relative.components() {
+            let name = component.as_os_str().to_string_lossy();
+            current = *self.entries[current]
+                .children()
+                .iter()
+                .find(|&&child| self.entries[child].name.eq_ignore_ascii_case(&name))?;
+        }
+
+        (!self.entries[current].is_dir()).then_some(current)
+    }
+
+    fn sorted_children<K: Ord>(&self, index: usize, key: impl Fn(&Entry) -> K) -> Vec<usize> {
+        let mut children = self.entries[index].children().to_vec();
+        children.sort_by_key(|&child| key(&self.entries[child]));
+        children
+    }
+
+    /// Breadth-first directory order used for path tables
+    fn directory_order<K: Ord>(&self, key: impl Fn(&Entry) -> K + Copy) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([0]);
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+            for child in self.sorted_children(index, key) {
+                if self.entries[child].is_dir() {
+                    queue.push_back(child);
+                }
+            }
+        }
+        order
+    }
+
+    fn allocate(&mut self, bootable: bool) -> Layout {
+        let mut cursor = PARTITION_START + 2; // File Set Descriptor + Terminating Descriptor
+
+        for entry in &mut self.entries {
+            entry.udf_entry = cursor;
+            cursor += 1;
+        }
+
+        for index in 0..self.entries.len() {
+            if self.entries[index].is_dir() {
+                let length = self.udf_directory_stream(index).len() as u64;
+                self.entries[index].udf_directory = (cursor, length);
+                cursor += sectors_for(length);
+            }
+        }
+
+        let boot_catalog = cursor;
+        if bootable {
+            cursor += 1;
+        }
+
+        let iso_table_size = self.path_table(false, false).len() as u64;
+        let iso_path_tables = (cursor, cursor + sectors_for(iso_table_size), iso_table_size);
+        cursor += 2 * sectors_for(iso_table_size);
+
+        let joliet_table_size = self.path_table(true, false).len() as u64;
+        let joliet_path_tables = (cursor, cursor + sectors_for(joliet_table_size), joliet_table_size);
+        cursor += 2 * sectors_for(joliet_table_size);
+
+        for joliet in [false, true] {
+            for index in 0..self.entries.len() {
+                if self.entries[index].is_dir() {
+                    let length = records_extent_length(&self.directory_records(index, joliet));
+                    let extent = (cursor, length);
+                    if joliet {
+                        self.entries[index].joliet_directory = extent;
+                    } else {
+                        self.entries[index].iso_directory = extent;
+                    }
+                    cursor += sectors_for(length);
+                }
+            }
+        }
+
+        for entry in &mut self.entries {
+            if let EntryKind::File { size, .. } = entry.kind {
+                if size > 0 {
+                    entry.data = cursor;
+                }
+                cursor += sectors_for(size);
+            }
+        }
+
+        Layout {
+            total_sectors: cursor + 1,
+            boot_catalog,
+            iso_path_tables,
+            joliet_path_tables,
+            last_anchor: cursor,
+        }
+    }
+
+    fn write_volume_descriptors(
+        &self,
+        out: &mut SectorWriter,
+        layout: &Layout,
+        options: &IsoOptions,
+        boot: Option<&BootImages>,
+    ) -> Result<()> {
+        // ISO 9660 descriptors must be contiguous: primary, boot record, Joliet, terminator
+        let mut sector = PRIMARY_DESCRIPTOR_SECTOR;
+        out.write_at(sector, &self.volume_descriptor(layout, options, false))?;
+        sector += 1;
+
+        if boot.is_some() {
+            let mut record = [0u8; SECTOR_SIZE];
+            record[1..6].copy_from_slice(b"CD001");
+            record[6] = 1;
+            record[7..7 + EL_TORITO_ID.len()].copy_from_slice(EL_TORITO_ID);
+            record[71..75].copy_from_slice(&(layout.boot_catalog as u32).to_le_bytes());
+            out.write_at(sector, &record)?;
+            sector += 1;
+        }
+
+        out.write_at(sector, &self.volume_descriptor(layout, options, true))?;
+        sector += 1;
+
+        let mut terminator = [0u8; SECTOR_SIZE];
+        terminator[0] = 255;
+        terminator[1..6].copy_from_slice(b"CD001");
+        terminator[6] = 1;
+        out.write_at(sector, &terminator)?;
+
+        // UDF volume recognition sequence
+        for (offset, identifier) in [b"BEA01", b"NSR02", b"TEA01"].iter().enumerate() {
+            let mut descriptor = [0u8; SECTOR_SIZE];
+            descriptor[1..6].copy_from_slice(*identifier);
+            descriptor[6] = 1;
+            out.write_at(VRS_START_SECTOR + offset as u64, &descriptor)?;
+        }
+
+        Ok(())
+    }
+
+    fn volume_descriptor(&self, layout: &Layout, options: &IsoOptions, joliet: bool) -> [u8; SECTOR_SIZE] {
+        let mut descriptor = [0u8; SECTOR_SIZE];
+        descriptor[0] = if joliet { 2 } else { 1 };
+        descriptor[1..6].copy_from_slice(b"CD001");
+        descriptor[6] = 1;
+
+        let volume_id = iso_volume_id(&options.volume_id);
+        if joliet {
+            put_ucs2_padded(&mut descriptor[8..40], "");
+            put_ucs2_padded(&mut descriptor[40..72], &options.volume_id);
+            // Escape sequence for UCS-2 Level 3
+            descriptor[88..91].copy_from_slice(b"%/E");
+        } else {
+            put_padded(&mut descriptor[8..40], "");
+            put_padded(&mut descriptor[40..72], &volume_id);
+        }
+
+        put_both_u32(&mut descriptor[80..88], layout.total_sectors as u32);
+        put_both_u16(&mut descriptor[120..124], 1);
+        put_both_u16(&mut descriptor[124..128], 1);
+        put_both_u16(&mut descriptor[128..132], SECTOR_SIZE as u16);
+
+        let (l_table, m_table, table_size) = if joliet {
+            layout.joliet_path_tables
+        } else {
+            layout.iso_path_tables
+        };
+        put_both_u32(&mut descriptor[132..140], table_size as u32);
+        descriptor[140..144].copy_from_slice(&(l_table as u32).to_le_bytes());
+        descriptor[148..152].copy_from_slice(&(m_table as u32).to_be_bytes());
+
+        let root = &self.entries[0];
+        let (extent, size) = if joliet { root.joliet_directory } else { root.iso_directory };
+        let root_record = encode_dir_record(&DirRecord {
+            identifier: vec![0],
+            extent: extent as u32,
+            size: size as u32,
+            flags: 0x02,
+            modified: root.modified,
+        });
+        descriptor[156..156 + root_record.len()].copy_from_slice(&root_record);
+
+        let text_fields = [(190, 128, ""), (318, 128, ""), (446, 128, ""), (574, 128, APPLICATION_ID)];
+        for (offset, length, value) in text_fields {
+            if joliet {
+                put_ucs2_padded(&mut descriptor[offset..offset + length], value);
+            } else {
+                put_padded(&mut descriptor[offset..offset + length], value);
+            }
+        }
+        for offset in [702, 739, 776] {
+            if joliet {
+                put_ucs2_padded(&mut descriptor[offset..offset + 36], "");
+            } else {
+                put_padded(&mut descriptor[offset..offset + 37], "");
+            }
+        }
+
+        let created = volume_date(&root.modified);
+        descriptor[813..830].copy_from_slice(&created);
+        descriptor[830..847].copy_from_slice(&created);
+        descriptor[847..864].copy_from_slice(&unset_volume_date());
+        descriptor[864..881].copy_from_slice(&unset_volume_date());
+        descriptor[881] = 1;
+
+        descriptor
+    }
+
+    fn boot_catalog(&self, boot: &BootImages) -> [u8; SECTOR_SIZE] {
+        let mut catalog = [0u8; SECTOR_SIZE];
+
+        // Validation entry: platform x86, checksum makes the 16-bit word sum zero
+        catalog[0] = 0x01;
+        catalog[0x1E] = 0x55;
+        catalog[0x1F] = 0xAA;
+        let sum = catalog[..32]
+            .chunks_exact(2)
+            .fold(0u16, |sum, word| sum.wrapping_add(u16::from_le_bytes([word[0], word[1]])));
+        catalog[0x1C..0x1E].copy_from_slice(&0u16.wrapping_sub(sum).to_le_bytes());
+
+        // Default entry (BIOS), then a final section header + entry for UEFI
+        if let Some(bios) = boot.bios {
+            self.boot_entry(&mut catalog[32..64], bios);
+        }
+
+        if let Some(uefi) = boot.uefi {
+            catalog[64] = 0x91;
+            catalog[65] = 0xEF;
+            catalog[66..68].copy_from_slice(&1u16.to_le_bytes());
+            self.boot_entry(&mut catalog[96..128], uefi);
+        }
+
+        catalog
+    }
+
+    fn boot_entry(&self, entry: &mut [u8], index: usize) {
+        let image = &self.entries[index];
+        let virtual_sectors = image.size().div_ceil(512).clamp(1, u64::from(u16::MAX)) as u16;
+
+        entry[0] = 0x88; // bootable, no emulation
+        entry[6..8].copy_from_slice(&virtual_sectors.to_le_bytes());
+        entry[8..12].copy_from_slice(&(image.data as u32).to_le_bytes());
+    }
+
+    fn path_table(&self, joliet: bool, big_endian: bool) -> Vec<u8> {
+        let order = if joliet { &self.joliet_order } else { &self.iso_order };
+        let mut table = Vec::new();
+
+        for &index in order {
+            let entry = &self.entries[index];
+            let identifier = if index == 0 {
+                vec![0]
+            } else {
+                self.identifier(index, joliet)
+            };
+            let parent_number = order
+                .iter()
+                .position(|&candidate| candidate == entry.parent)
+                .map(|position| position + 1)
+                .unwrap_or(1) as u16;
+            let extent = if joliet { entry.joliet_directory.0 } else { entry.iso_directory.0 } as u32;
+
+            table.push(identifier.len() as u8);
+            table.push(0);
+            if big_endian {
+                table.extend_from_slice(&extent.to_be_bytes());
+                table.extend_from_slice(&parent_number.to_be_bytes());
+            } else {
+                table.extend_from_slice(&extent.to_le_bytes());
+                table.extend_from_slice(&parent_number.to_le_bytes());
+            }
+            table.extend_from_slice(&identifier);
+            if identifier.len() % 2 == 1 {
+                table.push(0);
+            }
+        }
+
+        table
+    }
+
+    fn identifier(&self, index: usize, joliet: bool) -> Vec<u8> {
+        let entry = &self.entries[index];
+        if joliet {
+            entry.joliet_name.iter().flat_map(|unit| unit.to_be_bytes()).collect()
+        } else if entry.is_dir() {
+            entry.iso_name.as_bytes().to_vec()
+        } else {
+            format!("{};1", entry.iso_name).into_bytes()
+        }
+    }
+
+    fn directory_records(&self, index: usize, joliet: bool) -> Vec<DirRecord> {
+        let entry = &self.entries[index];
+        let parent = &self.entries[entry.parent];
+        let extent_of = |entry: &Entry| if joliet { entry.joliet_directory } else { entry.iso_directory };
+
+        let mut records = vec![
+            DirRecord {
+                identifier: vec![0],
+                extent: extent_of(entry).0 as u32,
+                size: extent_of(entry).1 as u32,
+                flags: 0x02,
+                modified: entry.modified,
+            },
+            DirRecord {
+                identifier: vec![1],
+                extent: extent_of(parent).0 as u32,
+                size: extent_of(parent).1 as u32,
+                flags: 0x02,
+                modified: parent.modified,
+            },
+        ];
+
+        let children = if joliet {
+            self.sorted_children(index, |entry| joliet_sort_key(&entry.joliet_name))
+        } else {
+            self.sorted_children(index, |entry| entry.iso_name.clone())
+        };
+
+        for child in children {
+            let child_entry = &self.entries[child];
+            let identifier = self.identifier(child, joliet);
+
+            if child_entry.is_dir() {
+                records.push(DirRecord {
+                    identifier,
+                    extent: extent_of(child_entry).0 as u32,
+                    size: extent_of(child_entry).1 as u32,
+                    flags: 0x02,
+                    modified: child_entry.modified,
+                });
+                continue;
+            }
+
+            // Files above 4 GiB (install.wim) are split into multi-extent records
+            let mut remaining = child_entry.size();
+            let mut sector = child_entry.data;
+            loop {
+                let length = remaining.min(MAX_ISO_EXTENT);
+                remaining -= length;
+                records.push(DirRecord {
+                    identifier: identifier.clone(),
+                    extent: sector as u32,
+                    size: length as u32,
+                    flags: if remaining > 0 { 0x80 } else { 0 },
+                    modified: child_entry.modified,
+                });
+                if remaining == 0 {
+                    break;
+                }
+                sector += sectors_for(length);
+            }
+        }
+
+        records
+    }
+
+    fn write_iso_trees(&self, out: &mut SectorWriter, layout: &Layout) -> Result<()> {
+        for (joliet, tables) in [(false, layout.iso_path_tables), (true, layout.joliet_path_tables)] {
+            out.write_at(tables.0, &self.path_table(joliet, false))?;
+            out.write_at(tables.1, &self.path_table(joliet, true))?;
+
+            for (index, entry) in self.entries.iter().enumerate() {
+                if entry.is_dir() {
+                    let extent = if joliet { entry.joliet_directory } else { entry.iso_directory };
+                    out.write_at(extent.0, &records_extent(&self.directory_records(index, joliet)))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_file_data(&self, out: &mut SectorWriter) -> Result<()> {
+        for entry in &self.entries {
+            let EntryKind::File { source, size } = &entry.kind else {
+                continue;
+            };
+            if *size == 0 {
+                continue;
+            }
+
+            debug!("Writing {} ({} bytes) at sector {}", source.display(), size, entry.data);
+            let mut input = File::open(source)
+                .with_context(|| format!("Failed to open {}", source.display()))?;
+            out.file.seek(SeekFrom::Start(entry.data * SECTOR_SIZE_U64))?;
+            let copied = std::io::copy(&mut (&mut input).take(*size), out.file)
+                .with_context(|| format!("Failed to copy {} into ISO", source.display()))?;
+
+            if copied != *size {
+                bail!("File changed while authoring ISO: {}", source.display());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_udf_volume(&self, out: &mut SectorWriter, layout: &Layout, options: &IsoOptions) -> Result<()> {
+        let recorded = self.entries[0].modified;
+        let partition_length = (layout.last_anchor - PARTITION_START) as u32;
+
+        for base in [MAIN_VDS_SECTOR, RESERVE_VDS_SECTOR] {
+            let descriptors = [
+                udf_primary_volume(base, options, &recorded),
+                udf_implementation_use(base + 1, options),
+                udf_partition(base + 2, partition_length),
+                udf_logical_volume(base + 3, options),
+                udf_unallocated_space(base + 4),
+                udf_terminating(base + 5),
+            ];
+            for (offset, descriptor) in descriptors.iter().enumerate() {
+                out.write_at(base + offset as u64, descriptor)?;
+            }
+        }
+
+        let files = self.entries.iter().filter(|entry| !entry.is_dir()).count() as u32;
+        let directories = self.entries.iter().filter(|entry| entry.is_dir()).count() as u32;
+        out.write_at(
+            INTEGRITY_SECTOR,
+            &udf_integrity(INTEGRITY_SECTOR, &recorded, partition_length, files, directories, self.next_unique_id()),
+        )?;
+        out.write_at(INTEGRITY_SECTOR + 1, &udf_terminating(INTEGRITY_SECTOR + 1))?;
+
+        for anchor in [ANCHOR_SECTOR, layout.last_anchor] {
+            out.write_at(anchor, &udf_anchor(anchor))?;
+        }
+
+        let root_icb = self.entries[0].udf_entry - PARTITION_START;
+        out.write_at(PARTITION_START, &udf_file_set(options, &recorded, root_icb as u32))?;
+        out.write_at(PARTITION_START + 1, &udf_terminating(1))?;
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            out.write_at(entry.udf_entry, &self.udf_file_entry(index))?;
+            if entry.is_dir() {
+                out.write_at(entry.udf_directory.0, &self.udf_directory_stream(index))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unique_id(&self, index: usize) -> u64 {
+        // Unique IDs 1-15 are reserved; the root directory uses 0
+        if index == 0 { 0 } else { index as u64 + 15 }
+    }
+
+    fn next_unique_id(&self) -> u64 {
+        self.entries.len() as u64 + 16
+    }
+
+    fn udf_file_entry(&self, index: usize) -> Vec<u8> {
+        let entry = &self.entries[index];
+        let mut descriptor = vec![0u8; SECTOR_SIZE];
+
+        // ICB tag: strategy 4, short allocation descriptors
+        descriptor[20..22].copy_from_slice(&4u16.to_le_bytes());
+        descriptor[24..26].copy_from_slice(&1u16.to_le_bytes());
+        descriptor[27] = if entry.is_dir() { 4 } else { 5 };
+
+        descriptor[36..40].copy_from_slice(&u32::MAX.to_le_bytes());
+        descriptor[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+        let permissions: u32 = if entry.is_dir() { 0x14A5 } else { 0x1084 };
+        descriptor[44..48].copy_from_slice(&permissions.to_le_bytes());
+
+        let link_count = if entry.is_dir() {
+            1 + entry.children().iter().filter(|&&child| self.entries[child].is_dir()).count() as u16
+        } else {
+            1
+        };
+        descriptor[48..50].copy_from_slice(&link_count.to_le_bytes());
+
+        let (start, length) = if entry.is_dir() {
+            entry.udf_directory
+        } else {
+            (entry.data, entry.size())
+        };
+        descriptor[56..64].copy_from_slice(&length.to_le_bytes());
+        descriptor[64..72].copy_from_slice(&sectors_for(length).to_le_bytes());
+
+        for offset in [72, 84, 96] {
+            put_udf_timestamp(&mut descriptor[offset..offset + 12], &entry.modified);
+        }
+        descriptor[108..112].copy_from_slice(&1u32.to_le_bytes());
+        put_regid(&mut descriptor[128..160], IMPLEMENTATION_ID, &[]);
+        descriptor[160..168].copy_from_slice(&self.unique_id(index).to_le_bytes());
+
+        let mut allocation = Vec::new();
+        let mut remaining = length;
+        let mut lbn = start.saturating_sub(PARTITION_START);
+        while remaining > 0 {
+            let extent = remaining.min(MAX_UDF_EXTENT);
+            allocation.extend_from_slice(&(extent as u32).to_le_bytes());
+            allocation.extend_from_slice(&(lbn as u32).to_le_bytes());
+            remaining -= extent;
+            lbn += sectors_for(extent);
+        }
+        descriptor[172..176].copy_from_slice(&(allocation.len() as u32).to_le_bytes());
+        descriptor[176..176 + allocation.len()].copy_from_slice(&allocation);
+
+        let length = 176 + allocation.len();
+        finish_udf_tag(&mut descriptor, TAG_FILE_ENTRY, entry.udf_entry - PARTITION_START, length);
+        descriptor
+    }
+
+    fn udf_directory_stream(&self, index: usize) -> Vec<u8> {
+        let entry = &self.entries[index];
+        let mut stream = Vec::new();
+        let base_lbn = entry.udf_directory.0.saturating_sub(PARTITION_START);
+
+        let parent = &self.entries[entry.parent];
+        append_file_identifier(&mut stream, base_lbn, 0x0A, parent.udf_entry.saturating_sub(PARTITION_START), &[]);
+
+        for &child in entry.children() {
+            let child_entry = &self.entries[child];
+            let characteristics = if child_entry.is_dir() { 0x02 } else { 0x00 };
+            append_file_identifier(
+                &mut stream,
+                base_lbn,
+                characteristics,
+                child_entry.udf_entry.saturating_sub(PARTITION_START),
+                &udf_name(&child_entry.name),
+            );
+        }
+
+        stream
+    }
+}
+
+impl Entry {
+    fn new(name: String, parent: usize, metadata: &std::fs::Metadata, kind: EntryKind) -> Self {
+        let modified = metadata
+            .modified()
+            .map(DateTime::from_system_time)
+            .unwrap_or_else(|_| DateTime::from_system_time(std::time::UNIX_EPOCH));
+
+        Self {
+            name,
+            parent,
+            modified,
+            kind,
+            iso_name: String::new(),
+            joliet_name: Vec::new(),
+            udf_entry: 0,
+            udf_directory: (0, 0),
+            iso_directory: (0, 0),
+            joliet_directory: (0, 0),
+            data: 0,
+        }
+    }
+}
+
+fn iso_name(entry: &Entry) -> String {
+    let sanitize = |value: &str| -> String {
+        value
+            .chars()
+            .map(|c| c.to_ascii_uppercase())
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect()
+    };
+
+    if entry.is_dir() {
+        let mut name = sanitize(&entry.name);
+        name.truncate(ISO_DIR_NAME_LIMIT);
+        return name;
+    }
+
+    let (stem, extension) = match entry.name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (sanitize(stem), sanitize(extension)),
+        _ => (sanitize(&entry.name), String::new()),
+    };
+    let mut extension = extension;
+    extension.truncate(ISO_FILE_NAME_LIMIT / 2);
+    let mut stem = stem;
+    stem.truncate(ISO_FILE_NAME_LIMIT - 1 - extension.len());
+    format!("{}.{}", stem, extension)
+}
+
+fn joliet_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if matches!(c, '*' | '/' | ':' | ';' | '?' | '\\') { '_' } else { c })
+        .take(JOLIET_NAME_LIMIT)
+        .collect()
+}
+
+/// Resolve identifier collisions by replacing the tail of the stem with a counter
+fn unique_name(candidate: String, used: &mut HashSet<String>) -> String {
+    if used.insert(candidate.to_uppercase()) {
+        return candidate;
+    }
+
+    let (stem, extension) = match candidate.rsplit_once('.') {
+        Some((stem, extension)) => (stem.to_string(), format!(".{}", extension)),
+        None => (candidate.clone(), String::new()),
+    };
+
+    for counter in 1.. {
+        let suffix = format!("_{}", counter);
+        let keep = stem.chars().count().saturating_sub(suffix.len()).max(1);
+        let name = format!("{}{}{}", stem.chars().take(keep).collect::<String>(), suffix, extension);
+        if used.insert(name.to_uppercase()) {
+            return name;
+        }
+    }
+
+    unreachable!("counter space exhausted")
+}
+
+fn joliet_sort_key(name: &[u16]) -> Vec<u16> {
+    name.to_vec()
+}
+
+fn iso_volume_id(value: &str) -> String {
+    let mut id: String = value
+        .chars()
+        .map(|c| c.to_ascii_uppercase())
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    id.truncate(32);
+    id
+}
+
+fn encode_dir_record(record: &DirRecord) -> Vec<u8> {
+    let length = 33 + record.identifier.len() + (record.identifier.len() + 1) % 2;
+    let mut bytes = vec![0u8; length];
+    bytes[0] = length as u8;
+    put_both_u32(&mut bytes[2..10], record.extent);
+    put_both_u32(&mut bytes[10..18], record.size);
+    bytes[18] = (record.modified.year - 1900).clamp(0, 255) as u8;
+    bytes[19] = record.modified.month;
+    bytes[20] = record.modified.day;
+    bytes[21] = record.modified.hour;
+    bytes[22] = record.modified.minute;
+    bytes[23] = record.modified.second;
+    bytes[25] = record.flags;
+    put_both_u16(&mut bytes[28..32], 1);
+    bytes[32] = record.identifier.len() as u8;
+    bytes[33..33 + record.identifier.len()].copy_from_slice(&record.identifier);
+    bytes
+}
+
+/// Directory records may not straddle sector boundaries
+fn records_extent(records: &[DirRecord]) -> Vec<u8> {
+    let mut extent = Vec::new();
+    for record in records {
+        let bytes = encode_dir_record(record);
+        if extent.len() % SECTOR_SIZE + bytes.len() > SECTOR_SIZE {
+            extent.resize(extent.len().next_multiple_of(SECTOR_SIZE), 0);
+        }
+        extent.extend_from_slice(&bytes);
+    }
+    extent.resize(extent.len().next_multiple_of(SECTOR_SIZE), 0);
+    extent
+}
+
+fn records_extent_length(records: &[DirRecord]) -> u64 {
+    records_extent(records).len() as u64
+}
+
+fn volume_date(date: &DateTime) -> [u8; 17] {
+    let mut bytes = [0u8; 17];
+    let text = format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}00",
+        date.year.clamp(0, 9999),
+        date.month,
+        date.day,
+        date.hour,
+        date.minute,
+        date.second
+    );
+    bytes[..16].copy_from_slice(text.as_bytes());
+    bytes
+}
+
+fn unset_volume_date() -> [u8; 17] {
+    let mut bytes = [b'0'; 17];
+    bytes[16] = 0;
+    bytes
+}
+
+fn put_padded(field: &mut [u8], value: &str) {
+    field.fill(b' ');
+    let bytes = value.as_bytes();
+    let length = bytes.len().min(field.len());
+    field[..length].copy_from_slice(&bytes[..length]);
+}
+
+fn put_ucs2_padded(field: &mut [u8], value: &str) {
+    for pair in field.chunks_exact_mut(2) {
+        pair.copy_from_slice(&[0x00, 0x20]);
+    }
+    for (pair, unit) in field.chunks_exact_mut(2).zip(value.encode_utf16()) {
+        pair.copy_from_slice(&unit.to_be_bytes());
+    }
+}
+
+fn put_both_u16(field: &mut [u8], value: u16) {
+    field[..2].copy_from_slice(&value.to_le_bytes());
+    field[2..4].copy_from_slice(&value.to_be_bytes());
+}
+
+fn put_both_u32(field: &mut [u8], value: u32) {
+    field[..4].copy_from_slice(&value.to_le_bytes());
+    field[4..8].copy_from_slice(&value.to_be_bytes());
+}
+
+/// OSTA compressed unicode: 8-bit when every character fits, otherwise UCS-2 BE
+fn udf_name(name: &str) -> Vec<u8> {
+    if name.chars().all(|c| (c as u32) < 0x100) {
+        std::iter::once(8).chain(name.chars().map(|c| c as u8)).take(255).collect()
+    } else {
+        std::iter::once(16)
+            .chain(name.encode_utf16().flat_map(|unit| unit.to_be_bytes()))
+            .take(255)
+            .collect()
+    }
+}
+
+fn put_dstring(field: &mut [u8], value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    let mut encoded = udf_name(value);
+    encoded.truncate(field.len() - 1);
+    field[..encoded.len()].copy_from_slice(&encoded);
+    let last = field.len() - 1;
+    field[last] = encoded.len() as u8;
+}
+
+fn put_charspec(field: &mut [u8]) {
+    field[0] = 0;
+    field[1..24].copy_from_slice(b"OSTA Compressed Unicode");
+}
+
+fn put_regid(field: &mut [u8], identifier: &str, suffix: &[u8]) {
+    field[1..1 + identifier.len()].copy_from_slice(identifier.as_bytes());
+    field[24..24 + suffix.len()].copy_from_slice(suffix);
+}
+
+fn udf_revision_suffix() -> [u8; 2] {
+    UDF_REVISION.to_le_bytes()
+}
+
+fn put_udf_timestamp(field: &mut [u8], date: &DateTime) {
+    // Type 1 (local time) with a zero UTC offset
+    field[0..2].copy_from_slice(&0x1000u16.to_le_bytes());
+    field[2..4].copy_from_slice(&(date.year as i16).to_le_bytes());
+    field[4] = date.month;
+    field[5] = date.day;
+    field[6] = date.hour;
+    field[7] = date.minute;
+    field[8] = date.second;
+}
+
+fn finish_udf_tag(descriptor: &mut [u8], tag_id: u16, location: u64, length: usize) {
+    descriptor[0..2].copy_from_slice(&tag_id.to_le_bytes());
+    descriptor[2..4].copy_from_slice(&2u16.to_le_bytes());
+    descriptor[6..8].copy_from_slice(&1u16.to_le_bytes());
+    let crc = udf_crc(&descriptor[16..length]);
+    descriptor[8..10].copy_from_slice(&crc.to_le_bytes());
+    descriptor[10..12].copy_from_slice(&((length - 16) as u16).to_le_bytes());
+    descriptor[12..16].copy_from_slice(&(location as u32).to_le_bytes());
+    descriptor[4] = udf_tag_checksum(descriptor);
+}
+
+fn append_file_identifier(stream: &mut Vec<u8>, base_lbn: u64, characteristics: u8, icb_lbn: u64, name: &[u8]) {
+    let length = (38 + name.len()).next_multiple_of(4);
+    let mut descriptor = vec![0u8; length];
+    descriptor[16..18].copy_from_slice(&1u16.to_le_bytes());
+    descriptor[18] = characteristics;
+    descriptor[19] = name.len() as u8;
+    descriptor[20..24].copy_from_slice(&(SECTOR_SIZE as u32).to_le_bytes());
+    descriptor[24..28].copy_from_slice(&(icb_lbn as u32).to_le_bytes());
+    descriptor[38..38 + name.len()].copy_from_slice(name);
+
+    let location = base_lbn + (stream.len() / SECTOR_SIZE) as u64;
+    finish_udf_tag(&mut descriptor, TAG_FILE_IDENTIFIER, location, length);
+    stream.extend_from_slice(&descriptor);
+}
+
+fn udf_primary_volume(sector: u64, options: &IsoOptions, recorded: &DateTime) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    put_dstring(&mut descriptor[24..56], &options.volume_id);
+    descriptor[56..58].copy_from_slice(&1u16.to_le_bytes());
+    descriptor[58..60].copy_from_slice(&1u16.to_le_bytes());
+    descriptor[60..62].copy_from_slice(&2u16.to_le_bytes());
+    descriptor[62..64].copy_from_slice(&2u16.to_le_bytes());
+    descriptor[64..68].copy_from_slice(&1u32.to_le_bytes());
+    descriptor[68..72].copy_from_slice(&1u32.to_le_bytes());
+    put_dstring(&mut descriptor[72..200], &format!("{:016X}{}", recorded_serial(recorded), options.volume_id));
+    put_charspec(&mut descriptor[200..264]);
+    put_charspec(&mut descriptor[264..328]);
+    put_regid(&mut descriptor[344..376], IMPLEMENTATION_ID, &[]);
+    put_udf_timestamp(&mut descriptor[376..388], recorded);
+    put_regid(&mut descriptor[388..420], IMPLEMENTATION_ID, &[]);
+    finish_udf_tag(&mut descriptor, TAG_PRIMARY_VOLUME, sector, 512);
+    descriptor
+}
+
+fn recorded_serial(recorded: &DateTime) -> u64 {
+    recorded
+        .to_system_time()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn udf_implementation_use(sector: u64, options: &IsoOptions) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    descriptor[16..20].copy_from_slice(&1u32.to_le_bytes());
+    put_regid(&mut descriptor[20..52], "*UDF LV Info", &udf_revision_suffix());
+    put_charspec(&mut descriptor[52..116]);
+    put_dstring(&mut descriptor[116..244], &options.volume_id);
+    put_regid(&mut descriptor[352..384], IMPLEMENTATION_ID, &[]);
+    finish_udf_tag(&mut descriptor, TAG_IMPLEMENTATION_USE, sector, 512);
+    descriptor
+}
+
+fn udf_partition(sector: u64, partition_length: u32) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    descriptor[16..20].copy_from_slice(&2u32.to_le_bytes());
+    descriptor[20..22].copy_from_slice(&1u16.to_le_bytes());
+    put_regid(&mut descriptor[24..56], "+NSR02", &[]);
+    descriptor[184..188].copy_from_slice(&1u32.to_le_bytes()); // read-only
+    descriptor[188..192].copy_from_slice(&(PARTITION_START as u32).to_le_bytes());
+    descriptor[192..196].copy_from_slice(&partition_length.to_le_bytes());
+    put_regid(&mut descriptor[196..228], IMPLEMENTATION_ID, &[]);
+    finish_udf_tag(&mut descriptor, TAG_PARTITION, sector, 512);
+    descriptor
+}
+
+fn udf_logical_volume(sector: u64, options: &IsoOptions) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    descriptor[16..20].copy_from_slice(&3u32.to_le_bytes());
+    put_charspec(&mut descriptor[20..84]);
+    put_dstring(&mut descriptor[84..212], &options.volume_id);
+    descriptor[212..216].copy_from_slice(&(SECTOR_SIZE as u32).to_le_bytes());
+    let mut domain_suffix = [0u8; 3];
+    domain_suffix[..2].copy_from_slice(&udf_revision_suffix());
+    put_regid(&mut descriptor[216..248], UDF_DOMAIN, &domain_suffix);
+    // File Set Descriptor location (long_ad at partition block 0)
+    descriptor[248..252].copy_from_slice(&(SECTOR_SIZE as u32).to_le_bytes());
+    descriptor[264..268].copy_from_slice(&6u32.to_le_bytes());
+    descriptor[268..272].copy_from_slice(&1u32.to_le_bytes());
+    put_regid(&mut descriptor[272..304], IMPLEMENTATION_ID, &[]);
+    descriptor[432..436].copy_from_slice(&(2 * SECTOR_SIZE as u32).to_le_bytes());
+    descriptor[436..440].copy_from_slice(&(INTEGRITY_SECTOR as u32).to_le_bytes());
+    // Type 1 partition map for partition 0 on volume 1
+    descriptor[440] = 1;
+    descriptor[441] = 6;
+    descriptor[442..444].copy_from_slice(&1u16.to_le_bytes());
+    finish_udf_tag(&mut descriptor, TAG_LOGICAL_VOLUME, sector, 446);
+    descriptor
+}
+
+fn udf_unallocated_space(sector: u64) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    descriptor[16..20].copy_from_slice(&4u32.to_le_bytes());
+    finish_udf_tag(&mut descriptor, TAG_UNALLOCATED_SPACE, sector, 24);
+    descriptor
+}
+
+fn udf_terminating(location: u64) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    finish_udf_tag(&mut descriptor, TAG_TERMINATING, location, 512);
+    descriptor
+}
+
+fn udf_integrity(
+    sector: u64,
+    recorded: &DateTime,
+    partition_length: u32,
+    files: u32,
+    directories: u32,
+    next_unique_id: u64,
+) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    put_udf_timestamp(&mut descriptor[16..28], recorded);
+    descriptor[28..32].copy_from_slice(&1u32.to_le_bytes()); // closed
+    descriptor[40..48].copy_from_slice(&next_unique_id.to_le_bytes());
+    descriptor[72..76].copy_from_slice(&1u32.to_le_bytes());
+    descriptor[76..80].copy_from_slice(&46u32.to_le_bytes());
+    descriptor[84..88].copy_from_slice(&partition_length.to_le_bytes());
+    put_regid(&mut descriptor[88..120], IMPLEMENTATION_ID, &[]);
+    descriptor[120..124].copy_from_slice(&files.to_le_bytes());
+    descriptor[124..128].copy_from_slice(&directories.to_le_bytes());
+    for offset in [128, 130, 132] {
+        descriptor[offset..offset + 2].copy_from_slice(&UDF_REVISION.to_le_bytes());
+    }
+    finish_udf_tag(&mut descriptor, TAG_INTEGRITY, sector, 134);
+    descriptor
+}
+
+fn udf_anchor(sector: u64) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    let vds_length = VDS_LENGTH_SECTORS * SECTOR_SIZE as u32;
+    descriptor[16..20].copy_from_slice(&vds_length.to_le_bytes());
+    descriptor[20..24].copy_from_slice(&(MAIN_VDS_SECTOR as u32).to_le_bytes());
+    descriptor[24..28].copy_from_slice(&vds_length.to_le_bytes());
+    descriptor[28..32].copy_from_slice(&(RESERVE_VDS_SECTOR as u32).to_le_bytes());
+    finish_udf_tag(&mut descriptor, TAG_ANCHOR, sector, 512);
+    descriptor
+}
+
+fn udf_file_set(options: &IsoOptions, recorded: &DateTime, root_lbn: u32) -> Vec<u8> {
+    let mut descriptor = vec![0u8; SECTOR_SIZE];
+    put_udf_timestamp(&mut descriptor[16..28], recorded);
+    descriptor[28..30].copy_from_slice(&3u16.to_le_bytes());
+    descriptor[30..32].copy_from_slice(&3u16.to_le_bytes());
+    descriptor[32..36].copy_from_slice(&1u32.to_le_bytes());
+    descriptor[36..40].copy_from_slice(&1u32.to_le_bytes());
+    put_charspec(&mut descriptor[48..112]);
+    put_dstring(&mut descriptor[112..240], &options.volume_id);
+    put_charspec(&mut descriptor[240..304]);
+    put_dstring(&mut descriptor[304..336], &options.volume_id);
+    descriptor[400..404].copy_from_slice(&(SECTOR_SIZE as u32).to_le_bytes());
+    descriptor[404..408].copy_from_slice(&root_lbn.to_le_bytes());
+    let mut domain_suffix = [0u8; 3];
+    domain_suffix[..2].copy_from_slice(&udf_revision_suffix());
+    put_regid(&mut descriptor[416..448], UDF_DOMAIN, &domain_suffix);
+    finish_udf_tag(&mut descriptor, TAG_FILE_SET, 0, 512);
+    descriptor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IsoOptions, IsoWriter, MAX_ISO_EXTENT, unique_name};
+    use crate::iso::{ANCHOR_SECTOR, SECTOR_SIZE, udf_crc, udf_tag_checksum};
+    use std::collections::HashSet;
+    use std::io::{Read, Seek, SeekFrom};
+    use tempfile::tempdir;
+
+    fn read_sector(file: &mut std::fs::File, sector: u64) -> Vec<u8> {
+        let mut buffer = vec![0u8; SECTOR_SIZE];
+        file.seek(SeekFrom::Start(sector * SECTOR_SIZE as u64)).unwrap();
+        file.read_exact(&mut buffer).unwrap();
+        buffer
+    }
+
+    fn windows_media_tree(root: &std::path::Path) {
+        std::fs::create_dir_all(root.join("boot")).unwrap();
+        std::fs::create_dir_all(root.join("efi/microsoft/boot")).unwrap();
+        std::fs::create_dir_all(root.join("sources")).unwrap();
+        std::fs::write(root.join("bootmgr"), b"bootmgr").unwrap();
+        std::fs::write(root.join("boot/etfsboot.com"), vec![0xEB; 2048]).unwrap();
+        std::fs::write(root.join("boot/bcd"), b"bcd").unwrap();
+        std::fs::write(root.join("efi/microsoft/boot/efisys.bin"), vec![0xEF; 1_474_560]).unwrap();
+        std::fs::write(root.join("sources/boot.wim"), b"MSWIM\0\0\0").unwrap();
+    }
+
+    #[test]
+    fn writes_iso9660_joliet_and_udf_descriptors() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        windows_media_tree(&media);
+        let iso_path = temp.path().join("out.iso");
+
+        let summary = IsoWriter::new(IsoOptions::windows_media("GhostWin"))
+            .write(&media, &iso_path)
+            .unwrap();
+        assert_eq!(summary.files, 5);
+        assert_eq!(summary.directories, 6);
+
+        let mut file = std::fs::File::open(&iso_path).unwrap();
+        assert_eq!(file.metadata().unwrap().len(), summary.total_sectors * SECTOR_SIZE as u64);
+
+        let primary = read_sector(&mut file, 16);
+        assert_eq!(primary[0], 1);
+        assert_eq!(&primary[1..6], b"CD001");
+        assert_eq!(&primary[40..48], b"GHOSTWIN");
+        assert_eq!(u32::from_le_bytes(primary[80..84].try_into().unwrap()) as u64, summary.total_sectors);
+
+        let boot_record = read_sector(&mut file, 17);
+        assert_eq!(boot_record[0], 0);
+        assert_eq!(&boot_record[7..30], b"EL TORITO SPECIFICATION");
+        let catalog_sector = u32::from_le_bytes(boot_record[71..75].try_into().unwrap());
+        assert_eq!(Some(catalog_sector), summary.boot_catalog_sector);
+
+        let joliet = read_sector(&mut file, 18);
+        assert_eq!(joliet[0], 2);
+        assert_eq!(&joliet[88..91], b"%/E");
+
+        assert_eq!(read_sector(&mut file, 19)[0], 255);
+        assert_eq!(&read_sector(&mut file, 20)[1..6], b"BEA01");
+        assert_eq!(&read_sector(&mut file, 21)[1..6], b"NSR02");
+        assert_eq!(&read_sector(&mut file, 22)[1..6], b"TEA01");
+
+        for anchor in [ANCHOR_SECTOR, summary.total_sectors - 1] {
+            let descriptor = read_sector(&mut file, anchor);
+            assert_eq!(u16::from_le_bytes([descriptor[0], descriptor[1]]), 2);
+            assert_eq!(descriptor[4], udf_tag_checksum(&descriptor));
+            let crc_length = u16::from_le_bytes([descriptor[10], descriptor[11]]) as usize;
+            assert_eq!(u16::from_le_bytes([descriptor[8], descriptor[9]]), udf_crc(&descriptor[16..16 + crc_length]));
+        }
+    }
+
+    #[test]
+    fn writes_dual_el_torito_boot_catalog() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        windows_media_tree(&media);
+        let iso_path = temp.path().join("out.iso");
+
+        let summary = IsoWriter::new(IsoOptions::windows_media("GHOSTWIN"))
+            .write(&media, &iso_path)
+            .unwrap();
+
+        let mut file = std::fs::File::open(&iso_path).unwrap();
+        let catalog = read_sector(&mut file, summary.boot_catalog_sector.unwrap() as u64);
+
+        let word_sum = catalog[..32]
+            .chunks_exact(2)
+            .fold(0u16, |sum, word| sum.wrapping_add(u16::from_le_bytes([word[0], word[1]])));
+        assert_eq!(catalog[0], 0x01);
+        assert_eq!(word_sum, 0);
+        assert_eq!(&catalog[30..32], &[0x55, 0xAA]);
+
+        assert_eq!(catalog[32], 0x88);
+        assert_eq!(u16::from_le_bytes([catalog[38], catalog[39]]), 4);
+        let bios_lba = u32::from_le_bytes(catalog[40..44].try_into().unwrap());
+        assert_eq!(read_sector(&mut file, bios_lba as u64), vec![0xEB; SECTOR_SIZE]);
+
+        assert_eq!(catalog[64], 0x91);
+        assert_eq!(catalog[65], 0xEF);
+        assert_eq!(catalog[96], 0x88);
+        assert_eq!(u16::from_le_bytes([catalog[102], catalog[103]]), 2880);
+        let uefi_lba = u32::from_le_bytes(catalog[104..108].try_into().unwrap());
+        assert_eq!(read_sector(&mut file, uefi_lba as u64), vec![0xEF; SECTOR_SIZE]);
+    }
+
+    #[test]
+    fn rejects_media_without_boot_images() {
+        let temp = tempdir().unwrap();
+        let media = temp.path().join("media");
+        std::fs::create_dir_all(&media).unwrap();
+
+        let error = IsoWriter::new(IsoOptions::windows_media("GHOSTWIN"))
+            .write(&media, &temp.path().join("out.iso"))
+            .unwrap_err();
+        assert!(error.to_string().contains("BIOS boot image not found"));
+    }
+
+    #[test]
+    fn makes_colliding_names_unique() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_name("SETUP.EXE".to_string(), &mut used), "SETUP.EXE");
+        assert_eq!(unique_name("SETUP.EXE".to_string(), &mut used), "SET_1.EXE");
+        assert_eq!(unique_name("setup.exe".to_string(), &mut used), "set_2.exe");
+    }
+
+    #[test]
+    fn iso_extent_limit_is_sector_aligned() {
+        assert_eq!(MAX_ISO_EXTENT % SECTOR_SIZE as u64, 0);
+    }
+}
@@ -1,7 +1,6 @@
 use clap::{Parser, Subcommand};
 use anyhow::Result;
 use tracing::info;
-use tracing_subscriber;
 
 mod cli;
 mod wim;
@@ -11,6 +10,7 @@ mod utils;
 mod vnc;
 mod executor;
 mod drivers;
+mod iso;
 
 use cli::*;
 
@@ -40,6 +40,14 @@ enum Commands {
     Logon(LogonArgs),
     /// Run system setup tasks (before user logon)
     SystemSetup(SystemSetupArgs),
+    /// Load staged drivers matching the present devices (WinPE)
+    LoadDrivers(LoadDriversArgs),
+    /// Inspect WIM images
+    Wim(WimArgs),
+    /// Manage the extracted source ISO cache
+    Cache(CacheArgs),
+    /// Import and manage driver packs
+    Drivers(DriversArgs),
 }
 
 #[tokio::main]
@@ -57,8 +65,10 @@ async fn run_cli(cli: Cli) -> Result<()> {
         tracing::Level::INFO
     };
     
+    // Logs go to stderr so command output (e.g. `build --plan json`) stays parseable
     let _ = tracing_subscriber::fmt()
         .with_max_level(log_level)
+        .with_writer(std::io::stderr)
         .try_init();
 
     info!("GhostWin v{} starting", env!("CARGO_PKG_VERSION"));
@@ -88,6 +98,19 @@ async fn run_cli(cli: Cli) -> Result<()> {
             info!("Running system setup tasks");
             cli::system_setup::execute(args).await?;
         }
+        Commands::LoadDrivers(args) => {
+            info!("Loading staged drivers");
+            cli::load_drivers::execute(args).await?;
+        }
+        Commands::Wim(args) => {
+            cli::wim::execute(args).await?;
+        }
+        Commands::Cache(args) => {
+            cli::cache::execute(args).await?;
+        }
+        Commands::Drivers(args) => {
+            cli::drivers::execute(args).await?;
+        }
     }
 
     Ok(())
@@ -126,6 +149,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_build_plan_format() {
+        let base = ["ghostwin", "build", "-s", "src.iso", "-d", "out", "-o", "final.iso"];
+        let plan_format = |extra: &[&str]| match Cli::try_parse_from(base.iter().chain(extra)).unwrap().command {
+            Commands::Build(args) => args.plan,
+            _ => panic!("expected build command"),
+        };
+
+        assert_eq!(plan_format(&[]), None);
+        assert_eq!(plan_format(&["--plan"]), Some(crate::cli::PlanFormat::Text));
+        assert_eq!(plan_format(&["--plan", "json"]), Some(crate::cli::PlanFormat::Json));
+    }
+
     #[test]
     fn parses_logon_dry_run_flag() {
         let cli = Cli::try_parse_from(["ghostwin", "logon", "--dry-run"]).unwrap();
@@ -152,6 +188,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_wim_info_path() {
+        let cli = Cli::try_parse_from(["ghostwin", "wim", "info", "sources/boot.wim"]).unwrap();
+
+        match cli.command {
+            Commands::Wim(args) => match args.command {
+                crate::cli::WimCommand::Info { path } => assert_eq!(path, "sources/boot.wim"),
+            },
+            _ => panic!("expected wim command"),
+        }
+    }
+
+    #[test]
+    fn parses_cache_prune_override() {
+        let cli = Cli::try_parse_from(["ghostwin", "cache", "prune", "--max-size-gb", "5"]).unwrap();
+
+        match cli.command {
+            Commands::Cache(args) => match args.command {
+                crate::cli::CacheCommand::Prune { max_size_gb } => assert_eq!(max_size_gb, Some(5)),
+                _ => panic!("expected cache prune"),
+            },
+            _ => panic!("expected cache command"),
+        }
+    }
+
+    #[test]
+    fn parses_drivers_import() {
+        let cli = Cli::try_parse_from([
+            "ghostwin", "drivers", "import", "--catalog", "DriverPackCatalog.xml", "--model", "OptiPlex 7010",
+            "--model", "0B1E", "--os", "win11", "--dry-run",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Drivers(args) => match args.command {
+                crate::cli::DriversCommand::Import(import) => {
+                    assert_eq!(import.model, vec!["OptiPlex 7010", "0B1E"]);
+                    assert_eq!(import.os.as_deref(), Some("win11"));
+                    assert_eq!(import.downloads, ".");
+                    assert!(import.dry_run);
+                }
+                _ => panic!("expected drivers import"),
+            },
+            _ => panic!("expected drivers command"),
+        }
+    }
+
+    #[test]
+    fn parses_drivers_check_with_path_override() {
+        let cli = Cli::try_parse_from([
+            "ghostwin", "drivers", "check", "--path", "drivers", "--path", "vendor", "--format", "json",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Drivers(args) => match args.command {
+                crate::cli::DriversCommand::Check { source, format } => {
+                    assert_eq!(source.paths, vec!["drivers", "vendor"]);
+                    assert_eq!(format, crate::cli::OutputFormat::Json);
+                }
+                _ => panic!("expected drivers check"),
+            },
+            _ => panic!("expected drivers command"),
+        }
+    }
+
+    #[test]
+    fn parses_load_drivers_preview() {
+        let cli = Cli::try_parse_from([
+            "ghostwin", "load-drivers", "--dry-run", "--index", "index.json", "--devices", "optiplex.json",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::LoadDrivers(args) => {
+                assert!(args.dry_run);
+                assert_eq!(args.index.as_deref(), Some("index.json"));
+                assert_eq!(args.devices.as_deref(), Some("optiplex.json"));
+                assert_eq!(args.format, crate::cli::OutputFormat::Table);
+            }
+            _ => panic!("expected load-drivers command"),
+        }
+    }
+
     #[tokio::test]
     async fn run_cli_dispatches_logon_guardrail_error() {
         let cli = Cli::try_parse_from(["ghostwin", "logon"]).unwrap();
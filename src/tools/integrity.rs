@@ -0,0 +1,313 @@
+use anyhow::{Context, Result, anyhow, bail};
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+use crate::cli::SecurityConfig;
+use crate::utils::sha256_file;
+use super::DetectedTool;
+
+/// Signed hash list the build writes at the root of `Helper/`
+pub const HASH_MANIFEST_FILE: &str = "ghostwin-hashes.json";
+
+/// SHA-256 of every file under a folder, signed with Ed25519
+///
+/// The build holds the private key; images carry only the public key, so whoever
+/// can read the media still cannot sign a manifest of their own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashManifest {
+    /// Lower-case `/`-separated path relative to the manifest's folder to lower-case hex SHA-256
+    pub files: BTreeMap<String, String>,
+    /// Hex Ed25519 signature over the `files` entries
+    pub signature: String,
+}
+
+/// How a tool compares against the manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Verified,
+    Modified,
+    /// Not in the manifest, including anything outside its folder
+    Unlisted,
+}
+
+impl HashManifest {
+    /// Hash everything under `root` and sign the list with `key`
+    pub fn generate(root: &Path, key: &SigningKey) -> Result<Self> {
+        let root = canonical(root);
+        let mut files = BTreeMap::new();
+        for entry in WalkDir::new(&root).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(relative) = relative_key(&root, entry.path()) else {
+                continue;
+            };
+            if relative == HASH_MANIFEST_FILE {
+                continue;
+            }
+            files.insert(relative, sha256_file(entry.path())?);
+        }
+        let signature = encode_hex(&key.sign(&signed_bytes(&files)).to_bytes());
+        Ok(Self { files, signature })
+    }
+
+    /// Read a manifest, rejecting it unless `public_key` verifies its signature
+    pub fn load(path: &Path, public_key: &VerifyingKey) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tool manifest {}", path.display()))?;
+        let manifest: Self =
+            serde_json::from_str(&content).with_context(|| format!("Invalid tool manifest {}", path.display()))?;
+        let signature = decode_hex(&manifest.signature)
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| anyhow!("Tool manifest {} has a malformed signature", path.display()))?;
+        public_key
+            .verify(&signed_bytes(&manifest.files), &signature)
+            .map_err(|_| anyhow!("Tool manifest {} is not signed by the configured public key", path.display()))?;
+        Ok(manifest)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write tool manifest {}", path.display()))
+    }
+
+    /// Compare `file` with its entry, `root` being the manifest's folder
+    pub fn verify(&self, root: &Path, file: &Path) -> Result<Verification> {
+        let Some(expected) = relative_key(&canonical(root), file).and_then(|relative| self.files.get(&relative)) else {
+            return Ok(Verification::Unlisted);
+        };
+        Ok(if sha256_file(file)? == *expected {
+            Verification::Verified
+        } else {
+            Verification::Modified
+        })
+    }
+}
+
+/// Checks tools against the signed manifest before they run
+pub struct ToolVerifier {
+    enforce: bool,
+    state: VerifierState,
+}
+
+enum VerifierState {
+    /// No public key configured and nothing enforced
+    Off,
+    Loaded { manifest: HashManifest, root: PathBuf },
+    Unavailable(String),
+}
+
+impl ToolVerifier {
+    pub fn from_config(security: &SecurityConfig) -> Self {
+        let state = match &security.tool_manifest_public_key {
+            None if !security.enforce_tool_manifest => VerifierState::Off,
+            None => VerifierState::Unavailable("no security.tool_manifest_public_key is configured".to_string()),
+            Some(public_key) => {
+                let path = security
+                    .tool_manifest_path
+                    .as_ref()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(default_manifest_path);
+                match parse_public_key(public_key).and_then(|public_key| HashManifest::load(&path, &public_key)) {
+                    Ok(manifest) => {
+                        info!("🔏 Verifying tools against {} ({} files)", path.display(), manifest.files.len());
+                        VerifierState::Loaded {
+                            root: path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+                            manifest,
+                        }
+                    }
+                    Err(e) => {
+                        warn!("⚠️ {:#}", e);
+                        VerifierState::Unavailable(format!("{:#}", e))
+                    }
+                }
+            }
+        };
+        Self {
+            enforce: security.enforce_tool_manifest,
+            state,
+        }
+    }
+
+    /// Refuse an unverified tool when enforcing, otherwise only warn
+    pub fn check(&self, tool: &DetectedTool) -> Result<()> {
+        let problem = match &self.state {
+            VerifierState::Off => return Ok(()),
+            VerifierState::Unavailable(reason) => format!("the tool manifest is unavailable: {}", reason),
+            VerifierState::Loaded { manifest, root } => match manifest.verify(root, &tool.path) {
+                Ok(Verification::Verified) => return Ok(()),
+                Ok(Verification::Modified) => "its SHA-256 does not match the signed tool manifest".to_string(),
+                Ok(Verification::Unlisted) => "it is not listed in the signed tool manifest".to_string(),
+                Err(e) => format!("it could not be hashed: {:#}", e),
+            },
+        };
+        if self.enforce {
+            bail!("🛡️ Refusing to run {}: {}", tool.path.display(), problem);
+        }
+        warn!("⚠️ Running unverified tool {}: {}", tool.path.display(), problem);
+        Ok(())
+    }
+}
+
+/// `%SystemDrive%\Helper\ghostwin-hashes.json`, which is `X:` in WinPE
+pub fn default_manifest_path() -> PathBuf {
+    let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "X:".to_string());
+    PathBuf::from(format!("{}\\", drive)).join("Helper").join(HASH_MANIFEST_FILE)
+}
+
+/// Private key from a PKCS#8 PEM file, as written by `openssl genpkey -algorithm ed25519`
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let pem = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read tool manifest signing key {}", path.display()))?;
+    SigningKey::from_pkcs8_pem(&pem).map_err(|e| anyhow!("{} is not an Ed25519 private key: {}", path.display(), e))
+}
+
+/// Public key from its 64-character hex form
+pub fn parse_public_key(text: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = decode_hex(text.trim())
+        .and_then(|bytes| bytes.try_into().ok())
+        .context("security.tool_manifest_public_key must be 64 hex characters")?;
+    VerifyingKey::from_bytes(&bytes).context("security.tool_manifest_public_key is not a valid Ed25519 public key")
+}
+
+/// Hex form of `key`, as `security.tool_manifest_public_key` expects it
+pub fn public_key_hex(key: &VerifyingKey) -> String {
+    encode_hex(key.as_bytes())
+}
+
+fn signed_bytes(files: &BTreeMap<String, String>) -> Vec<u8> {
+    files.iter().flat_map(|(path, hash)| format!("{}\t{}\n", path, hash).into_bytes()).collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(text.get(start..start + 2)?, 16).ok())
+        .collect()
+}
+
+/// Resolves `..` and symlinks so a path cannot escape the manifest folder
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Windows paths are case-insensitive, so entries are matched lower-case
+fn relative_key(root: &Path, file: &Path) -> Option<String> {
+    let file = canonical(file);
+    let relative = file.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_lowercase())
+        .collect();
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{HASH_MANIFEST_FILE, HashManifest, ToolVerifier, Verification, load_signing_key, parse_public_key, public_key_hex};
+    use crate::cli::GhostwinConfig;
+    use crate::tools::{DetectedTool, ToolCategory};
+    use ed25519_dalek::SigningKey;
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// PKCS#8 PEM signing key derived from `seed`; returns the hex public key
+    pub(crate) fn write_signing_key(path: &Path, seed: u8) -> String {
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        std::fs::write(path, key.to_pkcs8_pem(LineEnding::LF).unwrap().as_bytes()).unwrap();
+        public_key_hex(&key.verifying_key())
+    }
+
+    pub(crate) fn tool(path: std::path::PathBuf) -> DetectedTool {
+        DetectedTool {
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            path,
+            category: ToolCategory::PEAutoRun,
+            executable: false,
+            hidden: false,
+            auto_run: true,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn signs_helper_hashes_and_refuses_unverified_tools() {
+        let temp = tempdir().unwrap();
+        let helper = temp.path().join("Helper");
+        std::fs::create_dir_all(helper.join("PEAutoRun")).unwrap();
+        let script = helper.join("PEAutoRun/Setup.ps1");
+        std::fs::write(&script, "Write-Host setup").unwrap();
+        std::fs::write(helper.join("readme.txt"), "docs").unwrap();
+
+        let key_path = temp.path().join("signing.pem");
+        let public_key = write_signing_key(&key_path, 7);
+        let other_public_key = write_signing_key(&temp.path().join("other.pem"), 8);
+        let key = load_signing_key(&key_path).unwrap();
+        assert_eq!(public_key_hex(&key.verifying_key()), public_key);
+
+        let manifest = HashManifest::generate(&helper, &key).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert!(manifest.files.contains_key("peautorun/setup.ps1"));
+        let manifest_path = helper.join(HASH_MANIFEST_FILE);
+        manifest.write(&manifest_path).unwrap();
+        // Regenerating skips the manifest itself
+        assert_eq!(HashManifest::generate(&helper, &key).unwrap(), manifest);
+
+        let verifying_key = parse_public_key(&public_key).unwrap();
+        assert_eq!(HashManifest::load(&manifest_path, &verifying_key).unwrap(), manifest);
+        let error = HashManifest::load(&manifest_path, &parse_public_key(&other_public_key).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("is not signed by the configured public key"));
+        let mut forged = manifest.clone();
+        forged.files.insert("peautorun/evil.ps1".to_string(), "00".repeat(32));
+        forged.write(&temp.path().join("forged.json")).unwrap();
+        assert!(HashManifest::load(&temp.path().join("forged.json"), &verifying_key).is_err());
+        assert!(parse_public_key("abcd").is_err());
+        assert!(load_signing_key(&helper.join("readme.txt")).is_err());
+
+        let usb = temp.path().join("USB/Helper/PEAutoRun");
+        std::fs::create_dir_all(&usb).unwrap();
+        std::fs::write(usb.join("Setup.ps1"), "Write-Host setup").unwrap();
+        assert_eq!(manifest.verify(&helper, &script).unwrap(), Verification::Verified);
+        assert_eq!(manifest.verify(&helper, &usb.join("Setup.ps1")).unwrap(), Verification::Unlisted);
+        assert_eq!(
+            manifest.verify(&helper, &helper.join("PEAutoRun/../../USB/Helper/PEAutoRun/Setup.ps1")).unwrap(),
+            Verification::Unlisted
+        );
+
+        let mut security = GhostwinConfig::default().security;
+        security.tool_manifest_public_key = Some(public_key);
+        security.tool_manifest_path = Some(manifest_path.to_string_lossy().into_owned());
+        security.enforce_tool_manifest = true;
+        let verifier = ToolVerifier::from_config(&security);
+        verifier.check(&tool(script.clone())).unwrap();
+        let error = verifier.check(&tool(usb.join("Setup.ps1"))).unwrap_err();
+        assert!(error.to_string().contains("not listed in the signed tool manifest"));
+
+        std::fs::write(&script, "Write-Host tampered").unwrap();
+        let error = verifier.check(&tool(script.clone())).unwrap_err();
+        assert!(error.to_string().contains("does not match the signed tool manifest"));
+
+        // Without enforcement mismatches only warn
+        security.enforce_tool_manifest = false;
+        ToolVerifier::from_config(&security).check(&tool(script.clone())).unwrap();
+
+        security.enforce_tool_manifest = true;
+        security.tool_manifest_public_key = Some(other_public_key);
+        let error = ToolVerifier::from_config(&security).check(&tool(script)).unwrap_err();
+        assert!(error.to_string().contains("the tool manifest is unavailable"));
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{Result, bail};
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::cli::InterpreterConfig;
+
+/// Replaced by the tool's path inside any `command` element
+pub const PATH_PLACEHOLDER: &str = "{path}";
+/// A `command` element that expands to the sidecar `arguments`
+pub const ARGS_PLACEHOLDER: &str = "{args}";
+
+/// Extension-to-interpreter map shared by tool detection and execution
+#[derive(Debug, Clone)]
+pub struct InterpreterRegistry {
+    interpreters: BTreeMap<String, InterpreterConfig>,
+}
+
+impl InterpreterRegistry {
+    /// Built-in types with `[tools.interpreters]` entries layered on top
+    pub fn new(configured: &BTreeMap<String, InterpreterConfig>) -> Self {
+        let mut interpreters = built_in_interpreters();
+        for (extension, interpreter) in configured {
+            let extension = normalize_extension(extension);
+            if interpreter.command.is_empty() {
+                interpreters.remove(&extension);
+            } else {
+                interpreters.insert(extension, interpreter.clone());
+            }
+        }
+        Self { interpreters }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&InterpreterConfig> {
+        let extension = path.extension()?.to_str()?;
+        self.interpreters.get(&normalize_extension(extension))
+    }
+
+    pub fn is_executable(&self, path: &Path) -> bool {
+        self.get(path).is_some_and(|interpreter| interpreter.executable)
+    }
+
+    pub fn is_script(&self, path: &Path) -> bool {
+        self.get(path).is_some_and(|interpreter| !interpreter.executable)
+    }
+
+    /// Program and arguments that run `path`, with `arguments` spliced in for `{args}`
+    pub fn command_line(&self, path: &Path, arguments: &[String]) -> Result<Vec<String>> {
+        let Some(interpreter) = self.get(path) else {
+            bail!(
+                "Unsupported file type: {}",
+                path.extension().map(|ext| ext.to_string_lossy()).unwrap_or_default()
+            );
+        };
+        let path = path.to_string_lossy();
+        let mut command_line = Vec::new();
+        for (position, element) in interpreter.command.iter().enumerate() {
+            if element == ARGS_PLACEHOLDER {
+                command_line.extend(arguments.iter().cloned());
+            } else if position == 0 && !element.contains(PATH_PLACEHOLDER) {
+                command_line.push(resolve_program(element, &interpreter.search_paths));
+            } else {
+                command_line.push(element.replace(PATH_PLACEHOLDER, &path));
+            }
+        }
+        Ok(command_line)
+    }
+}
+
+/// Configured extensions may be written with a dot or in any case
+fn normalize_extension(extension: &str) -> String {
+    extension.trim().trim_start_matches('.').to_ascii_lowercase()
+}
+
+/// First existing `search_paths` entry, else `program` for a `PATH` lookup
+fn resolve_program(program: &str, search_paths: &[String]) -> String {
+    search_paths
+        .iter()
+        .find(|candidate| Path::new(candidate).is_file())
+        .cloned()
+        .unwrap_or_else(|| program.to_string())
+}
+
+fn built_in_interpreters() -> BTreeMap<String, InterpreterConfig> {
+    let interpreter = |command: &[&str], executable: bool| InterpreterConfig {
+        command: command.iter().map(|element| element.to_string()).collect(),
+        search_paths: Vec::new(),
+        executable,
+    };
+    let cscript = ["cscript", "/nologo", PATH_PLACEHOLDER, ARGS_PLACEHOLDER];
+    let batch = ["cmd", "/c", PATH_PLACEHOLDER, ARGS_PLACEHOLDER];
+    let direct = [PATH_PLACEHOLDER, ARGS_PLACEHOLDER];
+
+    let mut interpreters = BTreeMap::new();
+    interpreters.insert("exe".to_string(), interpreter(&direct, true));
+    interpreters.insert("com".to_string(), interpreter(&direct, true));
+    interpreters.insert("bat".to_string(), interpreter(&batch, true));
+    interpreters.insert("cmd".to_string(), interpreter(&batch, true));
+    interpreters.insert(
+        "ps1".to_string(),
+        interpreter(&["powershell", "-ExecutionPolicy", "Bypass", "-File", PATH_PLACEHOLDER, ARGS_PLACEHOLDER], false),
+    );
+    interpreters.insert("vbs".to_string(), interpreter(&cscript, false));
+    interpreters.insert("js".to_string(), interpreter(&cscript, false));
+    interpreters.insert(
+        "au3".to_string(),
+        InterpreterConfig {
+            search_paths: vec![
+                "C:\\Program Files (x86)\\AutoIt3\\AutoIt3.exe".to_string(),
+                "C:\\Program Files\\AutoIt3\\AutoIt3.exe".to_string(),
+            ],
+            ..interpreter(&["AutoIt3.exe", PATH_PLACEHOLDER, ARGS_PLACEHOLDER], false)
+        },
+    );
+    interpreters.insert("reg".to_string(), interpreter(&["reg", "import", PATH_PLACEHOLDER], false));
+    interpreters.insert(
+        "msi".to_string(),
+        interpreter(&["msiexec", "/i", PATH_PLACEHOLDER, "/qn", "/norestart", ARGS_PLACEHOLDER], false),
+    );
+    interpreters.insert(
+        "msu".to_string(),
+        interpreter(&["wusa", PATH_PLACEHOLDER, "/quiet", "/norestart", ARGS_PLACEHOLDER], false),
+    );
+    interpreters.insert("py".to_string(), interpreter(&["python", PATH_PLACEHOLDER, ARGS_PLACEHOLDER], false));
+    interpreters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InterpreterRegistry;
+    use crate::cli::InterpreterConfig;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn builds_command_lines_from_built_in_and_configured_interpreters() {
+        let temp = tempdir().unwrap();
+        let autohotkey = temp.path().join("AutoHotkey64.exe");
+        std::fs::write(&autohotkey, b"").unwrap();
+        let mut configured = BTreeMap::new();
+        configured.insert(
+            ".AHK".to_string(),
+            InterpreterConfig {
+                command: vec!["AutoHotkey.exe".to_string(), "/ErrorStdOut".to_string(), "{path}".to_string(), "{args}".to_string()],
+                search_paths: vec!["C:\\missing\\AutoHotkey.exe".to_string(), autohotkey.to_string_lossy().into_owned()],
+                executable: false,
+            },
+        );
+        configured.insert(
+            "py".to_string(),
+            InterpreterConfig {
+                command: Vec::new(),
+                search_paths: Vec::new(),
+                executable: false,
+            },
+        );
+        let registry = InterpreterRegistry::new(&configured);
+        let arguments = vec!["/silent".to_string(), "two words".to_string()];
+
+        assert!(registry.is_executable(Path::new("Tools/setup.EXE")));
+        assert!(registry.is_executable(Path::new("Tools/run.cmd")));
+        assert!(registry.is_script(Path::new("Tools/fix.vbs")));
+        assert!(registry.is_script(Path::new("Tools/hotkeys.ahk")));
+        assert!(registry.get(Path::new("Tools/tool.py")).is_none());
+        assert!(registry.get(Path::new("Tools/readme.txt")).is_none());
+        assert!(registry.get(Path::new("Tools/noextension")).is_none());
+
+        assert_eq!(
+            registry.command_line(Path::new("setup.exe"), &arguments).unwrap(),
+            vec!["setup.exe", "/silent", "two words"]
+        );
+        assert_eq!(
+            registry.command_line(Path::new("tweaks.reg"), &arguments).unwrap(),
+            vec!["reg", "import", "tweaks.reg"]
+        );
+        assert_eq!(
+            registry.command_line(Path::new("update.msu"), &[]).unwrap(),
+            vec!["wusa", "update.msu", "/quiet", "/norestart"]
+        );
+        assert_eq!(
+            registry.command_line(Path::new("hotkeys.ahk"), &arguments).unwrap(),
+            vec![autohotkey.to_string_lossy().as_ref(), "/ErrorStdOut", "hotkeys.ahk", "/silent", "two words"]
+        );
+        // No AutoIt install here, so the program is left to the PATH lookup
+        assert_eq!(registry.command_line(Path::new("a.au3"), &[]).unwrap()[0], "AutoIt3.exe");
+        assert_eq!(
+            registry.command_line(Path::new("tool.py"), &[]).unwrap_err().to_string(),
+            "Unsupported file type: py"
+        );
+    }
+}
@@ -1,9 +1,17 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
-use tracing::debug;
+use tracing::{debug, warn};
 use serde::{Deserialize, Serialize};
 use crate::cli::ToolsConfig;
+use interpreters::InterpreterRegistry;
+
+pub mod integrity;
+pub mod interpreters;
+
+/// Optional sidecar describing the tools in its folder and below
+pub const TOOL_MANIFEST_FILE: &str = "ghostwin-tool.toml";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectedTool {
@@ -13,6 +21,164 @@ pub struct DetectedTool {
     pub executable: bool,
     pub hidden: bool,
     pub auto_run: bool,
+    /// Merged from `ghostwin-tool.toml` sidecars
+    #[serde(default)]
+    pub metadata: ToolMetadata,
+}
+
+impl DetectedTool {
+    /// Sidecar `name`, else the file name
+    pub fn display_name(&self) -> &str {
+        self.metadata.display_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Whether an `after`/`requires` entry names this tool
+    pub fn is_referenced_by(&self, reference: &str) -> bool {
+        let normalized = reference.replace('\\', "/");
+        self.path.ends_with(&normalized) || self.display_name().eq_ignore_ascii_case(reference)
+    }
+}
+
+/// What a tool's sidecar declares; paths are resolved against the sidecar's folder
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolMetadata {
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<PathBuf>,
+    pub arguments: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    /// WinPE optional components the build adds to boot.wim
+    pub packages: Vec<String>,
+    /// Lower runs first; tools without one count as 0
+    pub order: Option<i32>,
+    pub timeout_secs: Option<u64>,
+    /// Refuse to run without administrator rights
+    pub elevated: bool,
+    /// Initialize networking first (WinPE)
+    pub network: bool,
+    /// Tools (file name, relative path or display name) that must finish first
+    pub after: Vec<String>,
+    /// Tools that must finish successfully first; otherwise this tool is skipped
+    pub requires: Vec<String>,
+    pub on_failure: FailurePolicy,
+}
+
+/// What a phase run does after a tool fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Start no further tools
+    Abort,
+    /// Only the tool's dependents are skipped
+    #[default]
+    Continue,
+    /// Run up to N more times, then continue
+    Retry(u32),
+}
+
+/// One table of a `ghostwin-tool.toml`; unset keys inherit from outer sidecars
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct ToolManifestEntry {
+    name: Option<String>,
+    description: Option<String>,
+    icon: Option<String>,
+    arguments: Option<Vec<String>>,
+    working_dir: Option<String>,
+    packages: Option<Vec<String>>,
+    order: Option<i32>,
+    /// Seconds
+    timeout: Option<u64>,
+    elevated: Option<bool>,
+    network: Option<bool>,
+    after: Option<Vec<String>>,
+    requires: Option<Vec<String>>,
+    on_failure: Option<FailurePolicy>,
+    hidden: Option<bool>,
+    auto_run: Option<bool>,
+}
+
+/// Top-level keys apply to every tool in the folder; `[tools."<relative path>"]` to one file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct ToolManifest {
+    #[serde(flatten)]
+    defaults: ToolManifestEntry,
+    tools: BTreeMap<String, ToolManifestEntry>,
+}
+
+impl ToolManifest {
+    fn load(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read tool manifest {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid tool manifest {}", path.display()))
+    }
+
+    /// Entry for `tool`, keyed by its path relative to the manifest folder (`/`-separated, case-insensitive)
+    fn entry_for(&self, manifest_dir: &Path, tool: &Path) -> Option<&ToolManifestEntry> {
+        let relative = tool.strip_prefix(manifest_dir).ok()?;
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.tools
+            .iter()
+            .find(|(key, _)| key.replace('\\', "/").eq_ignore_ascii_case(&relative))
+            .map(|(_, entry)| entry)
+    }
+}
+
+impl ToolManifestEntry {
+    fn apply(&self, tool: &mut DetectedTool, manifest_dir: &Path) {
+        let metadata = &mut tool.metadata;
+        if let Some(name) = &self.name {
+            metadata.display_name = Some(name.clone());
+        }
+        if let Some(description) = &self.description {
+            metadata.description = Some(description.clone());
+        }
+        if let Some(icon) = &self.icon {
+            metadata.icon = Some(manifest_dir.join(icon));
+        }
+        if let Some(arguments) = &self.arguments {
+            metadata.arguments = arguments.clone();
+        }
+        if let Some(working_dir) = &self.working_dir {
+            metadata.working_dir = Some(manifest_dir.join(working_dir));
+        }
+        if let Some(packages) = &self.packages {
+            metadata.packages = packages.clone();
+        }
+        if self.order.is_some() {
+            metadata.order = self.order;
+        }
+        if self.timeout.is_some() {
+            metadata.timeout_secs = self.timeout;
+        }
+        if let Some(elevated) = self.elevated {
+            metadata.elevated = elevated;
+        }
+        if let Some(network) = self.network {
+            metadata.network = network;
+        }
+        if let Some(after) = &self.after {
+            metadata.after = after.clone();
+        }
+        if let Some(requires) = &self.requires {
+            metadata.requires = requires.clone();
+        }
+        if let Some(on_failure) = self.on_failure {
+            metadata.on_failure = on_failure;
+        }
+        if let Some(hidden) = self.hidden {
+            tool.hidden = hidden;
+        }
+        if let Some(auto_run) = self.auto_run {
+            tool.auto_run = auto_run;
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +190,7 @@ pub enum ToolCategory {
 
 pub struct ToolDetector {
     config: ToolsConfig,
+    interpreters: InterpreterRegistry,
 }
 
 pub struct ToolManager {
@@ -34,6 +201,7 @@ impl ToolDetector {
     pub fn new(config: &ToolsConfig) -> Self {
         Self {
             config: config.clone(),
+            interpreters: InterpreterRegistry::new(&config.interpreters),
         }
     }
     
@@ -83,7 +251,8 @@ impl ToolDetector {
         if self.config.auto_detect {
             tools.extend(self.scan_all_drives()?);
         }
-        
+
+        sort_by_order(&mut tools);
         Ok(tools)
     }
     
@@ -112,10 +281,11 @@ impl ToolDetector {
         Ok(dirs)
     }
     
-    fn scan_folder(&self, folder_path: &Path, category: ToolCategory) -> Result<Vec<DetectedTool>> {
+    pub(crate) fn scan_folder(&self, folder_path: &Path, category: ToolCategory) -> Result<Vec<DetectedTool>> {
         let mut tools = Vec::new();
+        let mut manifests: HashMap<PathBuf, Option<ToolManifest>> = HashMap::new();
         
-        for entry in WalkDir::new(folder_path).max_depth(3) {
+        for entry in WalkDir::new(folder_path).max_depth(3).sort_by_file_name() {
             let entry = entry?;
             let path = entry.path();
             
@@ -128,22 +298,25 @@ impl ToolDetector {
                 let hidden = file_name.starts_with('.');
                 
                 // Check if it's an executable type
-                let executable = self.is_executable(path);
+                let executable = self.interpreters.is_executable(path);
                 
                 // Check if it should auto-run
                 let auto_run = matches!(category, ToolCategory::PEAutoRun);
                 
-                if executable || self.is_script(path) {
-                    tools.push(DetectedTool {
+                if executable || self.interpreters.is_script(path) {
+                    let mut tool = DetectedTool {
                         name: file_name.to_string(),
                         path: path.to_path_buf(),
                         category: category.clone(),
                         executable,
                         hidden,
                         auto_run,
-                    });
+                        metadata: ToolMetadata::default(),
+                    };
+                    apply_tool_manifests(&mut tool, folder_path, &mut manifests);
                     
                     debug!("Detected tool: {} at {}", file_name, path.display());
+                    tools.push(tool);
                 }
             }
         }
@@ -153,7 +326,7 @@ impl ToolDetector {
     
     fn scan_all_drives(&self) -> Result<Vec<DetectedTool>> {
         #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
-        let mut tools = Vec::new();
+        let mut roots = Vec::new();
         
         #[cfg(target_os = "windows")]
         {
@@ -164,32 +337,32 @@ impl ToolDetector {
             for i in 0..26 {
                 if (drives >> i) & 1 != 0 {
                     let drive_letter = (b'A' + i) as char;
-                    let drive_path = format!("{}:\\", drive_letter);
-                    
-                    for folder_name in &self.config.folders {
-                        let folder_path = Path::new(&drive_path).join("Helper").join(folder_name);
-                        if folder_path.exists() {
-                            let category = Self::category_for_folder_name(folder_name);
-                            
-                            if let Ok(drive_tools) = self.scan_folder(&folder_path, category) {
-                                tools.extend(drive_tools);
-                            }
-                        }
-                    }
+                    roots.push(PathBuf::from(format!("{}:\\", drive_letter)));
                 }
             }
         }
         
-        Ok(tools)
+        Ok(self.scan_drive_helpers(&roots))
     }
-    
-    fn is_executable(&self, path: &Path) -> bool {
-        match path.extension().and_then(|ext| ext.to_str()) {
-            Some("exe") | Some("com") | Some("bat") | Some("cmd") => true,
-            _ => false,
+
+    /// Tools under `<root>/Helper/<folder>` for each drive root
+    pub(crate) fn scan_drive_helpers(&self, roots: &[PathBuf]) -> Vec<DetectedTool> {
+        let mut tools = Vec::new();
+        for root in roots {
+            for folder_name in &self.config.folders {
+                let folder_path = root.join("Helper").join(folder_name);
+                if folder_path.exists() {
+                    let category = Self::category_for_folder_name(folder_name);
+                    
+                    if let Ok(drive_tools) = self.scan_folder(&folder_path, category) {
+                        tools.extend(drive_tools);
+                    }
+                }
+            }
         }
+        tools
     }
-
+    
     pub(crate) fn category_for_folder_name(folder_name: &str) -> ToolCategory {
         let leaf_name = Path::new(folder_name)
             .file_name()
@@ -223,13 +396,6 @@ impl ToolDetector {
         Self::helper_destination_for_category(&category)
     }
     
-    fn is_script(&self, path: &Path) -> bool {
-        match path.extension().and_then(|ext| ext.to_str()) {
-            Some("au3") | Some("ps1") | Some("reg") | Some("vbs") => true,
-            _ => false,
-        }
-    }
-    
     pub fn load_options_file<P: AsRef<Path>>(&self, folder_path: P) -> Result<ToolOptions> {
         let options_file = folder_path.as_ref().join(".Options.txt");
         if !options_file.exists() {
@@ -259,6 +425,53 @@ impl ToolDetector {
     }
 }
 
+/// Apply every `ghostwin-tool.toml` from `root` down to the tool's folder, nearest last
+fn apply_tool_manifests(tool: &mut DetectedTool, root: &Path, manifests: &mut HashMap<PathBuf, Option<ToolManifest>>) {
+    let Some(parent) = tool.path.parent() else {
+        return;
+    };
+    let mut dirs: Vec<PathBuf> = parent.ancestors().take_while(|dir| dir.starts_with(root)).map(Path::to_path_buf).collect();
+    dirs.reverse();
+
+    for dir in dirs {
+        let manifest = manifests.entry(dir.clone()).or_insert_with(|| {
+            let path = dir.join(TOOL_MANIFEST_FILE);
+            if !path.is_file() {
+                return None;
+            }
+            ToolManifest::load(&path)
+                .inspect_err(|error| warn!("⚠️ Ignoring {:#}", error))
+                .ok()
+        });
+        if let Some(manifest) = manifest {
+            manifest.defaults.apply(tool, &dir);
+            if let Some(entry) = manifest.entry_for(&dir, &tool.path) {
+                entry.apply(tool, &dir);
+            }
+        }
+    }
+}
+
+/// Stable sort by sidecar `order`; discovery order breaks ties
+pub fn sort_by_order(tools: &mut [DetectedTool]) {
+    tools.sort_by_key(|tool| tool.metadata.order.unwrap_or(0));
+}
+
+/// WinPE packages the tools under `folders` declare, without case-insensitive duplicates
+pub fn required_packages(detector: &ToolDetector, folders: &[PathBuf]) -> Result<Vec<String>> {
+    let mut packages: Vec<String> = Vec::new();
+    for folder in folders {
+        for tool in detector.scan_folder(folder, ToolCategory::Tool)? {
+            for package in tool.metadata.packages {
+                if !packages.iter().any(|existing| existing.eq_ignore_ascii_case(&package)) {
+                    packages.push(package);
+                }
+            }
+        }
+    }
+    Ok(packages)
+}
+
 impl ToolManager {
     pub fn new(config: &ToolsConfig) -> Self {
         Self {
@@ -281,7 +494,7 @@ pub struct ToolOptions {
 
 #[cfg(test)]
 mod tests {
-    use super::{ToolCategory, ToolDetector};
+    use super::{TOOL_MANIFEST_FILE, ToolCategory, ToolDetector, integrity, required_packages};
     use crate::cli::ToolsConfig;
     use tempfile::tempdir;
 
@@ -335,16 +548,31 @@ mod tests {
         std::fs::write(autorun_dir.join("launch.ps1"), "ps1").unwrap();
         std::fs::write(logon_dir.join("finish.cmd"), "cmd").unwrap();
         std::fs::write(tools_dir.join("notes.txt"), "ignored").unwrap();
+        std::fs::write(tools_dir.join("hotkeys.ahk"), "ahk").unwrap();
 
-        let config = ToolsConfig {
+        let mut config = ToolsConfig {
             folders: vec!["Tools".into(), "PEAutoRun".into(), "Logon".into()],
             auto_detect: false,
+            interpreters: Default::default(),
         };
         let detector = ToolDetector::new(&config);
 
         let detected = detector.detect_tools(temp.path()).unwrap();
         assert_eq!(detected.len(), 3);
 
+        // Configured interpreters make new types detectable
+        config.interpreters.insert(
+            "ahk".to_string(),
+            crate::cli::InterpreterConfig {
+                command: vec!["AutoHotkey.exe".into(), "{path}".into()],
+                search_paths: Vec::new(),
+                executable: false,
+            },
+        );
+        let with_ahk = ToolDetector::new(&config).detect_tools(temp.path()).unwrap();
+        assert_eq!(with_ahk.len(), 4);
+        assert!(with_ahk.iter().any(|tool| tool.name == "hotkeys.ahk" && !tool.executable));
+
         let tool = detected.iter().find(|tool| tool.name == "diskpart.exe").unwrap();
         assert!(matches!(tool.category, ToolCategory::Tool));
         assert!(tool.executable);
@@ -375,6 +603,7 @@ mod tests {
         let config = ToolsConfig {
             folders: vec!["Tools".into()],
             auto_detect: false,
+            interpreters: Default::default(),
         };
         let detector = ToolDetector::new(&config);
         let options = detector.load_options_file(&tools_dir).unwrap();
@@ -383,4 +612,112 @@ mod tests {
         assert!(options.collapse_tree);
         assert_eq!(options.default_checked, vec!["installer.ps1", "helper.cmd"]);
     }
+
+    #[test]
+    fn merges_nested_tool_manifests_and_orders_tools() {
+        let temp = tempdir().unwrap();
+        let tools_dir = temp.path().join("PEAutoRun");
+        let network_dir = tools_dir.join("Network");
+        std::fs::create_dir_all(&network_dir).unwrap();
+        for path in [tools_dir.join("a-last.cmd"), tools_dir.join("b-first.ps1"), network_dir.join("Map-Share.ps1")] {
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::write(
+            tools_dir.join(TOOL_MANIFEST_FILE),
+            r#"
+timeout = 300
+packages = ["WinPE-WMI"]
+
+[tools."b-first.ps1"]
+name = "Prepare disks"
+order = -10
+arguments = ["-Force"]
+elevated = true
+
+[tools."Network/Map-Share.ps1"]
+auto_run = false
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            network_dir.join(TOOL_MANIFEST_FILE),
+            r#"
+description = "Maps the deployment share"
+icon = "share.png"
+working_dir = "."
+network = true
+packages = ["WinPE-PowerShell", "WinPE-WMI"]
+"#,
+        )
+        .unwrap();
+
+        let config = ToolsConfig {
+            folders: vec!["PEAutoRun".into()],
+            auto_detect: false,
+            interpreters: Default::default(),
+        };
+        let detector = ToolDetector::new(&config);
+        let detected = detector.detect_tools(temp.path()).unwrap();
+
+        let names: Vec<&str> = detected.iter().map(|tool| tool.display_name()).collect();
+        assert_eq!(names, vec!["Prepare disks", "Map-Share.ps1", "a-last.cmd"]);
+
+        let first = &detected[0];
+        assert_eq!(first.metadata.arguments, vec!["-Force"]);
+        assert_eq!(first.metadata.timeout_secs, Some(300));
+        assert!(first.metadata.elevated);
+        assert!(first.auto_run);
+
+        let share = &detected[1];
+        assert!(!share.auto_run);
+        assert!(share.metadata.network);
+        assert_eq!(share.metadata.timeout_secs, Some(300));
+        assert_eq!(share.metadata.description.as_deref(), Some("Maps the deployment share"));
+        assert_eq!(share.metadata.icon.as_deref(), Some(network_dir.join("share.png").as_path()));
+        assert_eq!(share.metadata.working_dir.as_deref(), Some(network_dir.join(".").as_path()));
+
+        let packages = required_packages(&detector, &[tools_dir]).unwrap();
+        assert_eq!(packages, vec!["WinPE-PowerShell", "WinPE-WMI"]);
+
+        std::fs::write(network_dir.join(TOOL_MANIFEST_FILE), "network = \"yes\"").unwrap();
+        let detected = detector.detect_tools(temp.path()).unwrap();
+        let share = detected.iter().find(|tool| tool.name == "Map-Share.ps1").unwrap();
+        assert!(!share.metadata.network);
+        assert_eq!(share.metadata.timeout_secs, Some(300));
+    }
+
+    #[test]
+    fn tools_on_other_drives_are_held_to_the_signed_manifest() {
+        let temp = tempdir().unwrap();
+        let helper = temp.path().join("X/Helper");
+        std::fs::create_dir_all(helper.join("PEAutoRun")).unwrap();
+        std::fs::write(helper.join("PEAutoRun/Setup.ps1"), "Write-Host setup").unwrap();
+        let key_path = temp.path().join("signing.pem");
+        let public_key = integrity::tests::write_signing_key(&key_path, 7);
+        let key = integrity::load_signing_key(&key_path).unwrap();
+        let manifest_path = helper.join(integrity::HASH_MANIFEST_FILE);
+        integrity::HashManifest::generate(&helper, &key).unwrap().write(&manifest_path).unwrap();
+
+        let usb = temp.path().join("USB");
+        std::fs::create_dir_all(usb.join("Helper/PEAutoRun")).unwrap();
+        std::fs::write(usb.join("Helper/PEAutoRun/evil.cmd"), "format c:").unwrap();
+        let config = ToolsConfig {
+            folders: vec!["Tools".into(), "PEAutoRun".into(), "Logon".into()],
+            auto_detect: true,
+            interpreters: Default::default(),
+        };
+        let detected = ToolDetector::new(&config).scan_drive_helpers(&[temp.path().join("X"), usb]);
+        assert_eq!(detected.len(), 2);
+
+        let mut security = crate::cli::GhostwinConfig::default().security;
+        security.tool_manifest_public_key = Some(public_key);
+        security.tool_manifest_path = Some(manifest_path.to_string_lossy().into_owned());
+        security.enforce_tool_manifest = true;
+        let verifier = integrity::ToolVerifier::from_config(&security);
+        let signed = detected.iter().find(|tool| tool.name == "Setup.ps1").unwrap();
+        verifier.check(signed).unwrap();
+        let usb_tool = detected.iter().find(|tool| tool.name == "evil.cmd").unwrap();
+        let error = verifier.check(usb_tool).unwrap_err();
+        assert!(error.to_string().contains("not listed in the signed tool manifest"));
+    }
 }
@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Checkpoint file kept at the root of the build `output_dir`
+pub const BUILD_STATE_FILE: &str = ".ghostwin-build-state.json";
+const BUILD_STATE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildCheckpoint {
+    pub step: String,
+    /// Hash of everything the step consumed (inputs plus the previous checkpoint)
+    pub fingerprint: String,
+    /// Unix seconds
+    pub completed_at: u64,
+}
+
+/// Completed build checkpoints persisted between `ghostwin build` runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildState {
+    pub version: u32,
+    pub checkpoints: Vec<BuildCheckpoint>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl BuildState {
+    /// Load the previous state when resuming, otherwise start (and persist) an empty one
+    pub fn prepare(output_dir: &Path, resume: bool) -> Result<Self> {
+        std::fs::create_dir_all(output_dir)
+            .with_context(|| format!("Failed to create output directory {}", output_dir.display()))?;
+        let path = output_dir.join(BUILD_STATE_FILE);
+
+        if resume {
+            match Self::load(&path) {
+                Ok(Some(state)) => {
+                    info!(
+                        "♻️ Resuming build with {} completed checkpoint(s) from {}",
+                        state.checkpoints.len(),
+                        path.display()
+                    );
+                    return Ok(state);
+                }
+                Ok(None) => warn!("No build state found in {}, starting a full build", output_dir.display()),
+                Err(error) => warn!("Ignoring unreadable build state ({:#}), starting a full build", error),
+            }
+        }
+
+        let state = Self {
+            version: BUILD_STATE_VERSION,
+            checkpoints: Vec::new(),
+            path,
+        };
+        state.save()?;
+        Ok(state)
+    }
+
+    fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut state: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if state.version != BUILD_STATE_VERSION {
+            anyhow::bail!("unsupported build state version {}", state.version);
+        }
+        state.path = path.to_path_buf();
+        Ok(Some(state))
+    }
+
+    pub fn is_complete(&self, step: &str, fingerprint: &str) -> bool {
+        self.checkpoints
+            .iter()
+            .any(|checkpoint| checkpoint.step == step && checkpoint.fingerprint == fingerprint)
+    }
+
+    /// Record a finished step, replacing any stale entry for it
+    pub fn record(&mut self, step: &str, fingerprint: &str) -> Result<()> {
+        self.checkpoints.retain(|checkpoint| checkpoint.step != step);
+        self.checkpoints.push(BuildCheckpoint {
+            step: step.to_string(),
+            fingerprint: fingerprint.to_string(),
+            completed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+        });
+        self.save()
+    }
+
+    /// Drop every checkpoint recorded after `step` (or all of them when it is missing)
+    pub fn truncate_after(&mut self, step: &str) -> Result<()> {
+        let keep = self
+            .checkpoints
+            .iter()
+            .position(|checkpoint| checkpoint.step == step)
+            .map(|position| position + 1)
+            .unwrap_or(0);
+        self.checkpoints.truncate(keep);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, content)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to update {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// SHA-256 over labelled build inputs
+///
+/// Files are hashed by content, not size and modification time: copies that keep
+/// timestamps (robocopy, `cp -p`, archive extraction) would otherwise go unnoticed.
+/// The source ISO is not walked here; builds fold in the SHA-256 they already computed.
+pub struct Fingerprint {
+    hasher: Sha256,
+}
+
+impl Fingerprint {
+    pub fn new(step: &str) -> Self {
+        let mut fingerprint = Self { hasher: Sha256::new() };
+        fingerprint.text("step", step);
+        fingerprint
+    }
+
+    pub fn text(&mut self, label: &str, value: &str) -> &mut Self {
+        self.hasher.update((label.len() as u64).to_le_bytes());
+        self.hasher.update(label.as_bytes());
+        self.hasher.update((value.len() as u64).to_le_bytes());
+        self.hasher.update(value.as_bytes());
+        self
+    }
+
+    pub fn path(&mut self, label: &str, path: &Path) -> Result<&mut Self> {
+        self.text(label, &path.to_string_lossy());
+        if !path.exists() {
+            self.text("missing", "");
+            return Ok(self);
+        }
+
+        for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+            let entry = entry.with_context(|| format!("Failed to walk {}", path.display()))?;
+            let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+            if entry.file_type().is_dir() {
+                self.text("directory", &relative.to_string_lossy());
+                continue;
+            }
+
+            let mut file = std::fs::File::open(entry.path())
+                .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+            let mut content = Sha256::new();
+            std::io::copy(&mut file, &mut content)
+                .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+            let digest: String = content.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+            self.text("file", &format!("{}|{}", relative.to_string_lossy(), digest));
+        }
+        Ok(self)
+    }
+
+    pub fn finish(&self) -> String {
+        self.hasher
+            .clone()
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BUILD_STATE_FILE, BuildState, Fingerprint};
+    use tempfile::tempdir;
+
+    #[test]
+    fn persists_checkpoints_for_resume() {
+        let temp = tempdir().unwrap();
+        let mut state = BuildState::prepare(temp.path(), false).unwrap();
+        state.record("extract", "aaa").unwrap();
+        state.record("service", "bbb").unwrap();
+        state.record("create_iso", "ccc").unwrap();
+
+        let mut resumed = BuildState::prepare(temp.path(), true).unwrap();
+        assert!(resumed.is_complete("extract", "aaa"));
+        assert!(resumed.is_complete("service", "bbb"));
+        assert!(!resumed.is_complete("service", "changed"));
+
+        resumed.truncate_after("extract").unwrap();
+        let reloaded = BuildState::prepare(temp.path(), true).unwrap();
+        assert_eq!(reloaded.checkpoints.len(), 1);
+
+        let fresh = BuildState::prepare(temp.path(), false).unwrap();
+        assert!(fresh.checkpoints.is_empty());
+        assert!(temp.path().join(BUILD_STATE_FILE).exists());
+    }
+
+    #[test]
+    fn ignores_unreadable_state_when_resuming() {
+        let temp = tempdir().unwrap();
+        std::fs::write(temp.path().join(BUILD_STATE_FILE), "{not json").unwrap();
+
+        let state = BuildState::prepare(temp.path(), true).unwrap();
+        assert!(state.checkpoints.is_empty());
+    }
+
+    #[test]
+    fn fingerprint_tracks_directory_changes() {
+        let temp = tempdir().unwrap();
+        let helper = temp.path().join("Helper");
+        std::fs::create_dir_all(helper.join("Tools")).unwrap();
+        std::fs::write(helper.join("Tools/a.txt"), "one").unwrap();
+
+        let hash = |root: &std::path::Path| {
+            Fingerprint::new("service").path("helper", root).unwrap().finish()
+        };
+        let before = hash(&helper);
+        assert_eq!(before, hash(&helper));
+
+        std::fs::write(helper.join("Tools/a.txt"), "three").unwrap();
+        assert_ne!(before, hash(&helper));
+
+        // Same size and timestamp, different bytes
+        let changed = hash(&helper);
+        let modified = std::fs::metadata(helper.join("Tools/a.txt")).unwrap().modified().unwrap();
+        std::fs::write(helper.join("Tools/a.txt"), "THREE").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(helper.join("Tools/a.txt"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_ne!(changed, hash(&helper));
+        assert_ne!(hash(&helper), hash(&temp.path().join("missing")));
+        assert_ne!(
+            Fingerprint::new("service").finish(),
+            Fingerprint::new("create_iso").finish()
+        );
+    }
+}
@@ -1,6 +1,7 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 
+pub mod build_state;
 pub mod recovery;
 
 #[cfg(target_os = "windows")]
@@ -33,37 +34,6 @@ pub fn import_reg_file(reg_path: &Path) -> Result<std::process::Output> {
         .output()?)
 }
 
-pub async fn execute_tools_with_dry_run(
-    tools: &[crate::tools::DetectedTool],
-    executor: &crate::executor::ScriptExecutor,
-    dry_run: bool,
-    action_label: &str,
-) -> Result<()> {
-    for tool in tools {
-        tracing::info!("Executing {}: {}", action_label, tool.path.display());
-
-        if dry_run {
-            tracing::info!("Dry run: would execute {}", tool.path.display());
-            continue;
-        }
-
-        match executor.execute_script(&tool.path).await {
-            Ok(output) => {
-                tracing::info!("✅ Successfully executed: {}", tool.name);
-                if !output.trim().is_empty() {
-                    tracing::info!("Output: {}", output);
-                }
-            }
-            Err(e) => {
-                tracing::error!("❌ Failed to execute {}: {}", tool.name, e);
-                tracing::warn!("Continuing with next script...");
-            }
-        }
-    }
-
-    Ok(())
-}
-
 pub fn resolve_detected_tools(
     configured_paths: &[String],
     detected_tools: &[crate::tools::DetectedTool],
@@ -80,45 +50,6 @@ pub fn resolve_detected_tools(
         .collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::resolve_detected_tools;
-    use crate::tools::{DetectedTool, ToolCategory};
-    use std::path::PathBuf;
-
-    fn tool(path: &str, category: ToolCategory) -> DetectedTool {
-        DetectedTool {
-            name: PathBuf::from(path).file_name().unwrap().to_string_lossy().to_string(),
-            path: PathBuf::from(path),
-            category,
-            executable: true,
-            hidden: false,
-            auto_run: true,
-        }
-    }
-
-    #[test]
-    fn resolves_phase_paths_against_detected_tools() {
-        let detected = vec![
-            tool("./pe_autorun/system_setup/fontfix.reg", ToolCategory::PEAutoRun),
-            tool("./pe_autorun/drivers/Load-Drivers.ps1", ToolCategory::PEAutoRun),
-            tool("./scripts/basic/registry/disable_auto_logon.reg", ToolCategory::Logon),
-        ];
-
-        let resolved = resolve_detected_tools(
-            &[
-                "pe_autorun/system_setup/fontfix.reg".to_string(),
-                "scripts/basic/registry/disable_auto_logon.reg".to_string(),
-            ],
-            &detected,
-        );
-
-        assert_eq!(resolved.len(), 2);
-        assert_eq!(resolved[0].name, "fontfix.reg");
-        assert_eq!(resolved[1].name, "disable_auto_logon.reg");
-    }
-}
-
 pub fn ensure_admin_privileges() -> Result<()> {
     #[cfg(target_os = "windows")]
     {
@@ -207,22 +138,6 @@ pub fn check_dependencies() -> Result<Vec<String>> {
             missing.push("DISM (Windows ADK) - Required for WIM mounting".to_string());
         }
         
-        // Check for 7-Zip
-        if !command_exists("7z") {
-            missing.push("7-Zip - Required for ISO extraction. Install from https://www.7-zip.org/".to_string());
-        }
-        
-        // Check for oscdimg
-        let adk_paths = vec![
-            "C:\\Program Files (x86)\\Windows Kits\\10\\Assessment and Deployment Kit\\Deployment Tools\\amd64\\Oscdimg\\oscdimg.exe",
-            "C:\\Program Files\\Windows Kits\\10\\Assessment and Deployment Kit\\Deployment Tools\\amd64\\Oscdimg\\oscdimg.exe",
-        ];
-        
-        let oscdimg_found = adk_paths.iter().any(|path| Path::new(path).exists());
-        if !oscdimg_found {
-            missing.push("oscdimg (Windows ADK) - Required for ISO creation. Install Windows ADK from Microsoft".to_string());
-        }
-        
         // Check for Windows ADK WinPE packages
         let winpe_paths = vec![
             "C:\\Program Files (x86)\\Windows Kits\\10\\Assessment and Deployment Kit\\Windows Preinstallation Environment",
@@ -288,6 +203,28 @@ pub fn format_file_size(bytes: u64) -> String {
     }
 }
 
+/// Streaming SHA-256 of a file as lowercase hex
+pub fn sha256_file(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open {} for hashing", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0_u8; 1024 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {} for hashing", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 #[allow(dead_code)]
 pub fn validate_iso_file<P: AsRef<Path>>(path: P) -> Result<()> {
     let path = path.as_ref();
@@ -315,3 +252,43 @@ pub fn validate_iso_file<P: AsRef<Path>>(path: P) -> Result<()> {
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_detected_tools;
+    use crate::tools::{DetectedTool, ToolCategory};
+    use std::path::PathBuf;
+
+    fn tool(path: &str, category: ToolCategory) -> DetectedTool {
+        DetectedTool {
+            name: PathBuf::from(path).file_name().unwrap().to_string_lossy().to_string(),
+            path: PathBuf::from(path),
+            category,
+            executable: true,
+            hidden: false,
+            auto_run: true,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn resolves_phase_paths_against_detected_tools() {
+        let detected = vec![
+            tool("./pe_autorun/system_setup/fontfix.reg", ToolCategory::PEAutoRun),
+            tool("./pe_autorun/drivers/Load-Drivers.ps1", ToolCategory::PEAutoRun),
+            tool("./scripts/basic/registry/disable_auto_logon.reg", ToolCategory::Logon),
+        ];
+
+        let resolved = resolve_detected_tools(
+            &[
+                "pe_autorun/system_setup/fontfix.reg".to_string(),
+                "scripts/basic/registry/disable_auto_logon.reg".to_string(),
+            ],
+            &detected,
+        );
+
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].name, "fontfix.reg");
+        assert_eq!(resolved[1].name, "disable_auto_logon.reg");
+    }
+}
@@ -10,8 +10,8 @@ pub struct RecoveryManager;
 
 #[allow(dead_code)]
 impl RecoveryManager {
-    /// Clean up failed build artifacts
-    pub async fn cleanup_failed_build(build_dir: &Path) -> Result<()> {
+    /// Clean up failed build artifacts, optionally leaving the build directory in place
+    pub async fn cleanup_failed_build(build_dir: &Path, keep_build_dir: bool) -> Result<()> {
         #[cfg(target_os = "windows")]
         {
             info!("🧹 Cleaning up failed build artifacts");
@@ -23,7 +23,9 @@ impl RecoveryManager {
             Self::cleanup_dism_operations().await?;
 
             // Remove build directory if it exists
-            if build_dir.exists() {
+            if keep_build_dir {
+                info!("Keeping build directory for inspection: {}", build_dir.display());
+            } else if build_dir.exists() {
                 info!("Removing build directory: {}", build_dir.display());
                 match std::fs::remove_dir_all(build_dir) {
                     Ok(_) => info!("✅ Build directory cleaned"),
@@ -41,7 +43,9 @@ impl RecoveryManager {
         #[cfg(not(target_os = "windows"))]
         {
             info!("🧹 Cleaning up failed build artifacts");
-            if build_dir.exists() {
+            if keep_build_dir {
+                info!("Keeping build directory for inspection: {}", build_dir.display());
+            } else if build_dir.exists() {
                 info!("Removing build directory: {}", build_dir.display());
                 match std::fs::remove_dir_all(build_dir) {
                     Ok(_) => info!("✅ Build directory cleaned"),
@@ -0,0 +1,331 @@
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use std::path::Path;
+use tracing::debug;
+use crate::cli::{GhostwinConfig, ServicingBackend};
+
+/// Offline image-servicing operations behind `WimManager`
+#[async_trait]
+pub trait ImageBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn mount(&self, wim_path: &Path, index: u32, mount_dir: &Path) -> Result<()>;
+
+    async fn unmount(&self, mount_dir: &Path, commit: bool) -> Result<()>;
+
+    async fn add_package(&self, mount_dir: &Path, package_path: &Path) -> Result<()>;
+
+    async fn add_driver(&self, mount_dir: &Path, inf_path: &Path) -> Result<()>;
+
+    /// Append image `index` of `source` (e.g. a solid-compressed install.esd) to `destination` as a mountable WIM image
+    async fn export_image(&self, source: &Path, index: u32, destination: &Path) -> Result<()>;
+
+    /// Best-effort synchronous discard when a manager is dropped while still mounted
+    fn discard_on_drop(&self, _mount_dir: &Path) {}
+}
+
+pub fn backend_for_config(config: &GhostwinConfig) -> Box<dyn ImageBackend> {
+    match config.iso.servicing_backend {
+        ServicingBackend::Dism => Box::new(DismBackend),
+        ServicingBackend::Wimlib => Box::new(WimlibBackend),
+        ServicingBackend::Auto if cfg!(target_os = "windows") => Box::new(DismBackend),
+        ServicingBackend::Auto => Box::new(WimlibBackend),
+    }
+}
+
+/// Windows DISM servicing
+pub struct DismBackend;
+
+#[async_trait]
+impl ImageBackend for DismBackend {
+    fn name(&self) -> &'static str {
+        "dism"
+    }
+
+    async fn mount(&self, wim_path: &Path, index: u32, mount_dir: &Path) -> Result<()> {
+        run_tool("dism", Self::mount_args(wim_path, index, mount_dir), "DISM mount").await
+    }
+
+    async fn unmount(&self, mount_dir: &Path, commit: bool) -> Result<()> {
+        run_tool("dism", Self::unmount_args(mount_dir, commit), "DISM unmount").await
+    }
+
+    async fn add_package(&self, mount_dir: &Path, package_path: &Path) -> Result<()> {
+        if !package_path.exists() {
+            bail!("WinPE package not found: {}", package_path.display());
+        }
+
+        run_tool(
+            "dism",
+            vec![
+                format!("/Image:{}", mount_dir.display()),
+                "/Add-Package".to_string(),
+                format!("/PackagePath:{}", package_path.display()),
+            ],
+            "DISM add-package",
+        )
+        .await
+    }
+
+    async fn add_driver(&self, mount_dir: &Path, inf_path: &Path) -> Result<()> {
+        run_tool(
+            "dism",
+            vec![
+                format!("/Image:{}", mount_dir.display()),
+                "/Add-Driver".to_string(),
+                format!("/Driver:{}", inf_path.display()),
+            ],
+            "DISM add-driver",
+        )
+        .await
+    }
+
+    async fn export_image(&self, source: &Path, index: u32, destination: &Path) -> Result<()> {
+        run_tool("dism", Self::export_args(source, index, destination), "DISM export-image").await
+    }
+
+    fn discard_on_drop(&self, mount_dir: &Path) {
+        let _ = std::process::Command::new("dism")
+            .args(Self::unmount_args(mount_dir, false))
+            .status();
+    }
+}
+
+impl DismBackend {
+    fn mount_args(wim_path: &Path, index: u32, mount_dir: &Path) -> Vec<String> {
+        vec![
+            "/Mount-Wim".to_string(),
+            format!("/WimFile:{}", wim_path.display()),
+            format!("/Index:{}", index),
+            format!("/MountDir:{}", mount_dir.display()),
+        ]
+    }
+
+    fn unmount_args(mount_dir: &Path, commit: bool) -> Vec<String> {
+        vec![
+            "/Unmount-Wim".to_string(),
+            format!("/MountDir:{}", mount_dir.display()),
+            if commit { "/Commit" } else { "/Discard" }.to_string(),
+        ]
+    }
+
+    fn export_args(source: &Path, index: u32, destination: &Path) -> Vec<String> {
+        vec![
+            "/Export-Image".to_string(),
+            format!("/SourceImageFile:{}", source.display()),
+            format!("/SourceIndex:{}", index),
+            format!("/DestinationImageFile:{}", destination.display()),
+            "/Compress:max".to_string(),
+            "/CheckIntegrity".to_string(),
+        ]
+    }
+}
+
+/// wimlib-imagex servicing (FUSE `mountrw` on Linux/macOS)
+///
+/// wimlib can mount, modify, and commit image contents, but it has no
+/// component-servicing stack: package and driver-store injection still need DISM.
+pub struct WimlibBackend;
+
+#[async_trait]
+impl ImageBackend for WimlibBackend {
+    fn name(&self) -> &'static str {
+        "wimlib"
+    }
+
+    async fn mount(&self, wim_path: &Path, index: u32, mount_dir: &Path) -> Result<()> {
+        run_tool("wimlib-imagex", Self::mount_args(wim_path, index, mount_dir), "wimlib-imagex mountrw").await
+    }
+
+    async fn unmount(&self, mount_dir: &Path, commit: bool) -> Result<()> {
+        run_tool("wimlib-imagex", Self::unmount_args(mount_dir, commit), "wimlib-imagex unmount").await
+    }
+
+    async fn add_package(&self, _mount_dir: &Path, package_path: &Path) -> Result<()> {
+        bail!(
+            "Adding WinPE package {} requires DISM; use the dism servicing backend or --skip-packages",
+            package_path.display()
+        )
+    }
+
+    async fn add_driver(&self, _mount_dir: &Path, inf_path: &Path) -> Result<()> {
+        bail!(
+            "Driver store injection of {} requires DISM; the driver is only staged into the image",
+            inf_path.display()
+        )
+    }
+
+    async fn export_image(&self, source: &Path, index: u32, destination: &Path) -> Result<()> {
+        run_tool("wimlib-imagex", Self::export_args(source, index, destination), "wimlib-imagex export").await
+    }
+
+    fn discard_on_drop(&self, mount_dir: &Path) {
+        let _ = std::process::Command::new("wimlib-imagex")
+            .args(Self::unmount_args(mount_dir, false))
+            .status();
+    }
+}
+
+impl WimlibBackend {
+    fn mount_args(wim_path: &Path, index: u32, mount_dir: &Path) -> Vec<String> {
+        vec![
+            "mountrw".to_string(),
+            wim_path.to_string_lossy().into_owned(),
+            index.to_string(),
+            mount_dir.to_string_lossy().into_owned(),
+        ]
+    }
+
+    fn unmount_args(mount_dir: &Path, commit: bool) -> Vec<String> {
+        let mut args = vec!["unmount".to_string(), mount_dir.to_string_lossy().into_owned()];
+        if commit {
+            args.push("--commit".to_string());
+        }
+        args
+    }
+
+    fn export_args(source: &Path, index: u32, destination: &Path) -> Vec<String> {
+        vec![
+            "export".to_string(),
+            source.to_string_lossy().into_owned(),
+            index.to_string(),
+            destination.to_string_lossy().into_owned(),
+            "--compress=LZX".to_string(),
+        ]
+    }
+}
+
+async fn run_tool(program: &str, args: Vec<String>, label: &str) -> Result<()> {
+    debug!("Running {} {}", program, args.join(" "));
+
+    let output = tokio::process::Command::new(program)
+        .args(&args)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {} command", label))?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let details = stderr.trim();
+    let details = if details.is_empty() { stdout.trim() } else { details };
+
+    bail!("{} failed: {}", label, details)
+}
+
+/// Test backend that records every call instead of touching the host
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct RecordingBackend {
+    calls: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[cfg(test)]
+impl RecordingBackend {
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[cfg(test)]
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+#[async_trait]
+impl ImageBackend for RecordingBackend {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn mount(&self, wim_path: &Path, index: u32, _mount_dir: &Path) -> Result<()> {
+        self.record(format!("mount {} {}", file_name(wim_path), index));
+        Ok(())
+    }
+
+    async fn unmount(&self, _mount_dir: &Path, commit: bool) -> Result<()> {
+        self.record(format!("unmount {}", if commit { "commit" } else { "discard" }));
+        Ok(())
+    }
+
+    async fn add_package(&self, _mount_dir: &Path, package_path: &Path) -> Result<()> {
+        self.record(format!("add_package {}", file_name(package_path)));
+        Ok(())
+    }
+
+    async fn add_driver(&self, _mount_dir: &Path, inf_path: &Path) -> Result<()> {
+        self.record(format!("add_driver {}", file_name(inf_path)));
+        Ok(())
+    }
+
+    async fn export_image(&self, source: &Path, index: u32, destination: &Path) -> Result<()> {
+        self.record(format!("export {} {} {}", file_name(source), index, file_name(destination)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DismBackend, WimlibBackend, backend_for_config};
+    use crate::cli::{GhostwinConfig, ServicingBackend};
+    use std::path::Path;
+
+    #[test]
+    fn selects_backend_from_config() {
+        let mut config = GhostwinConfig::default();
+        let expected = if cfg!(target_os = "windows") { "dism" } else { "wimlib" };
+        assert_eq!(backend_for_config(&config).name(), expected);
+
+        config.iso.servicing_backend = ServicingBackend::Dism;
+        assert_eq!(backend_for_config(&config).name(), "dism");
+        config.iso.servicing_backend = ServicingBackend::Wimlib;
+        assert_eq!(backend_for_config(&config).name(), "wimlib");
+    }
+
+    #[test]
+    fn builds_mount_and_unmount_arguments() {
+        assert_eq!(
+            DismBackend::mount_args(Path::new("C:/build/sources/boot.wim"), 2, Path::new("C:/mount")),
+            vec!["/Mount-Wim", "/WimFile:C:/build/sources/boot.wim", "/Index:2", "/MountDir:C:/mount"]
+        );
+        assert_eq!(
+            DismBackend::unmount_args(Path::new("C:/mount"), false),
+            vec!["/Unmount-Wim", "/MountDir:C:/mount", "/Discard"]
+        );
+        assert_eq!(
+            WimlibBackend::mount_args(Path::new("/build/sources/boot.wim"), 2, Path::new("/mnt/wim")),
+            vec!["mountrw", "/build/sources/boot.wim", "2", "/mnt/wim"]
+        );
+        assert_eq!(
+            WimlibBackend::unmount_args(Path::new("/mnt/wim"), true),
+            vec!["unmount", "/mnt/wim", "--commit"]
+        );
+    }
+
+    #[test]
+    fn builds_export_arguments() {
+        assert_eq!(
+            DismBackend::export_args(Path::new("C:/build/sources/install.esd"), 6, Path::new("C:/build/sources/install.wim")),
+            vec![
+                "/Export-Image",
+                "/SourceImageFile:C:/build/sources/install.esd",
+                "/SourceIndex:6",
+                "/DestinationImageFile:C:/build/sources/install.wim",
+                "/Compress:max",
+                "/CheckIntegrity",
+            ]
+        );
+        assert_eq!(
+            WimlibBackend::export_args(Path::new("/build/sources/install.esd"), 6, Path::new("/build/sources/install.wim")),
+            vec!["export", "/build/sources/install.esd", "6", "/build/sources/install.wim", "--compress=LZX"]
+        );
+    }
+}
@@ -0,0 +1,1193 @@
+use anyhow::{Context, Result, bail};
+use std::fmt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BASE_BLOCK_SIZE: usize = 4096;
+const HBIN_ALIGNMENT: usize = 4096;
+const HBIN_HEADER_SIZE: usize = 32;
+const CELL_ALIGNMENT: usize = 8;
+const NO_CELL: u32 = 0xFFFF_FFFF;
+const KEY_NODE_HEADER: usize = 76;
+const VALUE_NODE_HEADER: usize = 20;
+/// Largest payload of a single data cell; bigger values are split into `db` segments
+const MAX_DATA_SEGMENT: usize = 16344;
+/// Windows switches from one `lh` leaf to an `ri` index above this many subkeys
+const MAX_LEAF_ENTRIES: usize = 1012;
+const MAX_INDEX_DEPTH: usize = 8;
+const MAX_KEY_DEPTH: usize = 512;
+const INLINE_DATA_FLAG: u32 = 0x8000_0000;
+
+const KEY_HIVE_ENTRY: u16 = 0x0004;
+const KEY_NO_DELETE: u16 = 0x0008;
+const KEY_COMP_NAME: u16 = 0x0020;
+const VALUE_COMP_NAME: u16 = 0x0001;
+
+const REG_SZ: u32 = 1;
+const REG_EXPAND_SZ: u32 = 2;
+const REG_BINARY: u32 = 3;
+const REG_DWORD: u32 = 4;
+const REG_MULTI_SZ: u32 = 7;
+const REG_QWORD: u32 = 11;
+
+/// Typed registry value data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegValue {
+    Sz(String),
+    ExpandSz(String),
+    Binary(Vec<u8>),
+    Dword(u32),
+    MultiSz(Vec<String>),
+    Qword(u64),
+    Other { kind: u32, data: Vec<u8> },
+}
+
+impl fmt::Display for RegValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegValue::Sz(text) => write!(f, "REG_SZ {:?}", text),
+            RegValue::ExpandSz(text) => write!(f, "REG_EXPAND_SZ {:?}", text),
+            RegValue::Binary(data) => write!(f, "REG_BINARY {} byte(s)", data.len()),
+            RegValue::Dword(value) => write!(f, "REG_DWORD 0x{:08x}", value),
+            RegValue::MultiSz(items) => write!(f, "REG_MULTI_SZ {:?}", items),
+            RegValue::Qword(value) => write!(f, "REG_QWORD 0x{:016x}", value),
+            RegValue::Other { kind, data } => write!(f, "type {} {} byte(s)", kind, data.len()),
+        }
+    }
+}
+
+impl RegValue {
+    pub fn kind(&self) -> u32 {
+        match self {
+            RegValue::Sz(_) => REG_SZ,
+            RegValue::ExpandSz(_) => REG_EXPAND_SZ,
+            RegValue::Binary(_) => REG_BINARY,
+            RegValue::Dword(_) => REG_DWORD,
+            RegValue::MultiSz(_) => REG_MULTI_SZ,
+            RegValue::Qword(_) => REG_QWORD,
+            RegValue::Other { kind, .. } => *kind,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            RegValue::Sz(text) | RegValue::ExpandSz(text) => utf16_terminated(text),
+            RegValue::Binary(data) | RegValue::Other { data, .. } => data.clone(),
+            RegValue::Dword(value) => value.to_le_bytes().to_vec(),
+            RegValue::Qword(value) => value.to_le_bytes().to_vec(),
+            RegValue::MultiSz(items) => {
+                let mut bytes: Vec<u8> = items.iter().flat_map(|item| utf16_terminated(item)).collect();
+                bytes.extend_from_slice(&[0, 0]);
+                bytes
+            }
+        }
+    }
+
+    /// Interpret raw value bytes of registry type `kind`
+    pub fn from_raw(kind: u32, data: Vec<u8>) -> Self {
+        match kind {
+            REG_SZ => RegValue::Sz(utf16_until_nul(&data)),
+            REG_EXPAND_SZ => RegValue::ExpandSz(utf16_until_nul(&data)),
+            REG_BINARY => RegValue::Binary(data),
+            REG_DWORD if data.len() >= 4 => RegValue::Dword(le_u32(&data, 0)),
+            REG_QWORD if data.len() >= 8 => RegValue::Qword(le_u64(&data, 0)),
+            REG_MULTI_SZ => {
+                let units: Vec<u16> = data.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+                RegValue::MultiSz(
+                    units
+                        .split(|unit| *unit == 0)
+                        .take_while(|item| !item.is_empty())
+                        .map(String::from_utf16_lossy)
+                        .collect(),
+                )
+            }
+            _ => RegValue::Other { kind, data },
+        }
+    }
+}
+
+/// In-memory regf hive (`Windows/System32/config/SYSTEM`, `SOFTWARE`, ...)
+///
+/// Edits are applied to the primary file image and written back by `save`, so
+/// offline images can be customized without `reg load` on an elevated Windows host.
+pub struct Hive {
+    data: Vec<u8>,
+}
+
+struct KeyNode {
+    subkey_count: u32,
+    subkey_list: u32,
+    value_count: u32,
+    value_list: u32,
+    security: u32,
+    name: String,
+}
+
+struct ValueNode {
+    name: String,
+    kind: u32,
+    size: u32,
+    data_offset: u32,
+}
+
+impl Hive {
+    /// Empty hive containing only a root key
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn new() -> Result<Self> {
+        let mut data = vec![0u8; BASE_BLOCK_SIZE];
+        data[0..4].copy_from_slice(b"regf");
+        put_u32(&mut data, 4, 1);
+        put_u32(&mut data, 8, 1);
+        put_u64(&mut data, 12, filetime_now());
+        put_u32(&mut data, 20, 1);
+        put_u32(&mut data, 24, 5);
+        put_u32(&mut data, 32, 1);
+        put_u32(&mut data, 44, 1);
+
+        let mut hive = Self { data };
+        let descriptor = default_security_descriptor();
+        let security = hive.alloc(20 + descriptor.len())?;
+        {
+            let cell = hive.cell_mut(security)?;
+            cell[0..2].copy_from_slice(b"sk");
+            put_u32(cell, 4, security);
+            put_u32(cell, 8, security);
+            put_u32(cell, 12, 0);
+            put_u32(cell, 16, descriptor.len() as u32);
+            cell[20..20 + descriptor.len()].copy_from_slice(&descriptor);
+        }
+
+        let root = hive.alloc_key_node("ROOT", NO_CELL, security, KEY_HIVE_ENTRY | KEY_NO_DELETE)?;
+        put_u32(&mut hive.data, 36, root);
+        Ok(hive)
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read registry hive {}", path.display()))?;
+        Self::from_bytes(data).with_context(|| format!("Invalid registry hive {}", path.display()))
+    }
+
+    pub fn from_bytes(mut data: Vec<u8>) -> Result<Self> {
+        if data.len() < BASE_BLOCK_SIZE || &data[0..4] != b"regf" {
+            bail!("missing regf signature");
+        }
+        if le_u32(&data, 508) != base_block_checksum(&data) {
+            bail!("base block checksum mismatch");
+        }
+        if le_u32(&data, 4) != le_u32(&data, 8) {
+            bail!("hive was not cleanly unloaded; its .LOG1/.LOG2 transaction logs must be replayed first");
+        }
+        if le_u32(&data, 20) != 1 {
+            bail!("unsupported regf major version {}", le_u32(&data, 20));
+        }
+
+        let bins_size = le_u32(&data, 40) as usize;
+        if bins_size == 0 || !bins_size.is_multiple_of(HBIN_ALIGNMENT) || BASE_BLOCK_SIZE + bins_size > data.len() {
+            bail!("hive bins size {} does not match the file", bins_size);
+        }
+        data.truncate(BASE_BLOCK_SIZE + bins_size);
+
+        let hive = Self { data };
+        hive.key_node(hive.root_offset())
+            .context("root key is unreadable")?;
+        Ok(hive)
+    }
+
+    /// Write the hive back, bumping the sequence numbers and checksum
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        let sequence = le_u32(&self.data, 4).wrapping_add(1);
+        put_u32(&mut self.data, 4, sequence);
+        put_u32(&mut self.data, 8, sequence);
+        put_u64(&mut self.data, 12, filetime_now());
+        let checksum = base_block_checksum(&self.data);
+        put_u32(&mut self.data, 508, checksum);
+
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(".ghostwin-tmp");
+        let temp_path = path.with_file_name(temp_name);
+        std::fs::write(&temp_path, &self.data)
+            .with_context(|| format!("Failed to write registry hive {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to replace registry hive {}", path.display()))?;
+        Ok(())
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn get_value(&self, key_path: &str, name: &str) -> Result<Option<RegValue>> {
+        let Some(key) = self.find_key(key_path)? else {
+            return Ok(None);
+        };
+        let node = self.key_node(key)?;
+        match self.find_value(&node, name)? {
+            Some(offset) => {
+                let value = self.value_node(offset)?;
+                Ok(Some(RegValue::from_raw(value.kind, self.value_data(&value)?)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn values(&self, key_path: &str) -> Result<Vec<(String, RegValue)>> {
+        let key = self.require_key(key_path)?;
+        let node = self.key_node(key)?;
+        self.value_offsets(&node)?
+            .into_iter()
+            .map(|offset| {
+                let value = self.value_node(offset)?;
+                let data = self.value_data(&value)?;
+                Ok((value.name, RegValue::from_raw(value.kind, data)))
+            })
+            .collect()
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn subkeys(&self, key_path: &str) -> Result<Vec<String>> {
+        let key = self.require_key(key_path)?;
+        let node = self.key_node(key)?;
+        self.child_offsets(&node)?
+            .into_iter()
+            .map(|offset| Ok(self.key_node(offset)?.name))
+            .collect()
+    }
+
+    /// Resolve `Select\Current` of a SYSTEM hive to its `ControlSetNNN` key name
+    pub fn current_control_set(&self) -> Result<String> {
+        match self.get_value("Select", "Current")? {
+            Some(RegValue::Dword(current)) => {
+                let control_set = format!("ControlSet{:03}", current);
+                if self.find_key(&control_set)?.is_none() {
+                    bail!("Select\\Current points at {} which does not exist", control_set);
+                }
+                Ok(control_set)
+            }
+            Some(other) => bail!("Select\\Current is not a REG_DWORD (type {})", other.kind()),
+            None => bail!("SYSTEM hive has no Select\\Current value"),
+        }
+    }
+
+    /// Create every missing key along `key_path` and return the final key's cell
+    pub fn create_key(&mut self, key_path: &str) -> Result<u32> {
+        let mut current = self.root_offset();
+        for component in path_components(key_path) {
+            let node = self.key_node(current)?;
+            current = match self.find_child(&node, component)? {
+                Some(child) => child,
+                None => self.add_subkey(current, component)?,
+            };
+        }
+        Ok(current)
+    }
+
+    /// Set (or replace) a value, creating the key path as needed
+    pub fn set_value(&mut self, key_path: &str, name: &str, value: &RegValue) -> Result<()> {
+        let key = self.create_key(key_path)?;
+        let node = self.key_node(key)?;
+        let data = value.to_bytes();
+        let existing = self.find_value(&node, name)?;
+        let (size, data_offset) = self.store_value_data(&data)?;
+
+        match existing {
+            Some(offset) => {
+                let old = self.value_node(offset)?;
+                self.free_value_data(&old)?;
+                let cell = self.cell_mut(offset)?;
+                put_u32(cell, 4, size);
+                put_u32(cell, 8, data_offset);
+                put_u32(cell, 12, value.kind());
+            }
+            None => {
+                let (name_bytes, compressed) = encode_name(name);
+                let offset = self.alloc(VALUE_NODE_HEADER + name_bytes.len())?;
+                {
+                    let cell = self.cell_mut(offset)?;
+                    cell[0..2].copy_from_slice(b"vk");
+                    put_u16(cell, 2, name_bytes.len() as u16);
+                    put_u32(cell, 4, size);
+                    put_u32(cell, 8, data_offset);
+                    put_u32(cell, 12, value.kind());
+                    put_u16(cell, 16, if compressed { VALUE_COMP_NAME } else { 0 });
+                    cell[VALUE_NODE_HEADER..VALUE_NODE_HEADER + name_bytes.len()].copy_from_slice(&name_bytes);
+                }
+
+                let mut offsets = self.value_offsets(&node)?;
+                offsets.push(offset);
+                let list = self.alloc(offsets.len() * 4)?;
+                {
+                    let cell = self.cell_mut(list)?;
+                    for (position, value_offset) in offsets.iter().enumerate() {
+                        put_u32(cell, position * 4, *value_offset);
+                    }
+                }
+                if node.value_count > 0 {
+                    self.free(node.value_list);
+                }
+
+                let cell = self.cell_mut(key)?;
+                put_u32(cell, 36, offsets.len() as u32);
+                put_u32(cell, 40, list);
+                let name_length = (name.encode_utf16().count() * 2) as u32;
+                if name_length > le_u32(cell, 60) {
+                    put_u32(cell, 60, name_length);
+                }
+            }
+        }
+
+        let cell = self.cell_mut(key)?;
+        if data.len() as u32 > le_u32(cell, 64) {
+            put_u32(cell, 64, data.len() as u32);
+        }
+        put_u64(cell, 4, filetime_now());
+        Ok(())
+    }
+
+    /// Remove a value; returns false when the key or value does not exist
+    pub fn delete_value(&mut self, key_path: &str, name: &str) -> Result<bool> {
+        let Some(key) = self.find_key(key_path)? else {
+            return Ok(false);
+        };
+        let node = self.key_node(key)?;
+        let Some(offset) = self.find_value(&node, name)? else {
+            return Ok(false);
+        };
+
+        let value = self.value_node(offset)?;
+        self.free_value_data(&value)?;
+        self.free(offset);
+
+        let remaining: Vec<u32> = self
+            .value_offsets(&node)?
+            .into_iter()
+            .filter(|candidate| *candidate != offset)
+            .collect();
+        if remaining.is_empty() {
+            self.free(node.value_list);
+        } else {
+            let list = self.cell_mut(node.value_list)?;
+            for (position, value_offset) in remaining.iter().enumerate() {
+                put_u32(list, position * 4, *value_offset);
+            }
+        }
+
+        let cell = self.cell_mut(key)?;
+        put_u32(cell, 36, remaining.len() as u32);
+        if remaining.is_empty() {
+            put_u32(cell, 40, NO_CELL);
+        }
+        put_u64(cell, 4, filetime_now());
+        Ok(true)
+    }
+
+    /// Remove a key and everything below it; returns false when it does not exist
+    pub fn delete_key(&mut self, key_path: &str) -> Result<bool> {
+        let components: Vec<&str> = path_components(key_path).collect();
+        let Some((name, parent_components)) = components.split_last() else {
+            bail!("Refusing to delete the hive root key");
+        };
+        let Some(parent) = self.find_key(&parent_components.join("\\"))? else {
+            return Ok(false);
+        };
+        let parent_node = self.key_node(parent)?;
+        let Some(child) = self.find_child(&parent_node, name)? else {
+            return Ok(false);
+        };
+
+        let remaining: Vec<u32> = self
+            .child_offsets(&parent_node)?
+            .into_iter()
+            .filter(|candidate| *candidate != child)
+            .collect();
+        self.free_key_tree(child, 0)?;
+        self.replace_subkey_list(parent, &parent_node, remaining)?;
+        Ok(true)
+    }
+
+    fn root_offset(&self) -> u32 {
+        le_u32(&self.data, 36)
+    }
+
+    fn find_key(&self, key_path: &str) -> Result<Option<u32>> {
+        let mut current = self.root_offset();
+        for component in path_components(key_path) {
+            let node = self.key_node(current)?;
+            match self.find_child(&node, component)? {
+                Some(child) => current = child,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(current))
+    }
+
+    fn require_key(&self, key_path: &str) -> Result<u32> {
+        self.find_key(key_path)?
+            .ok_or_else(|| anyhow::anyhow!("Registry key not found: {}", key_path))
+    }
+
+    fn find_child(&self, node: &KeyNode, name: &str) -> Result<Option<u32>> {
+        for offset in self.child_offsets(node)? {
+            if names_equal(&self.key_node(offset)?.name, name) {
+                return Ok(Some(offset));
+            }
+        }
+        Ok(None)
+    }
+
+    fn find_value(&self, node: &KeyNode, name: &str) -> Result<Option<u32>> {
+        for offset in self.value_offsets(node)? {
+            if names_equal(&self.value_node(offset)?.name, name) {
+                return Ok(Some(offset));
+            }
+        }
+        Ok(None)
+    }
+
+    fn key_node(&self, offset: u32) -> Result<KeyNode> {
+        let cell = self.cell(offset)?;
+        if cell.len() < KEY_NODE_HEADER || &cell[0..2] != b"nk" {
+            bail!("cell {:#x} is not a key node", offset);
+        }
+        let name_length = le_u16(cell, 72) as usize;
+        if KEY_NODE_HEADER + name_length > cell.len() {
+            bail!("key node {:#x} name overruns its cell", offset);
+        }
+
+        Ok(KeyNode {
+            subkey_count: le_u32(cell, 20),
+            subkey_list: le_u32(cell, 28),
+            value_count: le_u32(cell, 36),
+            value_list: le_u32(cell, 40),
+            security: le_u32(cell, 44),
+            name: decode_name(
+                &cell[KEY_NODE_HEADER..KEY_NODE_HEADER + name_length],
+                le_u16(cell, 2) & KEY_COMP_NAME != 0,
+            ),
+        })
+    }
+
+    fn value_node(&self, offset: u32) -> Result<ValueNode> {
+        let cell = self.cell(offset)?;
+        if cell.len() < VALUE_NODE_HEADER || &cell[0..2] != b"vk" {
+            bail!("cell {:#x} is not a value node", offset);
+        }
+        let name_length = le_u16(cell, 2) as usize;
+        if VALUE_NODE_HEADER + name_length > cell.len() {
+            bail!("value node {:#x} name overruns its cell", offset);
+        }
+
+        Ok(ValueNode {
+            name: decode_name(
+                &cell[VALUE_NODE_HEADER..VALUE_NODE_HEADER + name_length],
+                le_u16(cell, 16) & VALUE_COMP_NAME != 0,
+            ),
+            kind: le_u32(cell, 12),
+            size: le_u32(cell, 4),
+            data_offset: le_u32(cell, 8),
+        })
+    }
+
+    fn child_offsets(&self, node: &KeyNode) -> Result<Vec<u32>> {
+        let mut offsets = Vec::new();
+        if node.subkey_count > 0 {
+            self.collect_subkey_list(node.subkey_list, 0, &mut offsets)?;
+        }
+        Ok(offsets)
+    }
+
+    fn collect_subkey_list(&self, list: u32, depth: usize, offsets: &mut Vec<u32>) -> Result<()> {
+        if depth > MAX_INDEX_DEPTH {
+            bail!("subkey index nesting is too deep at {:#x}", list);
+        }
+        let cell = self.cell(list)?;
+        if cell.len() < 4 {
+            bail!("subkey list {:#x} is truncated", list);
+        }
+        let count = le_u16(cell, 2) as usize;
+        let stride = match &cell[0..2] {
+            b"lf" | b"lh" => 8,
+            b"li" | b"ri" => 4,
+            _ => bail!("cell {:#x} is not a subkey list", list),
+        };
+        if 4 + count * stride > cell.len() {
+            bail!("subkey list {:#x} overruns its cell", list);
+        }
+
+        let is_index = &cell[0..2] == b"ri";
+        for position in 0..count {
+            let offset = le_u32(cell, 4 + position * stride);
+            if is_index {
+                self.collect_subkey_list(offset, depth + 1, offsets)?;
+            } else {
+                offsets.push(offset);
+            }
+        }
+        Ok(())
+    }
+
+    fn value_offsets(&self, node: &KeyNode) -> Result<Vec<u32>> {
+        if node.value_count == 0 {
+            return Ok(Vec::new());
+        }
+        let cell = self.cell(node.value_list)?;
+        let count = node.value_count as usize;
+        if count * 4 > cell.len() {
+            bail!("value list {:#x} overruns its cell", node.value_list);
+        }
+        Ok((0..count).map(|position| le_u32(cell, position * 4)).collect())
+    }
+
+    fn value_data(&self, value: &ValueNode) -> Result<Vec<u8>> {
+        if value.size & INLINE_DATA_FLAG != 0 {
+            let length = ((value.size & !INLINE_DATA_FLAG) as usize).min(4);
+            return Ok(value.data_offset.to_le_bytes()[..length].to_vec());
+        }
+
+        let length = value.size as usize;
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+
+        let cell = self.cell(value.data_offset)?;
+        if length > MAX_DATA_SEGMENT && cell.len() >= 8 && &cell[0..2] == b"db" {
+            let segments = self.big_data_segments(cell)?;
+            let mut data = Vec::with_capacity(length);
+            for segment in segments {
+                let segment = self.cell(segment)?;
+                let take = segment.len().min(MAX_DATA_SEGMENT).min(length - data.len());
+                data.extend_from_slice(&segment[..take]);
+            }
+            if data.len() != length {
+                bail!("big data value {:#x} is truncated", value.data_offset);
+            }
+            return Ok(data);
+        }
+
+        if length > cell.len() {
+            bail!("value data {:#x} overruns its cell", value.data_offset);
+        }
+        Ok(cell[..length].to_vec())
+    }
+
+    fn big_data_segments(&self, db_cell: &[u8]) -> Result<Vec<u32>> {
+        let count = le_u16(db_cell, 2) as usize;
+        let list = self.cell(le_u32(db_cell, 4))?;
+        if count * 4 > list.len() {
+            bail!("big data segment list is truncated");
+        }
+        Ok((0..count).map(|position| le_u32(list, position * 4)).collect())
+    }
+
+    /// Returns the (size, offset) pair stored in the value node
+    fn store_value_data(&mut self, data: &[u8]) -> Result<(u32, u32)> {
+        if data.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..data.len()].copy_from_slice(data);
+            return Ok((data.len() as u32 | INLINE_DATA_FLAG, u32::from_le_bytes(inline)));
+        }
+
+        if data.len() <= MAX_DATA_SEGMENT {
+            let cell = self.alloc(data.len())?;
+            self.cell_mut(cell)?[..data.len()].copy_from_slice(data);
+            return Ok((data.len() as u32, cell));
+        }
+
+        let mut segments = Vec::new();
+        for chunk in data.chunks(MAX_DATA_SEGMENT) {
+            let segment = self.alloc(chunk.len())?;
+            self.cell_mut(segment)?[..chunk.len()].copy_from_slice(chunk);
+            segments.push(segment);
+        }
+        let list = self.alloc(segments.len() * 4)?;
+        {
+            let cell = self.cell_mut(list)?;
+            for (position, segment) in segments.iter().enumerate() {
+                put_u32(cell, position * 4, *segment);
+            }
+        }
+        let db = self.alloc(8)?;
+        let cell = self.cell_mut(db)?;
+        cell[0..2].copy_from_slice(b"db");
+        put_u16(cell, 2, segments.len() as u16);
+        put_u32(cell, 4, list);
+        Ok((data.len() as u32, db))
+    }
+
+    fn free_value_data(&mut self, value: &ValueNode) -> Result<()> {
+        if value.size & INLINE_DATA_FLAG != 0 || value.size == 0 || value.data_offset == NO_CELL {
+            return Ok(());
+        }
+
+        let cell = self.cell(value.data_offset)?;
+        if value.size as usize > MAX_DATA_SEGMENT && cell.len() >= 8 && &cell[0..2] == b"db" {
+            let list = le_u32(cell, 4);
+            for segment in self.big_data_segments(cell)? {
+                self.free(segment);
+            }
+            self.free(list);
+        }
+        self.free(value.data_offset);
+        Ok(())
+    }
+
+    fn add_subkey(&mut self, parent: u32, name: &str) -> Result<u32> {
+        if name.is_empty() || name.chars().count() > 255 {
+            bail!("Invalid registry key name '{}'", name);
+        }
+
+        let parent_node = self.key_node(parent)?;
+        let child = self.alloc_key_node(name, parent, parent_node.security, 0)?;
+
+        let mut children = self.child_offsets(&parent_node)?;
+        children.push(child);
+        self.replace_subkey_list(parent, &parent_node, children)?;
+
+        let cell = self.cell_mut(parent)?;
+        let name_length = (name.encode_utf16().count() * 2) as u16;
+        if name_length > le_u16(cell, 52) {
+            put_u16(cell, 52, name_length);
+        }
+        Ok(child)
+    }
+
+    /// Write a fresh sorted list for `children` and release the parent's old one
+    fn replace_subkey_list(&mut self, parent: u32, parent_node: &KeyNode, children: Vec<u32>) -> Result<()> {
+        let mut named = children
+            .into_iter()
+            .map(|offset| Ok((self.key_node(offset)?.name.to_uppercase(), offset)))
+            .collect::<Result<Vec<_>>>()?;
+        named.sort();
+
+        let list = if named.is_empty() { NO_CELL } else { self.write_subkey_list(&named)? };
+        if parent_node.subkey_count > 0 {
+            self.free_subkey_list(parent_node.subkey_list, 0)?;
+        }
+
+        let cell = self.cell_mut(parent)?;
+        put_u32(cell, 20, named.len() as u32);
+        put_u32(cell, 28, list);
+        put_u64(cell, 4, filetime_now());
+        Ok(())
+    }
+
+    /// Free a key node together with its values, subkeys, class name, and security reference
+    fn free_key_tree(&mut self, offset: u32, depth: usize) -> Result<()> {
+        if depth > MAX_KEY_DEPTH {
+            bail!("key nesting is too deep at {:#x}", offset);
+        }
+
+        let node = self.key_node(offset)?;
+        for child in self.child_offsets(&node)? {
+            self.free_key_tree(child, depth + 1)?;
+        }
+        if node.subkey_count > 0 {
+            self.free_subkey_list(node.subkey_list, 0)?;
+        }
+
+        for value_offset in self.value_offsets(&node)? {
+            let value = self.value_node(value_offset)?;
+            self.free_value_data(&value)?;
+            self.free(value_offset);
+        }
+        if node.value_count > 0 {
+            self.free(node.value_list);
+        }
+
+        let class_name = le_u32(self.cell(offset)?, 48);
+        if le_u16(self.cell(offset)?, 74) > 0 {
+            self.free(class_name);
+        }
+        if node.security != NO_CELL {
+            self.release_security(node.security)?;
+        }
+        self.free(offset);
+        Ok(())
+    }
+
+    /// Drop one reference to an `sk` cell, unlinking it from the descriptor list when unused
+    fn release_security(&mut self, security: u32) -> Result<()> {
+        let cell = self.cell_mut(security)?;
+        if &cell[0..2] != b"sk" {
+            bail!("cell {:#x} is not a security descriptor", security);
+        }
+        let references = le_u32(cell, 12).saturating_sub(1);
+        put_u32(cell, 12, references);
+        if references > 0 {
+            return Ok(());
+        }
+
+        let (next, previous) = (le_u32(cell, 4), le_u32(cell, 8));
+        if next != security {
+            put_u32(self.cell_mut(previous)?, 4, next);
+            put_u32(self.cell_mut(next)?, 8, previous);
+        }
+        self.free(security);
+        Ok(())
+    }
+
+    fn alloc_key_node(&mut self, name: &str, parent: u32, security: u32, flags: u16) -> Result<u32> {
+        let (name_bytes, compressed) = encode_name(name);
+        let offset = self.alloc(KEY_NODE_HEADER + name_bytes.len())?;
+        {
+            let cell = self.cell_mut(offset)?;
+            cell[0..2].copy_from_slice(b"nk");
+            put_u16(cell, 2, flags | if compressed { KEY_COMP_NAME } else { 0 });
+            put_u64(cell, 4, filetime_now());
+            put_u32(cell, 16, parent);
+            put_u32(cell, 28, NO_CELL);
+            put_u32(cell, 32, NO_CELL);
+            put_u32(cell, 40, NO_CELL);
+            put_u32(cell, 44, security);
+            put_u32(cell, 48, NO_CELL);
+            put_u16(cell, 72, name_bytes.len() as u16);
+            cell[KEY_NODE_HEADER..KEY_NODE_HEADER + name_bytes.len()].copy_from_slice(&name_bytes);
+        }
+
+        if security != NO_CELL {
+            let cell = self.cell_mut(security)?;
+            if &cell[0..2] != b"sk" {
+                bail!("cell {:#x} is not a security descriptor", security);
+            }
+            let references = le_u32(cell, 12).saturating_add(1);
+            put_u32(cell, 12, references);
+        }
+        Ok(offset)
+    }
+
+    fn write_subkey_list(&mut self, named: &[(String, u32)]) -> Result<u32> {
+        if named.len() <= MAX_LEAF_ENTRIES {
+            return self.write_hash_leaf(named);
+        }
+
+        let mut leaves = Vec::new();
+        for chunk in named.chunks(MAX_LEAF_ENTRIES) {
+            leaves.push(self.write_hash_leaf(chunk)?);
+        }
+        let index = self.alloc(4 + leaves.len() * 4)?;
+        let cell = self.cell_mut(index)?;
+        cell[0..2].copy_from_slice(b"ri");
+        put_u16(cell, 2, leaves.len() as u16);
+        for (position, leaf) in leaves.iter().enumerate() {
+            put_u32(cell, 4 + position * 4, *leaf);
+        }
+        Ok(index)
+    }
+
+    fn write_hash_leaf(&mut self, named: &[(String, u32)]) -> Result<u32> {
+        let leaf = self.alloc(4 + named.len() * 8)?;
+        let cell = self.cell_mut(leaf)?;
+        cell[0..2].copy_from_slice(b"lh");
+        put_u16(cell, 2, named.len() as u16);
+        for (position, (upper_name, offset)) in named.iter().enumerate() {
+            put_u32(cell, 4 + position * 8, *offset);
+            put_u32(cell, 8 + position * 8, name_hash(upper_name));
+        }
+        Ok(leaf)
+    }
+
+    fn free_subkey_list(&mut self, list: u32, depth: usize) -> Result<()> {
+        if depth > MAX_INDEX_DEPTH {
+            bail!("subkey index nesting is too deep at {:#x}", list);
+        }
+        let cell = self.cell(list)?;
+        if &cell[0..2] == b"ri" {
+            let count = le_u16(cell, 2) as usize;
+            let leaves: Vec<u32> = (0..count).map(|position| le_u32(cell, 4 + position * 4)).collect();
+            for leaf in leaves {
+                self.free_subkey_list(leaf, depth + 1)?;
+            }
+        }
+        self.free(list);
+        Ok(())
+    }
+
+    fn cell_range(&self, offset: u32) -> Result<std::ops::Range<usize>> {
+        let start = BASE_BLOCK_SIZE + offset as usize;
+        if offset == NO_CELL || start + 4 > self.data.len() {
+            bail!("cell offset {:#x} is outside the hive", offset);
+        }
+        let size = le_i32(&self.data, start);
+        if size >= 0 {
+            bail!("cell {:#x} is not allocated", offset);
+        }
+        let size = size.unsigned_abs() as usize;
+        if size < 4 || start + size > self.data.len() {
+            bail!("cell {:#x} has an invalid size", offset);
+        }
+        Ok(start + 4..start + size)
+    }
+
+    fn cell(&self, offset: u32) -> Result<&[u8]> {
+        Ok(&self.data[self.cell_range(offset)?])
+    }
+
+    fn cell_mut(&mut self, offset: u32) -> Result<&mut [u8]> {
+        let range = self.cell_range(offset)?;
+        Ok(&mut self.data[range])
+    }
+
+    /// First-fit allocation over the free cells, appending a new bin when nothing fits
+    fn alloc(&mut self, content_size: usize) -> Result<u32> {
+        let needed = (content_size + 4).next_multiple_of(CELL_ALIGNMENT);
+        let mut bin = BASE_BLOCK_SIZE;
+
+        while bin < self.data.len() {
+            if bin + HBIN_HEADER_SIZE > self.data.len() || &self.data[bin..bin + 4] != b"hbin" {
+                bail!("hive bin at {:#x} has a bad signature", bin - BASE_BLOCK_SIZE);
+            }
+            let bin_size = le_u32(&self.data, bin + 8) as usize;
+            if bin_size < HBIN_HEADER_SIZE || bin + bin_size > self.data.len() {
+                bail!("hive bin at {:#x} has an invalid size", bin - BASE_BLOCK_SIZE);
+            }
+
+            let mut cell = bin + HBIN_HEADER_SIZE;
+            while cell < bin + bin_size {
+                let size = le_i32(&self.data, cell);
+                let length = size.unsigned_abs() as usize;
+                if length < CELL_ALIGNMENT || cell + length > bin + bin_size {
+                    bail!("corrupt cell at {:#x}", cell - BASE_BLOCK_SIZE);
+                }
+                if size > 0 && length >= needed {
+                    self.claim(cell, length, needed);
+                    return Ok((cell - BASE_BLOCK_SIZE) as u32);
+                }
+                cell += length;
+            }
+            bin += bin_size;
+        }
+
+        let (cell, length) = self.append_bin(needed);
+        self.claim(cell, length, needed);
+        Ok((cell - BASE_BLOCK_SIZE) as u32)
+    }
+
+    fn claim(&mut self, cell: usize, free_length: usize, needed: usize) {
+        let length = if free_length - needed >= CELL_ALIGNMENT {
+            put_u32(&mut self.data, cell + needed, (free_length - needed) as u32);
+            needed
+        } else {
+            free_length
+        };
+        put_u32(&mut self.data, cell, (length as i32).wrapping_neg() as u32);
+        self.data[cell + 4..cell + length].fill(0);
+    }
+
+    /// Returns the position and length of the new bin's single free cell
+    fn append_bin(&mut self, needed: usize) -> (usize, usize) {
+        let bin = self.data.len();
+        let bin_size = (needed + HBIN_HEADER_SIZE).next_multiple_of(HBIN_ALIGNMENT);
+        self.data.resize(bin + bin_size, 0);
+
+        self.data[bin..bin + 4].copy_from_slice(b"hbin");
+        put_u32(&mut self.data, bin + 4, (bin - BASE_BLOCK_SIZE) as u32);
+        put_u32(&mut self.data, bin + 8, bin_size as u32);
+        put_u64(&mut self.data, bin + 20, filetime_now());
+
+        let cell = bin + HBIN_HEADER_SIZE;
+        let length = bin_size - HBIN_HEADER_SIZE;
+        put_u32(&mut self.data, cell, length as u32);
+
+        let bins_size = (self.data.len() - BASE_BLOCK_SIZE) as u32;
+        put_u32(&mut self.data, 40, bins_size);
+        (cell, length)
+    }
+
+    fn free(&mut self, offset: u32) {
+        let start = BASE_BLOCK_SIZE + offset as usize;
+        if offset == NO_CELL || start + 4 > self.data.len() {
+            return;
+        }
+        let size = le_i32(&self.data, start);
+        if size < 0 {
+            put_u32(&mut self.data, start, size.unsigned_abs());
+        }
+    }
+}
+
+fn path_components(key_path: &str) -> impl Iterator<Item = &str> {
+    key_path.split(['\\', '/']).filter(|component| !component.is_empty())
+}
+
+fn names_equal(left: &str, right: &str) -> bool {
+    left.eq_ignore_ascii_case(right) || left.to_uppercase() == right.to_uppercase()
+}
+
+/// `lh` leaf hash over the upper-cased UTF-16 name
+fn name_hash(upper_name: &str) -> u32 {
+    upper_name
+        .encode_utf16()
+        .fold(0u32, |hash, unit| hash.wrapping_mul(37).wrapping_add(u32::from(unit)))
+}
+
+/// Names that fit in Latin-1 are stored "compressed" (one byte per character)
+fn encode_name(name: &str) -> (Vec<u8>, bool) {
+    if name.chars().all(|character| (character as u32) <= 0xFF) {
+        (name.chars().map(|character| character as u8).collect(), true)
+    } else {
+        (name.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect(), false)
+    }
+}
+
+fn decode_name(bytes: &[u8], compressed: bool) -> String {
+    if compressed {
+        bytes.iter().map(|byte| char::from(*byte)).collect()
+    } else {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    }
+}
+
+fn utf16_terminated(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect()
+}
+
+fn utf16_until_nul(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// XOR of the first 127 dwords, with 0 and -1 remapped as Windows does
+fn base_block_checksum(data: &[u8]) -> u32 {
+    let checksum = (0..127).fold(0u32, |checksum, index| checksum ^ le_u32(data, index * 4));
+    match checksum {
+        0 => 1,
+        0xFFFF_FFFF => 0xFFFF_FFFE,
+        other => other,
+    }
+}
+
+fn filetime_now() -> u64 {
+    const UNIX_EPOCH_AS_FILETIME: u64 = 116_444_736_000_000_000;
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    UNIX_EPOCH_AS_FILETIME + (since_epoch.as_nanos() / 100) as u64
+}
+
+/// Self-relative descriptor: owner Administrators, group SYSTEM, DACL granting
+/// SYSTEM/Administrators full control and Everyone read, inherited by subkeys
+#[cfg_attr(not(test), allow(dead_code))]
+fn default_security_descriptor() -> Vec<u8> {
+    const SYSTEM_SID: [u8; 12] = [1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0];
+    const ADMINISTRATORS_SID: [u8; 16] = [1, 2, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0, 0x20, 0x02, 0, 0];
+    const EVERYONE_SID: [u8; 12] = [1, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0];
+    const KEY_ALL_ACCESS: u32 = 0x000F_003F;
+    const KEY_READ: u32 = 0x0002_0019;
+    const CONTAINER_INHERIT_ACE: u8 = 0x02;
+
+    let aces: [(u32, &[u8]); 3] = [
+        (KEY_ALL_ACCESS, &SYSTEM_SID),
+        (KEY_ALL_ACCESS, &ADMINISTRATORS_SID),
+        (KEY_READ, &EVERYONE_SID),
+    ];
+    let mut acl = Vec::new();
+    for (mask, sid) in aces {
+        acl.push(0);
+        acl.push(CONTAINER_INHERIT_ACE);
+        acl.extend_from_slice(&((8 + sid.len()) as u16).to_le_bytes());
+        acl.extend_from_slice(&mask.to_le_bytes());
+        acl.extend_from_slice(sid);
+    }
+
+    let owner = 20u32;
+    let group = owner + ADMINISTRATORS_SID.len() as u32;
+    let dacl = group + SYSTEM_SID.len() as u32;
+
+    let mut descriptor = vec![1, 0];
+    descriptor.extend_from_slice(&0x8004u16.to_le_bytes());
+    descriptor.extend_from_slice(&owner.to_le_bytes());
+    descriptor.extend_from_slice(&group.to_le_bytes());
+    descriptor.extend_from_slice(&0u32.to_le_bytes());
+    descriptor.extend_from_slice(&dacl.to_le_bytes());
+    descriptor.extend_from_slice(&ADMINISTRATORS_SID);
+    descriptor.extend_from_slice(&SYSTEM_SID);
+    descriptor.extend_from_slice(&[2, 0]);
+    descriptor.extend_from_slice(&((8 + acl.len()) as u16).to_le_bytes());
+    descriptor.extend_from_slice(&(aces.len() as u16).to_le_bytes());
+    descriptor.extend_from_slice(&[0, 0]);
+    descriptor.extend_from_slice(&acl);
+    descriptor
+}
+
+fn le_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn le_i32(data: &[u8], offset: usize) -> i32 {
+    le_u32(data, offset) as i32
+}
+
+fn le_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from(le_u32(data, offset)) | (u64::from(le_u32(data, offset + 4)) << 32)
+}
+
+fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hive, RegValue};
+    use tempfile::tempdir;
+
+    #[test]
+    fn round_trips_every_value_type_through_a_saved_hive() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("SOFTWARE");
+        let big = (0..40_000u32).map(|index| index as u8).collect::<Vec<_>>();
+
+        let mut hive = Hive::new().unwrap();
+        let key = r"Microsoft\Windows\CurrentVersion\SideBySide";
+        hive.set_value(key, "PreferExternalManifest", &RegValue::Dword(1)).unwrap();
+        hive.set_value(key, "Label", &RegValue::Sz("GhostWin".to_string())).unwrap();
+        hive.set_value(key, "Path", &RegValue::ExpandSz(r"%SystemRoot%\System32".to_string())).unwrap();
+        hive.set_value(key, "Stages", &RegValue::MultiSz(vec!["pe".to_string(), "logon".to_string()])).unwrap();
+        hive.set_value(key, "Blob", &RegValue::Binary(vec![1, 2, 3, 4, 5, 6])).unwrap();
+        hive.set_value(key, "Big", &RegValue::Binary(big.clone())).unwrap();
+        hive.set_value(key, "Counter", &RegValue::Qword(1 << 40)).unwrap();
+        hive.set_value(key, "", &RegValue::Sz("default".to_string())).unwrap();
+        hive.set_value("Ünïcode\\键", "名前", &RegValue::Dword(7)).unwrap();
+        hive.save(&path).unwrap();
+
+        let hive = Hive::open(&path).unwrap();
+        let key = r"microsoft/windows/currentversion/sidebyside";
+        assert_eq!(hive.get_value(key, "preferexternalmanifest").unwrap(), Some(RegValue::Dword(1)));
+        assert_eq!(hive.get_value(key, "Label").unwrap(), Some(RegValue::Sz("GhostWin".to_string())));
+        assert_eq!(
+            hive.get_value(key, "Path").unwrap(),
+            Some(RegValue::ExpandSz(r"%SystemRoot%\System32".to_string()))
+        );
+        assert_eq!(
+            hive.get_value(key, "Stages").unwrap(),
+            Some(RegValue::MultiSz(vec!["pe".to_string(), "logon".to_string()]))
+        );
+        assert_eq!(hive.get_value(key, "Blob").unwrap(), Some(RegValue::Binary(vec![1, 2, 3, 4, 5, 6])));
+        assert_eq!(hive.get_value(key, "Big").unwrap(), Some(RegValue::Binary(big)));
+        assert_eq!(hive.get_value(key, "Counter").unwrap(), Some(RegValue::Qword(1 << 40)));
+        assert_eq!(hive.get_value(key, "").unwrap(), Some(RegValue::Sz("default".to_string())));
+        assert_eq!(hive.get_value("ünïcode\\键", "名前").unwrap(), Some(RegValue::Dword(7)));
+        assert_eq!(hive.get_value(key, "Missing").unwrap(), None);
+        assert_eq!(hive.get_value("No\\Such\\Key", "Value").unwrap(), None);
+        assert_eq!(hive.values(key).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn resolves_current_control_set_from_select() {
+        let mut hive = Hive::new().unwrap();
+        hive.create_key(r"ControlSet002\Control").unwrap();
+        hive.set_value("Select", "Current", &RegValue::Dword(2)).unwrap();
+        assert_eq!(hive.current_control_set().unwrap(), "ControlSet002");
+
+        hive.set_value("Select", "Current", &RegValue::Dword(3)).unwrap();
+        let error = hive.current_control_set().unwrap_err();
+        assert!(error.to_string().contains("ControlSet003 which does not exist"));
+
+        let error = Hive::new().unwrap().current_control_set().unwrap_err();
+        assert!(error.to_string().contains("no Select\\Current"));
+    }
+
+    #[test]
+    fn keeps_large_subkey_lists_sorted_and_searchable() {
+        let mut hive = Hive::new().unwrap();
+        for index in (0..1100).rev() {
+            hive.create_key(&format!(r"Services\svc{:04}", index)).unwrap();
+        }
+
+        let subkeys = hive.subkeys("Services").unwrap();
+        assert_eq!(subkeys.len(), 1100);
+        assert_eq!(subkeys.first().map(String::as_str), Some("svc0000"));
+        assert_eq!(subkeys.last().map(String::as_str), Some("svc1099"));
+        assert!(subkeys.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("SYSTEM");
+        hive.set_value(r"SERVICES\SVC0500", "Start", &RegValue::Dword(3)).unwrap();
+        hive.save(&path).unwrap();
+        let hive = Hive::open(&path).unwrap();
+        assert_eq!(hive.get_value(r"Services\svc0500", "Start").unwrap(), Some(RegValue::Dword(3)));
+    }
+
+    #[test]
+    fn reuses_freed_cells_when_values_are_replaced() {
+        let mut hive = Hive::new().unwrap();
+        hive.set_value("Key", "Blob", &RegValue::Binary(vec![0xAA; 200])).unwrap();
+        let size = hive.data.len();
+
+        for round in 0..50u8 {
+            hive.set_value("Key", "Blob", &RegValue::Binary(vec![round; 200])).unwrap();
+        }
+        hive.set_value("Key", "Blob", &RegValue::Dword(9)).unwrap();
+
+        assert_eq!(hive.data.len(), size);
+        assert_eq!(hive.get_value("Key", "Blob").unwrap(), Some(RegValue::Dword(9)));
+    }
+
+    #[test]
+    fn deletes_values_and_key_trees() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("SOFTWARE");
+        let mut hive = Hive::new().unwrap();
+        hive.set_value(r"Policies\Vendor", "Keep", &RegValue::Dword(1)).unwrap();
+        hive.set_value(r"Policies\Vendor", "Drop", &RegValue::Binary(vec![7; 64])).unwrap();
+        hive.set_value(r"Policies\Vendor\Nested\Deep", "Value", &RegValue::Sz("x".to_string())).unwrap();
+        hive.create_key(r"Policies\Other").unwrap();
+
+        assert!(hive.delete_value(r"Policies\Vendor", "drop").unwrap());
+        assert!(!hive.delete_value(r"Policies\Vendor", "drop").unwrap());
+        assert!(!hive.delete_value(r"Policies\Missing", "Keep").unwrap());
+        assert!(hive.delete_key(r"Policies\Vendor\Nested").unwrap());
+        assert!(!hive.delete_key(r"Policies\Vendor\Nested").unwrap());
+        assert!(hive.delete_key("").is_err());
+        hive.save(&path).unwrap();
+
+        let mut hive = Hive::open(&path).unwrap();
+        assert_eq!(hive.values(r"Policies\Vendor").unwrap(), vec![("Keep".to_string(), RegValue::Dword(1))]);
+        assert!(hive.subkeys(r"Policies\Vendor").unwrap().is_empty());
+        assert_eq!(hive.subkeys("Policies").unwrap(), vec!["Other", "Vendor"]);
+
+        assert!(hive.delete_key("Policies").unwrap());
+        assert!(hive.subkeys("").unwrap().is_empty());
+        hive.set_value(r"Policies\Vendor", "Again", &RegValue::Dword(2)).unwrap();
+        assert_eq!(hive.get_value(r"Policies\Vendor", "Again").unwrap(), Some(RegValue::Dword(2)));
+    }
+
+    #[test]
+    fn rejects_dirty_or_corrupt_hives() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("SYSTEM");
+        let mut hive = Hive::new().unwrap();
+        hive.save(&path).unwrap();
+        let clean = std::fs::read(&path).unwrap();
+
+        let mut dirty = clean.clone();
+        dirty[8] = dirty[8].wrapping_add(1);
+        let checksum = super::base_block_checksum(&dirty);
+        dirty[508..512].copy_from_slice(&checksum.to_le_bytes());
+        let error = Hive::from_bytes(dirty).err().unwrap();
+        assert!(error.to_string().contains("transaction logs"));
+
+        let mut corrupt = clean.clone();
+        corrupt[100] ^= 0xFF;
+        let error = Hive::from_bytes(corrupt).err().unwrap();
+        assert!(error.to_string().contains("checksum"));
+
+        let error = Hive::from_bytes(vec![0u8; 8192]).err().unwrap();
+        assert!(error.to_string().contains("regf"));
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::{Context, Result, bail};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const WIM_MAGIC: &[u8; 8] = b"MSWIM\0\0\0";
+const PIPABLE_WIM_MAGIC: &[u8; 8] = b"WLPWM\0\0\0";
+const HEADER_SIZE: usize = 208;
+/// Guard against corrupt headers pointing at huge XML blobs
+const MAX_XML_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Parsed WIM header plus the per-image XML metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimInfo {
+    pub version: u32,
+    pub image_count: u32,
+    pub boot_index: u32,
+    pub part_number: u16,
+    pub total_parts: u16,
+    pub images: Vec<WimImage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimImage {
+    pub index: u32,
+    pub name: String,
+    pub description: Option<String>,
+    pub edition: Option<String>,
+    pub architecture: Option<String>,
+    pub build: Option<u32>,
+    pub total_bytes: u64,
+}
+
+/// Read the header and XML metadata of a `.wim` / `.esd` file without mounting it
+pub fn read_wim_info(path: &Path) -> Result<WimInfo> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open WIM {}", path.display()))?;
+
+    let mut header = [0u8; HEADER_SIZE];
+    file.read_exact(&mut header)
+        .with_context(|| format!("{} is too small to be a WIM file", path.display()))?;
+
+    if &header[0..8] == PIPABLE_WIM_MAGIC {
+        bail!("Pipable WIM files are not supported: {}", path.display());
+    }
+    if &header[0..8] != WIM_MAGIC {
+        bail!("Not a WIM file (bad magic): {}", path.display());
+    }
+
+    // RESHDR_DISK_SHORT for the XML blob: 7-byte size + flags, offset, original size
+    let xml_offset = le_u64(&header, 80);
+    let xml_size = le_u64(&header, 88);
+    if xml_size == 0 || xml_size > MAX_XML_SIZE {
+        bail!("WIM XML metadata has an invalid size ({} bytes)", xml_size);
+    }
+
+    let mut xml = vec![0u8; xml_size as usize];
+    file.seek(SeekFrom::Start(xml_offset))?;
+    file.read_exact(&mut xml)
+        .context("WIM XML metadata extends past end of file")?;
+
+    let images = parse_image_xml(&decode_utf16le(&xml)?)?;
+    let image_count = le_u32(&header, 44);
+    if images.len() != image_count as usize {
+        bail!(
+            "WIM header lists {} images but XML metadata describes {}",
+            image_count,
+            images.len()
+        );
+    }
+
+    Ok(WimInfo {
+        version: le_u32(&header, 12),
+        image_count,
+        boot_index: le_u32(&header, 120),
+        part_number: le_u16(&header, 40),
+        total_parts: le_u16(&header, 42),
+        images,
+    })
+}
+
+/// Resolve a configured `wim_index` (number or image name) against the images in a WIM
+pub fn resolve_image_index(info: &WimInfo, selector: &str) -> Result<u32> {
+    let selector = selector.trim();
+
+    if let Ok(index) = selector.parse::<u32>() {
+        if info.images.iter().any(|image| image.index == index) {
+            return Ok(index);
+        }
+        bail!("WIM has no image with index {} (available: 1-{})", index, info.image_count);
+    }
+
+    info.images
+        .iter()
+        .find(|image| image.name.eq_ignore_ascii_case(selector))
+        .map(|image| image.index)
+        .ok_or_else(|| {
+            let names: Vec<String> = info
+                .images
+                .iter()
+                .map(|image| format!("{}: {}", image.index, image.name))
+                .collect();
+            anyhow::anyhow!("WIM has no image named '{}' (available: {})", selector, names.join(", "))
+        })
+}
+
+fn parse_image_xml(xml: &str) -> Result<Vec<WimImage>> {
+    let document = roxmltree::Document::parse(xml).context("Failed to parse WIM XML metadata")?;
+    let root = document.root_element();
+    if !root.has_tag_name("WIM") {
+        bail!("WIM XML metadata has unexpected root element <{}>", root.tag_name().name());
+    }
+
+    let mut images = Vec::new();
+    for image in root.children().filter(|node| node.has_tag_name("IMAGE")) {
+        let index = image
+            .attribute("INDEX")
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .context("WIM image entry is missing a numeric INDEX attribute")?;
+        let windows = child(image, "WINDOWS");
+
+        images.push(WimImage {
+            index,
+            name: child_text(Some(image), "NAME").unwrap_or_default(),
+            description: child_text(Some(image), "DESCRIPTION"),
+            edition: child_text(windows, "EDITIONID"),
+            architecture: child_text(windows, "ARCH").map(|arch| architecture_name(&arch)),
+            build: child_text(windows.and_then(|node| child(node, "VERSION")), "BUILD")
+                .and_then(|build| build.parse().ok()),
+            total_bytes: child_text(Some(image), "TOTALBYTES")
+                .and_then(|bytes| bytes.parse().ok())
+                .unwrap_or(0),
+        });
+    }
+
+    images.sort_by_key(|image| image.index);
+    Ok(images)
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|candidate| candidate.has_tag_name(name))
+}
+
+fn child_text(node: Option<roxmltree::Node>, name: &str) -> Option<String> {
+    node.and_then(|node| child(node, name))
+        .and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// PROCESSOR_ARCHITECTURE values used in the ARCH element
+fn architecture_name(value: &str) -> String {
+    match value {
+        "0" => "x86".to_string(),
+        "5" => "arm".to_string(),
+        "6" => "ia64".to_string(),
+        "9" => "amd64".to_string(),
+        "12" => "arm64".to_string(),
+        other => format!("unknown ({})", other),
+    }
+}
+
+fn decode_utf16le(bytes: &[u8]) -> Result<String> {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    let text = String::from_utf16(&units).context("WIM XML metadata is not valid UTF-16")?;
+    Ok(text.trim_start_matches('\u{feff}').trim_end_matches('\0').to_string())
+}
+
+fn le_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn le_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from(le_u32(data, offset)) | (u64::from(le_u32(data, offset + 4)) << 32)
+}
+
+/// Minimal WIM (header + XML, no resources) for tests elsewhere in the crate
+#[cfg(test)]
+pub(crate) fn write_test_wim(path: &Path, images: &[(&str, &str, &str, u32)]) {
+    let mut xml = String::from("<WIM><TOTALBYTES>0</TOTALBYTES>");
+    for (position, (name, edition, arch, build)) in images.iter().enumerate() {
+        xml.push_str(&format!(
+            "<IMAGE INDEX=\"{}\"><TOTALBYTES>{}</TOTALBYTES><WINDOWS><ARCH>{}</ARCH><EDITIONID>{}</EDITIONID>\
+             <VERSION><MAJOR>10</MAJOR><MINOR>0</MINOR><BUILD>{}</BUILD></VERSION></WINDOWS>\
+             <NAME>{}</NAME><DESCRIPTION>{}</DESCRIPTION></IMAGE>",
+            position + 1,
+            (position as u64 + 1) * 1_000_000,
+            arch,
+            edition,
+            build,
+            name,
+            name
+        ));
+    }
+    xml.push_str("</WIM>");
+
+    let mut xml_bytes = vec![0xFF, 0xFE];
+    xml_bytes.extend(xml.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+
+    let mut header = vec![0u8; HEADER_SIZE];
+    header[0..8].copy_from_slice(WIM_MAGIC);
+    header[8..12].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+    header[12..16].copy_from_slice(&0x10d00u32.to_le_bytes());
+    header[40..42].copy_from_slice(&1u16.to_le_bytes());
+    header[42..44].copy_from_slice(&1u16.to_le_bytes());
+    header[44..48].copy_from_slice(&(images.len() as u32).to_le_bytes());
+    header[72..79].copy_from_slice(&(xml_bytes.len() as u64).to_le_bytes()[..7]);
+    header[80..88].copy_from_slice(&(HEADER_SIZE as u64).to_le_bytes());
+    header[88..96].copy_from_slice(&(xml_bytes.len() as u64).to_le_bytes());
+    header[120..124].copy_from_slice(&(images.len() as u32).to_le_bytes());
+
+    header.extend_from_slice(&xml_bytes);
+    std::fs::write(path, header).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_wim_info, resolve_image_index, write_test_wim};
+    use tempfile::tempdir;
+
+    fn boot_wim(dir: &std::path::Path) -> std::path::PathBuf {
+        let path = dir.join("boot.wim");
+        write_test_wim(
+            &path,
+            &[
+                ("Microsoft Windows PE (amd64)", "WindowsPE", "9", 22621),
+                ("Microsoft Windows Setup (amd64)", "WindowsPE", "9", 22621),
+            ],
+        );
+        path
+    }
+
+    #[test]
+    fn reads_header_and_image_metadata() {
+        let temp = tempdir().unwrap();
+        let info = read_wim_info(&boot_wim(temp.path())).unwrap();
+
+        assert_eq!(info.image_count, 2);
+        assert_eq!(info.boot_index, 2);
+        assert_eq!(info.images[1].index, 2);
+        assert_eq!(info.images[1].name, "Microsoft Windows Setup (amd64)");
+        assert_eq!(info.images[1].edition.as_deref(), Some("WindowsPE"));
+        assert_eq!(info.images[1].architecture.as_deref(), Some("amd64"));
+        assert_eq!(info.images[1].build, Some(22621));
+        assert_eq!(info.images[1].total_bytes, 2_000_000);
+    }
+
+    #[test]
+    fn resolves_index_by_number_or_name() {
+        let temp = tempdir().unwrap();
+        let info = read_wim_info(&boot_wim(temp.path())).unwrap();
+
+        assert_eq!(resolve_image_index(&info, "1").unwrap(), 1);
+        assert_eq!(resolve_image_index(&info, "microsoft windows setup (amd64)").unwrap(), 2);
+
+        let error = resolve_image_index(&info, "3").unwrap_err();
+        assert!(error.to_string().contains("no image with index 3"));
+        let error = resolve_image_index(&info, "Windows 11 Pro").unwrap_err();
+        assert!(error.to_string().contains("2: Microsoft Windows Setup (amd64)"));
+    }
+
+    #[test]
+    fn rejects_files_without_wim_magic() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("not.wim");
+        std::fs::write(&path, vec![0u8; 512]).unwrap();
+
+        let error = read_wim_info(&path).unwrap_err();
+        assert!(error.to_string().contains("bad magic"));
+    }
+}
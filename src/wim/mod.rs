@@ -4,17 +4,31 @@ use tracing::{info, debug, error};
 use tempfile::TempDir;
 use crate::cli::GhostwinConfig;
 
+pub mod backend;
+pub mod hive;
+pub mod metadata;
+pub mod registry;
+
+use backend::ImageBackend;
+use hive::RegValue;
+use registry::RegistryEdit;
+use crate::cli::RegistryHive;
+
 pub struct WimManager {
     mount_path: PathBuf,
     #[allow(dead_code)]
     temp_dir: Option<TempDir>,
-    #[allow(dead_code)]
     config: GhostwinConfig,
+    backend: Box<dyn ImageBackend>,
     is_mounted: bool,
 }
 
 impl WimManager {
     pub fn new(config: &GhostwinConfig) -> Result<Self> {
+        Self::with_backend(config, backend::backend_for_config(config))
+    }
+
+    pub fn with_backend(config: &GhostwinConfig, backend: Box<dyn ImageBackend>) -> Result<Self> {
         let temp_dir = TempDir::new().context("Failed to create temporary directory")?;
         let mount_path = if let Some(ref path) = config.iso.mount_path {
             PathBuf::from(path)
@@ -29,45 +43,26 @@ impl WimManager {
             mount_path,
             temp_dir: Some(temp_dir),
             config: config.clone(),
+            backend,
             is_mounted: false,
         })
     }
     
-    pub async fn mount(&mut self, wim_path: &Path, index: &str) -> Result<()> {
+    pub async fn mount(&mut self, wim_path: &Path, index: u32) -> Result<()> {
         if self.is_mounted {
             bail!("WIM is already mounted");
         }
         
-        info!("Mounting WIM: {} (index: {})", wim_path.display(), index);
-        
-        #[cfg(target_os = "windows")]
-        {
-            let status = tokio::process::Command::new("dism")
-                .args(&[
-                    "/Mount-Wim",
-                    &format!("/WimFile:{}", wim_path.display()),
-                    &format!("/Index:{}", index),
-                    &format!("/MountDir:{}", self.mount_path.display()),
-                ])
-                .status()
-                .await
-                .context("Failed to run DISM mount command")?;
-            
-            if !status.success() {
-                bail!("DISM mount failed");
-            }
-        }
-
-        #[cfg(not(target_os = "windows"))]
-        {
-            bail!("WIM mounting is only supported on Windows");
-        }
+        info!(
+            "Mounting WIM: {} (index: {}, backend: {})",
+            wim_path.display(),
+            index,
+            self.backend.name()
+        );
 
-        #[cfg(target_os = "windows")]
-        {
-            self.is_mounted = true;
-            Ok(())
-        }
+        self.backend.mount(wim_path, index, &self.mount_path).await?;
+        self.is_mounted = true;
+        Ok(())
     }
     
     pub async fn unmount_and_commit(&mut self) -> Result<()> {
@@ -76,24 +71,7 @@ impl WimManager {
         }
         
         info!("Unmounting and committing WIM changes");
-        
-        #[cfg(target_os = "windows")]
-        {
-            let status = tokio::process::Command::new("dism")
-                .args(&[
-                    "/Unmount-Wim",
-                    &format!("/MountDir:{}", self.mount_path.display()),
-                    "/Commit",
-                ])
-                .status()
-                .await
-                .context("Failed to run DISM unmount command")?;
-            
-            if !status.success() {
-                bail!("DISM unmount failed");
-            }
-        }
-        
+        self.backend.unmount(&self.mount_path, true).await?;
         self.is_mounted = false;
         Ok(())
     }
@@ -104,24 +82,7 @@ impl WimManager {
         }
 
         info!("Unmounting and discarding WIM changes");
-
-        #[cfg(target_os = "windows")]
-        {
-            let status = tokio::process::Command::new("dism")
-                .args(&[
-                    "/Unmount-Wim",
-                    &format!("/MountDir:{}", self.mount_path.display()),
-                    "/Discard",
-                ])
-                .status()
-                .await
-                .context("Failed to run DISM discard unmount command")?;
-
-            if !status.success() {
-                bail!("DISM discard unmount failed");
-            }
-        }
-
+        self.backend.unmount(&self.mount_path, false).await?;
         self.is_mounted = false;
         Ok(())
     }
@@ -177,368 +138,198 @@ impl WimManager {
         }
         
         debug!("Adding WinPE package: {}", package);
-        
-        #[cfg(target_os = "windows")]
-        {
-            let adk_path = if let Some(ref path) = self.config.iso.adk_path {
-                PathBuf::from(path)
-            } else {
-                let program_files = std::env::var("ProgramFiles(x86)")
-                    .unwrap_or_else(|_| "C:\\Program Files (x86)".to_string());
-                PathBuf::from(format!("{}\\Windows Kits\\10\\Assessment and Deployment Kit", program_files))
-            };
-            
-            let package_path = adk_path
-                .join("Windows Preinstallation Environment")
-                .join("amd64")
-                .join("WinPE_OCs")
-                .join(format!("{}.cab", package));
-            
-            if !package_path.exists() {
-                bail!("WinPE package not found: {}", package_path.display());
-            }
-            
-            let status = tokio::process::Command::new("dism")
-                .args(&[
-                    &format!("/Image:{}", self.mount_path.display()),
-                    "/Add-Package",
-                    &format!("/PackagePath:{}", package_path.display()),
-                ])
-                .status()
-                .await
-                .context("Failed to add WinPE package")?;
-            
-            if !status.success() {
-                bail!("Failed to add WinPE package: {}", package);
-            }
+
+        let package_path = winpe_package_path(&self.config, package);
+        self.backend.add_package(&self.mount_path, &package_path).await
+    }
+
+    pub async fn add_driver(&self, inf_path: &Path) -> Result<()> {
+        if !self.is_mounted {
+            bail!("WIM is not mounted");
         }
-        
-        Ok(())
+
+        self.backend.add_driver(&self.mount_path, inf_path).await
+    }
+
+    /// Append one image of `source` to `destination`, creating it if needed
+    pub async fn export_image(&self, source: &Path, index: u32, destination: &Path) -> Result<()> {
+        info!(
+            "Exporting image {} of {} to {} (backend: {})",
+            index,
+            source.display(),
+            destination.display(),
+            self.backend.name()
+        );
+        self.backend.export_image(source, index, destination).await
     }
     
     pub async fn apply_registry_fix(&self, fix_type: &str) -> Result<()> {
         if !self.is_mounted {
             bail!("WIM is not mounted");
         }
-        
+
         match fix_type {
             "dpi_scaling" => {
                 debug!("Applying DPI scaling fix");
-                
-                #[cfg(target_os = "windows")]
-                {
-                    let hives = Self::offline_hive_paths(&self.mount_path);
-                    let mut loaded_hives = Vec::new();
-
-                    Self::load_hive(Self::WIM_SOFTWARE_HIVE, &hives.software).await?;
-                    loaded_hives.push(Self::WIM_SOFTWARE_HIVE);
-
-                    Self::load_hive(Self::WIM_SYSTEM_HIVE, &hives.system).await?;
-                    loaded_hives.push(Self::WIM_SYSTEM_HIVE);
-
-                    let result: Result<()> = async {
-                        Self::add_registry_dword(
-                            &Self::side_by_side_key(),
-                            "PreferExternalManifest",
-                            1,
-                        )
-                        .await?;
-
-                        let current_control_set = Self::query_current_control_set().await?;
-                        let graphics_key = Self::graphics_drivers_key(&current_control_set);
-
-                        Self::add_registry_dword(
-                            &graphics_key,
-                            "DisableScalingOptimizations",
-                            1,
-                        )
-                        .await?;
-
-                        Ok(())
-                    }
-                    .await;
-
-                    let unload_result = Self::unload_loaded_hives(&loaded_hives).await;
-
-                    result?;
-                    unload_result?;
-                }
+                self.apply_registry_edits(dpi_scaling_edits()).await?;
+                Ok(())
             }
             _ => bail!("Unknown registry fix type: {}", fix_type),
         }
-        
-        Ok(())
+    }
+
+    /// Write edits straight into the mounted image's hive files; returns the number applied
+    pub async fn apply_registry_edits(&self, edits: Vec<RegistryEdit>) -> Result<usize> {
+        if !self.is_mounted {
+            bail!("WIM is not mounted");
+        }
+
+        let mount_path = self.mount_path.clone();
+        tokio::task::spawn_blocking(move || registry::apply_registry_edits(&mount_path, &edits))
+            .await
+            .context("Registry edit task panicked")?
     }
     
-    #[allow(dead_code)]
     pub fn mount_path(&self) -> &Path {
         &self.mount_path
     }
 
-    #[allow(dead_code)]
     pub fn is_mounted(&self) -> bool {
         self.is_mounted
     }
+}
 
-    #[cfg_attr(not(test), allow(dead_code))]
-    const WIM_SOFTWARE_HIVE: &'static str = r"HKLM\WIM_SOFTWARE";
-    #[cfg_attr(not(test), allow(dead_code))]
-    const WIM_SYSTEM_HIVE: &'static str = r"HKLM\WIM_SYSTEM";
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn offline_hive_paths(mount_path: &Path) -> OfflineHives {
-        OfflineHives {
-            software: mount_path.join("Windows/System32/config/SOFTWARE"),
-            system: mount_path.join("Windows/System32/config/SYSTEM"),
-        }
-    }
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn side_by_side_key() -> String {
-        format!(
-            r"{}\Microsoft\Windows\CurrentVersion\SideBySide",
-            Self::WIM_SOFTWARE_HIVE
-        )
-    }
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn graphics_drivers_key(control_set: &str) -> String {
-        format!(
-            r"{}\{}\Control\GraphicsDrivers\Configuration",
-            Self::WIM_SYSTEM_HIVE,
-            control_set
-        )
-    }
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn format_control_set(value: u32) -> String {
-        format!("ControlSet{:03}", value)
-    }
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn parse_current_control_set_output(stdout: &str) -> Result<String> {
-        let current_value = stdout
-            .lines()
-            .find_map(|line| {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3 && parts[0] == "Current" && parts[1] == "REG_DWORD" {
-                    parts.last().copied()
-                } else {
-                    None
-                }
-            })
-            .ok_or_else(|| anyhow::anyhow!("Unable to parse offline SYSTEM control set"))?;
-
-        let value = if let Some(hex) = current_value.strip_prefix("0x") {
-            u32::from_str_radix(hex, 16)
-        } else {
-            current_value.parse::<u32>()
-        }
-        .context("Failed to parse offline SYSTEM control set value")?;
-
-        Ok(Self::format_control_set(value))
-    }
-
-    #[cfg(target_os = "windows")]
-    async fn unload_loaded_hives(hives: &[&str]) -> Result<()> {
-        let mut unload_error = None;
-
-        for hive_name in hives.iter().rev() {
-            if let Err(error) = Self::unload_hive(hive_name).await {
-                error!("Failed to unload offline hive {}: {}", hive_name, error);
-                if unload_error.is_none() {
-                    unload_error = Some(error);
-                }
-            }
-        }
-
-        if let Some(error) = unload_error {
-            return Err(error);
-        }
-
-        Ok(())
-    }
-
-    #[cfg(target_os = "windows")]
-    async fn load_hive(hive_name: &str, hive_path: &Path) -> Result<()> {
-        Self::run_reg_command(Self::load_hive_args(hive_name, hive_path)).await
-    }
-
-    #[cfg(target_os = "windows")]
-    async fn unload_hive(hive_name: &str) -> Result<()> {
-        Self::run_reg_command(Self::unload_hive_args(hive_name)).await
-    }
-
-    #[cfg(target_os = "windows")]
-    async fn add_registry_dword(key: &str, value_name: &str, value: u32) -> Result<()> {
-        Self::run_reg_command(Self::add_registry_dword_args(key, value_name, value)).await
-    }
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn load_hive_args(hive_name: &str, hive_path: &Path) -> Vec<String> {
-        vec![
-            "load".to_string(),
-            hive_name.to_string(),
-            hive_path.to_string_lossy().into_owned(),
-        ]
-    }
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn unload_hive_args(hive_name: &str) -> Vec<String> {
-        vec!["unload".to_string(), hive_name.to_string()]
-    }
-
-    #[cfg_attr(not(test), allow(dead_code))]
-    fn add_registry_dword_args(key: &str, value_name: &str, value: u32) -> Vec<String> {
-        vec![
-            "add".to_string(),
-            key.to_string(),
-            "/v".to_string(),
-            value_name.to_string(),
-            "/t".to_string(),
-            "REG_DWORD".to_string(),
-            "/d".to_string(),
-            value.to_string(),
-            "/f".to_string(),
-        ]
-    }
-
-    #[cfg(target_os = "windows")]
-    async fn query_current_control_set() -> Result<String> {
-        let output = tokio::process::Command::new("reg")
-            .args(["query", r"HKLM\WIM_SYSTEM\Select", "/v", "Current"])
-            .output()
-            .await
-            .context("Failed to query offline SYSTEM control set")?;
-
-        if !output.status.success() {
-            bail!(
-                "Failed to query offline SYSTEM control set: {}",
-                String::from_utf8_lossy(&output.stderr).trim()
-            );
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Self::parse_current_control_set_output(&stdout)
-    }
-
-    #[cfg(target_os = "windows")]
-    async fn run_reg_command(args: Vec<String>) -> Result<()> {
-        let output = tokio::process::Command::new("reg")
-            .args(args.iter().map(String::as_str))
-            .output()
-            .await
-            .context("Failed to run reg command")?;
-
-        if output.status.success() {
-            return Ok(());
-        }
-
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let details = stderr.trim();
-        let details = if details.is_empty() { stdout.trim() } else { details };
+/// Location of a WinPE optional component cab in the ADK
+pub fn winpe_package_path(config: &GhostwinConfig, package: &str) -> PathBuf {
+    let adk_path = if let Some(ref path) = config.iso.adk_path {
+        PathBuf::from(path)
+    } else {
+        let program_files = std::env::var("ProgramFiles(x86)")
+            .unwrap_or_else(|_| "C:\\Program Files (x86)".to_string());
+        PathBuf::from(format!("{}\\Windows Kits\\10\\Assessment and Deployment Kit", program_files))
+    };
+
+    adk_path
+        .join("Windows Preinstallation Environment")
+        .join("amd64")
+        .join("WinPE_OCs")
+        .join(format!("{}.cab", package))
+}
 
-        bail!("reg command failed: {}", details)
-    }
+/// Prefer external manifests and disable display scaling optimizations in the offline image
+pub(crate) fn dpi_scaling_edits() -> Vec<RegistryEdit> {
+    vec![
+        RegistryEdit::SetValue {
+            hive: RegistryHive::Software,
+            key: r"Microsoft\Windows\CurrentVersion\SideBySide".to_string(),
+            name: "PreferExternalManifest".to_string(),
+            value: RegValue::Dword(1),
+        },
+        RegistryEdit::SetValue {
+            hive: RegistryHive::System,
+            key: r"CurrentControlSet\Control\GraphicsDrivers\Configuration".to_string(),
+            name: "DisableScalingOptimizations".to_string(),
+            value: RegValue::Dword(1),
+        },
+    ]
 }
 
-#[cfg_attr(not(test), allow(dead_code))]
-struct OfflineHives {
-    software: PathBuf,
-    system: PathBuf,
+/// Minimal SOFTWARE/SYSTEM hives under `mount_path`, shaped like a WinPE image
+#[cfg(test)]
+pub(crate) fn write_test_offline_hives(mount_path: &Path) {
+    let software_path = registry::hive_path(mount_path, RegistryHive::Software);
+    std::fs::create_dir_all(software_path.parent().unwrap()).unwrap();
+
+    let mut software = hive::Hive::new().unwrap();
+    software.create_key(r"Microsoft\Windows\CurrentVersion").unwrap();
+    software.save(&software_path).unwrap();
+
+    let mut system = hive::Hive::new().unwrap();
+    system.create_key(r"ControlSet001\Control").unwrap();
+    system.set_value("Select", "Current", &RegValue::Dword(1)).unwrap();
+    system.save(&registry::hive_path(mount_path, RegistryHive::System)).unwrap();
 }
 
 impl Drop for WimManager {
     fn drop(&mut self) {
         if self.is_mounted {
             error!("WIM was not properly unmounted!");
-            // Try to unmount synchronously
-            #[cfg(target_os = "windows")]
-            {
-                let _ = std::process::Command::new("dism")
-                    .args(&[
-                        "/Unmount-Wim",
-                        &format!("/MountDir:{}", self.mount_path.display()),
-                        "/Discard",
-                    ])
-                    .status();
-            }
+            self.backend.discard_on_drop(&self.mount_path);
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::WimManager;
+    use super::{WimManager, write_test_offline_hives};
+    use super::backend::RecordingBackend;
+    use super::hive::{Hive, RegValue};
+    use super::registry::hive_path;
+    use crate::cli::{GhostwinConfig, RegistryHive};
     use std::path::Path;
+    use tempfile::tempdir;
 
-    #[test]
-    fn builds_offline_hive_paths_from_mount_root() {
-        let hives = WimManager::offline_hive_paths(Path::new("/mnt/wim"));
-        assert_eq!(hives.software, Path::new("/mnt/wim/Windows/System32/config/SOFTWARE"));
-        assert_eq!(hives.system, Path::new("/mnt/wim/Windows/System32/config/SYSTEM"));
-    }
-
-    #[test]
-    fn builds_registry_command_arguments() {
-        let load_args = WimManager::load_hive_args("HKLM\\WIM_SOFTWARE", Path::new("C:/mount/SOFTWARE"));
-        assert_eq!(load_args, vec!["load", "HKLM\\WIM_SOFTWARE", "C:/mount/SOFTWARE"]);
+    #[tokio::test]
+    async fn delegates_servicing_calls_to_backend() {
+        let backend = RecordingBackend::default();
+        let mut manager = WimManager::with_backend(&GhostwinConfig::default(), Box::new(backend.clone())).unwrap();
 
-        let unload_args = WimManager::unload_hive_args("HKLM\\WIM_SYSTEM");
-        assert_eq!(unload_args, vec!["unload", "HKLM\\WIM_SYSTEM"]);
+        manager.mount(Path::new("/build/sources/boot.wim"), 2).await.unwrap();
+        manager.add_package("WinPE-WMI").await.unwrap();
+        manager.add_driver(Path::new("/drivers/iaStorVD.inf")).await.unwrap();
+        manager.unmount_and_commit().await.unwrap();
 
-        let add_args = WimManager::add_registry_dword_args(
-            r"HKLM\WIM_SYSTEM\ControlSet001\Control\GraphicsDrivers\Configuration",
-            "DisableScalingOptimizations",
-            1,
-        );
         assert_eq!(
-            add_args,
+            backend.calls(),
             vec![
-                "add",
-                r"HKLM\WIM_SYSTEM\ControlSet001\Control\GraphicsDrivers\Configuration",
-                "/v",
-                "DisableScalingOptimizations",
-                "/t",
-                "REG_DWORD",
-                "/d",
-                "1",
-                "/f",
+                "mount boot.wim 2",
+                "add_package WinPE-WMI.cab",
+                "add_driver iaStorVD.inf",
+                "unmount commit",
             ]
         );
+        assert!(!manager.is_mounted());
     }
 
-    #[test]
-    fn formats_registry_keys_consistently() {
-        assert_eq!(
-            WimManager::side_by_side_key(),
-            r"HKLM\WIM_SOFTWARE\Microsoft\Windows\CurrentVersion\SideBySide"
-        );
-        assert_eq!(
-            WimManager::graphics_drivers_key("ControlSet007"),
-            r"HKLM\WIM_SYSTEM\ControlSet007\Control\GraphicsDrivers\Configuration"
-        );
+    #[tokio::test]
+    async fn rejects_servicing_before_mount() {
+        let manager = WimManager::with_backend(&GhostwinConfig::default(), Box::new(RecordingBackend::default())).unwrap();
+
+        let error = manager.add_package("WinPE-WMI").await.unwrap_err();
+        assert!(error.to_string().contains("WIM is not mounted"));
     }
 
-    #[test]
-    fn parses_current_control_set_from_decimal_and_hex() {
-        let decimal = "HKEY_LOCAL_MACHINE\\WIM_SYSTEM\\Select\n    Current    REG_DWORD    0x1\n";
-        let hex = "HKEY_LOCAL_MACHINE\\WIM_SYSTEM\\Select\n    Current    REG_DWORD    2\n";
+    #[tokio::test]
+    async fn applies_dpi_fix_to_offline_hives() {
+        let temp = tempdir().unwrap();
+        let mut config = GhostwinConfig::default();
+        config.iso.mount_path = Some(temp.path().display().to_string());
+        write_test_offline_hives(temp.path());
+
+        let mut manager = WimManager::with_backend(&config, Box::new(RecordingBackend::default())).unwrap();
+        manager.mount(Path::new("/build/sources/boot.wim"), 1).await.unwrap();
+        manager.apply_registry_fix("dpi_scaling").await.unwrap();
 
+        let software = Hive::open(&hive_path(temp.path(), RegistryHive::Software)).unwrap();
         assert_eq!(
-            WimManager::parse_current_control_set_output(decimal).unwrap(),
-            "ControlSet001"
+            software
+                .get_value(r"Microsoft\Windows\CurrentVersion\SideBySide", "PreferExternalManifest")
+                .unwrap(),
+            Some(RegValue::Dword(1))
         );
+        let system = Hive::open(&hive_path(temp.path(), RegistryHive::System)).unwrap();
         assert_eq!(
-            WimManager::parse_current_control_set_output(hex).unwrap(),
-            "ControlSet002"
+            system
+                .get_value(
+                    r"ControlSet001\Control\GraphicsDrivers\Configuration",
+                    "DisableScalingOptimizations"
+                )
+                .unwrap(),
+            Some(RegValue::Dword(1))
         );
-    }
 
-    #[test]
-    fn rejects_invalid_control_set_output() {
-        let error = WimManager::parse_current_control_set_output("missing Current value").unwrap_err();
-        assert!(error.to_string().contains("Unable to parse offline SYSTEM control set"));
+        let error = manager.apply_registry_fix("unknown").await.unwrap_err();
+        assert!(error.to_string().contains("Unknown registry fix type"));
+        manager.unmount_and_discard().await.unwrap();
     }
 }
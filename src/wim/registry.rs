@@ -0,0 +1,569 @@
+use anyhow::{Context, Result, bail};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::cli::{RegistryData, RegistryHive, RegistryTweak, RegistryValueType};
+use super::hive::{Hive, RegValue};
+
+const REG_HEADER_V5: &str = "Windows Registry Editor Version 5.00";
+const REG_HEADER_V4: &str = "REGEDIT4";
+const CURRENT_CONTROL_SET: &str = "CurrentControlSet";
+
+/// One change to an offline hive, from `[[winpe.registry]]` or an imported `.reg` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEdit {
+    SetValue { hive: RegistryHive, key: String, name: String, value: RegValue },
+    DeleteValue { hive: RegistryHive, key: String, name: String },
+    DeleteKey { hive: RegistryHive, key: String },
+}
+
+impl RegistryEdit {
+    pub fn hive(&self) -> RegistryHive {
+        match self {
+            RegistryEdit::SetValue { hive, .. }
+            | RegistryEdit::DeleteValue { hive, .. }
+            | RegistryEdit::DeleteKey { hive, .. } => *hive,
+        }
+    }
+}
+
+impl fmt::Display for RegistryEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryEdit::SetValue { hive, key, name, .. } => {
+                write!(f, "set {}\\{} [{}]", hive_name(*hive), key, display_value_name(name))
+            }
+            RegistryEdit::DeleteValue { hive, key, name } => {
+                write!(f, "delete {}\\{} [{}]", hive_name(*hive), key, display_value_name(name))
+            }
+            RegistryEdit::DeleteKey { hive, key } => write!(f, "delete key {}\\{}", hive_name(*hive), key),
+        }
+    }
+}
+
+pub fn hive_name(hive: RegistryHive) -> &'static str {
+    match hive {
+        RegistryHive::Software => "SOFTWARE",
+        RegistryHive::System => "SYSTEM",
+        RegistryHive::Default => "DEFAULT",
+        RegistryHive::Ntuser => "NTUSER",
+    }
+}
+
+/// Location of a hive file inside a mounted Windows/WinPE image
+pub fn hive_path(mount_path: &Path, hive: RegistryHive) -> PathBuf {
+    match hive {
+        RegistryHive::Ntuser => mount_path.join("Users").join("Default").join("NTUSER.DAT"),
+        _ => mount_path
+            .join("Windows")
+            .join("System32")
+            .join("config")
+            .join(hive_name(hive)),
+    }
+}
+
+/// Convert a `[[winpe.registry]]` entry into a typed edit, checking `data` against `type`
+pub fn tweak_edit(tweak: &RegistryTweak) -> Result<RegistryEdit> {
+    let label = format!("{}\\{} [{}]", hive_name(tweak.hive), tweak.key, display_value_name(&tweak.name));
+    let value = match (tweak.value_type, &tweak.data) {
+        (RegistryValueType::Sz, RegistryData::Text(text)) => RegValue::Sz(text.clone()),
+        (RegistryValueType::ExpandSz, RegistryData::Text(text)) => RegValue::ExpandSz(text.clone()),
+        (RegistryValueType::MultiSz, RegistryData::List(items)) => RegValue::MultiSz(items.clone()),
+        (RegistryValueType::MultiSz, RegistryData::Text(text)) => RegValue::MultiSz(vec![text.clone()]),
+        (RegistryValueType::Dword, RegistryData::Integer(number)) => RegValue::Dword(
+            u32::try_from(*number).with_context(|| format!("{}: {} does not fit in a DWORD", label, number))?,
+        ),
+        (RegistryValueType::Dword, RegistryData::Text(text)) => {
+            let number = parse_number(text).with_context(|| format!("{}: invalid DWORD '{}'", label, text))?;
+            RegValue::Dword(
+                u32::try_from(number).with_context(|| format!("{}: {} does not fit in a DWORD", label, text))?,
+            )
+        }
+        (RegistryValueType::Qword, RegistryData::Integer(number)) => RegValue::Qword(
+            u64::try_from(*number).with_context(|| format!("{}: QWORD cannot be negative", label))?,
+        ),
+        (RegistryValueType::Qword, RegistryData::Text(text)) => RegValue::Qword(
+            parse_number(text).with_context(|| format!("{}: invalid QWORD '{}'", label, text))?,
+        ),
+        (RegistryValueType::Binary, RegistryData::Bytes(bytes)) => RegValue::Binary(bytes.clone()),
+        (RegistryValueType::Binary, RegistryData::List(items)) if items.is_empty() => RegValue::Binary(Vec::new()),
+        (RegistryValueType::Binary, RegistryData::Text(text)) => RegValue::Binary(
+            parse_hex_bytes(text).with_context(|| format!("{}: invalid binary data '{}'", label, text))?,
+        ),
+        (value_type, data) => bail!("{}: data {:?} does not match type {:?}", label, data, value_type),
+    };
+
+    Ok(RegistryEdit::SetValue {
+        hive: tweak.hive,
+        key: tweak.key.clone(),
+        name: tweak.name.clone(),
+        value,
+    })
+}
+
+/// Parse a regedit export (UTF-16LE or UTF-8) into offline edits
+pub fn parse_reg_file(path: &Path) -> Result<Vec<RegistryEdit>> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read registry file {}", path.display()))?;
+    parse_reg_text(&decode_reg_text(&bytes))
+        .with_context(|| format!("Failed to parse registry file {}", path.display()))
+}
+
+pub fn parse_reg_text(text: &str) -> Result<Vec<RegistryEdit>> {
+    let mut lines = logical_lines(text)
+        .into_iter()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with(';'));
+
+    match lines.next() {
+        Some((_, header)) if header.trim() == REG_HEADER_V5 || header.trim() == REG_HEADER_V4 => {}
+        _ => bail!("missing '{}' header", REG_HEADER_V5),
+    }
+
+    let mut edits = Vec::new();
+    let mut current_key: Option<(RegistryHive, String)> = None;
+
+    for (number, line) in lines {
+        let line = line.trim();
+
+        if let Some(section) = line.strip_prefix('[') {
+            let section = section
+                .strip_suffix(']')
+                .with_context(|| format!("line {}: unterminated key section", number))?;
+            if let Some(deleted) = section.strip_prefix('-') {
+                let (hive, key) = map_reg_key(deleted).with_context(|| format!("line {}", number))?;
+                edits.push(RegistryEdit::DeleteKey { hive, key });
+                current_key = None;
+            } else {
+                current_key = Some(map_reg_key(section).with_context(|| format!("line {}", number))?);
+            }
+            continue;
+        }
+
+        let Some((hive, key)) = &current_key else {
+            bail!("line {}: value outside of a [key] section", number);
+        };
+        let (name, rest) = parse_value_name(line).with_context(|| format!("line {}", number))?;
+        let data = rest
+            .trim_start()
+            .strip_prefix('=')
+            .with_context(|| format!("line {}: expected '=' after value name", number))?
+            .trim();
+
+        if data == "-" {
+            edits.push(RegistryEdit::DeleteValue { hive: *hive, key: key.clone(), name });
+        } else {
+            let value = parse_reg_data(data).with_context(|| format!("line {}", number))?;
+            edits.push(RegistryEdit::SetValue { hive: *hive, key: key.clone(), name, value });
+        }
+    }
+
+    Ok(edits)
+}
+
+/// Apply edits hive by hive, saving each touched hive once; returns the number applied
+pub fn apply_registry_edits(mount_path: &Path, edits: &[RegistryEdit]) -> Result<usize> {
+    let mut applied = 0;
+
+    for hive_kind in [RegistryHive::Software, RegistryHive::System, RegistryHive::Default, RegistryHive::Ntuser] {
+        let hive_edits: Vec<&RegistryEdit> = edits.iter().filter(|edit| edit.hive() == hive_kind).collect();
+        if hive_edits.is_empty() {
+            continue;
+        }
+
+        let path = hive_path(mount_path, hive_kind);
+        if !path.exists() {
+            bail!("Offline {} hive not found at {}", hive_name(hive_kind), path.display());
+        }
+
+        let mut hive = Hive::open(&path)?;
+        for edit in hive_edits {
+            apply_edit(&mut hive, edit).with_context(|| format!("Failed to {}", edit))?;
+            applied += 1;
+        }
+        hive.save(&path)?;
+    }
+
+    Ok(applied)
+}
+
+fn apply_edit(hive: &mut Hive, edit: &RegistryEdit) -> Result<()> {
+    match edit {
+        RegistryEdit::SetValue { hive: kind, key, name, value } => {
+            let key = resolve_control_set(hive, *kind, key)?;
+            hive.set_value(&key, name, value)
+        }
+        RegistryEdit::DeleteValue { hive: kind, key, name } => {
+            let key = resolve_control_set(hive, *kind, key)?;
+            hive.delete_value(&key, name).map(|_| ())
+        }
+        RegistryEdit::DeleteKey { hive: kind, key } => {
+            let key = resolve_control_set(hive, *kind, key)?;
+            hive.delete_key(&key).map(|_| ())
+        }
+    }
+}
+
+/// `CurrentControlSet` only exists at runtime; offline it is whatever `Select\Current` names
+fn resolve_control_set(hive: &Hive, kind: RegistryHive, key: &str) -> Result<String> {
+    let trimmed = key.trim_start_matches(['\\', '/']);
+    if kind != RegistryHive::System {
+        return Ok(trimmed.to_string());
+    }
+
+    let (first, rest) = trimmed.split_once(['\\', '/']).unwrap_or((trimmed, ""));
+    if !first.eq_ignore_ascii_case(CURRENT_CONTROL_SET) {
+        return Ok(trimmed.to_string());
+    }
+
+    let control_set = hive.current_control_set()?;
+    Ok(if rest.is_empty() { control_set } else { format!("{}\\{}", control_set, rest) })
+}
+
+/// Map a `.reg` root path (`HKEY_LOCAL_MACHINE\SOFTWARE\...`) onto an offline hive
+fn map_reg_key(path: &str) -> Result<(RegistryHive, String)> {
+    let (root, rest) = path.trim().split_once('\\').unwrap_or((path.trim(), ""));
+    let (first, remainder) = rest.split_once('\\').unwrap_or((rest, ""));
+
+    match root.to_ascii_uppercase().as_str() {
+        "HKEY_LOCAL_MACHINE" | "HKLM" => match first.to_ascii_uppercase().as_str() {
+            "SOFTWARE" => Ok((RegistryHive::Software, remainder.to_string())),
+            "SYSTEM" => Ok((RegistryHive::System, remainder.to_string())),
+            _ => bail!("HKEY_LOCAL_MACHINE\\{} is not an offline hive ghostwin can edit", first),
+        },
+        "HKEY_CLASSES_ROOT" | "HKCR" => Ok((
+            RegistryHive::Software,
+            if rest.is_empty() { "Classes".to_string() } else { format!("Classes\\{}", rest) },
+        )),
+        "HKEY_CURRENT_USER" | "HKCU" => Ok((RegistryHive::Ntuser, rest.to_string())),
+        "HKEY_USERS" | "HKU" if first.eq_ignore_ascii_case(".DEFAULT") => {
+            Ok((RegistryHive::Default, remainder.to_string()))
+        }
+        _ => bail!("unsupported registry root '{}'", path),
+    }
+}
+
+fn parse_value_name(line: &str) -> Result<(String, &str)> {
+    if let Some(rest) = line.strip_prefix('@') {
+        return Ok((String::new(), rest));
+    }
+    if line.starts_with('"') {
+        return parse_quoted(line);
+    }
+    bail!("expected a quoted value name or '@'")
+}
+
+/// Parse a `"..."` string with regedit's `\\` and `\"` escapes, returning the remainder
+fn parse_quoted(text: &str) -> Result<(String, &str)> {
+    let mut value = String::new();
+    let mut escaped = false;
+
+    for (index, character) in text.char_indices().skip(1) {
+        if escaped {
+            value.push(character);
+            escaped = false;
+        } else if character == '\\' {
+            escaped = true;
+        } else if character == '"' {
+            return Ok((value, &text[index + 1..]));
+        } else {
+            value.push(character);
+        }
+    }
+
+    bail!("unterminated string")
+}
+
+fn parse_reg_data(data: &str) -> Result<RegValue> {
+    if data.starts_with('"') {
+        let (text, rest) = parse_quoted(data)?;
+        if !rest.trim().is_empty() && !rest.trim_start().starts_with(';') {
+            bail!("unexpected text after string value: {}", rest.trim());
+        }
+        return Ok(RegValue::Sz(text));
+    }
+
+    let lower = data.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("dword:") {
+        return u32::from_str_radix(hex.trim(), 16)
+            .map(RegValue::Dword)
+            .with_context(|| format!("invalid dword value '{}'", hex.trim()));
+    }
+    if let Some(bytes) = lower.strip_prefix("hex:") {
+        return Ok(RegValue::Binary(parse_hex_bytes(bytes)?));
+    }
+    if let Some(typed) = lower.strip_prefix("hex(") {
+        let (kind, bytes) = typed
+            .split_once("):")
+            .context("malformed hex(type): value")?;
+        let kind = u32::from_str_radix(kind.trim(), 16)
+            .with_context(|| format!("invalid value type '{}'", kind))?;
+        return Ok(RegValue::from_raw(kind, parse_hex_bytes(bytes)?));
+    }
+
+    bail!("unsupported value data '{}'", data)
+}
+
+fn parse_number(text: &str) -> Result<u64> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => Ok(u64::from_str_radix(hex, 16)?),
+        None => Ok(text.parse::<u64>()?),
+    }
+}
+
+/// Hex bytes as `01,02,ff`, `01 02 ff`, or `0102ff`
+fn parse_hex_bytes(text: &str) -> Result<Vec<u8>> {
+    let digits: String = text
+        .chars()
+        .filter(|character| !character.is_whitespace() && *character != ',')
+        .collect();
+    if let Some(invalid) = digits.chars().find(|character| !character.is_ascii_hexdigit()) {
+        bail!("invalid hex digit '{}'", invalid);
+    }
+    if !digits.len().is_multiple_of(2) {
+        bail!("odd number of hex digits");
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| Ok(u8::from_str_radix(&digits[index..index + 2], 16)?))
+        .collect()
+}
+
+fn decode_reg_text(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Join `\`-continued lines (long hex values), keeping the first line number of each
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, raw) in text.lines().enumerate() {
+        let (number, mut line) = match pending.take() {
+            Some((number, mut joined)) => {
+                joined.push_str(raw.trim_start());
+                (number, joined)
+            }
+            None => (index + 1, raw.to_string()),
+        };
+
+        let trimmed_length = line.trim_end().len();
+        if line[..trimmed_length].ends_with('\\') {
+            line.truncate(trimmed_length - 1);
+            pending = Some((number, line));
+        } else {
+            lines.push((number, line));
+        }
+    }
+    if let Some(last) = pending {
+        lines.push(last);
+    }
+
+    lines
+}
+
+fn display_value_name(name: &str) -> &str {
+    if name.is_empty() { "(Default)" } else { name }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RegistryEdit, apply_registry_edits, hive_path, parse_reg_file, parse_reg_text, tweak_edit};
+    use crate::cli::{RegistryData, RegistryHive, RegistryTweak, RegistryValueType};
+    use crate::wim::hive::{Hive, RegValue};
+    use crate::wim::write_test_offline_hives;
+    use tempfile::tempdir;
+
+    fn tweak(value_type: RegistryValueType, data: RegistryData) -> RegistryTweak {
+        RegistryTweak {
+            hive: RegistryHive::Software,
+            key: r"Policies\Vendor".to_string(),
+            name: "Setting".to_string(),
+            value_type,
+            data,
+        }
+    }
+
+    fn set_value(edit: RegistryEdit) -> RegValue {
+        match edit {
+            RegistryEdit::SetValue { value, .. } => value,
+            other => panic!("unexpected edit {:?}", other),
+        }
+    }
+
+    #[test]
+    fn converts_tweak_data_by_type() {
+        let convert = |value_type, data| set_value(tweak_edit(&tweak(value_type, data)).unwrap());
+
+        assert_eq!(convert(RegistryValueType::Dword, RegistryData::Integer(1)), RegValue::Dword(1));
+        assert_eq!(
+            convert(RegistryValueType::Dword, RegistryData::Text("0xFFFFFFFF".to_string())),
+            RegValue::Dword(u32::MAX)
+        );
+        assert_eq!(convert(RegistryValueType::Qword, RegistryData::Integer(1 << 40)), RegValue::Qword(1 << 40));
+        assert_eq!(
+            convert(RegistryValueType::ExpandSz, RegistryData::Text("%SystemRoot%".to_string())),
+            RegValue::ExpandSz("%SystemRoot%".to_string())
+        );
+        assert_eq!(
+            convert(RegistryValueType::MultiSz, RegistryData::List(vec!["a".to_string(), "b".to_string()])),
+            RegValue::MultiSz(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            convert(RegistryValueType::Binary, RegistryData::Text("01,02,ff".to_string())),
+            RegValue::Binary(vec![1, 2, 0xFF])
+        );
+        assert_eq!(
+            convert(RegistryValueType::Binary, RegistryData::Bytes(vec![9, 8])),
+            RegValue::Binary(vec![9, 8])
+        );
+
+        let error = tweak_edit(&tweak(RegistryValueType::Dword, RegistryData::Integer(-1))).unwrap_err();
+        assert!(error.to_string().contains("does not fit in a DWORD"));
+        let error = tweak_edit(&tweak(RegistryValueType::Sz, RegistryData::Integer(3))).unwrap_err();
+        assert!(error.to_string().contains("does not match type"));
+    }
+
+    #[test]
+    fn parses_regedit_exports() {
+        let text = r#"Windows Registry Editor Version 5.00
+
+; PE autorun tweaks
+[HKEY_LOCAL_MACHINE\SOFTWARE\Vendor\Tool]
+@="default"
+"Path"="C:\\Tools\\\"quoted\""
+"Enabled"=dword:00000001
+"Blob"=hex:01,02,\
+  03,ff
+"Expand"=hex(2):25,00,41,00,25,00,00,00
+"Multi"=hex(7):61,00,00,00,62,00,00,00,00,00
+"Big"=hex(b):00,00,00,00,00,01,00,00
+"Old"=-
+
+[-HKEY_LOCAL_MACHINE\SYSTEM\CurrentControlSet\Services\Legacy]
+
+[HKEY_CURRENT_USER\Control Panel\Desktop]
+"LogPixels"=dword:00000060
+
+[HKEY_USERS\.DEFAULT\Console]
+"QuickEdit"=dword:00000001
+
+[HKEY_CLASSES_ROOT\.log]
+@="txtfile"
+"#;
+        let edits = parse_reg_text(text).unwrap();
+        let software = |name: &str, value: RegValue| RegistryEdit::SetValue {
+            hive: RegistryHive::Software,
+            key: r"Vendor\Tool".to_string(),
+            name: name.to_string(),
+            value,
+        };
+
+        assert_eq!(
+            edits,
+            vec![
+                software("", RegValue::Sz("default".to_string())),
+                software("Path", RegValue::Sz(r#"C:\Tools\"quoted""#.to_string())),
+                software("Enabled", RegValue::Dword(1)),
+                software("Blob", RegValue::Binary(vec![1, 2, 3, 0xFF])),
+                software("Expand", RegValue::ExpandSz("%A%".to_string())),
+                software("Multi", RegValue::MultiSz(vec!["a".to_string(), "b".to_string()])),
+                software("Big", RegValue::Qword(1 << 40)),
+                RegistryEdit::DeleteValue {
+                    hive: RegistryHive::Software,
+                    key: r"Vendor\Tool".to_string(),
+                    name: "Old".to_string(),
+                },
+                RegistryEdit::DeleteKey {
+                    hive: RegistryHive::System,
+                    key: r"CurrentControlSet\Services\Legacy".to_string(),
+                },
+                RegistryEdit::SetValue {
+                    hive: RegistryHive::Ntuser,
+                    key: r"Control Panel\Desktop".to_string(),
+                    name: "LogPixels".to_string(),
+                    value: RegValue::Dword(0x60),
+                },
+                RegistryEdit::SetValue {
+                    hive: RegistryHive::Default,
+                    key: "Console".to_string(),
+                    name: "QuickEdit".to_string(),
+                    value: RegValue::Dword(1),
+                },
+                RegistryEdit::SetValue {
+                    hive: RegistryHive::Software,
+                    key: r"Classes\.log".to_string(),
+                    name: String::new(),
+                    value: RegValue::Sz("txtfile".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_reg_files() {
+        let error = parse_reg_text("[HKEY_LOCAL_MACHINE\\SOFTWARE\\X]\n").unwrap_err();
+        assert!(error.to_string().contains("header"));
+
+        let error = parse_reg_text("REGEDIT4\n\"Orphan\"=dword:1\n").unwrap_err();
+        assert!(error.to_string().contains("line 2: value outside of a [key] section"));
+
+        let error = parse_reg_text("REGEDIT4\n[HKEY_LOCAL_MACHINE\\SAM\\X]\n").unwrap_err();
+        assert!(format!("{:#}", error).contains("not an offline hive"));
+    }
+
+    #[test]
+    fn imports_utf16_reg_file_into_offline_hives() {
+        let temp = tempdir().unwrap();
+        write_test_offline_hives(temp.path());
+        let mut system = Hive::open(&hive_path(temp.path(), RegistryHive::System)).unwrap();
+        system
+            .set_value(r"ControlSet001\Services\Legacy", "Start", &RegValue::Dword(2))
+            .unwrap();
+        system.save(&hive_path(temp.path(), RegistryHive::System)).unwrap();
+
+        let text = "Windows Registry Editor Version 5.00\r\n\r\n\
+            [HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\Session Manager]\r\n\
+            \"Ghostwin\"=\"baked\"\r\n\r\n\
+            [-HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services\\Legacy]\r\n";
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        let reg_path = temp.path().join("tweaks.reg");
+        std::fs::write(&reg_path, bytes).unwrap();
+
+        let edits = parse_reg_file(&reg_path).unwrap();
+        assert_eq!(apply_registry_edits(temp.path(), &edits).unwrap(), 2);
+
+        let system = Hive::open(&hive_path(temp.path(), RegistryHive::System)).unwrap();
+        assert_eq!(
+            system
+                .get_value(r"ControlSet001\Control\Session Manager", "Ghostwin")
+                .unwrap(),
+            Some(RegValue::Sz("baked".to_string()))
+        );
+        assert!(system.subkeys(r"ControlSet001\Services").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_missing_hive_files() {
+        let temp = tempdir().unwrap();
+        write_test_offline_hives(temp.path());
+        let edits = vec![RegistryEdit::SetValue {
+            hive: RegistryHive::Ntuser,
+            key: r"Control Panel\Desktop".to_string(),
+            name: "LogPixels".to_string(),
+            value: RegValue::Dword(96),
+        }];
+
+        let error = apply_registry_edits(temp.path(), &edits).unwrap_err();
+        assert!(error.to_string().contains("Offline NTUSER hive not found"));
+    }
+}